@@ -0,0 +1,107 @@
+//! `cargo bench` 运行的基准测试：依赖解析、翻译查找、文件缓存读取和插件注册表加载。
+//! 结果和历史对比由 criterion 保存在 target/criterion/ 中；
+//! `cargo bench -- --save-baseline main` 保存基线，`cargo bench -- --baseline main` 与之比较
+
+// 二进制 crate 没有库目标，这里直接引入 main.rs 的模块树；
+// 根模块的重新导出让各模块中的 `crate::` 路径照常解析
+#[allow(unused, clippy::all)]
+#[path = "../src/main.rs"]
+mod geektools;
+#[allow(unused_imports)]
+use geektools::*;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use i18n::{t, Language};
+use plugins::{InstalledPlugin, PluginInfo, PluginManager, ScriptEntry};
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// 200 个脚本的依赖图，每个脚本依赖前面最多 3 个脚本
+fn synthetic_graph() -> HashMap<String, Vec<String>> {
+    (0..200usize)
+        .map(|i| {
+            let imports = (i.saturating_sub(3)..i).map(|j| format!("s{}.sh", j)).collect();
+            (format!("s{}.sh", i), imports)
+        })
+        .collect()
+}
+
+fn dependency_resolution(c: &mut Criterion) {
+    let graph = synthetic_graph();
+    c.bench_function("scripts::topological_sort/200", |b| {
+        b.iter(|| scripts::topological_sort(black_box(&graph)).unwrap())
+    });
+}
+
+fn translation_lookup(c: &mut Criterion) {
+    c.bench_function("i18n::t/nested", |b| {
+        b.iter(|| t(black_box("menu.run_existing_script"), &[], Language::English))
+    });
+    c.bench_function("i18n::t/params", |b| {
+        b.iter(|| t(black_box("update_menu.downloading"), &[("0", "v1.0.0")], Language::Chinese))
+    });
+}
+
+fn cached_read(c: &mut Criterion) {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("config.json");
+    fileio::write(&path, &"x".repeat(16 * 1024)).unwrap();
+    c.bench_function("fileio::read/cached_16k", |b| b.iter(|| fileio::read(black_box(&path)).unwrap()));
+}
+
+/// 在 `dir` 中写入 60 个插件 (每个 3 个脚本，其中一个缺失) 及其注册表
+fn write_many_plugins(dir: &Path) {
+    let mut plugins = HashMap::new();
+    for i in 0..60 {
+        let id = format!("plugin-{}", i);
+        let install_path = dir.join(&id);
+        let scripts: Vec<ScriptEntry> = (0..3)
+            .map(|j| ScriptEntry {
+                name: format!("s{}", j),
+                file: format!("s{}.sh", j),
+                description: String::new(),
+                executable: true,
+                interpreter: None,
+                working_dir: None,
+                keep_artifacts: false,
+            })
+            .collect();
+        for script in &scripts[..2] {
+            fileio::write(install_path.join("scripts").join(&script.file), "echo\n").unwrap();
+        }
+        let info = PluginInfo {
+            id: id.clone(),
+            name: id.clone(),
+            version: "1.0.0".to_string(),
+            description: String::new(),
+            author: "bench".to_string(),
+            scripts,
+            dependencies: Vec::new(),
+            tags: Vec::new(),
+            min_geektools_version: None,
+        };
+        plugins.insert(id, InstalledPlugin {
+            info,
+            install_path,
+            installed_at: String::new(),
+            enabled: true,
+            previous_version: None,
+            dev: false,
+        });
+    }
+    fileio::write(dir.join("registry.json"), &serde_json::to_string(&plugins).unwrap()).unwrap();
+}
+
+fn plugin_registry_load(c: &mut Criterion) {
+    let dir = TempDir::new().unwrap();
+    write_many_plugins(dir.path());
+    c.bench_function("plugins::open/60", |b| b.iter(|| PluginManager::open(dir.path().to_path_buf())));
+
+    let manager = PluginManager::open(dir.path().to_path_buf());
+    c.bench_function("plugins::get_enabled_scripts/60", |b| b.iter(|| manager.get_enabled_scripts()));
+}
+
+criterion_group!(benches, dependency_resolution, translation_lookup, cached_read, plugin_registry_load);
+criterion_main!(benches);
@@ -0,0 +1,50 @@
+//! 合并内置脚本的分语言描述。
+//!
+//! `src/scripts/i18n/<语言>.json` 每个文件是 `{"脚本名": "描述"}`，`en.json` 列出全部内置脚本，
+//! 其他语言可以只翻译一部分。构建时合并为 `$OUT_DIR/script_info.json`：
+//! `{"脚本名": {"en": "...", "zh": "..."}}`，缺少的翻译在运行时回退到英文。
+
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+const CATALOG_DIR: &str = "src/scripts/i18n";
+const BASE_LANGUAGE: &str = "en";
+
+fn main() {
+    println!("cargo:rerun-if-changed={}", CATALOG_DIR);
+
+    let mut catalogs: BTreeMap<String, Map<String, Value>> = BTreeMap::new();
+    for entry in fs::read_dir(CATALOG_DIR).expect("read src/scripts/i18n") {
+        let path = entry.expect("read src/scripts/i18n").path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        println!("cargo:rerun-if-changed={}", path.display());
+        let code = path.file_stem().unwrap().to_string_lossy().into_owned();
+        let content = fs::read_to_string(&path).unwrap_or_else(|e| panic!("read {}: {}", path.display(), e));
+        let catalog: Map<String, Value> =
+            serde_json::from_str(&content).unwrap_or_else(|e| panic!("{} is not a JSON object: {}", path.display(), e));
+        catalogs.insert(code, catalog);
+    }
+
+    let base = catalogs
+        .get(BASE_LANGUAGE)
+        .unwrap_or_else(|| panic!("{}/{}.json is required", CATALOG_DIR, BASE_LANGUAGE));
+    let mut merged: Map<String, Value> = base.keys().map(|name| (name.clone(), Value::Object(Map::new()))).collect();
+    for (code, catalog) in &catalogs {
+        for (name, description) in catalog {
+            let Some(Value::Object(entry)) = merged.get_mut(name) else {
+                panic!("{}/{}.json: '{}' is not listed in {}.json", CATALOG_DIR, code, name, BASE_LANGUAGE);
+            };
+            let Value::String(description) = description else {
+                panic!("{}/{}.json: the description of '{}' must be a string", CATALOG_DIR, code, name);
+            };
+            entry.insert(code.clone(), Value::String(description.clone()));
+        }
+    }
+
+    let out = Path::new(&std::env::var("OUT_DIR").unwrap()).join("script_info.json");
+    fs::write(out, serde_json::to_string(&merged).unwrap()).expect("write script_info.json");
+}
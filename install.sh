@@ -54,7 +54,7 @@ case "$OS" in
     case "$ARCH" in
       x86_64|amd64)   FILE="geektools-linux-x64" ;;
       aarch64|arm64)  FILE="geektools-linux-arm64" ;;
-      armv7l|armv6l)  FILE="geektools-linux-armhf" ;;  # 若无此产物会在下载时报错
+      armv7l)         FILE="geektools-linux-armv7" ;;
       *) echo "❌ 不支持的 Linux 架构: $ARCH"; exit 1 ;;
     esac
     ;;
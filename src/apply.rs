@@ -0,0 +1,249 @@
+use crate::batch;
+use crate::config::{Config, CustomScript};
+use crate::errors::{GeekToolsError, Result};
+use crate::yaml;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+/// 期望的机器状态 (`geektools apply state.yaml`)
+///
+/// ```yaml
+/// config:
+///   ui.show_timestamps: true
+/// plugins:
+///   - id: hello-tools
+///     version: 1.0.0
+///     source: https://example.com/hello-tools-1.0.0.tar.gz   # 省略时从插件市场下载
+///     sha256: <摘要>                                           # 可选
+/// custom_scripts:
+///   - name: backup
+///     file: /opt/scripts/backup.sh     # 或 url: https://...
+///     description: 每日备份
+/// ```
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct State {
+    /// 配置键 (点分隔，同 `batch` 的 `set`) → 值
+    #[serde(default)]
+    pub config: BTreeMap<String, Value>,
+    #[serde(default)]
+    pub plugins: Vec<PluginState>,
+    #[serde(default)]
+    pub custom_scripts: Vec<ScriptState>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PluginState {
+    pub id: String,
+    pub version: String,
+    /// 插件包的路径或 URL
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub sha256: Option<String>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ScriptState {
+    pub name: String,
+    #[serde(default)]
+    pub file: Option<String>,
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub interpreter: Option<String>,
+    #[serde(default)]
+    pub working_dir: Option<String>,
+    #[serde(default)]
+    pub keep_artifacts: bool,
+}
+
+/// 解析状态文件 (YAML 子集，见 [`yaml::parse`]；JSON 也是合法输入)
+pub fn parse(content: &str) -> Result<State> {
+    let invalid = |message: String| GeekToolsError::ValidationError { field: "state".to_string(), message };
+    let value = match yaml::parse(content).map_err(invalid)? {
+        Value::Null => return Ok(State::default()),
+        value => value,
+    };
+    let state: State = serde_json::from_value(value).map_err(|e| invalid(e.to_string()))?;
+    for script in &state.custom_scripts {
+        if script.file.is_some() == script.url.is_some() {
+            return Err(invalid(format!("custom script {} needs exactly one of `file` or `url`", script.name)));
+        }
+    }
+    Ok(state)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Change {
+    Unchanged,
+    Created,
+    Updated,
+}
+
+/// 一项资源的收敛结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    /// `config`、`plugin` 或 `script`
+    pub kind: &'static str,
+    pub name: String,
+    pub change: Change,
+    pub detail: String,
+}
+
+impl Outcome {
+    pub fn new(kind: &'static str, name: &str, change: Change, detail: impl Into<String>) -> Self {
+        Self { kind, name: name.to_string(), change, detail: detail.into() }
+    }
+}
+
+/// 已安装版本与期望版本的比较
+#[cfg(feature = "plugins")]
+pub fn plugin_change(installed: Option<&str>, desired: &str) -> Change {
+    match installed {
+        Some(version) if version == desired => Change::Unchanged,
+        Some(_) => Change::Updated,
+        None => Change::Created,
+    }
+}
+
+/// 已登记的自定义脚本与期望是否一致 (只比较状态文件能声明的字段)
+pub fn script_change(existing: Option<&CustomScript>, desired: &CustomScript) -> Change {
+    match existing {
+        None => Change::Created,
+        Some(existing)
+            if existing.file_path == desired.file_path
+                && existing.url == desired.url
+                && existing.description == desired.description
+                && existing.interpreter == desired.interpreter
+                && existing.working_dir == desired.working_dir
+                && existing.keep_artifacts == desired.keep_artifacts
+                && existing.enabled =>
+        {
+            Change::Unchanged
+        }
+        Some(_) => Change::Updated,
+    }
+}
+
+/// 把所有配置键应用到 `config`，返回新配置和每个键的结果；已经是期望值的键不算改动
+pub fn apply_config(config: &Config, entries: &BTreeMap<String, Value>) -> Result<(Config, Vec<Outcome>)> {
+    let mut config = config.clone();
+    let mut outcomes = Vec::new();
+    for (key, value) in entries {
+        let text = match value {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        let pointer: String = key.split('.').map(|part| format!("/{}", part)).collect();
+        let before = serde_json::to_value(&config)?.pointer(&pointer).cloned();
+        let updated = batch::set_config_value(&config, key, &text)?;
+        let after = serde_json::to_value(&updated)?.pointer(&pointer).cloned();
+        let show = |v: &Option<Value>| v.as_ref().map(Value::to_string).unwrap_or_else(|| "-".to_string());
+        outcomes.push(if before == after {
+            Outcome::new("config", key, Change::Unchanged, show(&after))
+        } else {
+            Outcome::new("config", key, Change::Updated, format!("{} -> {}", show(&before), show(&after)))
+        });
+        config = updated;
+    }
+    Ok((config, outcomes))
+}
+
+/// 变更摘要：每项一行 (`+` 新建、`~` 修改、`=` 未变)，最后一行为统计
+pub fn render_summary(outcomes: &[Outcome]) -> String {
+    let mut out = String::new();
+    for outcome in outcomes {
+        let mark = match outcome.change {
+            Change::Created => '+',
+            Change::Updated => '~',
+            Change::Unchanged => '=',
+        };
+        let _ = writeln!(out, "{} {:<6} {} {}", mark, outcome.kind, outcome.name, outcome.detail);
+    }
+    let changed = outcomes.iter().filter(|o| o.change != Change::Unchanged).count();
+    let _ = writeln!(out, "{} changed, {} unchanged", changed, outcomes.len() - changed);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_state() {
+        let state = parse(
+            "\
+config:
+  ui.show_timestamps: true
+plugins:
+  - id: hello-tools
+    version: 1.0.0
+custom_scripts:
+  - name: backup
+    url: https://example.com/backup.sh
+",
+        )
+        .unwrap();
+        assert_eq!(state.config["ui.show_timestamps"], Value::Bool(true));
+        assert_eq!(state.plugins[0].version, "1.0.0");
+        assert_eq!(state.plugins[0].source, None);
+        assert_eq!(state.custom_scripts[0].url.as_deref(), Some("https://example.com/backup.sh"));
+
+        assert_eq!(parse("# nothing yet\n").unwrap(), State::default());
+        assert!(parse("plugins:\n  - id: x\n    versoin: 1\n").is_err());
+        assert!(parse("custom_scripts:\n  - name: a\n").is_err());
+        assert!(parse("{\"plugins\": []}").is_ok());
+    }
+
+    #[test]
+    fn test_apply_config_is_idempotent() {
+        let mut entries = BTreeMap::new();
+        entries.insert("ui.show_timestamps".to_string(), Value::Bool(false));
+        entries.insert("language".to_string(), Value::String("zh".to_string()));
+
+        let (config, outcomes) = apply_config(&Config::default(), &entries).unwrap();
+        assert!(!config.ui.show_timestamps);
+        assert_eq!(outcomes[1].detail, "true -> false");
+        assert!(outcomes.iter().all(|o| o.change == Change::Updated));
+
+        let (_, outcomes) = apply_config(&config, &entries).unwrap();
+        assert!(outcomes.iter().all(|o| o.change == Change::Unchanged));
+        assert!(render_summary(&outcomes).ends_with("0 changed, 2 unchanged\n"));
+    }
+
+    #[test]
+    fn test_plugin_and_script_changes() {
+        #[cfg(feature = "plugins")]
+        {
+            assert_eq!(plugin_change(None, "1.0.0"), Change::Created);
+            assert_eq!(plugin_change(Some("1.0.0"), "1.0.0"), Change::Unchanged);
+            assert_eq!(plugin_change(Some("0.9.0"), "1.0.0"), Change::Updated);
+        }
+
+        let desired = CustomScript {
+            name: "backup".to_string(),
+            description: Some("daily".to_string()),
+            descriptions: BTreeMap::new(),
+            url: None,
+            file_path: Some("/opt/backup.sh".to_string()),
+            enabled: true,
+            last_updated: None,
+            interpreter: None,
+            working_dir: None,
+            keep_artifacts: false,
+        };
+        let mut existing = desired.clone();
+        existing.last_updated = Some("2024-01-01 00:00:00".to_string());
+        assert_eq!(script_change(Some(&existing), &desired), Change::Unchanged);
+        existing.description = None;
+        assert_eq!(script_change(Some(&existing), &desired), Change::Updated);
+        assert_eq!(script_change(None, &desired), Change::Created);
+    }
+}
@@ -0,0 +1,305 @@
+use crate::config::{Config, ConfigValidator};
+use crate::errors::{GeekToolsError, Result};
+use serde_json::{Map, Value};
+
+/// 命令文件中的一个动作
+///
+/// ```text
+/// # 注释和空行会被忽略
+/// set ui.show_timestamps true
+/// install-plugin https://example.com/tools.tar.gz sha256=<摘要>
+/// install-package curl
+/// run cleanup.sh DAYS=3 TARGET="/var/tmp"
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    /// `run <脚本> [NAME=VALUE ...]`：脚本可以是路径、自定义脚本名、插件脚本名或内置脚本名
+    Run { script: String, params: Vec<(String, String)> },
+    /// `install-plugin <路径或URL> [sha256=<摘要>]`
+    InstallPlugin { source: String, sha256: Option<String> },
+    /// `install-package <包名>`：用系统包管理器安装，已安装时跳过
+    InstallPackage { name: String },
+    /// `set <键> <值>`：键以点分隔 (如 `ui.show_timestamps`)，值按 JSON 解析，失败时视为字符串
+    Set { key: String, value: String },
+}
+
+impl Action {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Run { .. } => "run",
+            Self::InstallPlugin { .. } => "install-plugin",
+            Self::InstallPackage { .. } => "install-package",
+            Self::Set { .. } => "set",
+        }
+    }
+
+    /// 动作作用的对象：脚本、插件来源、包名或配置键
+    pub fn target(&self) -> &str {
+        match self {
+            Self::Run { script, .. } => script,
+            Self::InstallPlugin { source, .. } => source,
+            Self::InstallPackage { name } => name,
+            Self::Set { key, .. } => key,
+        }
+    }
+}
+
+/// 带行号的动作
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    pub line: usize,
+    pub action: Action,
+}
+
+/// 解析整个命令文件；出错时返回出错的行号，不执行任何动作
+pub fn parse(content: &str) -> std::result::Result<Vec<Step>, (usize, GeekToolsError)> {
+    let mut steps = Vec::new();
+    for (index, raw) in content.lines().enumerate() {
+        let line = index + 1;
+        let text = raw.trim();
+        if text.is_empty() || text.starts_with('#') {
+            continue;
+        }
+        let action = parse_action(text).map_err(|message| {
+            (line, GeekToolsError::ValidationError { field: format!("line {}", line), message })
+        })?;
+        steps.push(Step { line, action });
+    }
+    Ok(steps)
+}
+
+fn parse_action(text: &str) -> std::result::Result<Action, String> {
+    let (verb, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+    let rest = rest.trim();
+    match verb {
+        "set" => {
+            let (key, value) = rest.split_once(char::is_whitespace).ok_or("usage: set <KEY> <VALUE>")?;
+            Ok(Action::Set { key: key.to_string(), value: value.trim().to_string() })
+        }
+        "run" => {
+            let mut tokens = tokenize(rest)?.into_iter();
+            let script = tokens.next().ok_or("usage: run <SCRIPT> [NAME=VALUE ...]")?;
+            let params = tokens
+                .map(|token| match token.split_once('=') {
+                    Some((name, value)) if !name.is_empty() => Ok((name.to_string(), value.to_string())),
+                    _ => Err(format!("expected NAME=VALUE, got: {}", token)),
+                })
+                .collect::<std::result::Result<_, _>>()?;
+            Ok(Action::Run { script, params })
+        }
+        "install-plugin" => {
+            let mut tokens = tokenize(rest)?.into_iter();
+            let source = tokens.next().ok_or("usage: install-plugin <PATH|URL> [sha256=<HEX>]")?;
+            let mut sha256 = None;
+            for token in tokens {
+                match token.strip_prefix("sha256=") {
+                    Some(hex) => sha256 = Some(hex.to_string()),
+                    None => return Err(format!("unexpected argument: {}", token)),
+                }
+            }
+            Ok(Action::InstallPlugin { source, sha256 })
+        }
+        "install-package" => match tokenize(rest)?.as_slice() {
+            [name] => Ok(Action::InstallPackage { name: name.clone() }),
+            _ => Err("usage: install-package <NAME>".to_string()),
+        },
+        other => Err(format!("unknown action: {}", other)),
+    }
+}
+
+/// 按空白切分，双引号内的空白保留，引号内可用 `\"` 和 `\\`
+fn tokenize(text: &str) -> std::result::Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_token = false;
+    let mut in_quotes = false;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                in_token = true;
+            }
+            '\\' if in_quotes => match chars.next() {
+                Some(escaped) => current.push(escaped),
+                None => break,
+            },
+            c if c.is_whitespace() && !in_quotes => {
+                if in_token {
+                    tokens.push(std::mem::take(&mut current));
+                    in_token = false;
+                }
+            }
+            c => {
+                current.push(c);
+                in_token = true;
+            }
+        }
+    }
+    if in_quotes {
+        return Err("unterminated quote".to_string());
+    }
+    if in_token {
+        tokens.push(current);
+    }
+    Ok(tokens)
+}
+
+/// 修改一项配置并校验，返回新的配置；拼错的键会被拒绝而不是静默忽略
+pub fn set_config_value(config: &Config, key: &str, value: &str) -> Result<Config> {
+    let invalid = |message: String| GeekToolsError::ValidationError { field: key.to_string(), message };
+    let path: Vec<&str> = key.split('.').collect();
+    if path.iter().any(|part| part.is_empty()) {
+        return Err(invalid("invalid configuration key".to_string()));
+    }
+    let pointer: String = path.iter().map(|part| format!("/{}", part)).collect();
+    let (parent_pointer, field) = pointer.rsplit_once('/').unwrap_or_default();
+
+    // 先按 JSON 解析 (数字、布尔、数组等)，类型不符时再当作字符串
+    let mut candidates: Vec<Value> = serde_json::from_str(value).into_iter().collect();
+    if !candidates.iter().any(Value::is_string) {
+        candidates.push(Value::String(value.to_string()));
+    }
+
+    let root = serde_json::to_value(config)?;
+    let mut last_error = "unknown configuration key".to_string();
+    for candidate in candidates {
+        let mut root = root.clone();
+        let Some(parent) = root.pointer_mut(parent_pointer).and_then(Value::as_object_mut) else {
+            return Err(invalid("unknown configuration key".to_string()));
+        };
+        parent.insert(field.to_string(), candidate.clone());
+        let updated: Config = match serde_json::from_value(root) {
+            Ok(updated) => updated,
+            Err(e) => {
+                last_error = e.to_string();
+                continue;
+            }
+        };
+        // 未知的键在反序列化时被丢弃，回写后检查确实生效
+        if serde_json::to_value(&updated)?.pointer(&pointer) != Some(&candidate) {
+            return Err(invalid("unknown configuration key".to_string()));
+        }
+        ConfigValidator::validate_config(&updated)?;
+        return Ok(updated);
+    }
+    Err(invalid(last_error))
+}
+
+/// 一步的结果，输出为一行 JSON：
+/// `{"line":3,"action":"run","target":"a.sh","status":"ok"}`，
+/// 失败时 `status` 为 `error` 并带上 `code` 和 `message`
+pub fn event(line: usize, action: Option<&Action>, outcome: &Result<()>) -> String {
+    let mut event = Map::new();
+    event.insert("line".to_string(), line.into());
+    if let Some(action) = action {
+        event.insert("action".to_string(), action.name().into());
+        event.insert("target".to_string(), action.target().into());
+    }
+    match outcome {
+        Ok(()) => {
+            event.insert("status".to_string(), "ok".into());
+        }
+        Err(e) => {
+            event.insert("status".to_string(), "error".into());
+            event.insert("code".to_string(), e.code().into());
+            event.insert("message".to_string(), e.to_string().into());
+        }
+    }
+    Value::Object(event).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_actions() {
+        let content = "\
+# provision a new machine
+set ui.show_timestamps true
+
+install-plugin https://example.com/tools.tar.gz sha256=abc123
+install-package curl
+run cleanup.sh DAYS=3 TARGET=\"/var/my tmp\"
+run \"./local script.sh\"
+";
+        let steps = parse(content).unwrap();
+        assert_eq!(steps.len(), 5);
+        assert_eq!(steps[0].line, 2);
+        assert_eq!(
+            steps[0].action,
+            Action::Set { key: "ui.show_timestamps".to_string(), value: "true".to_string() }
+        );
+        assert_eq!(
+            steps[1].action,
+            Action::InstallPlugin {
+                source: "https://example.com/tools.tar.gz".to_string(),
+                sha256: Some("abc123".to_string())
+            }
+        );
+        assert_eq!(steps[2].action, Action::InstallPackage { name: "curl".to_string() });
+        assert_eq!(
+            steps[3].action,
+            Action::Run {
+                script: "cleanup.sh".to_string(),
+                params: vec![
+                    ("DAYS".to_string(), "3".to_string()),
+                    ("TARGET".to_string(), "/var/my tmp".to_string())
+                ]
+            }
+        );
+        assert_eq!(steps[4].action.target(), "./local script.sh");
+    }
+
+    #[test]
+    fn test_parse_reports_first_bad_line() {
+        let (line, err) = parse("run a.sh\nfrobnicate x\nset\n").unwrap_err();
+        assert_eq!(line, 2);
+        assert_eq!(err.code(), "validation");
+        assert!(err.to_string().contains("unknown action: frobnicate"));
+
+        assert_eq!(parse("set ui.theme\n").unwrap_err().0, 1);
+        assert_eq!(parse("run a.sh DAYS\n").unwrap_err().0, 1);
+        assert_eq!(parse("run \"a.sh\n").unwrap_err().0, 1);
+        assert_eq!(parse("install-plugin x.tar.gz md5=1\n").unwrap_err().0, 1);
+    }
+
+    #[test]
+    fn test_set_config_value() {
+        let config = Config::default();
+        let updated = set_config_value(&config, "ui.show_timestamps", "false").unwrap();
+        assert!(!updated.ui.show_timestamps);
+
+        let updated = set_config_value(&config, "language", "zh").unwrap();
+        assert_eq!(updated.language, "zh");
+
+        let updated = set_config_value(&config, "host_groups.web", "[\"a@web1\", \"a@web2\"]").unwrap();
+        assert_eq!(updated.host_groups["web"], vec!["a@web1", "a@web2"]);
+
+        let updated = set_config_value(&config, "github_token", "12345").unwrap();
+        assert_eq!(updated.github_token.as_deref(), Some("12345"));
+
+        assert!(set_config_value(&config, "ui.show_timestams", "true").is_err());
+        assert!(set_config_value(&config, "nope.deeper", "1").is_err());
+        assert!(set_config_value(&config, "ui..x", "1").is_err());
+        assert!(set_config_value(&config, "ui.show_timestamps", "sometimes").is_err());
+        assert_eq!(set_config_value(&config, "language", "fr").err().map(|e| e.code()), Some("validation"));
+    }
+
+    #[test]
+    fn test_event_json() {
+        let action = Action::Set { key: "language".to_string(), value: "zh".to_string() };
+        let ok: Value = serde_json::from_str(&event(4, Some(&action), &Ok(()))).unwrap();
+        assert_eq!(ok["line"], 4);
+        assert_eq!(ok["action"], "set");
+        assert_eq!(ok["target"], "language");
+        assert_eq!(ok["status"], "ok");
+
+        let failure = Err(GeekToolsError::ConfigError { message: "read-only".to_string() });
+        let err: Value = serde_json::from_str(&event(0, None, &failure)).unwrap();
+        assert_eq!(err["status"], "error");
+        assert_eq!(err["code"], "config");
+        assert!(err.get("action").is_none());
+    }
+}
@@ -0,0 +1,424 @@
+use crate::executor::Verbosity;
+use crate::logging::{self, LogLevel, LogLine, TimeRange};
+use chrono::NaiveDateTime;
+
+/// 命令行参数
+#[derive(Debug, Default, PartialEq)]
+pub struct CliArgs {
+    /// 本次会话中脚本运行的默认输出级别
+    pub verbosity: Verbosity,
+    pub show_help: bool,
+    /// `--on <组> <脚本>`：在主机组的每台主机上运行本地脚本后退出
+    pub fan_out: Option<(String, String)>,
+    /// `--profile-startup`：菜单出现前输出各启动阶段耗时
+    pub profile_startup: bool,
+    /// `batch <FILE>`：无交互地执行命令文件中的动作后退出
+    pub batch: Option<String>,
+    /// `apply <FILE>`：把机器收敛到状态文件声明的状态后退出
+    pub apply: Option<String>,
+    /// `logs [过滤条件]`：查询日志后退出
+    pub logs: Option<LogsArgs>,
+    /// `run`：预览并确认后运行脚本文件、标准输入或 URL 中的脚本
+    pub run: Option<RunSource>,
+    /// `plugin new|lint|publish`：插件开发辅助命令，完成后退出
+    pub plugin: Option<PluginCommand>,
+    /// `export-installer <脚本> [-o 文件]`：把脚本打包成不依赖 geektools 的自解压 .sh 后退出
+    pub export_installer: Option<ExportInstaller>,
+    /// `serve --socket <路径>`：在 unix socket 上提供 JSON-RPC 控制接口，直到被中断
+    pub serve: Option<String>,
+    /// `open <链接>`：确认后运行 `geektools://run/<脚本>` 链接指向的脚本
+    pub open: Option<String>,
+    /// `register-url-handler`：把本程序注册为 `geektools://` 链接的处理程序后退出
+    pub register_url_handler: bool,
+}
+
+/// `export-installer` 子命令的参数
+#[derive(Debug, PartialEq)]
+pub struct ExportInstaller {
+    /// 脚本文件、自定义脚本名、插件脚本名或内置脚本名
+    pub script: String,
+    /// 输出文件，默认 `./<脚本名>-installer.sh`
+    pub output: Option<String>,
+}
+
+/// `plugin` 子命令
+#[derive(Debug, PartialEq)]
+pub enum PluginCommand {
+    /// `new <NAME> [--lang]`：在当前目录生成插件骨架，`--lang` 同时生成 lang/en.sh 和 lang/zh.sh
+    New { name: String, lang: bool },
+    /// `lint [DIR]`：检查插件目录 (默认当前目录) 的 info.json
+    Lint { dir: String },
+    /// `publish <FILE> [--token TOKEN] [--org ORG]`：上传插件包到市场；
+    /// 未给出 `--token` 时依次使用环境变量 `GEEKTOOLS_TOKEN` 和已登录的令牌
+    Publish { file: String, token: Option<String>, org: Option<String> },
+}
+
+/// `run` 子命令的脚本来源
+#[derive(Debug, PartialEq)]
+pub enum RunSource {
+    /// 脚本文件，`-` 表示标准输入
+    File(String),
+    /// `--url <URL> --sha256 <HEX>`：下载内容的摘要必须匹配才会运行
+    Url { url: String, sha256: String },
+}
+
+/// `logs` 子命令的过滤条件，应用内的日志查看器使用同样的语法
+#[derive(Debug, Default, PartialEq)]
+pub struct LogsArgs {
+    pub range: TimeRange,
+    pub level: Option<LogLevel>,
+    pub category: Option<String>,
+    pub text: Option<String>,
+    /// 只看某个用户动作的条目 (关联 ID，可带 `#`)
+    pub action: Option<String>,
+}
+
+impl LogsArgs {
+    pub fn query(&self) -> Vec<LogLine> {
+        let mut lines = logging::query(&self.range, self.level, self.category.as_deref(), self.text.as_deref());
+        if let Some(action) = &self.action {
+            lines.retain(|line| line.action.as_ref() == Some(action));
+        }
+        lines
+    }
+}
+
+pub const USAGE: &str = "\
+Usage: geektools [OPTIONS]
+       geektools [OPTIONS] batch <FILE>
+       geektools apply <FILE>
+       geektools [OPTIONS] run <FILE|->
+       geektools [OPTIONS] run --url <URL> --sha256 <HEX>
+       geektools logs [--since TIME] [--until TIME] [--level LEVEL] [--category NAME]
+                     [--action ID] [TEXT]
+       geektools plugin new <NAME> [--lang]
+       geektools plugin lint [DIR]
+       geektools plugin publish <FILE> [--token TOKEN] [--org ORG]
+       geektools export-installer <SCRIPT> [-o FILE]
+       geektools [OPTIONS] serve --socket <PATH>
+       geektools [OPTIONS] open <geektools://run/SCRIPT[?NAME=VALUE&...]>
+       geektools register-url-handler
+
+Commands:
+  batch <FILE>     Run the actions in FILE (run / install-plugin / install-package /
+                   set) without prompts, printing one JSON line per step; stops
+                   at the first failure with exit code 1
+  apply <FILE>     Converge config keys, plugins (at given versions) and custom
+                   scripts to the state declared in the YAML FILE; items already
+                   in that state are left alone, then a change summary is printed
+  run <FILE|->     Show the script in FILE, or read from stdin with `-` (e.g.
+                   `curl -fsSL URL | geektools run -`), ask for confirmation on
+                   the terminal like URL scripts do, then run it with its shebang
+                   interpreter; the exit code is 1 if it fails or is cancelled.
+                   With --url the script is downloaded and refused (exit code 1)
+                   unless its SHA-256 equals --sha256, so documentation can pin
+                   the exact script it recommends
+  logs             Print matching entries from all logs, including rotated and
+                   compressed ones. TIME is a span back from now (30m, 1h, 2d, 1w)
+                   or a date (2024-05-01, 2024-05-01 08:00:00); --level shows that
+                   level and more severe ones; TEXT matches the message; --action
+                   keeps the entries of one user action (the #ID after the
+                   category). The exit code is 1 when nothing matches
+  plugin new <NAME>
+                   Create a plugin skeleton in ./<id> (id derived from NAME): an
+                   info.json, scripts/hello.sh with a metadata header and a
+                   pack.sh that builds the installable .tar.gz; --lang adds
+                   lang/en.sh and lang/zh.sh with the script's messages
+  plugin lint [DIR]
+                   Check the plugin in DIR (default .) against the info.json
+                   schema before packaging: unknown fields, bad semver, missing
+                   scripts and declared-executable scripts without the execute
+                   bit. The exit code is 1 if any error is found
+  plugin publish <FILE>
+                   Upload the packaged plugin FILE (.tar.gz) to the marketplace
+                   as a new release. Authenticates with --token, else the
+                   GEEKTOOLS_TOKEN environment variable, else the logged-in
+                   account; use an API key with the publish scope in CI.
+                   --org publishes a new plugin under that organization.
+                   The exit code is 1 if the upload is rejected
+  export-installer <SCRIPT>
+                   Bundle SCRIPT (a file, custom script, plugin script or
+                   built-in script), the scripts it imports and the gt-helpers
+                   library into one self-extracting POSIX sh file that runs
+                   without geektools; arguments given to the installer are
+                   passed to SCRIPT. Written to -o FILE, default
+                   ./<SCRIPT>-installer.sh. The exit code is 2 if SCRIPT is
+                   not found
+  serve --socket <PATH>
+                   Listen on the unix socket PATH (mode 0600) for JSON-RPC 2.0
+                   requests, one JSON object per line, so editors, launchers and
+                   GUIs can drive geektools: `list` returns the runnable scripts,
+                   `run` {script, params} and `install` {plugin, sha256} or
+                   {package} work like the batch actions of the same name.
+                   Failures are JSON-RPC errors with code -32000 and the error
+                   kind in data.code. Unix only
+  open <URL>       Handle a geektools://run/SCRIPT link: show the named built-in,
+                   custom or plugin script, ask for confirmation like `run`, then
+                   run it with the query parameters as its `# Param:` values.
+                   Links can only name scripts, never file paths. The exit code
+                   is 2 for a malformed link or unknown script, 1 if the script
+                   fails or is cancelled
+  register-url-handler
+                   Register this executable as the handler of geektools:// links
+                   so documentation can offer one-click actions: a desktop file
+                   plus `xdg-mime` on Linux, a small ~/Applications applet on
+                   macOS. Links open in a terminal window
+
+Options:
+  -q, --quiet      Only show the final status of script runs
+  -v, --verbose    Echo each command of shell scripts (sh -x)
+      --on <GROUP> <SCRIPT>
+                   Run SCRIPT on every host of GROUP (config `host_groups`) over SSH,
+                   then exit; the exit code is 1 if any host fails
+      --profile-startup
+                   Print per-phase startup timings to stderr before the menu
+  -h, --help       Print this help
+";
+
+/// 解析命令行参数 (不含程序名)
+pub fn parse<I, S>(args: I) -> Result<CliArgs, String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut cli = CliArgs::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_ref() {
+            "-q" | "--quiet" => cli.verbosity = Verbosity::Quiet,
+            "-v" | "--verbose" => cli.verbosity = Verbosity::Verbose,
+            "-h" | "--help" => cli.show_help = true,
+            "--profile-startup" => cli.profile_startup = true,
+            "--on" => match (args.next(), args.next()) {
+                (Some(group), Some(script)) => {
+                    cli.fan_out = Some((group.as_ref().to_string(), script.as_ref().to_string()))
+                }
+                _ => return Err("--on requires <GROUP> <SCRIPT>".to_string()),
+            },
+            "batch" => match args.next() {
+                Some(file) => cli.batch = Some(file.as_ref().to_string()),
+                None => return Err("batch requires <FILE>".to_string()),
+            },
+            "apply" => match args.next() {
+                Some(file) => cli.apply = Some(file.as_ref().to_string()),
+                None => return Err("apply requires <FILE>".to_string()),
+            },
+            "run" => cli.run = Some(parse_run(args.by_ref())?),
+            "plugin" => cli.plugin = Some(parse_plugin(args.by_ref())?),
+            "export-installer" => cli.export_installer = Some(parse_export_installer(args.by_ref())?),
+            "serve" => match (args.next(), args.next()) {
+                (Some(flag), Some(path)) if flag.as_ref() == "--socket" => cli.serve = Some(path.as_ref().to_string()),
+                _ => return Err("serve requires --socket <PATH>".to_string()),
+            },
+            "open" => match args.next() {
+                Some(link) => cli.open = Some(link.as_ref().to_string()),
+                None => return Err("open requires <URL>".to_string()),
+            },
+            "register-url-handler" => cli.register_url_handler = true,
+            "logs" => cli.logs = Some(parse_logs(args.by_ref(), chrono::Local::now().naive_local())?),
+            other => return Err(format!("unknown argument: {}", other)),
+        }
+    }
+    Ok(cli)
+}
+
+/// 解析 `run` 之后的参数：一个文件 (或 `-`)，或者 `--url` 和 `--sha256` 两者 (顺序不限)
+pub fn parse_run<I, S>(args: I) -> Result<RunSource, String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut args = args.into_iter();
+    let mut file = None;
+    let (mut url, mut sha256) = (None, None);
+    while let Some(arg) = args.next() {
+        match arg.as_ref() {
+            "--url" => url = Some(args.next().ok_or("--url requires a value")?.as_ref().to_string()),
+            "--sha256" => sha256 = Some(args.next().ok_or("--sha256 requires a value")?.as_ref().to_string()),
+            other if file.is_none() && !other.starts_with("--") => file = Some(other.to_string()),
+            other => return Err(format!("unknown argument: {}", other)),
+        }
+    }
+    match (file, url, sha256) {
+        (Some(file), None, None) => Ok(RunSource::File(file)),
+        (None, Some(url), Some(sha256)) => {
+            if sha256.len() != 64 || !sha256.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(format!("--sha256 must be 64 hex digits: {}", sha256));
+            }
+            Ok(RunSource::Url { url, sha256: sha256.to_ascii_lowercase() })
+        }
+        (None, Some(_), None) => Err("--url requires --sha256 <HEX>".to_string()),
+        _ => Err("run requires <FILE>, - for stdin, or --url <URL> --sha256 <HEX>".to_string()),
+    }
+}
+
+/// 解析 `plugin` 之后的参数：`new <NAME> [--lang]`、`lint [DIR]` 或 `publish <FILE> [--token TOKEN] [--org ORG]`
+pub fn parse_plugin<I, S>(args: I) -> Result<PluginCommand, String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut args = args.into_iter();
+    let command = args.next().map(|c| c.as_ref().to_string());
+    let (mut operand, mut lang) = (None, false);
+    let (mut token, mut org) = (None, None);
+    let publish = command.as_deref() == Some("publish");
+    while let Some(arg) = args.next() {
+        match arg.as_ref() {
+            "--lang" if command.as_deref() == Some("new") => lang = true,
+            "--token" if publish => token = Some(args.next().ok_or("--token requires a value")?.as_ref().to_string()),
+            "--org" if publish => org = Some(args.next().ok_or("--org requires a value")?.as_ref().to_string()),
+            other if operand.is_none() && !other.starts_with("--") => operand = Some(other.to_string()),
+            other => return Err(format!("unknown argument: {}", other)),
+        }
+    }
+    match command.as_deref() {
+        Some("new") => Ok(PluginCommand::New { name: operand.ok_or("plugin new requires <NAME>")?, lang }),
+        Some("lint") => Ok(PluginCommand::Lint { dir: operand.unwrap_or_else(|| ".".to_string()) }),
+        Some("publish") => Ok(PluginCommand::Publish { file: operand.ok_or("plugin publish requires <FILE>")?, token, org }),
+        _ => Err("plugin requires a command: new <NAME> [--lang], lint [DIR] or publish <FILE>".to_string()),
+    }
+}
+
+/// 解析 `export-installer` 之后的参数：一个脚本和可选的 `-o|--output FILE`
+pub fn parse_export_installer<I, S>(args: I) -> Result<ExportInstaller, String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut args = args.into_iter();
+    let (mut script, mut output) = (None, None);
+    while let Some(arg) = args.next() {
+        match arg.as_ref() {
+            "-o" | "--output" => output = Some(args.next().ok_or("--output requires a value")?.as_ref().to_string()),
+            other if script.is_none() && !other.starts_with('-') => script = Some(other.to_string()),
+            other => return Err(format!("unknown argument: {}", other)),
+        }
+    }
+    let script = script.ok_or("export-installer requires <SCRIPT>")?;
+    Ok(ExportInstaller { script, output })
+}
+
+/// 解析 `logs` 之后的全部参数；不以 `--` 开头的词合起来作为搜索文本
+pub fn parse_logs<I, S>(args: I, now: NaiveDateTime) -> Result<LogsArgs, String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut logs = LogsArgs::default();
+    let mut words = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let arg = arg.as_ref();
+        if !arg.starts_with("--") {
+            words.push(arg.to_string());
+            continue;
+        }
+        let Some(value) = args.next() else {
+            return Err(format!("{} requires a value", arg));
+        };
+        let value = value.as_ref();
+        let time = || logging::parse_time(value, now).ok_or_else(|| format!("invalid time for {}: {}", arg, value));
+        match arg {
+            "--since" => logs.range.since = Some(time()?),
+            "--until" => logs.range.until = Some(time()?),
+            "--level" => logs.level = Some(LogLevel::parse(value).ok_or_else(|| format!("unknown log level: {}", value))?),
+            "--category" => logs.category = Some(value.to_string()),
+            "--action" => logs.action = Some(value.trim_start_matches('#').to_ascii_lowercase()),
+            other => return Err(format!("unknown argument: {}", other)),
+        }
+    }
+    if !words.is_empty() {
+        logs.text = Some(words.join(" "));
+    }
+    Ok(logs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_verbosity_flags() {
+        assert_eq!(parse(Vec::<String>::new()).unwrap().verbosity, Verbosity::Normal);
+        assert_eq!(parse(["-q"]).unwrap().verbosity, Verbosity::Quiet);
+        assert_eq!(parse(["--quiet", "--verbose"]).unwrap().verbosity, Verbosity::Verbose);
+        assert!(parse(["--help"]).unwrap().show_help);
+        assert!(parse(["--profile-startup"]).unwrap().profile_startup);
+        assert!(parse(["--bogus"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_fan_out() {
+        let cli = parse(["-q", "--on", "web", "deploy.sh"]).unwrap();
+        assert_eq!(cli.fan_out, Some(("web".to_string(), "deploy.sh".to_string())));
+        assert_eq!(cli.verbosity, Verbosity::Quiet);
+        assert!(parse(["--on", "web"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_subcommands() {
+        let cli = parse(["-q", "batch", "provision.txt"]).unwrap();
+        assert_eq!(cli.batch.as_deref(), Some("provision.txt"));
+        assert_eq!(cli.verbosity, Verbosity::Quiet);
+        assert!(parse(["batch"]).is_err());
+        assert_eq!(parse(["apply", "state.yaml"]).unwrap().apply.as_deref(), Some("state.yaml"));
+        assert!(parse(["apply"]).is_err());
+        let cli = parse(["-v", "run", "-"]).unwrap();
+        assert_eq!(cli.run, Some(RunSource::File("-".to_string())));
+        assert_eq!(cli.verbosity, Verbosity::Verbose);
+        assert!(parse(["run"]).is_err());
+        assert_eq!(
+            parse(["export-installer", "deploy.sh", "-o", "out.sh"]).unwrap().export_installer,
+            Some(ExportInstaller { script: "deploy.sh".to_string(), output: Some("out.sh".to_string()) })
+        );
+        assert!(parse(["export-installer", "deploy.sh"]).unwrap().export_installer.unwrap().output.is_none());
+        assert_eq!(parse(["serve", "--socket", "/tmp/gt.sock"]).unwrap().serve.as_deref(), Some("/tmp/gt.sock"));
+        assert!(parse(["serve"]).is_err());
+        assert!(parse(["serve", "/tmp/gt.sock"]).is_err());
+        assert_eq!(parse(["open", "geektools://run/a.sh"]).unwrap().open.as_deref(), Some("geektools://run/a.sh"));
+        assert!(parse(["open"]).is_err());
+        assert!(parse(["register-url-handler"]).unwrap().register_url_handler);
+        assert!(parse(["export-installer"]).is_err());
+        assert!(parse(["export-installer", "a.sh", "-o"]).is_err());
+        let cli = parse(["plugin", "new", "Disk Tools", "--lang"]).unwrap();
+        assert_eq!(cli.plugin, Some(PluginCommand::New { name: "Disk Tools".to_string(), lang: true }));
+        assert!(parse(["plugin", "new"]).is_err());
+        assert_eq!(parse(["plugin", "lint"]).unwrap().plugin, Some(PluginCommand::Lint { dir: ".".to_string() }));
+        assert!(parse(["plugin", "lint", "--lang"]).is_err());
+        assert_eq!(
+            parse(["plugin", "publish", "x-1.0.0.tar.gz", "--token", "gtk_abc"]).unwrap().plugin,
+            Some(PluginCommand::Publish { file: "x-1.0.0.tar.gz".to_string(), token: Some("gtk_abc".to_string()), org: None })
+        );
+        assert!(parse(["plugin", "publish", "--token"]).is_err());
+        assert!(parse(["plugin", "lint", "--org", "acme"]).is_err());
+        assert!(parse(["plugin", "remove", "x"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_run_url() {
+        let hash = "AB".repeat(32);
+        assert_eq!(
+            parse_run(["--sha256", hash.as_str(), "--url", "https://example.com/i.sh"]).unwrap(),
+            RunSource::Url { url: "https://example.com/i.sh".to_string(), sha256: "ab".repeat(32) }
+        );
+        assert!(parse_run(["--url", "https://example.com/i.sh"]).is_err());
+        assert!(parse_run(["--url", "https://example.com/i.sh", "--sha256", "abc"]).is_err());
+        assert!(parse_run(["a.sh", "--url", "https://example.com/i.sh", "--sha256", hash.as_str()]).is_err());
+        assert!(parse_run(["a.sh", "b.sh"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_logs() {
+        let now = NaiveDateTime::parse_from_str("2024-05-01 12:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        let logs = parse_logs(["--since", "1h", "--level", "error", "dns", "failure"], now).unwrap();
+        assert_eq!(logs.range.since, logging::parse_time("2024-05-01 11:00:00", now));
+        assert_eq!(logs.level, Some(LogLevel::Error));
+        assert_eq!(logs.text.as_deref(), Some("dns failure"));
+        assert_eq!(parse_logs(Vec::<String>::new(), now).unwrap(), LogsArgs::default());
+        assert!(parse_logs(["--level", "loud"], now).is_err());
+        assert!(parse_logs(["--since"], now).is_err());
+        assert!(parse(["logs", "--category", "DOWNLOAD"]).unwrap().logs.is_some());
+        assert_eq!(parse_logs(["--action", "#0BADF00D"], now).unwrap().action.as_deref(), Some("0badf00d"));
+    }
+}
@@ -1,16 +1,60 @@
 use serde::{Deserialize, Deserializer, Serialize};
 use serde_json;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 use std::fs;
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex, RwLock};
 use chrono::Local;
+use once_cell::sync::Lazy;
+use serde_json::Value;
 use url::Url;
 use crate::errors::{GeekToolsError, Result};
 use crate::logging::LoggingConfig;
-use crate::plugins::MarketplaceConfig;
+use crate::container::ContainerConfig;
+use crate::notify::NotificationConfig;
+use crate::plugins::{ArchiveConfig, LocalScanConfig, MarketplaceConfig};
+use crate::scripts::catalog::CatalogConfig;
+use crate::stats::StatsConfig;
+use crate::metrics::MetricsConfig;
+use crate::fileio::CacheConfig;
+use crate::network::{NetworkConfig, TlsRoots};
+use crate::signature::SignatureConfig;
+use crate::ssh::SshConfig;
+use crate::updater::UpdateConfig;
+use crate::configdiff;
+use crate::log_only;
 
 pub const CURRENT_CONFIG_VERSION: u32 = 2;
 
+/// 值不能出现在日志和更新报告中的配置键
+const SECRET_KEYS: &[&str] = &["github_token", "marketplace_config.access_token"];
+
+/// 本次进程中加载配置时自动做的改动 (迁移、补全新键、合并新默认值)，等待交互界面展示
+static UPDATE_REPORT: Lazy<Mutex<Option<UpdateReport>>> = Lazy::new(|| Mutex::new(None));
+
+/// 加载时对用户配置文件的改动
+#[derive(Debug, Clone)]
+pub struct UpdateReport {
+    pub changes: Vec<configdiff::Change>,
+    pub conflicts: Vec<(String, Value)>,
+}
+
+impl UpdateReport {
+    /// 差异，加上以用户值为准的冲突键 (`! key = 新默认值`)
+    pub fn render(&self) -> String {
+        let mut out = configdiff::render(&self.changes);
+        for (key, default) in &self.conflicts {
+            out.push_str(&format!("! {} kept, new default would be {}\n", key, default));
+        }
+        out
+    }
+}
+
+/// 取走待展示的改动，每次改动只展示一次
+pub fn take_update_report() -> Option<UpdateReport> {
+    UPDATE_REPORT.lock().ok()?.take()
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct ConfigFile {
     pub version: u32,
@@ -42,6 +86,43 @@ pub struct Config {
     pub ui: UiConfig,
     #[serde(default)]
     pub marketplace_config: MarketplaceConfig,
+    #[serde(default)]
+    pub local_scan: LocalScanConfig,
+    /// 按脚本名记住的运行用户 (`sudo -u`)
+    #[serde(default)]
+    pub run_as_users: HashMap<String, String>,
+    #[serde(default)]
+    pub notifications: NotificationConfig,
+    /// 主机组：组名 → SSH 目标 (`user@host` 或 ~/.ssh/config 中的别名)
+    #[serde(default)]
+    pub host_groups: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    pub container: ContainerConfig,
+    /// 内置脚本目录的远程更新
+    #[serde(default)]
+    pub catalog: CatalogConfig,
+    #[serde(default)]
+    pub stats: StatsConfig,
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+    /// 文件读取缓存
+    #[serde(default)]
+    pub cache: CacheConfig,
+    /// 网络请求的客户端限速
+    #[serde(default)]
+    pub network: NetworkConfig,
+    /// 远程脚本的签名校验
+    #[serde(default)]
+    pub signatures: SignatureConfig,
+    /// SSH 主机的认证方式
+    #[serde(default)]
+    pub ssh: SshConfig,
+    /// 自更新
+    #[serde(default)]
+    pub update: UpdateConfig,
+    /// GitHub API 令牌，避免匿名请求每小时 60 次的限制 (环境变量 GITHUB_TOKEN 优先)
+    #[serde(default)]
+    pub github_token: Option<String>,
 }
 
 fn default_language() -> String {
@@ -76,10 +157,34 @@ where
 pub struct CustomScript {
     pub name: String,
     pub description: Option<String>,
+    /// 按语言代码 (`en`、`zh`) 的描述，来自脚本头部的 `# Description[zh]:`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub descriptions: BTreeMap<String, String>,
     pub url: Option<String>,
     pub file_path: Option<String>,
     pub enabled: bool,
     pub last_updated: Option<String>,
+    /// 指定解释器 (如 `bash`、`python3`)，优先于 shebang
+    #[serde(default)]
+    pub interpreter: Option<String>,
+    /// 工作目录，相对路径基于脚本所在目录；为空时沿用启动目录
+    #[serde(default)]
+    pub working_dir: Option<String>,
+    /// 运行结束后保留临时产物目录
+    #[serde(default)]
+    pub keep_artifacts: bool,
+}
+
+impl CustomScript {
+    /// 当前语言的描述 (`zh-TW` 没有时取 `zh`)，没有时退回 `description`
+    pub fn localized_description(&self, lang: &str) -> Option<&str> {
+        self.descriptions
+            .get(lang)
+            .or_else(|| self.descriptions.get(lang.split('-').next()?))
+            .or(self.description.as_ref())
+            .or_else(|| self.descriptions.values().next())
+            .map(String::as_str)
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -88,6 +193,9 @@ pub struct PluginConfig {
     pub auto_update: bool,
     pub allowed_plugins: Vec<String>,
     pub plugin_directory: Option<String>,
+    /// 本地缓存的插件包 (离线重装和回滚)
+    #[serde(default)]
+    pub archives: ArchiveConfig,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -97,6 +205,43 @@ pub struct SecurityConfig {
     pub allowed_domains: Vec<String>,
     pub block_all_network: bool,
     pub require_confirmation_for_custom_scripts: bool,
+    #[serde(default = "default_max_script_download_bytes")]
+    pub max_script_download_bytes: u64,
+    /// 拒绝明文 http 的脚本、市场和更新地址
+    #[serde(default = "default_require_https")]
+    pub require_https: bool,
+    /// 允许本机地址 (localhost、127.0.0.0/8、::1) 使用明文 http，用于本地开发的市场服务器
+    #[serde(default)]
+    pub allow_loopback_http: bool,
+    /// 额外信任的根证书 (PEM 文件路径)，用于企业代理或自建市场
+    #[serde(default)]
+    pub extra_ca_certs: Vec<String>,
+    /// TLS 根证书来源：内置 (默认)、系统 CA 证书包或两者
+    #[serde(default)]
+    pub tls_roots: TlsRoots,
+    /// 脚本进程的 nice 值 (-20..=19)
+    #[serde(default)]
+    pub nice_level: Option<i32>,
+    /// 脚本进程的 ionice 类别 (1=实时, 2=尽力, 3=空闲)，仅 Linux 生效
+    #[serde(default)]
+    pub ionice_class: Option<u8>,
+    /// 脚本进程可用的最大内存 (MB)
+    #[serde(default)]
+    pub max_memory_mb: Option<u64>,
+    /// 脚本进程可打开的最大文件数
+    #[serde(default)]
+    pub max_open_files: Option<u64>,
+    /// 允许为 `#@pip` / `#@npm` 声明的依赖创建隔离环境并安装，默认关闭
+    #[serde(default)]
+    pub allow_dependency_install: bool,
+}
+
+fn default_require_https() -> bool {
+    true
+}
+
+fn default_max_script_download_bytes() -> u64 {
+    crate::network::DEFAULT_MAX_SCRIPT_SIZE
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -105,6 +250,9 @@ pub struct UiConfig {
     pub show_timestamps: bool,
     pub max_output_lines: usize,
     pub auto_clear_output: bool,
+    /// 去掉输出中的 emoji 和制表符 (串口终端、读屏软件)
+    #[serde(default)]
+    pub plain_output: bool,
 }
 
 impl Default for Config {
@@ -117,6 +265,20 @@ impl Default for Config {
             security: SecurityConfig::default(),
             ui: UiConfig::default(),
             marketplace_config: MarketplaceConfig::default(),
+            local_scan: LocalScanConfig::default(),
+            run_as_users: HashMap::new(),
+            host_groups: HashMap::new(),
+            container: ContainerConfig::default(),
+            catalog: CatalogConfig::default(),
+            stats: StatsConfig::default(),
+            metrics: MetricsConfig::default(),
+            cache: CacheConfig::default(),
+            network: NetworkConfig::default(),
+            signatures: SignatureConfig::default(),
+            ssh: SshConfig::default(),
+            update: UpdateConfig::default(),
+            github_token: None,
+            notifications: NotificationConfig::default(),
         }
     }
 }
@@ -128,6 +290,7 @@ impl Default for PluginConfig {
             auto_update: false,
             allowed_plugins: Vec::new(),
             plugin_directory: None,
+            archives: ArchiveConfig::default(),
         }
     }
 }
@@ -140,6 +303,16 @@ impl Default for SecurityConfig {
             allowed_domains: Vec::new(),
             block_all_network: false,
             require_confirmation_for_custom_scripts: true,
+            max_script_download_bytes: default_max_script_download_bytes(),
+            require_https: true,
+            allow_loopback_http: false,
+            extra_ca_certs: Vec::new(),
+            tls_roots: TlsRoots::default(),
+            nice_level: None,
+            ionice_class: None,
+            max_memory_mb: None,
+            max_open_files: None,
+            allow_dependency_install: false,
         }
     }
 }
@@ -151,6 +324,7 @@ impl Default for UiConfig {
             show_timestamps: true,
             max_output_lines: 1000,
             auto_clear_output: false,
+            plain_output: false,
         }
     }
 }
@@ -209,15 +383,46 @@ impl ConfigValidator {
         Self::validate_custom_scripts(&config.custom_scripts)?;
         Self::validate_logging_config(&config.logging)?;
         Self::validate_security_config(&config.security)?;
+        Self::validate_run_as_users(&config.run_as_users)?;
+        Self::validate_host_groups(&config.host_groups)?;
+        if let Some(host) = config.ssh.hosts.keys().find(|h| !crate::remote::is_valid_host(h)) {
+            return Err(GeekToolsError::ValidationError {
+                field: "ssh.hosts".to_string(),
+                message: format!("Invalid host: {}", host),
+            });
+        }
+        Self::validate_container(&config.container)?;
+        Url::parse(&config.catalog.url).map_err(|_| GeekToolsError::ValidationError {
+            field: "catalog.url".to_string(),
+            message: format!("Invalid URL: {}", config.catalog.url),
+        })?;
+        Self::validate_notifications(&config.notifications)?;
+        let minutes = config.stats.minutes_saved_per_run;
+        if !minutes.is_finite() || minutes < 0.0 {
+            return Err(GeekToolsError::ValidationError {
+                field: "stats.minutes_saved_per_run".to_string(),
+                message: format!("Must be a non-negative number, got {}", minutes),
+            });
+        }
+        if config.metrics.enabled && config.metrics.port == 0 {
+            return Err(GeekToolsError::ValidationError {
+                field: "metrics.port".to_string(),
+                message: "Port must be between 1 and 65535".to_string(),
+            });
+        }
         Ok(())
     }
     
     fn validate_language(language: &str) -> Result<()> {
-        match language {
-            "en" | "English" | "zh" | "Chinese" => Ok(()),
-            _ => Err(GeekToolsError::ValidationError {
+        match crate::i18n::Language::from_code(language) {
+            Some(_) => Ok(()),
+            None => Err(GeekToolsError::ValidationError {
                 field: "language".to_string(),
-                message: format!("Unsupported language: {}. Supported: en, English, zh, Chinese", language),
+                message: format!(
+                    "Unsupported language: {}. Supported: {}",
+                    language,
+                    crate::i18n::Language::ALL.map(|lang| lang.code()).join(", ")
+                ),
             }),
         }
     }
@@ -269,6 +474,38 @@ impl ConfigValidator {
             });
         }
         
+        if security.max_script_download_bytes == 0 {
+            return Err(GeekToolsError::ValidationError {
+                field: "security.max_script_download_bytes".to_string(),
+                message: "Script download size limit must be greater than 0".to_string(),
+            });
+        }
+        
+        if let Some(nice) = security.nice_level
+            && !(-20..=19).contains(&nice)
+        {
+            return Err(GeekToolsError::ValidationError {
+                field: "security.nice_level".to_string(),
+                message: format!("Nice level must be between -20 and 19, got {}", nice),
+            });
+        }
+
+        if let Some(class) = security.ionice_class
+            && !(1..=3).contains(&class)
+        {
+            return Err(GeekToolsError::ValidationError {
+                field: "security.ionice_class".to_string(),
+                message: format!("ionice class must be 1, 2 or 3, got {}", class),
+            });
+        }
+
+        if security.max_memory_mb == Some(0) || security.max_open_files == Some(0) {
+            return Err(GeekToolsError::ValidationError {
+                field: "security".to_string(),
+                message: "Resource limits must be greater than 0".to_string(),
+            });
+        }
+
         if security.allowed_domains.is_empty() && security.block_all_network {
             return Err(GeekToolsError::ValidationError {
                 field: "security".to_string(),
@@ -278,6 +515,50 @@ impl ConfigValidator {
         
         Ok(())
     }
+
+    fn validate_notifications(notifications: &NotificationConfig) -> Result<()> {
+        if let Some(url) = &notifications.webhook_url {
+            Url::parse(url).map_err(|_| GeekToolsError::ValidationError {
+                field: "notifications.webhook_url".to_string(),
+                message: format!("Invalid URL: {}", url),
+            })?;
+        }
+        Ok(())
+    }
+
+    fn validate_run_as_users(users: &HashMap<String, String>) -> Result<()> {
+        for (script, user) in users {
+            if !crate::executor::is_valid_username(user) {
+                return Err(GeekToolsError::ValidationError {
+                    field: format!("run_as_users.{}", script),
+                    message: format!("Invalid user name: {}", user),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_host_groups(groups: &HashMap<String, Vec<String>>) -> Result<()> {
+        for (group, hosts) in groups {
+            if let Some(host) = hosts.iter().find(|h| !crate::remote::is_valid_host(h)) {
+                return Err(GeekToolsError::ValidationError {
+                    field: format!("host_groups.{}", group),
+                    message: format!("Invalid host: {}", host),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_container(container: &ContainerConfig) -> Result<()> {
+        if !crate::container::is_valid_image(&container.default_image) {
+            return Err(GeekToolsError::ValidationError {
+                field: "container.default_image".to_string(),
+                message: format!("Invalid image name: {}", container.default_image),
+            });
+        }
+        Ok(())
+    }
 }
 
 pub struct ConfigBackupManager {
@@ -303,10 +584,12 @@ impl ConfigBackupManager {
         let backup_filename = format!("config_backup_{}.json", timestamp);
         let backup_path = self.backup_dir.join(backup_filename);
         
-        fs::copy(config_path, &backup_path).map_err(|e| GeekToolsError::FileOperationError {
-            path: backup_path.display().to_string(),
+        // 配置中有市场和 GitHub 令牌，备份同样只允许本人读取
+        let content = fs::read_to_string(config_path).map_err(|e| GeekToolsError::FileOperationError {
+            path: config_path.display().to_string(),
             source: e,
         })?;
+        crate::fileio::write_private(&backup_path, &content)?;
         
         self.cleanup_old_backups()?;
         
@@ -341,11 +624,8 @@ impl ConfigBackupManager {
             self.create_backup(target_path)?;
         }
         
-        // 恢复配置
-        fs::copy(backup_path, target_path).map_err(|e| GeekToolsError::FileOperationError {
-            path: target_path.display().to_string(),
-            source: e,
-        })?;
+        // 恢复配置；写入已读取的内容，上面的备份在同一秒内可能覆盖了 backup_path
+        crate::fileio::write_private(target_path, &backup_content)?;
         
         Ok(())
     }
@@ -410,6 +690,21 @@ pub struct ConfigManager {
     current_config: Arc<RwLock<Config>>,
 }
 
+/// 用户配置文件 ~/.geektools/config.json
+pub fn user_config_path() -> PathBuf {
+    PathBuf::from(std::env::var("HOME").unwrap_or_else(|_| ".".to_string()))
+        .join(".geektools")
+        .join("config.json")
+}
+
+/// 读取用户配置，无法读取时使用默认配置
+pub fn load_user_config() -> Config {
+    match ConfigManager::new(user_config_path()) {
+        Ok(manager) => manager.get_config().read().unwrap().clone(),
+        Err(_) => Config::default(),
+    }
+}
+
 impl ConfigManager {
     pub fn new(config_path: PathBuf) -> Result<Self> {
         let backup_dir = config_path.parent()
@@ -418,7 +713,7 @@ impl ConfigManager {
         
         let backup_manager = ConfigBackupManager::new(backup_dir, 5);
         
-        let config = Self::load_or_create_config(&config_path)?;
+        let (config, _) = Self::load_or_create_config(&config_path)?;
         
         Ok(Self {
             config_path,
@@ -427,25 +722,30 @@ impl ConfigManager {
         })
     }
     
-    fn load_or_create_config(config_path: &Path) -> Result<Config> {
+    fn load_or_create_config(config_path: &Path) -> Result<(Config, Option<UpdateReport>)> {
         if config_path.exists() {
             Self::load_config(config_path)
         } else {
             let default_config = Self::create_default_config();
             Self::save_config_file(config_path, &default_config)?;
-            Ok(default_config.config)
+            Ok((default_config.config, None))
         }
     }
     
-    fn load_config(config_path: &Path) -> Result<Config> {
+    fn load_config(config_path: &Path) -> Result<(Config, Option<UpdateReport>)> {
         let content = fs::read_to_string(config_path).map_err(|e| GeekToolsError::FileOperationError {
             path: config_path.display().to_string(),
             source: e,
         })?;
         
         // Try to parse as new ConfigFile format first
+        let raw: Value = serde_json::from_str(&content).unwrap_or(Value::Null);
+        let mut before = raw.clone();
         let config_file = match serde_json::from_str::<ConfigFile>(&content) {
-            Ok(config_file) => config_file,
+            Ok(config_file) => {
+                before = raw.get("config").cloned().unwrap_or(Value::Null);
+                config_file
+            }
             Err(_) => {
                 // If that fails, try to parse as legacy Config format and wrap it
                 match serde_json::from_str::<Config>(&content) {
@@ -472,17 +772,76 @@ impl ConfigManager {
         };
         
         // 迁移配置版本
-        let migrated_config = ConfigMigrator::migrate(config_file.clone())?;
+        let mut migrated_config = ConfigMigrator::migrate(config_file.clone())?;
+        let conflicts = Self::merge_new_defaults(config_path, &mut migrated_config.config);
         
         // 验证配置
         ConfigValidator::validate_config(&migrated_config.config)?;
         
-        // 如果版本发生变化，保存迁移后的配置
-        if migrated_config.version != config_file.version {
+        // 迁移、补全的新键或合并的默认值改变了文件内容时，记录差异并保存
+        let after = serde_json::to_value(&migrated_config.config).unwrap_or(Value::Null);
+        let mut changes = configdiff::diff(&before, &after);
+        configdiff::redact(&mut changes, SECRET_KEYS);
+        let mut update_report = None;
+        if !changes.is_empty() || migrated_config.version != config_file.version {
+            let report = UpdateReport { changes, conflicts };
+            log_only!(
+                "INFO",
+                "CONFIG",
+                "{} 从版本 {} 更新到 {}:\n{}",
+                config_path.display(),
+                config_file.version,
+                migrated_config.version,
+                report.render()
+            );
+            if let Ok(mut pending) = UPDATE_REPORT.lock() {
+                *pending = Some(report.clone());
+            }
+            update_report = Some(report);
             Self::save_config_file(config_path, &migrated_config)?;
         }
         
-        Ok(migrated_config.config)
+        Ok((migrated_config.config, update_report))
+    }
+
+    /// 默认值快照：用户配置上次保存时的 `Config::default()`，作为三方合并的基准
+    fn defaults_path(config_path: &Path) -> PathBuf {
+        config_path.with_extension("defaults.json")
+    }
+
+    /// 程序升级改变了默认值时，三方合并到用户配置：用户没改过的键跟随新默认值。
+    /// 返回两边都改过的冲突键
+    fn merge_new_defaults(config_path: &Path, config: &mut Config) -> Vec<(String, Value)> {
+        let Ok(defaults) = serde_json::to_value(Config::default()) else {
+            return Vec::new();
+        };
+        let snapshot_path = Self::defaults_path(config_path);
+        let base: Option<Value> = fs::read_to_string(&snapshot_path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok());
+        let mut conflicts = Vec::new();
+        match &base {
+            Some(base) if *base == defaults => return conflicts,
+            Some(base) => {
+                if let Ok(user) = serde_json::to_value(&*config) {
+                    let merge = configdiff::merge3(base, &defaults, &user);
+                    match serde_json::from_value::<Config>(merge.merged) {
+                        Ok(merged) => {
+                            *config = merged;
+                            conflicts = merge.conflicts;
+                        }
+                        Err(e) => log_only!("WARN", "CONFIG", "合并新的默认值失败: {}", e),
+                    }
+                }
+            }
+            None => {}
+        }
+        if let Ok(content) = serde_json::to_string_pretty(&defaults)
+            && let Err(e) = crate::fileio::write(&snapshot_path, &content)
+        {
+            log_only!("WARN", "CONFIG", "保存默认值快照失败: {}", e);
+        }
+        conflicts
     }
     
     fn create_default_config() -> ConfigFile {
@@ -545,7 +904,8 @@ impl ConfigManager {
                 message: format!("Failed to serialize config: {}", e),
             })?;
         
-        crate::fileio::write(path, &content)
+        // 配置中有市场和 GitHub 令牌，只允许本人读取
+        crate::fileio::write_private(path, &content)
     }
 }
 
@@ -554,6 +914,21 @@ mod tests {
     use super::*;
     use tempfile::TempDir;
 
+    #[test]
+    fn test_custom_script_localized_description() {
+        let json = r#"{"name": "backup", "description": "Daily backup", "url": null, "file_path": "/opt/backup.sh", "enabled": true, "last_updated": null}"#;
+        let mut script: CustomScript = serde_json::from_str(json).unwrap();
+        assert!(script.descriptions.is_empty());
+        assert_eq!(script.localized_description("zh"), Some("Daily backup"));
+
+        script.descriptions.insert("zh".to_string(), "每日备份".to_string());
+        assert_eq!(script.localized_description("zh"), Some("每日备份"));
+        assert_eq!(script.localized_description("en"), Some("Daily backup"));
+        script.description = None;
+        assert_eq!(script.localized_description("en"), Some("每日备份"));
+        assert!(serde_json::to_string(&script).unwrap().contains(r#""descriptions":{"zh":"每日备份"}"#));
+    }
+
     #[test]
     fn test_config_validation() {
         let mut config = Config::default();
@@ -568,6 +943,21 @@ mod tests {
         }
     }
     
+    #[test]
+    fn test_resource_limit_validation() {
+        let mut config = Config::default();
+        config.security.nice_level = Some(25);
+
+        match ConfigValidator::validate_config(&config) {
+            Err(GeekToolsError::ValidationError { field, .. }) => assert_eq!(field, "security.nice_level"),
+            _ => panic!("expected nice_level validation error"),
+        }
+
+        config.security.nice_level = Some(10);
+        config.security.max_memory_mb = Some(512);
+        assert!(ConfigValidator::validate_config(&config).is_ok());
+    }
+
     #[test]
     fn test_config_backup_restore() {
         let temp_dir = TempDir::new().unwrap();
@@ -606,4 +996,54 @@ mod tests {
         let restored_config: ConfigFile = serde_json::from_str(&restored_content).unwrap();
         assert_eq!(restored_config.version, CURRENT_CONFIG_VERSION);
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_config_and_backups_are_private() {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = |p: &Path| std::fs::metadata(p).unwrap().permissions().mode() & 0o777;
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.json");
+        std::fs::write(&config_path, "{}").unwrap();
+        std::fs::set_permissions(&config_path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        let mut config_file = ConfigManager::create_default_config();
+        config_file.config.marketplace_config.access_token = Some("secret".to_string());
+        ConfigManager::save_config_file(&config_path, &config_file).unwrap();
+        assert_eq!(mode(&config_path), 0o600);
+
+        let backup_manager = ConfigBackupManager::new(temp_dir.path().join("backups"), 5);
+        let backup_path = backup_manager.create_backup(&config_path).unwrap();
+        assert_eq!(mode(&backup_path), 0o600);
+    }
+
+    #[test]
+    fn test_load_merges_changed_defaults() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.json");
+
+        // 上个版本的默认值：minutes_saved_per_run 为 3，plain_output 为 false
+        let mut old_defaults = serde_json::to_value(Config::default()).unwrap();
+        old_defaults["stats"]["minutes_saved_per_run"] = serde_json::json!(3.0);
+        std::fs::write(ConfigManager::defaults_path(&config_path), old_defaults.to_string()).unwrap();
+
+        // 用户没动 minutes_saved_per_run，改了 plain_output
+        let mut user = ConfigManager::create_default_config();
+        user.config.stats.minutes_saved_per_run = 3.0;
+        user.config.ui.plain_output = true;
+        std::fs::write(&config_path, serde_json::to_string(&user).unwrap()).unwrap();
+
+        // 直接取本次加载的报告，不经过全局的 UPDATE_REPORT (并行的测试也会写它)
+        let (config, report) = ConfigManager::load_config(&config_path).unwrap();
+        assert_eq!(config.stats.minutes_saved_per_run, 5.0);
+        assert!(config.ui.plain_output);
+
+        let report = report.unwrap();
+        assert!(report.render().contains("~ stats.minutes_saved_per_run: 3.0 -> 5.0\n"));
+        let saved: ConfigFile = serde_json::from_str(&std::fs::read_to_string(&config_path).unwrap()).unwrap();
+        assert_eq!(saved.config.stats.minutes_saved_per_run, 5.0);
+
+        // 快照已更新，再次加载不再有改动
+        assert!(ConfigManager::load_config(&config_path).unwrap().1.is_none());
+    }
 }
\ No newline at end of file
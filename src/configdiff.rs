@@ -0,0 +1,171 @@
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+
+/// 一个配置键的变化；键为点分路径，如 `ui.plain_output`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Added(String, Value),
+    Removed(String, Value),
+    Changed(String, Value, Value),
+}
+
+/// 比较两份配置，对象逐键递归，数组和标量整体比较
+pub fn diff(before: &Value, after: &Value) -> Vec<Change> {
+    let mut changes = Vec::new();
+    diff_at("", Some(before), Some(after), &mut changes);
+    changes
+}
+
+fn diff_at(path: &str, before: Option<&Value>, after: Option<&Value>, changes: &mut Vec<Change>) {
+    match (before, after) {
+        (Some(Value::Object(old)), Some(Value::Object(new))) => {
+            for key in keys(&[Some(old), Some(new)]) {
+                diff_at(&join(path, key), old.get(key), new.get(key), changes);
+            }
+        }
+        (Some(old), Some(new)) if old != new => changes.push(Change::Changed(path.to_string(), old.clone(), new.clone())),
+        (None, Some(new)) => changes.push(Change::Added(path.to_string(), new.clone())),
+        (Some(old), None) => changes.push(Change::Removed(path.to_string(), old.clone())),
+        _ => {}
+    }
+}
+
+/// `+ key = 值`、`- key = 值`、`~ key: 旧值 -> 新值`，每行一个
+pub fn render(changes: &[Change]) -> String {
+    let mut out = String::new();
+    for change in changes {
+        let _ = match change {
+            Change::Added(key, value) => writeln!(out, "+ {} = {}", key, value),
+            Change::Removed(key, value) => writeln!(out, "- {} = {}", key, value),
+            Change::Changed(key, old, new) => writeln!(out, "~ {}: {} -> {}", key, old, new),
+        };
+    }
+    out
+}
+
+/// 把 `secrets` 列出的键 (包括对象值中嵌套的这些键) 的非空值替换为 `"***"`，差异可以写进日志
+pub fn redact(changes: &mut [Change], secrets: &[&str]) {
+    for change in changes {
+        match change {
+            Change::Added(key, value) | Change::Removed(key, value) => redact_value(key, value, secrets),
+            Change::Changed(key, old, new) => {
+                redact_value(key, old, secrets);
+                redact_value(key, new, secrets);
+            }
+        }
+    }
+}
+
+fn redact_value(path: &str, value: &mut Value, secrets: &[&str]) {
+    if secrets.contains(&path) {
+        if !value.is_null() {
+            *value = Value::String("***".to_string());
+        }
+    } else if let Value::Object(map) = value {
+        for (key, nested) in map.iter_mut() {
+            redact_value(&join(path, key), nested, secrets);
+        }
+    }
+}
+
+/// 三方合并的结果
+#[derive(Debug, PartialEq)]
+pub struct Merge {
+    pub merged: Value,
+    /// 用户和默认值都改过且不一致的键，保留了用户的值；附带新的默认值
+    pub conflicts: Vec<(String, Value)>,
+}
+
+/// `base` 为用户文件写入时的默认值，`defaults` 为现在的默认值：
+/// 用户没改过的键跟随新默认值，默认值没变的键保留用户的值，两边都改了时以用户为准并记为冲突
+pub fn merge3(base: &Value, defaults: &Value, user: &Value) -> Merge {
+    let mut conflicts = Vec::new();
+    let merged = merge_at("", Some(base), Some(defaults), Some(user), &mut conflicts).unwrap_or(Value::Null);
+    Merge { merged, conflicts }
+}
+
+fn merge_at(
+    path: &str,
+    base: Option<&Value>,
+    defaults: Option<&Value>,
+    user: Option<&Value>,
+    conflicts: &mut Vec<(String, Value)>,
+) -> Option<Value> {
+    if let (Some(Value::Object(new)), Some(Value::Object(mine))) = (defaults, user) {
+        let old = base.and_then(Value::as_object);
+        let mut merged = Map::new();
+        for key in keys(&[old, Some(new), Some(mine)]) {
+            let value = merge_at(&join(path, key), old.and_then(|o| o.get(key)), new.get(key), mine.get(key), conflicts);
+            if let Some(value) = value {
+                merged.insert(key.clone(), value);
+            }
+        }
+        return Some(Value::Object(merged));
+    }
+    if user == base || user == defaults {
+        defaults.cloned()
+    } else if defaults == base {
+        user.cloned()
+    } else {
+        conflicts.push((path.to_string(), defaults.cloned().unwrap_or(Value::Null)));
+        user.cloned()
+    }
+}
+
+fn keys<'a>(maps: &[Option<&'a Map<String, Value>>]) -> BTreeSet<&'a String> {
+    maps.iter().flatten().flat_map(|map| map.keys()).collect()
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() { key.to_string() } else { format!("{}.{}", path, key) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_diff_reports_nested_keys() {
+        let before = json!({"language": "en", "ui": {"plain_output": false}, "old": 1});
+        let after = json!({"language": "en", "ui": {"plain_output": true, "theme": "dark"}, "stats": {"top": 10}});
+        let changes = diff(&before, &after);
+        assert_eq!(
+            render(&changes),
+            "- old = 1\n\
+             + stats = {\"top\":10}\n\
+             ~ ui.plain_output: false -> true\n\
+             + ui.theme = \"dark\"\n"
+        );
+        assert!(diff(&before, &before).is_empty());
+    }
+
+    #[test]
+    fn test_redact_hides_secret_values() {
+        let before = json!({"github_token": null, "market": {"url": "a"}});
+        let after = json!({"github_token": "ghp_x", "market": {"url": "b", "token": "t"}, "new": {"token": "t"}});
+        let mut changes = diff(&before, &after);
+        redact(&mut changes, &["github_token", "market.token", "new.token"]);
+        assert_eq!(
+            render(&changes),
+            "~ github_token: null -> \"***\"\n\
+             + market.token = \"***\"\n\
+             ~ market.url: \"a\" -> \"b\"\n\
+             + new = {\"token\":\"***\"}\n"
+        );
+    }
+
+    #[test]
+    fn test_merge3_follows_untouched_defaults() {
+        let base = json!({"timeout": 300, "level": "INFO", "top": 10, "retired": true, "groups": {}});
+        let defaults = json!({"timeout": 600, "level": "WARN", "top": 10, "added": "x", "groups": {}});
+        let user = json!({"timeout": 300, "level": "DEBUG", "top": 25, "retired": true, "groups": {"web": ["a"]}});
+        let merge = merge3(&base, &defaults, &user);
+        assert_eq!(
+            merge.merged,
+            json!({"timeout": 600, "level": "DEBUG", "top": 25, "added": "x", "groups": {"web": ["a"]}})
+        );
+        assert_eq!(merge.conflicts, vec![("level".to_string(), json!("WARN"))]);
+    }
+}
@@ -0,0 +1,175 @@
+use crate::executor::{self, RunOptions, Verbosity};
+use serde::{Deserialize, Serialize};
+use std::io::{self, IsTerminal};
+use std::path::PathBuf;
+use std::process::{Command, ExitStatus, Stdio};
+
+/// 容器内脚本的挂载目录
+const SCRIPTS_MOUNT: &str = "/geektools/scripts";
+/// 容器内工作目录
+const WORKDIR_MOUNT: &str = "/work";
+
+/// 在 Docker 容器中试运行脚本的默认设置
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ContainerConfig {
+    pub default_image: String,
+    /// 容器是否可以访问网络
+    #[serde(default = "default_network")]
+    pub network: bool,
+}
+
+fn default_network() -> bool {
+    true
+}
+
+impl Default for ContainerConfig {
+    fn default() -> Self {
+        Self {
+            default_image: "ubuntu:24.04".to_string(),
+            network: default_network(),
+        }
+    }
+}
+
+/// 单次容器运行的选项
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContainerOptions {
+    pub image: String,
+    /// 挂载到容器 `/work` 的目录
+    pub workdir: PathBuf,
+    pub network: bool,
+}
+
+/// 校验镜像名，避免被当成 docker 选项
+pub fn is_valid_image(image: &str) -> bool {
+    !image.is_empty()
+        && !image.starts_with('-')
+        && image
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-' | '/' | ':' | '@'))
+}
+
+/// docker 命令可用且守护进程在运行
+pub fn docker_available() -> bool {
+    Command::new("docker")
+        .args(["version", "--format", "{{.Server.Version}}"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+/// 构造 `docker run`：脚本只读挂载，工作目录挂载到 /work，依次运行 scripts
+pub fn build_command(scripts: &[PathBuf], run: &RunOptions, container: &ContainerOptions) -> Command {
+    let mut cmd = Command::new("docker");
+    cmd.args(["run", "--rm", "-i"]);
+    if io::stdin().is_terminal() && run.verbosity != Verbosity::Quiet {
+        cmd.arg("-t");
+    }
+    if !container.network {
+        cmd.args(["--network", "none"]);
+    }
+    cmd.arg("-v")
+        .arg(format!("{}:{}", container.workdir.display(), WORKDIR_MOUNT))
+        .args(["-w", WORKDIR_MOUNT]);
+
+    let mut invocations = Vec::new();
+    for (i, script) in scripts.iter().enumerate() {
+        let name = script.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        let target = format!("{}/{}_{}", SCRIPTS_MOUNT, i, name);
+        cmd.arg("-v").arg(format!("{}:{}:ro", script.display(), target));
+        let mut argv = executor::resolve_interpreter(script, run);
+        argv.push(target);
+        invocations.push(argv);
+    }
+    cmd.arg(&container.image);
+
+    match invocations.as_slice() {
+        [single] => {
+            cmd.args(single);
+        }
+        many => {
+            let script = many
+                .iter()
+                .map(|argv| argv.iter().map(|a| shell_quote(a)).collect::<Vec<_>>().join(" "))
+                .collect::<Vec<_>>()
+                .join(" && ");
+            cmd.args(["sh", "-c", &script]);
+        }
+    }
+    cmd
+}
+
+/// 在容器中运行脚本 (依赖脚本先运行)
+pub fn run(scripts: &[PathBuf], run: &RunOptions, container: &ContainerOptions) -> io::Result<ExitStatus> {
+    let mut cmd = build_command(scripts, run, container);
+    if run.verbosity == Verbosity::Quiet {
+        cmd.stdout(Stdio::null()).stderr(Stdio::null());
+    }
+    cmd.status()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsStr;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    fn options(workdir: &Path) -> ContainerOptions {
+        ContainerOptions {
+            image: "alpine:3".to_string(),
+            workdir: workdir.to_path_buf(),
+            network: false,
+        }
+    }
+
+    #[test]
+    fn test_build_command_single_script() {
+        let dir = TempDir::new().unwrap();
+        let script = dir.path().join("a.sh");
+        std::fs::write(&script, "echo hi\n").unwrap();
+
+        let cmd = build_command(std::slice::from_ref(&script), &RunOptions::default(), &options(dir.path()));
+        assert_eq!(cmd.get_program(), "docker");
+        let args: Vec<String> = cmd.get_args().map(|a| a.to_string_lossy().into_owned()).collect();
+        let args: Vec<&str> = args.iter().map(String::as_str).filter(|a| *a != "-t").collect();
+        let workdir = format!("{}:/work", dir.path().display());
+        let mount = format!("{}:/geektools/scripts/0_a.sh:ro", script.display());
+        assert_eq!(
+            args,
+            vec![
+                "run", "--rm", "-i", "--network", "none", "-v", &workdir, "-w", "/work", "-v", &mount, "alpine:3", "sh",
+                "/geektools/scripts/0_a.sh"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_build_command_chains_dependencies() {
+        let dir = TempDir::new().unwrap();
+        let common = dir.path().join("common.sh");
+        let main = dir.path().join("main.py");
+        std::fs::write(&common, "#!/bin/bash\nexport X=1\n").unwrap();
+        std::fs::write(&main, "print(1)\n").unwrap();
+
+        let cmd = build_command(&[common, main], &RunOptions::default(), &options(dir.path()));
+        let args: Vec<&OsStr> = cmd.get_args().collect();
+        assert_eq!(
+            args.last().unwrap(),
+            &OsStr::new("'/bin/bash' '/geektools/scripts/0_common.sh' && 'python3' '/geektools/scripts/1_main.py'")
+        );
+    }
+
+    #[test]
+    fn test_is_valid_image() {
+        assert!(is_valid_image("ubuntu:24.04"));
+        assert!(is_valid_image("ghcr.io/org/tool@sha256:abc"));
+        assert!(!is_valid_image("--privileged"));
+        assert!(!is_valid_image("ubuntu; rm -rf /"));
+    }
+}
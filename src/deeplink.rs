@@ -0,0 +1,191 @@
+use crate::log_only;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use url::Url;
+
+/// 链接的 URL scheme：`geektools://run/<脚本>`
+pub const SCHEME: &str = "geektools";
+/// Linux 上处理链接的桌面文件，由 xdg-mime 关联到 `x-scheme-handler/geektools`
+const DESKTOP_FILE: &str = "geektools-url.desktop";
+/// macOS 上接收链接的小程序，放在 ~/Applications
+const MACOS_APP: &str = "GeekTools Links.app";
+const MACOS_BUNDLE_ID: &str = "com.geektools.links";
+const LSREGISTER: &str =
+    "/System/Library/Frameworks/CoreServices.framework/Frameworks/LaunchServices.framework/Support/lsregister";
+
+/// `geektools://run/<脚本>?NAME=VALUE&…` 指向的脚本和参数
+#[derive(Debug, PartialEq)]
+pub struct DeepLink {
+    /// 内置、自定义或插件脚本名
+    pub script: String,
+    /// 查询参数，按脚本声明的 `# Param:` 传入
+    pub params: Vec<(String, String)>,
+}
+
+/// 解析链接。只接受脚本名而不接受文件路径，网页上的链接不能指向本机的任意文件
+pub fn parse(link: &str) -> Result<DeepLink, String> {
+    let url = Url::parse(link).map_err(|e| format!("invalid link {}: {}", link, e))?;
+    if url.scheme() != SCHEME {
+        return Err(format!("not a {}:// link: {}", SCHEME, link));
+    }
+    match url.host_str() {
+        Some("run") => {}
+        other => return Err(format!("unsupported link action: {}", other.unwrap_or(""))),
+    }
+    let script = urlencoding::decode(url.path().trim_start_matches('/'))
+        .map_err(|e| format!("invalid link {}: {}", link, e))?
+        .into_owned();
+    if script.is_empty() {
+        return Err(format!("link names no script: {}", link));
+    }
+    if script.contains(['/', '\\']) || script.starts_with('.') {
+        return Err(format!("link must name a script, not a path: {}", script));
+    }
+    let params = url.query_pairs().map(|(name, value)| (name.into_owned(), value.into_owned())).collect();
+    Ok(DeepLink { script, params })
+}
+
+/// 桌面文件 Exec 键的参数转义
+fn desktop_quote(arg: &str) -> String {
+    let escaped = arg.replace('%', "%%");
+    if escaped.chars().any(|c| c.is_whitespace() || "\"'\\`$<>~|&;*?#()".contains(c)) {
+        let escaped = escaped.replace('\\', "\\\\").replace('"', "\\\"").replace('`', "\\`").replace('$', "\\$");
+        format!("\"{}\"", escaped)
+    } else {
+        escaped
+    }
+}
+
+/// 生成 Linux 桌面文件：在终端中运行 `geektools open <链接>`，确认提示需要终端
+pub fn render_desktop_entry(exe: &Path) -> String {
+    format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name=GeekTools\n\
+         Comment=Run GeekTools scripts from {}:// links\n\
+         Exec={} open %u\n\
+         Terminal=true\n\
+         NoDisplay=true\n\
+         MimeType=x-scheme-handler/{};\n",
+        SCHEME,
+        desktop_quote(&exe.to_string_lossy()),
+        SCHEME
+    )
+}
+
+/// 生成 macOS 小程序的 AppleScript：链接以 Apple Event 送达，在终端中运行 `geektools open <链接>`
+pub fn render_applescript(exe: &Path) -> String {
+    let exe = exe.to_string_lossy().replace('\\', "\\\\").replace('"', "\\\"");
+    format!(
+        "on open location theURL\n\
+         \x20   tell application \"Terminal\"\n\
+         \x20       activate\n\
+         \x20       do script (quoted form of \"{}\") & \" open \" & (quoted form of theURL)\n\
+         \x20   end tell\n\
+         end open location\n",
+        exe
+    )
+}
+
+fn home() -> PathBuf {
+    PathBuf::from(env::var("HOME").unwrap_or_else(|_| ".".into()))
+}
+
+fn run_checked(program: &str, args: &[&str]) -> io::Result<()> {
+    log_only!("INFO", "DEEPLINK", "{} {}", program, args.join(" "));
+    let status = Command::new(program)
+        .args(args)
+        .status()
+        .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", program, e)))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!("{} {} failed: {}", program, args.join(" "), status)))
+    }
+}
+
+/// 把 `exe` 注册为 `geektools://` 链接的处理程序，返回写入的桌面文件或小程序路径
+pub fn register(exe: &Path) -> io::Result<PathBuf> {
+    match env::consts::OS {
+        "linux" => register_desktop_entry(exe),
+        "macos" => register_macos_app(exe),
+        os => Err(io::Error::new(io::ErrorKind::Unsupported, format!("link handlers are not supported on {}", os))),
+    }
+}
+
+fn register_desktop_entry(exe: &Path) -> io::Result<PathBuf> {
+    let data_home = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home().join(".local").join("share"));
+    let dir = data_home.join("applications");
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(DESKTOP_FILE);
+    fs::write(&path, render_desktop_entry(exe))?;
+    run_checked("xdg-mime", &["default", DESKTOP_FILE, &format!("x-scheme-handler/{}", SCHEME)])?;
+    // 只是刷新桌面环境的缓存，没有安装时 xdg-mime 的关联照样生效
+    if let Err(e) = run_checked("update-desktop-database", &[&dir.to_string_lossy()]) {
+        log_only!("WARN", "DEEPLINK", "update-desktop-database: {}", e);
+    }
+    Ok(path)
+}
+
+fn register_macos_app(exe: &Path) -> io::Result<PathBuf> {
+    let dir = home().join("Applications");
+    fs::create_dir_all(&dir)?;
+    let app = dir.join(MACOS_APP);
+    if app.exists() {
+        fs::remove_dir_all(&app)?;
+    }
+    let source = env::temp_dir().join(format!("geektools-links-{}.applescript", std::process::id()));
+    fs::write(&source, render_applescript(exe))?;
+    let compiled = run_checked("osacompile", &["-o", &app.to_string_lossy(), &source.to_string_lossy()]);
+    let _ = fs::remove_file(&source);
+    compiled?;
+
+    let plist = app.join("Contents").join("Info.plist");
+    let plist = plist.to_string_lossy();
+    let url_types = format!(r#"[{{"CFBundleURLName":"GeekTools","CFBundleURLSchemes":["{}"]}}]"#, SCHEME);
+    run_checked("plutil", &["-replace", "CFBundleIdentifier", "-string", MACOS_BUNDLE_ID, &plist])?;
+    run_checked("plutil", &["-replace", "CFBundleURLTypes", "-json", &url_types, &plist])?;
+    run_checked(LSREGISTER, &["-f", &app.to_string_lossy()])?;
+    Ok(app)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_links() {
+        let link = parse("geektools://run/cleanup.sh?DAYS=3&NAME=a%20b").unwrap();
+        assert_eq!(link.script, "cleanup.sh");
+        assert_eq!(link.params, vec![("DAYS".to_string(), "3".to_string()), ("NAME".to_string(), "a b".to_string())]);
+        // 插件脚本的显示名含空格
+        assert_eq!(parse("geektools://run/hello%20-%20My%20Tools").unwrap().script, "hello - My Tools");
+        assert!(parse("geektools://run/cleanup.sh").unwrap().params.is_empty());
+
+        assert!(parse("https://run/cleanup.sh").is_err());
+        assert!(parse("geektools://install/cleanup.sh").is_err());
+        assert!(parse("geektools://run/").is_err());
+        assert!(parse("geektools://run/tmp/evil.sh").is_err());
+        assert!(parse("geektools://run/%2Ftmp%2Fevil.sh").is_err());
+        assert!(parse("geektools://run/..").is_err());
+    }
+
+    #[test]
+    fn test_render_handlers() {
+        let entry = render_desktop_entry(Path::new("/opt/geek tools/geektools"));
+        assert!(entry.contains("Exec=\"/opt/geek tools/geektools\" open %u\n"));
+        assert!(entry.contains("MimeType=x-scheme-handler/geektools;\n"));
+        assert!(entry.contains("Terminal=true\n"));
+        assert_eq!(desktop_quote("/usr/bin/geektools"), "/usr/bin/geektools");
+        assert_eq!(desktop_quote("/a/100%/$x"), "\"/a/100%%/\\$x\"");
+
+        let script = render_applescript(Path::new("/Users/me/bin/geek\"tools"));
+        assert!(script.starts_with("on open location theURL\n"));
+        assert!(script.contains("do script (quoted form of \"/Users/me/bin/geek\\\"tools\") & \" open \" & (quoted form of theURL)"));
+    }
+}
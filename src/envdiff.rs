@@ -0,0 +1,230 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// 会被比较的 shell 配置文件 (相对 HOME)
+const DOTFILES: &[&str] = &[
+    ".profile",
+    ".bashrc",
+    ".bash_profile",
+    ".zshrc",
+    ".zprofile",
+    ".config/fish/config.fish",
+];
+
+/// 每次启动 shell 都会变化的变量，不参与比较
+const VOLATILE_VARS: &[&str] = &["_", "SHLVL", "PWD", "OLDPWD", "RANDOM", "SECONDS"];
+
+/// 某一时刻的登录 shell 环境和配置文件内容
+pub struct EnvSnapshot {
+    pub vars: BTreeMap<String, String>,
+    pub dotfiles: BTreeMap<PathBuf, String>,
+}
+
+/// 捕获新的登录 shell 的环境变量 (而不是本进程的，脚本改不了本进程)
+/// 以及常见配置文件的内容
+pub fn capture() -> EnvSnapshot {
+    let shell = env::var("SHELL").unwrap_or_else(|_| "sh".to_string());
+    let vars = Command::new(shell)
+        .args(["-l", "-c", "env -0"])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .map(|out| parse_env(&String::from_utf8_lossy(&out.stdout)))
+        .unwrap_or_default();
+
+    let home = PathBuf::from(env::var("HOME").unwrap_or_else(|_| ".".to_string()));
+    let dotfiles = DOTFILES
+        .iter()
+        .map(|name| home.join(name))
+        .filter_map(|path| fs::read_to_string(&path).ok().map(|content| (path, content)))
+        .collect();
+
+    EnvSnapshot { vars, dotfiles }
+}
+
+/// 解析 `env -0` 的输出
+pub fn parse_env(output: &str) -> BTreeMap<String, String> {
+    output
+        .split('\0')
+        .filter_map(|entry| entry.split_once('='))
+        .filter(|(key, _)| !key.is_empty() && !VOLATILE_VARS.contains(key))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+/// 配置文件的增删行
+#[derive(Debug, Default, PartialEq)]
+pub struct FileChange {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+/// 两次快照之间的差异
+#[derive(Debug, Default)]
+pub struct EnvDiff {
+    pub path_added: Vec<String>,
+    pub path_removed: Vec<String>,
+    pub vars_added: Vec<(String, String)>,
+    pub vars_removed: Vec<String>,
+    pub vars_changed: Vec<(String, String, String)>,
+    pub files: BTreeMap<PathBuf, FileChange>,
+}
+
+pub fn diff(before: &EnvSnapshot, after: &EnvSnapshot) -> EnvDiff {
+    let mut result = EnvDiff::default();
+
+    let split_path = |vars: &BTreeMap<String, String>| -> Vec<String> {
+        vars.get("PATH")
+            .map(|p| p.split(':').filter(|s| !s.is_empty()).map(str::to_string).collect())
+            .unwrap_or_default()
+    };
+    let change = line_diff(&split_path(&before.vars), &split_path(&after.vars));
+    result.path_added = change.added;
+    result.path_removed = change.removed;
+
+    for (key, value) in &after.vars {
+        match before.vars.get(key) {
+            _ if key == "PATH" => {}
+            None => result.vars_added.push((key.clone(), value.clone())),
+            Some(old) if old != value => result.vars_changed.push((key.clone(), old.clone(), value.clone())),
+            _ => {}
+        }
+    }
+    result.vars_removed = before
+        .vars
+        .keys()
+        .filter(|key| *key != "PATH" && !after.vars.contains_key(*key))
+        .cloned()
+        .collect();
+
+    let empty = String::new();
+    let paths: BTreeSet<&PathBuf> = before.dotfiles.keys().chain(after.dotfiles.keys()).collect();
+    for path in paths {
+        let old: Vec<String> = before.dotfiles.get(path).unwrap_or(&empty).lines().map(str::to_string).collect();
+        let new: Vec<String> = after.dotfiles.get(path).unwrap_or(&empty).lines().map(str::to_string).collect();
+        let change = line_diff(&old, &new);
+        if !change.added.is_empty() || !change.removed.is_empty() {
+            result.files.insert(path.to_path_buf(), change);
+        }
+    }
+
+    result
+}
+
+/// 按出现次数比较两组行，保持原有顺序
+pub fn line_diff(old: &[String], new: &[String]) -> FileChange {
+    let count = |lines: &[String]| {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for line in lines {
+            *counts.entry(line.clone()).or_default() += 1;
+        }
+        counts
+    };
+    let pick = |lines: &[String], mut other: HashMap<String, usize>| -> Vec<String> {
+        lines
+            .iter()
+            .filter(|line| match other.get_mut(*line) {
+                Some(n) if *n > 0 => {
+                    *n -= 1;
+                    false
+                }
+                _ => true,
+            })
+            .cloned()
+            .collect()
+    };
+    FileChange {
+        added: pick(new, count(old)),
+        removed: pick(old, count(new)),
+    }
+}
+
+impl EnvDiff {
+    pub fn is_empty(&self) -> bool {
+        self.path_added.is_empty()
+            && self.path_removed.is_empty()
+            && self.vars_added.is_empty()
+            && self.vars_removed.is_empty()
+            && self.vars_changed.is_empty()
+            && self.files.is_empty()
+    }
+
+    /// 生成可读的差异报告
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        for entry in &self.path_added {
+            let _ = writeln!(out, "  PATH + {}", entry);
+        }
+        for entry in &self.path_removed {
+            let _ = writeln!(out, "  PATH - {}", entry);
+        }
+        for (key, value) in &self.vars_added {
+            let _ = writeln!(out, "  + {}={}", key, value);
+        }
+        for key in &self.vars_removed {
+            let _ = writeln!(out, "  - {}", key);
+        }
+        for (key, old, new) in &self.vars_changed {
+            let _ = writeln!(out, "  ~ {}: {} → {}", key, old, new);
+        }
+        for (path, change) in &self.files {
+            let _ = writeln!(out, "  {}:", path.display());
+            for line in &change.added {
+                let _ = writeln!(out, "    + {}", line);
+            }
+            for line in &change.removed {
+                let _ = writeln!(out, "    - {}", line);
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_env_skips_volatile() {
+        let vars = parse_env("HOME=/home/pi\0SHLVL=2\0GREETING=a=b\nc\0");
+        assert_eq!(vars.get("HOME").map(String::as_str), Some("/home/pi"));
+        assert_eq!(vars.get("GREETING").map(String::as_str), Some("a=b\nc"));
+        assert!(!vars.contains_key("SHLVL"));
+    }
+
+    #[test]
+    fn test_line_diff_counts_duplicates() {
+        let change = line_diff(&lines(&["a", "b", "b"]), &lines(&["b", "c", "a", "c"]));
+        assert_eq!(change.added, lines(&["c", "c"]));
+        assert_eq!(change.removed, lines(&["b"]));
+    }
+
+    #[test]
+    fn test_diff_reports_path_and_rc_changes() {
+        let rc = PathBuf::from("/home/pi/.bashrc");
+        let before = EnvSnapshot {
+            vars: parse_env("PATH=/usr/bin:/bin\0EDITOR=vi\0"),
+            dotfiles: BTreeMap::from([(rc.clone(), "alias ll='ls -l'\n".to_string())]),
+        };
+        let after = EnvSnapshot {
+            vars: parse_env("PATH=/home/pi/.cargo/bin:/usr/bin:/bin\0EDITOR=nvim\0CARGO_HOME=/home/pi/.cargo\0"),
+            dotfiles: BTreeMap::from([(rc.clone(), "alias ll='ls -l'\n. \"$HOME/.cargo/env\"\n".to_string())]),
+        };
+
+        let d = diff(&before, &after);
+        assert_eq!(d.path_added, lines(&["/home/pi/.cargo/bin"]));
+        assert_eq!(d.vars_added, vec![("CARGO_HOME".to_string(), "/home/pi/.cargo".to_string())]);
+        assert_eq!(d.vars_changed.len(), 1);
+        assert_eq!(d.files[&rc].added, lines(&[". \"$HOME/.cargo/env\""]));
+        assert!(!d.is_empty());
+        assert!(diff(&after, &after).is_empty());
+    }
+}
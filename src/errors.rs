@@ -10,6 +10,7 @@ pub enum GeekToolsError {
         source: std::io::Error,
     },
     
+    #[cfg(feature = "network")]
     #[error("Network request failed: {url}")]
     NetworkError {
         url: String,
@@ -34,6 +35,30 @@ pub enum GeekToolsError {
         message: String,
     },
     
+    #[error("Invalid plugin package: {package} - {message}")]
+    PluginPackageError {
+        package: String,
+        message: String,
+    },
+    
+    #[error("Marketplace request failed: {endpoint} - {message}")]
+    MarketplaceError {
+        endpoint: String,
+        status: Option<u16>,
+        message: String,
+    },
+    
+    #[error("Insecure URL refused: {url}")]
+    InsecureTransportError { url: String },
+    
+    #[error("Rate limit exceeded: {service}, resets at {reset_at}")]
+    RateLimitError {
+        service: String,
+        reset_at: String,
+        /// 请求是否带了令牌 (未带令牌时建议配置令牌)
+        authenticated: bool,
+    },
+    
     #[error("Localization error: {key}")]
     LocalizationError { key: String },
     
@@ -56,6 +81,7 @@ impl GeekToolsError {
             Self::FileOperationError { path, .. } => {
                 t("error.file_operation", &[("path", path)], lang)
             }
+            #[cfg(feature = "network")]
             Self::NetworkError { url, .. } => {
                 t("error.network", &[("url", url)], lang)
             }
@@ -69,6 +95,18 @@ impl GeekToolsError {
             Self::PluginError { plugin_name, message } => {
                 t("error.plugin", &[("plugin", plugin_name), ("message", message)], lang)
             }
+            Self::PluginPackageError { package, message } => {
+                t("error.plugin_package", &[("package", package), ("message", message)], lang)
+            }
+            Self::MarketplaceError { endpoint, message, .. } => {
+                t("error.marketplace", &[("endpoint", endpoint), ("message", message)], lang)
+            }
+            Self::InsecureTransportError { url } => {
+                t("error.insecure_transport", &[("url", url)], lang)
+            }
+            Self::RateLimitError { service, reset_at, .. } => {
+                t("error.rate_limit", &[("service", service), ("reset", reset_at)], lang)
+            }
             Self::LocalizationError { key } => {
                 t("error.localization", &[("key", key)], lang)
             }
@@ -97,6 +135,7 @@ impl GeekToolsError {
                     _ => vec![t("recovery.retry_operation", &[], lang)],
                 }
             }
+            #[cfg(feature = "network")]
             Self::NetworkError { .. } => vec![
                 t("recovery.check_connection", &[], lang),
                 t("recovery.check_proxy", &[], lang),
@@ -114,6 +153,30 @@ impl GeekToolsError {
                 t("recovery.reinstall_plugin", &[], lang),
                 t("recovery.check_plugin_compatibility", &[], lang),
             ],
+            Self::PluginPackageError { .. } => vec![
+                t("recovery.check_plugin_package", &[], lang),
+                t("recovery.redownload_plugin", &[], lang),
+            ],
+            Self::MarketplaceError { status: Some(401), .. } => vec![
+                t("recovery.marketplace_login", &[], lang),
+            ],
+            Self::MarketplaceError { .. } => vec![
+                t("recovery.check_connection", &[], lang),
+                t("recovery.check_marketplace_config", &[], lang),
+                t("recovery.retry_later", &[], lang),
+            ],
+            Self::InsecureTransportError { .. } => vec![
+                t("recovery.use_https", &[], lang),
+                t("recovery.allow_insecure", &[], lang),
+            ],
+            Self::RateLimitError { reset_at, authenticated, .. } => {
+                let mut suggestions = Vec::new();
+                if !authenticated {
+                    suggestions.push(t("recovery.set_github_token", &[], lang));
+                }
+                suggestions.push(t("recovery.retry_after", &[("reset", reset_at)], lang));
+                suggestions
+            }
             Self::LocalizationError { .. } => vec![
                 t("recovery.check_language_files", &[], lang),
                 t("recovery.reset_language", &[], lang),
@@ -131,11 +194,33 @@ impl GeekToolsError {
     
     /// 是否可以自动恢复
     pub fn is_recoverable(&self) -> bool {
-        matches!(self, 
-            Self::NetworkError { .. } |
+        #[cfg(feature = "network")]
+        if matches!(self, Self::NetworkError { .. }) {
+            return true;
+        }
+        matches!(self,
             Self::FileOperationError { .. } |
             Self::ConfigError { .. }
-        )
+        ) || matches!(self, Self::MarketplaceError { status: Some(code), .. } if *code >= 500)
+    }
+
+    /// 稳定的错误类别代码，用于机器可读的输出
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::FileOperationError { .. } => "file_operation",
+            #[cfg(feature = "network")]
+            Self::NetworkError { .. } => "network",
+            Self::ConfigError { .. } => "config",
+            Self::ScriptExecutionError { .. } => "script_execution",
+            Self::PluginError { .. } => "plugin",
+            Self::PluginPackageError { .. } => "plugin_package",
+            Self::MarketplaceError { .. } => "marketplace",
+            Self::InsecureTransportError { .. } => "insecure_transport",
+            Self::RateLimitError { .. } => "rate_limit",
+            Self::LocalizationError { .. } => "localization",
+            Self::PermissionError { .. } => "permission",
+            Self::ValidationError { .. } => "validation",
+        }
     }
 }
 
@@ -149,6 +234,7 @@ impl From<std::io::Error> for GeekToolsError {
     }
 }
 
+#[cfg(feature = "network")]
 impl From<reqwest::Error> for GeekToolsError {
     fn from(error: reqwest::Error) -> Self {
         Self::NetworkError {
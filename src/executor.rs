@@ -0,0 +1,804 @@
+use crate::config::{Config, SecurityConfig};
+use crate::envdiff;
+use crate::fileio;
+use crate::history::{self, RunRecord};
+use crate::scripts::{helpers, metadata};
+use crate::log_only;
+use crate::metrics;
+use crate::pkg;
+use crate::notify::{self, NotificationConfig, RunSummary};
+use crate::runtime::{self, Language};
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::thread;
+use std::time::Instant;
+
+/// 脚本进程的资源限制，来自 `SecurityConfig`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ResourceLimits {
+    /// CPU 优先级 (nice 值)
+    pub nice: Option<i32>,
+    /// IO 调度类别 (ionice -c，仅 Linux)
+    pub ionice_class: Option<u8>,
+    /// 最大虚拟内存 (MB)
+    pub max_memory_mb: Option<u64>,
+    /// 最大打开文件数
+    pub max_open_files: Option<u64>,
+}
+
+impl ResourceLimits {
+    pub fn from_security(security: &SecurityConfig) -> Self {
+        Self {
+            nice: security.nice_level,
+            ionice_class: security.ionice_class,
+            max_memory_mb: security.max_memory_mb,
+            max_open_files: security.max_open_files,
+        }
+    }
+
+    /// 生成 ulimit 语句，没有限制时返回 None；某个限制设置失败时只在 stderr 警告，脚本照常运行
+    fn ulimit_script(&self) -> Option<String> {
+        let mut limits = Vec::new();
+        if let Some(mb) = self.max_memory_mb {
+            // macOS 拒绝设置 RLIMIT_AS，ulimit -v 总是失败
+            if cfg!(target_os = "macos") {
+                log_only!("WARN", "EXECUTOR", "此系统不支持限制虚拟内存，忽略 max_memory_mb");
+            } else {
+                limits.push(format!("-v {}", mb.saturating_mul(1024)));
+            }
+        }
+        if let Some(files) = self.max_open_files {
+            limits.push(format!("-n {}", files));
+        }
+        if limits.is_empty() {
+            return None;
+        }
+        let mut script: String = limits
+            .iter()
+            .map(|limit| {
+                format!("ulimit {0} 2>/dev/null || echo \"geektools: ulimit {0} failed, running without this limit\" >&2; ", limit)
+            })
+            .collect();
+        script.push_str("exec \"$@\"");
+        Some(script)
+    }
+}
+
+/// 脚本进程树 (子进程及其已回收的后代) 的资源消耗，来自 wait4 的 rusage
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct ResourceUsage {
+    /// 用户态加内核态 CPU 时间
+    pub cpu_seconds: f64,
+    /// 峰值常驻内存 (KB)
+    pub peak_memory_kb: u64,
+}
+
+impl ResourceUsage {
+    #[cfg(unix)]
+    fn from_rusage(usage: &libc::rusage) -> Self {
+        let seconds = |t: libc::timeval| t.tv_sec as f64 + t.tv_usec as f64 / 1_000_000.0;
+        // macOS 的 ru_maxrss 单位是字节，其他系统是 KB
+        let divisor = if cfg!(target_os = "macos") { 1024 } else { 1 };
+        Self {
+            cpu_seconds: seconds(usage.ru_utime) + seconds(usage.ru_stime),
+            peak_memory_kb: usage.ru_maxrss.max(0) as u64 / divisor,
+        }
+    }
+}
+
+impl std::fmt::Display for ResourceUsage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mb = self.peak_memory_kb as f64 / 1024.0;
+        write!(f, "CPU {:.2}s, peak memory {:.1} MB", self.cpu_seconds, mb)
+    }
+}
+
+/// 脚本运行的输出级别
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Verbosity {
+    /// 丢弃脚本输出，只显示最终状态
+    Quiet,
+    #[default]
+    Normal,
+    /// 通过 `sh -x` 回显每条命令
+    Verbose,
+}
+
+/// 单次脚本运行的选项
+#[derive(Clone, Debug, Default)]
+pub struct RunOptions {
+    /// 以其他用户身份运行 (通过 `sudo -u`)
+    pub run_as: Option<String>,
+    pub limits: ResourceLimits,
+    /// 捕获输出并在每行前加上耗时和脚本名
+    pub show_timestamps: bool,
+    pub verbosity: Verbosity,
+    /// 运行结束后的通知设置
+    pub notifications: NotificationConfig,
+    /// 运行前后比较 shell 环境和配置文件
+    pub env_diff: bool,
+    /// 写入运行历史 (含 `#@rollback` 段)
+    pub record_history: bool,
+    /// 指定解释器，优先于 shebang 检测
+    pub interpreter: Option<String>,
+    /// 允许安装 `#@pip` / `#@npm` 声明的依赖
+    pub allow_dependency_install: bool,
+    /// 传给脚本的位置参数
+    pub args: Vec<String>,
+    /// 脚本的工作目录，相对路径基于脚本所在目录；为空时沿用启动目录
+    pub working_dir: Option<PathBuf>,
+    /// 运行结束后保留临时产物目录 (`GEEKTOOLS_ARTIFACTS`)
+    pub keep_artifacts: bool,
+    /// 界面语言，用于运行结束后的资源用量等提示
+    pub ui_language: crate::i18n::Language,
+}
+
+impl RunOptions {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            run_as: None,
+            limits: ResourceLimits::from_security(&config.security),
+            show_timestamps: config.ui.show_timestamps,
+            verbosity: Verbosity::Normal,
+            notifications: config.notifications.clone(),
+            env_diff: false,
+            record_history: true,
+            interpreter: None,
+            allow_dependency_install: config.security.allow_dependency_install,
+            args: Vec::new(),
+            working_dir: None,
+            keep_artifacts: false,
+            ui_language: crate::i18n::Language::from_code(&config.language).unwrap_or_default(),
+        }
+    }
+
+    pub fn with_args(mut self, args: Vec<String>) -> Self {
+        self.args = args;
+        self
+    }
+
+    pub fn with_interpreter(mut self, interpreter: Option<&str>) -> Self {
+        self.interpreter = interpreter.map(str::to_string).filter(|i| !i.trim().is_empty());
+        self
+    }
+
+    pub fn with_env_diff(mut self, env_diff: bool) -> Self {
+        self.env_diff = env_diff;
+        self
+    }
+
+    pub fn with_verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.verbosity = verbosity;
+        self
+    }
+
+    pub fn with_run_as(mut self, user: Option<&str>) -> Self {
+        self.run_as = user.map(str::to_string);
+        self
+    }
+
+    pub fn with_workspace(mut self, working_dir: Option<&str>, keep_artifacts: bool) -> Self {
+        self.working_dir = working_dir.filter(|d| !d.trim().is_empty()).map(PathBuf::from);
+        self.keep_artifacts = keep_artifacts;
+        self
+    }
+}
+
+/// 解析脚本的工作目录：相对路径基于脚本所在目录
+pub fn resolve_working_dir(path: &Path, options: &RunOptions) -> Option<PathBuf> {
+    let dir = options.working_dir.as_ref()?;
+    if dir.is_absolute() {
+        return Some(dir.clone());
+    }
+    let base = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    Some(base.join(dir))
+}
+
+/// 单次运行的临时产物目录，脚本通过 `GEEKTOOLS_ARTIFACTS` 和 `TMPDIR` 使用；
+/// drop 时删除，除非设置了保留
+struct Artifacts {
+    dir: PathBuf,
+    keep: bool,
+}
+
+impl Artifacts {
+    /// 在私有的 ~/.geektools/tmp 下以随机名称 (0700) 创建，删除由 [`Drop`] 按 `keep` 决定
+    fn create(label: &str, keep: bool) -> io::Result<Self> {
+        let parent = fileio::private_tmp_dir().map_err(|e| io::Error::other(e.to_string()))?;
+        let dir = tempfile::Builder::new().prefix(&format!("run_{}_", label)).tempdir_in(parent)?.keep();
+        Ok(Self { dir, keep })
+    }
+}
+
+impl Drop for Artifacts {
+    fn drop(&mut self) {
+        if self.keep {
+            log_only!("INFO", "EXECUTOR", "保留临时产物: {}", self.dir.display());
+        } else {
+            let _ = fileio::remove_dir(&self.dir);
+        }
+    }
+}
+
+/// 校验用户名，避免把任意参数传给 sudo
+pub fn is_valid_username(user: &str) -> bool {
+    !user.is_empty()
+        && user.len() <= 32
+        && !user.starts_with('-')
+        && user
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+/// 从 shebang 解析解释器及其参数，没有 shebang 时按扩展名选择 (sh/python3/node)
+fn interpreter_for(path: &Path) -> Vec<String> {
+    if let Ok(content) = fileio::read(path)
+        && let Some(first_line) = content.lines().next()
+        && let Some(stripped) = first_line.strip_prefix("#!")
+    {
+        let parts: Vec<String> = stripped.split_whitespace().map(str::to_string).collect();
+        if !parts.is_empty() {
+            return parts;
+        }
+    }
+    vec![Language::from_path(path).default_interpreter().to_string()]
+}
+
+/// 指定的解释器优先，否则按脚本检测
+pub fn resolve_interpreter(path: &Path, options: &RunOptions) -> Vec<String> {
+    match &options.interpreter {
+        Some(custom) => custom.split_whitespace().map(str::to_string).collect(),
+        None => interpreter_for(path),
+    }
+}
+
+/// 解释器是 shell 时返回 `-x` 应插入的位置 (支持 `/usr/bin/env bash` 形式)
+fn trace_flag_index(interpreter: &[String]) -> Option<usize> {
+    const SHELLS: [&str; 5] = ["sh", "bash", "dash", "zsh", "ksh"];
+    let base = |s: &str| Path::new(s).file_name().and_then(|n| n.to_str()).unwrap_or("").to_string();
+    match interpreter.first().map(|p| base(p)) {
+        Some(p) if SHELLS.contains(&p.as_str()) => Some(1),
+        Some(p) if p == "env" && interpreter.get(1).is_some_and(|s| SHELLS.contains(&s.as_str())) => Some(2),
+        _ => None,
+    }
+}
+
+/// 构造执行脚本的命令：sudo -u → env → nice → ionice → ulimit → 解释器；
+/// sudo 默认的 env_reset 会清掉 `Command::env` 设置的变量，所以以其他用户运行时 `env` 通过 `env K=V` 显式传递
+pub fn build_command(path: &Path, options: &RunOptions, env: &[(String, OsString)]) -> Command {
+    let mut interpreter = resolve_interpreter(path, options);
+    if options.verbosity == Verbosity::Verbose
+        && let Some(index) = trace_flag_index(&interpreter)
+    {
+        interpreter.insert(index, "-x".to_string());
+    }
+    let mut argv: Vec<OsString> = interpreter.into_iter().map(OsString::from).collect();
+    argv.push(path.as_os_str().to_owned());
+    argv.extend(options.args.iter().map(OsString::from));
+
+    let limits = &options.limits;
+    if let Some(script) = limits.ulimit_script() {
+        let wrapper = ["sh", "-c", script.as_str(), "sh"];
+        argv.splice(0..0, wrapper.iter().map(OsString::from));
+    }
+    #[cfg(target_os = "linux")]
+    if let Some(class) = limits.ionice_class {
+        let wrapper = ["ionice".to_string(), "-c".to_string(), class.to_string()];
+        argv.splice(0..0, wrapper.into_iter().map(OsString::from));
+    }
+    if let Some(nice) = limits.nice {
+        let wrapper = ["nice".to_string(), "-n".to_string(), nice.to_string()];
+        argv.splice(0..0, wrapper.into_iter().map(OsString::from));
+    }
+    if let Some(user) = &options.run_as {
+        let mut wrapper: Vec<OsString> = ["sudo", "-u", user.as_str(), "--", "env"].iter().map(OsString::from).collect();
+        wrapper.extend(env.iter().map(|(key, value)| {
+            let mut assignment = OsString::from(format!("{}=", key));
+            assignment.push(value);
+            assignment
+        }));
+        argv.splice(0..0, wrapper);
+    }
+
+    let mut cmd = Command::new(&argv[0]);
+    cmd.args(&argv[1..]);
+    if options.run_as.is_none() {
+        cmd.envs(env.iter().map(|(key, value)| (key, value)));
+    }
+    cmd
+}
+
+/// 以其他用户运行时把脚本和 `GT_LIB` 复制到所有人可读的临时目录：
+/// 内置脚本在 ~/.geektools/scripts 下，目标用户通常无法进入调用者的主目录；drop 时删除
+struct RunAsStage {
+    dir: PathBuf,
+    script: PathBuf,
+    library: Option<PathBuf>,
+}
+
+impl RunAsStage {
+    fn create(script: &Path, library: Option<&Path>) -> io::Result<Self> {
+        let dir = std::env::temp_dir().join(format!("geektools_run_as_{:016x}", rand::random::<u64>()));
+        std::fs::create_dir(&dir)?;
+        let mut stage = Self { script: dir.join(script.file_name().unwrap_or(script.as_os_str())), library: None, dir };
+        std::fs::copy(script, &stage.script)?;
+        if let Some(lib) = library {
+            let staged = stage.dir.join(lib.file_name().unwrap_or(lib.as_os_str()));
+            std::fs::copy(lib, &staged)?;
+            stage.library = Some(staged);
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&stage.dir, std::fs::Permissions::from_mode(0o755))?;
+            std::fs::set_permissions(&stage.script, std::fs::Permissions::from_mode(0o755))?;
+            if let Some(lib) = &stage.library {
+                std::fs::set_permissions(lib, std::fs::Permissions::from_mode(0o644))?;
+            }
+        }
+        Ok(stage)
+    }
+}
+
+impl Drop for RunAsStage {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// 根据脚本的 shebang 或扩展名选择解释器执行脚本，需要时先准备依赖环境
+pub fn execute_script(path: &Path, options: &RunOptions) -> io::Result<ExitStatus> {
+    if let Some(user) = &options.run_as
+        && !is_valid_username(user)
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid user name: {}", user),
+        ));
+    }
+    let meta = fileio::read(path).map(|content| metadata::parse(&content)).unwrap_or_default();
+    if !meta.supports_platform(std::env::consts::OS) {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("script only supports: {}", meta.platforms.join(", ")),
+        ));
+    }
+    // 切换工作目录后相对的脚本路径会失效
+    let absolute;
+    let path = if options.working_dir.is_some() {
+        absolute = std::path::absolute(path)?;
+        absolute.as_path()
+    } else {
+        path
+    };
+    let interpreter = resolve_interpreter(path, options);
+    if let Some(program) = runtime::program_name(&interpreter)
+        && runtime::find_program(program).is_none()
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("interpreter not found: {}", program),
+        ));
+    }
+    let language = Language::from_interpreter(&interpreter).unwrap_or_else(|| Language::from_path(path));
+    let prepared = runtime::prepare(language, &interpreter, &meta, options.allow_dependency_install)?;
+    let mut env: Vec<(String, OsString)> =
+        prepared.env.into_iter().map(|(key, value)| (key, value.into_os_string())).collect();
+
+    let label = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let working_dir = resolve_working_dir(path, options);
+    if let Some(dir) = &working_dir
+        && !dir.is_dir()
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("working directory not found: {}", dir.display()),
+        ));
+    }
+    // 以其他用户运行时该用户无法写入我们创建的目录，沿用系统临时目录
+    let artifacts = match options.run_as {
+        Some(_) => None,
+        None => Some(Artifacts::create(&label, options.keep_artifacts)?),
+    };
+    if let Some(artifacts) = &artifacts {
+        env.push(("GEEKTOOLS_ARTIFACTS".to_string(), artifacts.dir.clone().into_os_string()));
+        env.push(("TMPDIR".to_string(), artifacts.dir.clone().into_os_string()));
+    }
+    let stage = match options.run_as {
+        Some(_) => Some(RunAsStage::create(path, helpers::library_path())?),
+        None => None,
+    };
+    let library = match &stage {
+        Some(stage) => stage.library.as_deref(),
+        None => helpers::library_path(),
+    };
+    if let Some(lib) = library {
+        env.push(("GT_LIB".to_string(), lib.as_os_str().to_owned()));
+    }
+    env.extend(pkg::script_env().into_iter().map(|(key, value)| (key.to_string(), OsString::from(value))));
+
+    let script = stage.as_ref().map_or(path, |stage| stage.script.as_path());
+    let mut cmd = match &prepared.interpreter {
+        Some(venv_python) => build_command(script, &options.clone().with_interpreter(Some(venv_python)), &env),
+        None => build_command(script, options, &env),
+    };
+    if let Some(dir) = working_dir {
+        cmd.current_dir(dir);
+    }
+    let before = options.env_diff.then(envdiff::capture);
+    let started_at = chrono::Local::now().to_rfc3339();
+    let start = Instant::now();
+    let (status, usage) = match run_command(cmd, options, &label) {
+        Ok((status, usage)) => (Ok(status), usage),
+        Err(e) => (Err(e), None),
+    };
+    let summary = RunSummary::new(&label, &status, start.elapsed()).with_usage(usage);
+    if let Some(usage) = usage {
+        log_only!("INFO", "EXECUTE", "{} 耗时 {:.2}s, {}", label, summary.duration_seconds, usage);
+        if options.verbosity != Verbosity::Quiet {
+            let duration = format!("{:.2}", summary.duration_seconds);
+            let cpu = format!("{:.2}", usage.cpu_seconds);
+            let memory = format!("{:.1}", usage.peak_memory_kb as f64 / 1024.0);
+            let params = [("0", label.as_str()), ("1", &duration), ("2", &cpu), ("3", &memory)];
+            // write_str 经过 theme::render，纯文本模式下去掉 emoji 和分隔点
+            crate::terminal::write_str(&format!("{}\n", crate::i18n::t("script_execution.usage", &params, options.ui_language)));
+        }
+    }
+    notify::notify_run_finished(&options.notifications, &summary);
+    metrics::record_run(&label, summary.success, summary.duration_seconds);
+    if options.record_history {
+        let record = RunRecord {
+            id: 0,
+            script: label.clone(),
+            started_at,
+            duration_seconds: summary.duration_seconds,
+            success: summary.success,
+            exit_code: summary.exit_code,
+            run_as: options.run_as.clone(),
+            rollback: meta.rollback,
+            rolled_back: false,
+            usage,
+        };
+        if let Err(e) = history::append(record) {
+            log_only!("WARN", "HISTORY", "写入运行历史失败: {}", e);
+        }
+    }
+    if let Some(before) = before {
+        report_env_diff(&label, &envdiff::diff(&before, &envdiff::capture()));
+    }
+    if let Some(artifacts) = artifacts.as_ref().filter(|a| a.keep) {
+        crate::terminal::write_str(&format!("📁 {} 的临时产物保留在 {}\n", label, artifacts.dir.display()));
+    }
+    status
+}
+
+fn report_env_diff(label: &str, diff: &envdiff::EnvDiff) {
+    if diff.is_empty() {
+        crate::terminal::write_str(&format!("🔍 {} 没有改变 shell 环境和配置文件\n", label));
+        return;
+    }
+    let report = diff.render();
+    log_only!("INFO", "ENV_DIFF", "{}:\n{}", label, report);
+    crate::terminal::write_str(&format!("🔍 {} 对 shell 环境的改动:\n{}\n", label, report));
+}
+
+fn run_command(mut cmd: Command, options: &RunOptions, label: &str) -> io::Result<(ExitStatus, Option<ResourceUsage>)> {
+    if options.verbosity == Verbosity::Quiet {
+        return wait_with_usage(cmd.stdout(Stdio::null()).stderr(Stdio::null()).spawn()?);
+    }
+    if !options.show_timestamps {
+        return wait_with_usage(cmd.spawn()?);
+    }
+
+    let mut child = cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+    let start = Instant::now();
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    thread::scope(|s| {
+        if let Some(out) = stdout {
+            s.spawn(|| pipe_with_prefix(out, io::stdout(), label, start));
+        }
+        if let Some(err) = stderr {
+            s.spawn(|| pipe_with_prefix(err, io::stderr(), label, start));
+        }
+    });
+    wait_with_usage(child)
+}
+
+/// 等待子进程结束并取得它的资源消耗；用 wait4 回收后不能再调用 `Child::wait`，所以按值接收
+#[cfg(unix)]
+fn wait_with_usage(child: Child) -> io::Result<(ExitStatus, Option<ResourceUsage>)> {
+    use std::os::unix::process::ExitStatusExt;
+    let pid = child.id() as libc::pid_t;
+    let mut status = 0;
+    // SAFETY: rusage 是纯数据结构，全零是合法值；status 和 usage 在调用期间有效
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    loop {
+        if unsafe { libc::wait4(pid, &mut status, 0, &mut usage) } == pid {
+            return Ok((ExitStatus::from_raw(status), Some(ResourceUsage::from_rusage(&usage))));
+        }
+        let error = io::Error::last_os_error();
+        if error.kind() != io::ErrorKind::Interrupted {
+            return Err(error);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn wait_with_usage(mut child: Child) -> io::Result<(ExitStatus, Option<ResourceUsage>)> {
+    Ok((child.wait()?, None))
+}
+
+/// 把子进程输出转发到终端；按块读取，没有换行的提示符也能及时显示
+fn pipe_with_prefix<R: Read, W: Write>(mut reader: R, mut writer: W, label: &str, start: Instant) -> io::Result<()> {
+    let mut buf = [0u8; 4096];
+    let mut at_line_start = true;
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => return Ok(()),
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        let prefix = format!("[{:>8.2}s] [{}] ", start.elapsed().as_secs_f64(), label);
+        write_prefixed(&mut writer, &buf[..n], &prefix, &mut at_line_start)?;
+        writer.flush()?;
+    }
+}
+
+/// 在每个新行开头写入前缀，at_line_start 记录跨块的行首状态
+fn write_prefixed<W: Write>(writer: &mut W, chunk: &[u8], prefix: &str, at_line_start: &mut bool) -> io::Result<()> {
+    for segment in chunk.split_inclusive(|b| *b == b'\n') {
+        if *at_line_start {
+            writer.write_all(prefix.as_bytes())?;
+        }
+        writer.write_all(segment)?;
+        *at_line_start = segment.ends_with(b"\n");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsStr;
+    use tempfile::TempDir;
+
+    fn args_of(cmd: &Command) -> Vec<&OsStr> {
+        cmd.get_args().collect()
+    }
+
+    #[test]
+    fn test_build_command_uses_shebang() {
+        let dir = TempDir::new().unwrap();
+        let script = dir.path().join("a.sh");
+        std::fs::write(&script, "#!/usr/bin/env bash\necho hi\n").unwrap();
+
+        let cmd = build_command(&script, &RunOptions::default(), &[]);
+        assert_eq!(cmd.get_program(), "/usr/bin/env");
+        assert_eq!(args_of(&cmd), vec![OsStr::new("bash"), script.as_os_str()]);
+    }
+
+    #[test]
+    fn test_build_command_with_run_as() {
+        let dir = TempDir::new().unwrap();
+        let script = dir.path().join("b.sh");
+        std::fs::write(&script, "echo hi\n").unwrap();
+
+        let env = [("GT_LIB".to_string(), OsString::from("/tmp/lib.sh")), ("GT_PKG_MANAGER".to_string(), OsString::from("apt"))];
+        let cmd = build_command(&script, &RunOptions::default().with_run_as(Some("backup")), &env);
+        assert_eq!(cmd.get_program(), "sudo");
+        assert_eq!(
+            args_of(&cmd),
+            vec![
+                OsStr::new("-u"),
+                OsStr::new("backup"),
+                OsStr::new("--"),
+                OsStr::new("env"),
+                OsStr::new("GT_LIB=/tmp/lib.sh"),
+                OsStr::new("GT_PKG_MANAGER=apt"),
+                OsStr::new("sh"),
+                script.as_os_str()
+            ]
+        );
+        // sudo 会重置环境，变量只能通过参数传递
+        assert_eq!(cmd.get_envs().count(), 0);
+
+        let cmd = build_command(&script, &RunOptions::default(), &env);
+        assert_eq!(cmd.get_program(), "sh");
+        assert_eq!(cmd.get_envs().count(), 2);
+    }
+
+    #[test]
+    fn test_run_as_stage_is_readable_by_others() {
+        let dir = TempDir::new().unwrap();
+        let script = dir.path().join("j.sh");
+        let lib = dir.path().join("lib.sh");
+        std::fs::write(&script, "echo hi\n").unwrap();
+        std::fs::write(&lib, "gt_log() { :; }\n").unwrap();
+
+        let stage = RunAsStage::create(&script, Some(&lib)).unwrap();
+        assert_eq!(std::fs::read_to_string(&stage.script).unwrap(), "echo hi\n");
+        let staged_lib = stage.library.clone().unwrap();
+        assert_eq!(std::fs::read_to_string(&staged_lib).unwrap(), "gt_log() { :; }\n");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = |p: &Path| std::fs::metadata(p).unwrap().permissions().mode() & 0o777;
+            assert_eq!(mode(&stage.dir), 0o755);
+            assert_eq!(mode(&stage.script), 0o755);
+            assert_eq!(mode(&staged_lib), 0o644);
+        }
+        let staged_dir = stage.dir.clone();
+        drop(stage);
+        assert!(!staged_dir.exists());
+    }
+
+    #[test]
+    #[cfg(not(target_os = "macos"))]
+    fn test_build_command_with_limits() {
+        let dir = TempDir::new().unwrap();
+        let script = dir.path().join("c.sh");
+        std::fs::write(&script, "echo hi\n").unwrap();
+
+        let options = RunOptions {
+            run_as: None,
+            show_timestamps: false,
+            verbosity: Verbosity::Normal,
+            notifications: NotificationConfig::default(),
+            env_diff: false,
+            record_history: false,
+            interpreter: None,
+            allow_dependency_install: false,
+            args: Vec::new(),
+            working_dir: None,
+            keep_artifacts: false,
+            ui_language: crate::i18n::Language::English,
+            limits: ResourceLimits {
+                nice: Some(10),
+                ionice_class: None,
+                max_memory_mb: Some(256),
+                max_open_files: Some(64),
+            },
+        };
+        let cmd = build_command(&script, &options, &[]);
+        assert_eq!(cmd.get_program(), "nice");
+        assert_eq!(
+            args_of(&cmd),
+            vec![
+                OsStr::new("-n"),
+                OsStr::new("10"),
+                OsStr::new("sh"),
+                OsStr::new("-c"),
+                OsStr::new(
+                    "ulimit -v 262144 2>/dev/null || echo \"geektools: ulimit -v 262144 failed, running without this limit\" >&2; \
+                     ulimit -n 64 2>/dev/null || echo \"geektools: ulimit -n 64 failed, running without this limit\" >&2; \
+                     exec \"$@\""
+                ),
+                OsStr::new("sh"),
+                OsStr::new("sh"),
+                script.as_os_str()
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_failing_limit_still_runs_script() {
+        let dir = TempDir::new().unwrap();
+        let script = dir.path().join("k.sh");
+        std::fs::write(&script, "echo ran\n").unwrap();
+
+        // 超过内核允许的最大打开文件数，ulimit -n 一定失败
+        let limits = ResourceLimits { max_open_files: Some(u64::MAX), ..ResourceLimits::default() };
+        let options = RunOptions { limits, ..RunOptions::default() };
+        let output = build_command(&script, &options, &[]).output().unwrap();
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "ran\n");
+        assert!(String::from_utf8_lossy(&output.stderr).contains("failed, running without this limit"));
+    }
+
+    #[test]
+    fn test_verbose_adds_trace_flag() {
+        let dir = TempDir::new().unwrap();
+        let script = dir.path().join("d.sh");
+        std::fs::write(&script, "#!/usr/bin/env bash\necho hi\n").unwrap();
+
+        let options = RunOptions::default().with_verbosity(Verbosity::Verbose);
+        let cmd = build_command(&script, &options, &[]);
+        assert_eq!(args_of(&cmd), vec![OsStr::new("bash"), OsStr::new("-x"), script.as_os_str()]);
+
+        let python = dir.path().join("e.py");
+        std::fs::write(&python, "#!/usr/bin/python3\nprint(1)\n").unwrap();
+        let cmd = build_command(&python, &options, &[]);
+        assert_eq!(args_of(&cmd), vec![python.as_os_str()]);
+    }
+
+    #[test]
+    fn test_interpreter_override_wins_over_shebang() {
+        let dir = TempDir::new().unwrap();
+        let script = dir.path().join("f.sh");
+        std::fs::write(&script, "#!/bin/sh\necho hi\n").unwrap();
+
+        let options = RunOptions::default().with_interpreter(Some("/usr/bin/env zsh"));
+        let cmd = build_command(&script, &options, &[]);
+        assert_eq!(cmd.get_program(), "/usr/bin/env");
+        assert_eq!(args_of(&cmd), vec![OsStr::new("zsh"), script.as_os_str()]);
+
+        let options = RunOptions::default().with_interpreter(Some("  "));
+        assert_eq!(build_command(&script, &options, &[]).get_program(), "/bin/sh");
+    }
+
+    #[test]
+    fn test_interpreter_falls_back_to_extension() {
+        let dir = TempDir::new().unwrap();
+        let python = dir.path().join("g.py");
+        std::fs::write(&python, "import sys\n").unwrap();
+        assert_eq!(build_command(&python, &RunOptions::default(), &[]).get_program(), "python3");
+
+        let node = dir.path().join("h.js");
+        std::fs::write(&node, "console.log(1)\n").unwrap();
+        assert_eq!(build_command(&node, &RunOptions::default(), &[]).get_program(), "node");
+    }
+
+    #[test]
+    fn test_working_dir_and_artifacts() {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join("work")).unwrap();
+        let script = dir.path().join("i.sh");
+        std::fs::write(&script, "pwd > cwd\ntouch \"$GEEKTOOLS_ARTIFACTS/out\"\necho \"$GEEKTOOLS_ARTIFACTS\" > artifacts\n. \"$GT_LIB\"\n").unwrap();
+        let run = |keep: bool| {
+            let options = RunOptions::default().with_workspace(Some("work"), keep);
+            assert!(execute_script(&script, &options).unwrap().success());
+            let read = |name: &str| std::fs::read_to_string(dir.path().join("work").join(name)).unwrap();
+            assert_eq!(Path::new(read("cwd").trim()), dir.path().join("work").canonicalize().unwrap());
+            PathBuf::from(read("artifacts").trim())
+        };
+
+        assert!(!run(false).exists());
+        let kept = run(true);
+        assert!(kept.join("out").exists());
+        std::fs::remove_dir_all(kept).unwrap();
+
+        let missing = RunOptions::default().with_workspace(Some("nope"), false);
+        assert_eq!(execute_script(&script, &missing).unwrap_err().kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_run_command_reports_usage() {
+        let quiet = RunOptions { verbosity: Verbosity::Quiet, ..RunOptions::default() };
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "i=0; while [ $i -lt 20000 ]; do i=$((i+1)); done; exit 3"]);
+        let (status, usage) = run_command(cmd, &quiet, "busy").unwrap();
+        assert_eq!(status.code(), Some(3));
+        let usage = usage.unwrap();
+        assert!(usage.cpu_seconds > 0.0);
+        assert!(usage.peak_memory_kb > 0);
+    }
+
+    #[test]
+    fn test_write_prefixed_across_chunks() {
+        let mut out = Vec::new();
+        let mut at_line_start = true;
+        write_prefixed(&mut out, b"one\ntw", "> ", &mut at_line_start).unwrap();
+        write_prefixed(&mut out, b"o\nthree? ", "> ", &mut at_line_start).unwrap();
+        assert!(!at_line_start);
+        assert_eq!(String::from_utf8(out).unwrap(), "> one\n> two\n> three? ");
+    }
+
+    #[test]
+    fn test_is_valid_username() {
+        assert!(is_valid_username("www-data"));
+        assert!(is_valid_username("backup_1"));
+        assert!(!is_valid_username(""));
+        assert!(!is_valid_username("-s"));
+        assert!(!is_valid_username("root; rm -rf /"));
+    }
+}
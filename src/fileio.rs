@@ -2,16 +2,20 @@ use std::fs::{self, File, OpenOptions};
 use std::io;
 use std::path::{Path, PathBuf};
 use std::collections::HashMap;
-use std::sync::{Mutex, Arc};
+use std::sync::{Mutex, Arc, RwLock};
 use std::time::{SystemTime, Duration};
 use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
 use crate::errors::{GeekToolsError, Result};
+use crate::log_only;
 
 /// 文件内容缓存条目
 #[derive(Clone)]
 struct CacheEntry {
     content: String,
     last_modified: SystemTime,
+    /// 文件大小，同一时间戳内的修改也能发现
+    len: u64,
     cached_at: SystemTime,
 }
 
@@ -20,45 +24,121 @@ static FILE_CACHE: Lazy<Arc<Mutex<HashMap<PathBuf, CacheEntry>>>> = Lazy::new(||
     Arc::new(Mutex::new(HashMap::new()))
 });
 
-const CACHE_TTL: Duration = Duration::from_secs(300); // 5分钟缓存
+/// 文件读取缓存的设置 (`cache`)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CacheConfig {
+    /// 关闭后每次都从磁盘读取
+    pub enabled: bool,
+    /// 缓存条目的有效期 (秒)
+    pub ttl_seconds: u64,
+    /// 缓存内容的总大小上限 (KB)，超过时先丢弃最早缓存的条目
+    pub max_size_kb: u64,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self { enabled: true, ttl_seconds: 300, max_size_kb: 8 * 1024 }
+    }
+}
+
+static CACHE_CONFIG: Lazy<RwLock<CacheConfig>> = Lazy::new(|| RwLock::new(CacheConfig::default()));
+
+/// 应用缓存设置；关闭缓存时同时清空
+pub fn configure_cache(config: &CacheConfig) {
+    if let Ok(mut current) = CACHE_CONFIG.write() {
+        *current = config.clone();
+    }
+    if !config.enabled {
+        clear_cache();
+    } else if let Ok(mut cache) = FILE_CACHE.lock() {
+        evict(&mut cache, 0, config.max_size_kb * 1024);
+    }
+}
+
+/// 清空读取缓存，返回丢弃的条目数
+pub fn clear_cache() -> usize {
+    FILE_CACHE.lock().map(|mut cache| cache.drain().count()).unwrap_or(0)
+}
+
+fn cache_config() -> CacheConfig {
+    CACHE_CONFIG.read().map(|config| config.clone()).unwrap_or_default()
+}
 
 /// 检查缓存条目是否有效
-fn is_cache_valid(entry: &CacheEntry, file_modified: SystemTime) -> bool {
+fn is_cache_valid(entry: &CacheEntry, metadata: &fs::Metadata, ttl: Duration) -> bool {
+    let Ok(file_modified) = metadata.modified() else {
+        return false;
+    };
     let now = SystemTime::now();
-    entry.last_modified >= file_modified && 
-    now.duration_since(entry.cached_at).unwrap_or(Duration::MAX) < CACHE_TTL
+    entry.last_modified >= file_modified && entry.len == metadata.len() &&
+    now.duration_since(entry.cached_at).unwrap_or(Duration::MAX) < ttl
+}
+
+/// 为 `incoming` 字节腾出空间：按缓存时间从早到晚丢弃，直到总大小不超过 `max_bytes`
+fn evict(cache: &mut HashMap<PathBuf, CacheEntry>, incoming: u64, max_bytes: u64) {
+    let mut total: u64 = cache.values().map(|entry| entry.content.len() as u64).sum::<u64>() + incoming;
+    if total <= max_bytes {
+        return;
+    }
+    let mut by_age: Vec<(SystemTime, PathBuf)> = cache.iter().map(|(path, entry)| (entry.cached_at, path.clone())).collect();
+    by_age.sort();
+    for (_, path) in by_age {
+        if total <= max_bytes {
+            break;
+        }
+        if let Some(entry) = cache.remove(&path) {
+            total -= entry.content.len() as u64;
+        }
+    }
+}
+
+/// 修改文件的操作记入日志 (带关联 ID)，读取不记录
+fn logged<T>(operation: &str, target: impl std::fmt::Display, result: Result<T>) -> Result<T> {
+    match &result {
+        Ok(_) => log_only!("INFO", "FILE", "{} {}", operation, target),
+        Err(e) => log_only!("ERROR", "FILE", "{} {} 失败: {}", operation, target, e),
+    }
+    result
 }
 
 /// Read file content as UTF-8 string with caching
 pub fn read(path: impl AsRef<Path>) -> Result<String> {
     let path_buf = path.as_ref().to_path_buf();
+    let config = cache_config();
+    let read_file = || fs::read_to_string(&path_buf).map_err(|e| GeekToolsError::FileOperationError {
+        path: path_buf.display().to_string(),
+        source: e,
+    });
+    if !config.enabled {
+        return read_file();
+    }
+    let ttl = Duration::from_secs(config.ttl_seconds);
     
     // 首先检查缓存
-    if let Ok(cache) = FILE_CACHE.lock() {
-        if let (Some(entry), Ok(metadata)) = (cache.get(&path_buf), fs::metadata(&path_buf)) {
-            if let Ok(modified) = metadata.modified() {
-                if is_cache_valid(entry, modified) {
-                    return Ok(entry.content.clone());
-                }
-            }
-        }
+    if let Ok(cache) = FILE_CACHE.lock()
+        && let (Some(entry), Ok(metadata)) = (cache.get(&path_buf), fs::metadata(&path_buf))
+        && is_cache_valid(entry, &metadata, ttl)
+    {
+        return Ok(entry.content.clone());
     }
     
     // 缓存未命中，读取文件
-    let content = fs::read_to_string(&path_buf).map_err(|e| GeekToolsError::FileOperationError {
-        path: path_buf.display().to_string(),
-        source: e,
-    })?;
+    let content = read_file()?;
     
-    // 缓存读取结果
-    if let (Ok(mut cache), Ok(metadata)) = (FILE_CACHE.lock(), fs::metadata(&path_buf)) {
-        if let Ok(modified) = metadata.modified() {
-            cache.insert(path_buf, CacheEntry {
-                content: content.clone(),
-                last_modified: modified,
-                cached_at: SystemTime::now(),
-            });
-        }
+    // 缓存读取结果；单个文件超过上限时不缓存
+    let max_bytes = config.max_size_kb * 1024;
+    if (content.len() as u64) <= max_bytes
+        && let (Ok(mut cache), Ok(metadata)) = (FILE_CACHE.lock(), fs::metadata(&path_buf))
+        && let Ok(modified) = metadata.modified()
+    {
+        cache.remove(&path_buf);
+        evict(&mut cache, content.len() as u64, max_bytes);
+        cache.insert(path_buf, CacheEntry {
+            content: content.clone(),
+            last_modified: modified,
+            len: metadata.len(),
+            cached_at: SystemTime::now(),
+        });
     }
     
     Ok(content)
@@ -89,7 +169,50 @@ pub fn write(path: impl AsRef<Path>, data: &str) -> Result<()> {
         }
     }
     
-    result
+    logged("write", path_buf.display(), result)
+}
+
+/// Write a UTF-8 string to a file only the owner can read and write (0600 on Unix),
+/// for files holding credentials such as config.json; existing files are tightened too
+pub fn write_private(path: impl AsRef<Path>, data: &str) -> Result<()> {
+    use std::io::Write;
+
+    let path_buf = path.as_ref().to_path_buf();
+    if let Some(parent) = path_buf.parent()
+        && !parent.exists()
+    {
+        create_dir(parent)?;
+    }
+
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let result = options
+        .open(&path_buf)
+        .and_then(|mut file| {
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                file.set_permissions(fs::Permissions::from_mode(0o600))?;
+            }
+            file.write_all(data.as_bytes())
+        })
+        .map_err(|e| GeekToolsError::FileOperationError {
+            path: path_buf.display().to_string(),
+            source: e,
+        });
+
+    if result.is_ok()
+        && let Ok(mut cache) = FILE_CACHE.lock()
+    {
+        cache.remove(&path_buf);
+    }
+
+    logged("write", path_buf.display(), result)
 }
 
 /// Write raw bytes to a file, creating parent directories if needed
@@ -102,10 +225,11 @@ pub fn write_bytes(path: impl AsRef<Path>, data: &[u8]) -> Result<()> {
             })?;
         }
     }
-    fs::write(&path, data).map_err(|e| GeekToolsError::FileOperationError {
+    let result = fs::write(&path, data).map_err(|e| GeekToolsError::FileOperationError {
         path: path.as_ref().display().to_string(),
         source: e,
-    })
+    });
+    logged("write", path.as_ref().display(), result)
 }
 
 /// Open a file in append mode, creating parent directories if needed
@@ -132,23 +256,72 @@ pub fn create_dir(path: impl AsRef<Path>) -> Result<()> {
     })
 }
 
+/// Create a directory only the current user can access (0700 on Unix); an existing
+/// path that is not a directory owned by the current user (e.g. planted in a shared
+/// location or replaced by a symlink) is refused
+pub fn create_private_dir(path: impl AsRef<Path>) -> Result<()> {
+    let path = path.as_ref();
+    create_dir(path)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+        let error = |source| GeekToolsError::FileOperationError { path: path.display().to_string(), source };
+        let metadata = fs::symlink_metadata(path).map_err(error)?;
+        // SAFETY: geteuid 没有前置条件，总是成功
+        let uid = unsafe { libc::geteuid() };
+        if !metadata.is_dir() || metadata.uid() != uid {
+            return Err(error(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "refusing to use a path that is not a directory owned by the current user",
+            )));
+        }
+        if metadata.mode() & 0o777 != 0o700 {
+            fs::set_permissions(path, fs::Permissions::from_mode(0o700)).map_err(error)?;
+        }
+    }
+    Ok(())
+}
+
+/// Per-user scratch directory `~/.geektools/tmp` (0700) for downloads, rollback scripts
+/// and run artifacts; files inside should still come from `tempfile` so names are unpredictable
+pub fn private_tmp_dir() -> Result<PathBuf> {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".into());
+    let dir = PathBuf::from(home).join(".geektools").join("tmp");
+    create_private_dir(&dir)?;
+    Ok(dir)
+}
+
+/// Create an empty file with an unpredictable name in [`private_tmp_dir`]; it is removed when dropped
+pub fn private_temp_file(prefix: &str, suffix: &str) -> Result<tempfile::NamedTempFile> {
+    let dir = private_tmp_dir()?;
+    tempfile::Builder::new()
+        .prefix(prefix)
+        .suffix(suffix)
+        .tempfile_in(&dir)
+        .map_err(|e| GeekToolsError::FileOperationError { path: dir.display().to_string(), source: e })
+}
+
 /// Remove a single file
 pub fn remove_file(path: impl AsRef<Path>) -> Result<()> {
-    fs::remove_file(&path).map_err(|e| GeekToolsError::FileOperationError {
+    let result = fs::remove_file(&path).map_err(|e| GeekToolsError::FileOperationError {
         path: path.as_ref().display().to_string(),
         source: e,
-    })
+    });
+    logged("remove", path.as_ref().display(), result)
 }
 
 /// Remove a directory recursively
 pub fn remove_dir(path: impl AsRef<Path>) -> Result<()> {
-    fs::remove_dir_all(&path).map_err(|e| GeekToolsError::FileOperationError {
+    let result = fs::remove_dir_all(&path).map_err(|e| GeekToolsError::FileOperationError {
         path: path.as_ref().display().to_string(),
         source: e,
-    })
+    });
+    logged("remove dir", path.as_ref().display(), result)
 }
 
 /// Rename a file
+#[cfg(any(feature = "plugins", feature = "self-update"))]
 pub fn rename(from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<()> {
     if let Some(parent) = to.as_ref().parent() {
         if !parent.exists() {
@@ -158,10 +331,26 @@ pub fn rename(from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<()> {
             })?;
         }
     }
-    fs::rename(&from, &to).map_err(|e| GeekToolsError::FileOperationError {
-        path: format!("{} -> {}", from.as_ref().display(), to.as_ref().display()),
+    let target = format!("{} -> {}", from.as_ref().display(), to.as_ref().display());
+    let result = fs::rename(&from, &to).map_err(|e| GeekToolsError::FileOperationError {
+        path: target.clone(),
         source: e,
-    })
+    });
+    logged("rename", target, result)
+}
+
+/// Copy a file, creating the destination's parent directories
+#[cfg(any(feature = "self-update", all(feature = "plugins", feature = "network")))]
+pub fn copy(from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<u64> {
+    if let Some(parent) = to.as_ref().parent() {
+        create_dir(parent)?;
+    }
+    let target = format!("{} -> {}", from.as_ref().display(), to.as_ref().display());
+    let result = fs::copy(&from, &to).map_err(|e| GeekToolsError::FileOperationError {
+        path: target.clone(),
+        source: e,
+    });
+    logged("copy", target, result)
 }
 
 #[cfg(unix)]
@@ -184,6 +373,7 @@ pub mod compat {
     use super::*;
     
     /// Backward compatibility wrapper for read operation
+    #[cfg(any(feature = "self-update", all(feature = "plugins", feature = "network")))]
     pub fn read_compat(path: impl AsRef<Path>) -> io::Result<String> {
         super::read(path).map_err(|e| match e {
             GeekToolsError::FileOperationError { source, .. } => source,
@@ -199,3 +389,68 @@ pub mod compat {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn entry(size: usize, age_secs: u64) -> CacheEntry {
+        CacheEntry {
+            content: "x".repeat(size),
+            last_modified: SystemTime::UNIX_EPOCH,
+            len: size as u64,
+            cached_at: SystemTime::UNIX_EPOCH + Duration::from_secs(1000 - age_secs),
+        }
+    }
+
+    #[test]
+    fn test_evict_drops_oldest_first() {
+        let mut cache = HashMap::new();
+        cache.insert(PathBuf::from("old"), entry(400, 30));
+        cache.insert(PathBuf::from("mid"), entry(400, 20));
+        cache.insert(PathBuf::from("new"), entry(400, 10));
+        evict(&mut cache, 300, 1200);
+        let mut left: Vec<_> = cache.keys().map(|p| p.display().to_string()).collect();
+        left.sort();
+        assert_eq!(left, vec!["mid", "new"]);
+        evict(&mut cache, 0, 0);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_read_notices_edit_with_same_mtime() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("script.sh");
+        std::fs::write(&path, "echo one\n").unwrap();
+        let modified = std::fs::metadata(&path).unwrap().modified().unwrap();
+        assert_eq!(read(&path).unwrap(), "echo one\n");
+
+        // 手工编辑后时间戳没变 (粗粒度文件系统或编辑器保留了时间戳)
+        std::fs::write(&path, "echo three\n").unwrap();
+        File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+        assert_eq!(read(&path).unwrap(), "echo three\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_create_private_dir() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new().unwrap();
+        let private = dir.path().join("tmp");
+        std::fs::create_dir(&private).unwrap();
+        std::fs::set_permissions(&private, fs::Permissions::from_mode(0o755)).unwrap();
+        create_private_dir(&private).unwrap();
+        assert_eq!(fs::metadata(&private).unwrap().permissions().mode() & 0o777, 0o700);
+
+        // 指向别处的符号链接不算私有目录
+        let link = dir.path().join("link");
+        std::os::unix::fs::symlink(&private, &link).unwrap();
+        assert!(create_private_dir(&link).is_err());
+        // 不属于当前用户的目录同样被拒绝 (以 root 运行时 / 也属于自己，跳过)
+        if unsafe { libc::geteuid() } != 0 {
+            assert!(create_private_dir("/").is_err());
+        }
+    }
+}
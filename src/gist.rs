@@ -0,0 +1,137 @@
+use crate::config::CustomScript;
+use crate::errors::{GeekToolsError, Result};
+use crate::fileio;
+use crate::network;
+use crate::scripts::metadata;
+use serde_json::{json, Value};
+use std::path::Path;
+
+const GISTS_API: &str = "https://api.github.com/gists";
+
+/// 把自定义脚本发布为 GitHub Gist，返回该版本的 raw URL (可直接用于"从 URL 运行")。
+/// 需要带 `gist` 权限的令牌 (配置 `github_token` 或环境变量 `GITHUB_TOKEN`)
+pub fn publish(script: &CustomScript, public: bool) -> Result<String> {
+    let Some(file_path) = &script.file_path else {
+        return Err(GeekToolsError::ValidationError {
+            field: "script".to_string(),
+            message: format!("custom script {} has no saved file", script.name),
+        });
+    };
+    let content = with_metadata_header(script, &fileio::read(file_path)?);
+    let name = file_name(script, Path::new(file_path));
+    let body = json!({
+        "description": script.description.as_deref().unwrap_or(&script.name),
+        "public": public,
+        "files": { name.as_str(): { "content": content } },
+    });
+    let response = network::github_post(GISTS_API, &body)?;
+    raw_url(&response, &name).ok_or_else(|| GeekToolsError::ConfigError {
+        message: format!("GitHub did not return a raw URL for {}", name),
+    })
+}
+
+/// Gist 中的文件名：由脚本名生成 (本地文件名是随机 ID)，沿用本地扩展名以便 GitHub 语法高亮
+fn file_name(script: &CustomScript, path: &Path) -> String {
+    let stem: String = script
+        .name
+        .trim()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '-' })
+        .collect();
+    let stem = if stem.is_empty() { "script".to_string() } else { stem };
+    let extension = path.extension().map(|e| e.to_string_lossy().into_owned()).unwrap_or_else(|| "sh".to_string());
+    format!("{}.{}", stem, extension)
+}
+
+/// 脚本头部缺少名称或描述时，按自定义脚本的设置补在 shebang 之后，
+/// 别人通过 URL 添加后看到的名称和描述与本地一致
+pub fn with_metadata_header(script: &CustomScript, content: &str) -> String {
+    let meta = metadata::parse(content);
+    let mut header = String::new();
+    if meta.name.is_none() {
+        header.push_str(&format!("# Name: {}\n", script.name));
+    }
+    if meta.descriptions.is_empty() {
+        if let Some(description) = &script.description {
+            header.push_str(&format!("# Description: {}\n", description));
+        }
+        for (lang, description) in &script.descriptions {
+            header.push_str(&format!("# Description[{}]: {}\n", lang, description));
+        }
+    }
+    if header.is_empty() {
+        return content.to_string();
+    }
+    if content.starts_with("#!") {
+        let (shebang, rest) = content.split_once('\n').unwrap_or((content, ""));
+        format!("{}\n{}{}", shebang, header, rest)
+    } else {
+        format!("{}{}", header, content)
+    }
+}
+
+/// 创建 Gist 的响应中某个文件的 raw URL，它带有提交哈希，之后修改 Gist 不会影响已分享的链接
+fn raw_url(response: &Value, file_name: &str) -> Option<String> {
+    response["files"][file_name]["raw_url"].as_str().map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn custom(description: Option<&str>) -> CustomScript {
+        CustomScript {
+            name: "cleanup".to_string(),
+            description: description.map(str::to_string),
+            descriptions: BTreeMap::from([("zh".to_string(), "清理".to_string())]),
+            url: None,
+            file_path: None,
+            enabled: true,
+            last_updated: None,
+            interpreter: None,
+            working_dir: None,
+            keep_artifacts: false,
+        }
+    }
+
+    #[test]
+    fn test_header_added_after_shebang() {
+        let content = with_metadata_header(&custom(Some("Clean caches")), "#!/bin/sh\necho hi\n");
+        assert_eq!(
+            content,
+            "#!/bin/sh\n# Name: cleanup\n# Description: Clean caches\n# Description[zh]: 清理\necho hi\n"
+        );
+        let meta = metadata::parse(&content);
+        assert_eq!(meta.name.as_deref(), Some("cleanup"));
+        assert_eq!(meta.description(Some("zh")), Some("清理"));
+    }
+
+    #[test]
+    fn test_existing_header_kept() {
+        let original = "#!/bin/sh\n# Name: mine\n# Description: Mine\necho hi\n";
+        assert_eq!(with_metadata_header(&custom(Some("Other")), original), original);
+        let content = with_metadata_header(&custom(None), "echo hi");
+        assert_eq!(content, "# Name: cleanup\n# Description[zh]: 清理\necho hi");
+    }
+
+    #[test]
+    fn test_file_name_from_script_name() {
+        let mut script = custom(None);
+        assert_eq!(file_name(&script, Path::new("/x/custom_42.py")), "cleanup.py");
+        script.name = "Disk usage/report".to_string();
+        assert_eq!(file_name(&script, Path::new("/x/custom_42")), "Disk-usage-report.sh");
+    }
+
+    #[test]
+    fn test_raw_url_from_response() {
+        let response = json!({
+            "files": { "cleanup.sh": { "raw_url": "https://gist.githubusercontent.com/u/abc/raw/123/cleanup.sh" } }
+        });
+        assert_eq!(
+            raw_url(&response, "cleanup.sh").as_deref(),
+            Some("https://gist.githubusercontent.com/u/abc/raw/123/cleanup.sh")
+        );
+        assert_eq!(raw_url(&response, "other.sh"), None);
+    }
+}
@@ -0,0 +1,197 @@
+use crate::errors::Result;
+use crate::executor::ResourceUsage;
+use crate::fileio;
+use chrono::Local;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// 运行历史文件：~/.geektools/history.json
+static HISTORY_PATH: Lazy<PathBuf> = Lazy::new(|| {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".into());
+    PathBuf::from(home).join(".geektools").join("history.json")
+});
+
+/// 最多保留的运行记录数
+const MAX_RECORDS: usize = 200;
+
+/// 一次脚本运行的记录
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RunRecord {
+    pub id: u64,
+    pub script: String,
+    pub started_at: String,
+    pub duration_seconds: f64,
+    pub success: bool,
+    pub exit_code: Option<i32>,
+    #[serde(default)]
+    pub run_as: Option<String>,
+    /// 脚本中 `#@rollback` 段声明的回滚命令 (见 `scripts::metadata::parse_rollback`)
+    #[serde(default)]
+    pub rollback: Option<String>,
+    #[serde(default)]
+    pub rolled_back: bool,
+    /// CPU 时间和峰值内存 (仅 Unix 记录)
+    #[serde(default)]
+    pub usage: Option<ResourceUsage>,
+}
+
+/// 按脚本累计的运行统计，保存在 history.json 旁的 stats.json，不受 MAX_RECORDS 截断影响
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ScriptStats {
+    pub runs: u64,
+    pub failures: u64,
+    pub total_seconds: f64,
+    pub last_run: String,
+}
+
+impl ScriptStats {
+    fn add(&mut self, record: &RunRecord) {
+        self.runs += 1;
+        if !record.success {
+            self.failures += 1;
+        }
+        self.total_seconds += record.duration_seconds;
+        self.last_run = record.started_at.clone();
+    }
+}
+
+/// 读取全部运行记录，最新的在最后
+pub fn load() -> Vec<RunRecord> {
+    load_from(&HISTORY_PATH)
+}
+
+fn load_from(path: &Path) -> Vec<RunRecord> {
+    fileio::read(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// 按脚本名汇总的统计
+pub fn stats() -> BTreeMap<String, ScriptStats> {
+    stats_from(&HISTORY_PATH)
+}
+
+fn stats_path(path: &Path) -> PathBuf {
+    path.with_file_name("stats.json")
+}
+
+/// 没有 stats.json 时 (旧版本升级上来) 从现有的运行记录重建
+fn stats_from(path: &Path) -> BTreeMap<String, ScriptStats> {
+    match fileio::read(stats_path(path)) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_default(),
+        Err(_) => aggregate(&load_from(path)),
+    }
+}
+
+/// 把运行记录按脚本名汇总
+pub fn aggregate(records: &[RunRecord]) -> BTreeMap<String, ScriptStats> {
+    let mut stats: BTreeMap<String, ScriptStats> = BTreeMap::new();
+    for record in records {
+        stats.entry(record.script.clone()).or_default().add(record);
+    }
+    stats
+}
+
+fn save_to(path: &Path, records: &[RunRecord]) -> Result<()> {
+    fileio::write(path, &serde_json::to_string_pretty(records)?)
+}
+
+/// 追加一条记录并分配 id
+pub fn append(record: RunRecord) -> Result<u64> {
+    append_to(&HISTORY_PATH, record)
+}
+
+fn append_to(path: &Path, mut record: RunRecord) -> Result<u64> {
+    let mut records = load_from(path);
+    record.id = records.last().map_or(1, |last| last.id + 1);
+    if record.started_at.is_empty() {
+        record.started_at = Local::now().to_rfc3339();
+    }
+    let mut stats = stats_from(path);
+    stats.entry(record.script.clone()).or_default().add(&record);
+    fileio::write(stats_path(path), &serde_json::to_string_pretty(&stats)?)?;
+
+    let id = record.id;
+    records.push(record);
+    if records.len() > MAX_RECORDS {
+        records.drain(..records.len() - MAX_RECORDS);
+    }
+    save_to(path, &records)?;
+    Ok(id)
+}
+
+/// 标记某次运行已回滚
+pub fn mark_rolled_back(id: u64) -> Result<()> {
+    mark_rolled_back_in(&HISTORY_PATH, id)
+}
+
+fn mark_rolled_back_in(path: &Path, id: u64) -> Result<()> {
+    let mut records = load_from(path);
+    if let Some(record) = records.iter_mut().find(|r| r.id == id) {
+        record.rolled_back = true;
+    }
+    save_to(path, &records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn record(script: &str) -> RunRecord {
+        RunRecord {
+            id: 0,
+            script: script.to_string(),
+            started_at: String::new(),
+            duration_seconds: 1.0,
+            success: true,
+            exit_code: Some(0),
+            run_as: None,
+            rollback: None,
+            rolled_back: false,
+            usage: None,
+        }
+    }
+
+    #[test]
+    fn test_append_and_mark_rolled_back() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("history.json");
+
+        assert_eq!(append_to(&path, record("a.sh")).unwrap(), 1);
+        assert_eq!(append_to(&path, record("b.sh")).unwrap(), 2);
+        mark_rolled_back_in(&path, 2).unwrap();
+
+        let records = load_from(&path);
+        assert_eq!(records.len(), 2);
+        assert!(!records[0].rolled_back);
+        assert!(records[1].rolled_back);
+        assert!(!records[0].started_at.is_empty());
+    }
+
+    #[test]
+    fn test_stats_outlive_truncated_history() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("history.json");
+        // 旧版本只有 history.json：第一次追加时从记录重建统计
+        save_to(&path, &[record("a.sh")]).unwrap();
+
+        let mut failed = record("a.sh");
+        failed.success = false;
+        failed.duration_seconds = 2.5;
+        append_to(&path, failed).unwrap();
+        for _ in 0..MAX_RECORDS {
+            append_to(&path, record("b.sh")).unwrap();
+        }
+
+        assert_eq!(load_from(&path).len(), MAX_RECORDS);
+        let stats = stats_from(&path);
+        assert_eq!(stats["a.sh"].runs, 2);
+        assert_eq!(stats["a.sh"].failures, 1);
+        assert_eq!(stats["a.sh"].total_seconds, 3.5);
+        assert_eq!(stats["b.sh"].runs, MAX_RECORDS as u64);
+    }
+}
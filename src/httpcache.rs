@@ -0,0 +1,268 @@
+use crate::errors::Result;
+use crate::fileio;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// HTTP 响应缓存目录：~/.geektools/cache/http/
+static HTTP_CACHE_DIR: Lazy<PathBuf> = Lazy::new(|| {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".into());
+    PathBuf::from(home).join(".geektools").join("cache").join("http")
+});
+
+/// 缓存的 GET 响应及其校验器，下次请求时用于条件请求
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CachedResponse {
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+    pub body: String,
+}
+
+impl CachedResponse {
+    /// 条件请求头：`If-None-Match` / `If-Modified-Since`
+    pub fn conditional_headers(&self) -> Vec<(&'static str, String)> {
+        validator_headers(&self.etag, &self.last_modified)
+    }
+}
+
+fn validator_headers(etag: &Option<String>, last_modified: &Option<String>) -> Vec<(&'static str, String)> {
+    let mut headers = Vec::new();
+    if let Some(etag) = etag {
+        headers.push(("if-none-match", etag.clone()));
+    }
+    if let Some(last_modified) = last_modified {
+        headers.push(("if-modified-since", last_modified.clone()));
+    }
+    headers
+}
+
+/// 最多缓存的下载文件数，超出时删除最久未用的
+#[cfg(any(feature = "plugins", feature = "self-update"))]
+const MAX_CACHED_FILES: usize = 20;
+
+/// 缓存的下载文件 (插件包等) 的元数据，文件内容保存在同名的 `.bin` 中
+#[cfg(any(feature = "plugins", feature = "self-update"))]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CachedFile {
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+    /// 服务器声明的 SHA-256，缓存命中时交给调用方校验
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+    /// 按 `Cache-Control: max-age` 算出的过期时间 (Unix 秒)，之前不必请求服务器
+    #[serde(default)]
+    pub fresh_until: i64,
+}
+
+#[cfg(any(feature = "plugins", feature = "self-update"))]
+impl CachedFile {
+    pub fn is_fresh(&self, now: i64) -> bool {
+        now < self.fresh_until
+    }
+
+    /// 条件请求头：`If-None-Match` / `If-Modified-Since`
+    pub fn conditional_headers(&self) -> Vec<(&'static str, String)> {
+        validator_headers(&self.etag, &self.last_modified)
+    }
+}
+
+/// `Cache-Control` 中与客户端缓存有关的指令
+#[cfg(any(feature = "plugins", feature = "self-update"))]
+#[derive(Debug, Default, PartialEq)]
+pub struct CacheControl {
+    pub no_store: bool,
+    /// `no-cache` 时为 0：可以缓存，但每次使用前都要向服务器确认
+    pub max_age: u64,
+}
+
+#[cfg(any(feature = "plugins", feature = "self-update"))]
+impl CacheControl {
+    pub fn parse(value: Option<&str>) -> Self {
+        let mut control = CacheControl::default();
+        let mut no_cache = false;
+        for directive in value.unwrap_or_default().split(',').map(|d| d.trim().to_ascii_lowercase()) {
+            match directive.split_once('=') {
+                Some(("max-age", age)) => control.max_age = age.trim_matches('"').parse().unwrap_or(0),
+                _ if directive == "no-store" => control.no_store = true,
+                _ if directive == "no-cache" => no_cache = true,
+                _ => {}
+            }
+        }
+        if no_cache {
+            control.max_age = 0;
+        }
+        control
+    }
+}
+
+/// 下载文件缓存目录：~/.geektools/cache/http/files/
+#[cfg(all(feature = "marketplace", not(test)))]
+pub fn files_dir() -> PathBuf {
+    HTTP_CACHE_DIR.join("files")
+}
+
+/// 在缓存目录 `dir` 中查找 URL 的缓存文件，返回元数据和文件路径
+#[cfg(any(feature = "plugins", feature = "self-update"))]
+pub fn lookup_file(dir: &Path, url: &str) -> Option<(CachedFile, PathBuf)> {
+    let meta_path = entry_path(dir, url);
+    let entry: CachedFile = serde_json::from_str(&std::fs::read_to_string(&meta_path).ok()?).ok()?;
+    let data = meta_path.with_extension("bin");
+    (entry.url == url && data.is_file()).then_some((entry, data))
+}
+
+/// 把下载完成的文件复制进缓存；`no-store` 或者既不能重新验证也没有有效期时不缓存
+#[cfg(any(feature = "plugins", feature = "self-update"))]
+pub fn store_file(dir: &Path, url: &str, entry: &CachedFile, control: &CacheControl, path: &Path) -> Result<()> {
+    if control.no_store || (entry.etag.is_none() && entry.last_modified.is_none() && control.max_age == 0) {
+        return Ok(());
+    }
+    let meta_path = entry_path(dir, url);
+    fileio::copy(path, meta_path.with_extension("bin"))?;
+    update_file(dir, entry)?;
+    prune_files(dir);
+    Ok(())
+}
+
+/// 服务器确认未修改 (304) 后更新缓存的元数据 (有效期等)
+#[cfg(any(feature = "plugins", feature = "self-update"))]
+pub fn update_file(dir: &Path, entry: &CachedFile) -> Result<()> {
+    let meta_path = entry_path(dir, &entry.url);
+    fileio::create_dir(dir)?;
+    std::fs::write(&meta_path, serde_json::to_string(entry)?).map_err(|e| crate::errors::GeekToolsError::FileOperationError {
+        path: meta_path.display().to_string(),
+        source: e,
+    })
+}
+
+/// 只保留最近写入的 [`MAX_CACHED_FILES`] 个缓存文件
+#[cfg(any(feature = "plugins", feature = "self-update"))]
+fn prune_files(dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut files: Vec<(std::time::SystemTime, PathBuf)> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "bin"))
+        .filter_map(|p| Some((std::fs::metadata(&p).ok()?.modified().ok()?, p)))
+        .collect();
+    files.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    for (_, path) in files.into_iter().skip(MAX_CACHED_FILES) {
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(path.with_extension("json"));
+    }
+}
+
+/// 缓存文件名取 URL 的 SHA-256 前 16 位
+fn entry_path(dir: &Path, url: &str) -> PathBuf {
+    let digest = Sha256::digest(url.as_bytes());
+    let name: String = digest.iter().take(8).map(|b| format!("{:02x}", b)).collect();
+    dir.join(format!("{}.json", name))
+}
+
+/// 查找 URL 的缓存响应
+pub fn lookup(url: &str) -> Option<CachedResponse> {
+    lookup_in(&HTTP_CACHE_DIR, url)
+}
+
+fn lookup_in(dir: &Path, url: &str) -> Option<CachedResponse> {
+    let entry: CachedResponse = serde_json::from_str(&fileio::read(entry_path(dir, url)).ok()?).ok()?;
+    // 哈希碰撞时视为未缓存
+    (entry.url == url).then_some(entry)
+}
+
+/// 保存响应；服务器没有给出 ETag 或 Last-Modified 时无法做条件请求，不缓存
+pub fn store(url: &str, etag: Option<String>, last_modified: Option<String>, body: &str) -> Result<()> {
+    store_in(&HTTP_CACHE_DIR, url, etag, last_modified, body)
+}
+
+fn store_in(dir: &Path, url: &str, etag: Option<String>, last_modified: Option<String>, body: &str) -> Result<()> {
+    if etag.is_none() && last_modified.is_none() {
+        return Ok(());
+    }
+    let entry = CachedResponse {
+        url: url.to_string(),
+        etag,
+        last_modified,
+        body: body.to_string(),
+    };
+    fileio::write(entry_path(dir, url), &serde_json::to_string(&entry)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const URL: &str = "https://api.github.com/repos/a/b/releases";
+
+    #[test]
+    fn test_store_and_lookup() {
+        let dir = TempDir::new().unwrap();
+        assert!(lookup_in(dir.path(), URL).is_none());
+
+        store_in(dir.path(), URL, Some("\"abc\"".to_string()), None, "[]").unwrap();
+        let entry = lookup_in(dir.path(), URL).unwrap();
+        assert_eq!(entry.body, "[]");
+        assert_eq!(entry.conditional_headers(), vec![("if-none-match", "\"abc\"".to_string())]);
+        assert!(lookup_in(dir.path(), "https://example.com/other").is_none());
+    }
+
+    #[cfg(any(feature = "plugins", feature = "self-update"))]
+    #[test]
+    fn test_cache_control() {
+        assert_eq!(CacheControl::parse(Some("public, max-age=86400")), CacheControl { no_store: false, max_age: 86400 });
+        assert_eq!(CacheControl::parse(Some("max-age=60, no-cache")).max_age, 0);
+        assert!(CacheControl::parse(Some("No-Store")).no_store);
+        assert_eq!(CacheControl::parse(None), CacheControl::default());
+    }
+
+    #[cfg(any(feature = "plugins", feature = "self-update"))]
+    #[test]
+    fn test_store_and_lookup_file() {
+        let dir = TempDir::new().unwrap();
+        let cache = dir.path().join("files");
+        let download = dir.path().join("plugin.tar.gz");
+        fileio::write_bytes(&download, b"archive").unwrap();
+        let entry = CachedFile {
+            url: URL.to_string(),
+            etag: None,
+            last_modified: None,
+            checksum: Some("abc".to_string()),
+            fresh_until: 100,
+        };
+
+        // 既不能重新验证又没有有效期
+        store_file(&cache, URL, &entry, &CacheControl::default(), &download).unwrap();
+        assert!(lookup_file(&cache, URL).is_none());
+        store_file(&cache, URL, &entry, &CacheControl { no_store: true, max_age: 60 }, &download).unwrap();
+        assert!(lookup_file(&cache, URL).is_none());
+
+        let entry = CachedFile { etag: Some("\"v1\"".to_string()), ..entry };
+        store_file(&cache, URL, &entry, &CacheControl::default(), &download).unwrap();
+        let (cached, path) = lookup_file(&cache, URL).unwrap();
+        assert_eq!(cached, entry);
+        assert!(cached.is_fresh(99) && !cached.is_fresh(100));
+        assert_eq!(std::fs::read(path).unwrap(), b"archive");
+
+        for i in 0..MAX_CACHED_FILES {
+            store_file(&cache, &format!("{}/{}", URL, i), &CachedFile { url: format!("{}/{}", URL, i), ..entry.clone() },
+                &CacheControl::default(), &download).unwrap();
+        }
+        assert_eq!(std::fs::read_dir(&cache).unwrap().count(), MAX_CACHED_FILES * 2);
+    }
+
+    #[test]
+    fn test_responses_without_validators_are_not_cached() {
+        let dir = TempDir::new().unwrap();
+        store_in(dir.path(), URL, None, None, "[]").unwrap();
+        assert!(lookup_in(dir.path(), URL).is_none());
+    }
+}
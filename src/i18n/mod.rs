@@ -1,15 +1,129 @@
+use crate::errors::{GeekToolsError, Result};
+use crate::fileio;
 use serde_json::Value;
 use std::collections::HashMap;
 use once_cell::sync::Lazy;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
 
 pub const EN_US_JSON: &str = include_str!("en_us.json");
+// `slim-i18n` 构建只内置英文，其他语言包按需下载到 ~/.geektools/lang/
+#[cfg(not(feature = "slim-i18n"))]
 pub const ZH_CN_JSON: &str = include_str!("zh_cn.json");
+#[cfg(not(feature = "slim-i18n"))]
+pub const JA_JP_JSON: &str = include_str!("ja_jp.json");
+#[cfg(not(feature = "slim-i18n"))]
+pub const KO_KR_JSON: &str = include_str!("ko_kr.json");
+#[cfg(not(feature = "slim-i18n"))]
+pub const ZH_TW_JSON: &str = include_str!("zh_tw.json");
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// 下载的语言包目录：~/.geektools/lang/
+static PACK_DIR: Lazy<PathBuf> = Lazy::new(|| {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".into());
+    PathBuf::from(home).join(".geektools").join("lang")
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub enum Language {
+    #[default]
     English,
     Chinese,
+    Japanese,
+    Korean,
+    TraditionalChinese,
+}
+
+impl Language {
+    /// 语言菜单中的顺序
+    pub const ALL: [Language; 5] = [
+        Language::English,
+        Language::Chinese,
+        Language::Japanese,
+        Language::Korean,
+        Language::TraditionalChinese,
+    ];
+
+    /// 配置文件和脚本头部 (`# Description[zh]:`) 使用的语言代码
+    pub fn code(self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::Chinese => "zh",
+            Language::Japanese => "ja",
+            Language::Korean => "ko",
+            Language::TraditionalChinese => "zh-TW",
+        }
+    }
+
+    /// 解析配置中的语言代码；兼容旧配置的 `English` / `Chinese`，`zh_TW` 和 `zh-tw` 等写法视为相同
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code.replace('_', "-").to_ascii_lowercase().as_str() {
+            "en" | "en-us" | "english" => Some(Language::English),
+            "zh" | "zh-cn" | "zh-hans" | "chinese" => Some(Language::Chinese),
+            "ja" | "ja-jp" => Some(Language::Japanese),
+            "ko" | "ko-kr" => Some(Language::Korean),
+            "zh-tw" | "zh-hk" | "zh-hant" => Some(Language::TraditionalChinese),
+            _ => None,
+        }
+    }
+
+    /// 从 `LANG` 这类 locale (`ja_JP.UTF-8`) 推断语言，不支持的语言返回 None
+    pub fn from_locale(locale: &str) -> Option<Self> {
+        let tag = locale.split(['.', '@']).next().unwrap_or(locale);
+        Self::from_code(tag).or_else(|| Self::from_code(tag.split(['_', '-']).next().unwrap_or(tag)))
+    }
+
+    /// 语言菜单中的翻译键
+    pub fn menu_key(self) -> &'static str {
+        match self {
+            Language::English => "language_menu.english",
+            Language::Chinese => "language_menu.chinese",
+            Language::Japanese => "language_menu.japanese",
+            Language::Korean => "language_menu.korean",
+            Language::TraditionalChinese => "language_menu.traditional_chinese",
+        }
+    }
+
+    /// 缺少翻译时回退的语言：繁体中文先回退到简体中文，其余回退到英文
+    fn fallback(self) -> Option<Self> {
+        match self {
+            Language::English => None,
+            Language::TraditionalChinese => Some(Language::Chinese),
+            _ => Some(Language::English),
+        }
+    }
+
+    /// 语言包文件名，`src/i18n/` 和 `~/.geektools/lang/` 中相同
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Language::English => "en_us.json",
+            Language::Chinese => "zh_cn.json",
+            Language::Japanese => "ja_jp.json",
+            Language::Korean => "ko_kr.json",
+            Language::TraditionalChinese => "zh_tw.json",
+        }
+    }
+
+    /// 内置在程序中的翻译，`slim-i18n` 构建中只有英文
+    #[cfg(not(feature = "slim-i18n"))]
+    fn embedded(self) -> Option<&'static str> {
+        Some(match self {
+            Language::English => EN_US_JSON,
+            Language::Chinese => ZH_CN_JSON,
+            Language::Japanese => JA_JP_JSON,
+            Language::Korean => KO_KR_JSON,
+            Language::TraditionalChinese => ZH_TW_JSON,
+        })
+    }
+
+    #[cfg(feature = "slim-i18n")]
+    fn embedded(self) -> Option<&'static str> {
+        (self == Language::English).then_some(EN_US_JSON)
+    }
+
+    /// 内置或已下载，切换后界面会使用这种语言
+    pub fn is_available(self) -> bool {
+        self.embedded().is_some() || PACK_DIR.join(self.file_name()).is_file()
+    }
 }
 
 // 性能优化：按需延迟加载翻译，避免启动时解析所有JSON
@@ -17,40 +131,78 @@ static TRANSLATIONS: Lazy<Arc<RwLock<HashMap<Language, Value>>>> = Lazy::new(||
     Arc::new(RwLock::new(HashMap::new()))
 });
 
-/// 延迟加载指定语言的翻译
+/// 延迟加载指定语言的翻译：内置的优先，其次是下载的语言包；都没有时记为空，不再重复读取
 fn ensure_language_loaded(lang: Language) {
     let mut translations = TRANSLATIONS.write().unwrap();
     if !translations.contains_key(&lang) {
-        let json_content = match lang {
-            Language::English => EN_US_JSON,
-            Language::Chinese => ZH_CN_JSON,
+        let json = match lang.embedded() {
+            Some(content) => serde_json::from_str(content).ok(),
+            None => read_pack(&PACK_DIR, lang),
         };
-        
-        if let Ok(json) = serde_json::from_str(json_content) {
-            translations.insert(lang, json);
-        }
+        translations.insert(lang, json.unwrap_or(Value::Null));
     }
 }
 
-/// 翻译函数：根据 key 和参数获取翻译文本，按需加载
+fn read_pack(dir: &Path, lang: Language) -> Option<Value> {
+    let content = fileio::read(dir.join(lang.file_name())).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// 语言包的下载地址：当前版本发布标签 (`v<版本>`) 下的 `src/i18n/<文件名>`，
+/// 翻译键与程序版本一致，主分支后来增删的键不会混进来
+pub fn pack_url(lang: Language) -> String {
+    format!(
+        "https://raw.githubusercontent.com/{}/v{}/src/i18n/{}",
+        env!("CARGO_PKG_REPOSITORY"),
+        env!("CARGO_PKG_VERSION"),
+        lang.file_name()
+    )
+}
+
+/// 从 GitHub 下载语言包到 ~/.geektools/lang/，之后的翻译立即使用新的语言包
+pub fn download_pack(lang: Language) -> Result<PathBuf> {
+    let pack: Value = crate::network::get_json(&pack_url(lang))?;
+    let path = save_pack(&PACK_DIR, lang, &pack)?;
+    TRANSLATIONS.write().unwrap().remove(&lang);
+    Ok(path)
+}
+
+/// 保存语言包；内容必须是包含 `menu` 的 JSON 对象，防止把错误页面当成语言包
+fn save_pack(dir: &Path, lang: Language, pack: &Value) -> Result<PathBuf> {
+    if !pack.get("menu").is_some_and(Value::is_object) {
+        return Err(GeekToolsError::ValidationError {
+            field: lang.file_name().to_string(),
+            message: "not a language pack".to_string(),
+        });
+    }
+    fileio::create_dir(dir)?;
+    let path = dir.join(lang.file_name());
+    fileio::write(&path, &serde_json::to_string_pretty(pack)?)?;
+    Ok(path)
+}
+
+/// 翻译函数：根据 key 和参数获取翻译文本，按需加载；缺少的翻译沿 [`Language::fallback`] 回退
 pub fn t(key: &str, params: &[(&str, &str)], lang: Language) -> String {
-    // 确保语言包已加载
-    ensure_language_loaded(lang);
-    
-    let translations = TRANSLATIONS.read().unwrap();
-    
-    if let Some(lang_map) = translations.get(&lang) {
-        if let Some(text) = get_nested_value(lang_map, key) {
-            if let Some(text_str) = text.as_str() {
-                let mut result = text_str.to_string();
-                for (param_key, param_value) in params {
-                    result = result.replace(&format!("{{{}}}", param_key), param_value);
-                }
-                return result;
+    let mut current = Some(lang);
+    while let Some(lang) = current {
+        // 确保语言包已加载
+        ensure_language_loaded(lang);
+
+        let translations = TRANSLATIONS.read().unwrap();
+        if let Some(text_str) = translations
+            .get(&lang)
+            .and_then(|lang_map| get_nested_value(lang_map, key))
+            .and_then(Value::as_str)
+        {
+            let mut result = text_str.to_string();
+            for (param_key, param_value) in params {
+                result = result.replace(&format!("{{{}}}", param_key), param_value);
             }
+            return result;
         }
+        current = lang.fallback();
     }
-    
+
     // 如果翻译不存在，返回 key 本身
     key.to_string()
 }
@@ -66,3 +218,49 @@ fn get_nested_value<'a>(value: &'a Value, key: &str) -> Option<&'a Value> {
     
     Some(current)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language_codes_round_trip() {
+        for lang in Language::ALL {
+            assert_eq!(Language::from_code(lang.code()), Some(lang));
+        }
+        assert_eq!(Language::from_code("Chinese"), Some(Language::Chinese));
+        assert_eq!(Language::from_code("zh_TW"), Some(Language::TraditionalChinese));
+        assert_eq!(Language::from_code("fr"), None);
+        assert_eq!(Language::from_locale("ja_JP.UTF-8"), Some(Language::Japanese));
+        assert_eq!(Language::from_locale("zh_HK.UTF-8"), Some(Language::TraditionalChinese));
+        assert_eq!(Language::from_locale("ko_KR"), Some(Language::Korean));
+        assert_eq!(Language::from_locale("de_DE.UTF-8"), None);
+    }
+
+    #[test]
+    fn test_save_and_read_language_pack() {
+        let dir = tempfile::TempDir::new().unwrap();
+        assert!(read_pack(dir.path(), Language::Korean).is_none());
+        let pack: Value = serde_json::from_str(EN_US_JSON).unwrap();
+        let path = save_pack(dir.path(), Language::Korean, &pack).unwrap();
+        assert_eq!(path, dir.path().join("ko_kr.json"));
+        assert_eq!(read_pack(dir.path(), Language::Korean), Some(pack));
+
+        let error = save_pack(dir.path(), Language::Japanese, &serde_json::json!({"message": "Not Found"}));
+        assert!(error.unwrap_err().to_string().contains("not a language pack"));
+        assert!(!dir.path().join("ja_jp.json").exists());
+        assert!(pack_url(Language::Korean).ends_with(&format!("/v{}/src/i18n/ko_kr.json", env!("CARGO_PKG_VERSION"))));
+    }
+
+    #[test]
+    #[cfg(not(feature = "slim-i18n"))]
+    fn test_every_language_translates_the_menu() {
+        for lang in Language::ALL {
+            for key in Language::ALL.map(Language::menu_key) {
+                assert_ne!(t(key, &[], lang), key);
+            }
+        }
+        assert_eq!(t("menu.settings", &[], Language::Japanese), "設定");
+        assert_eq!(t("no.such.key", &[], Language::Korean), "no.such.key");
+    }
+}
@@ -0,0 +1,151 @@
+use crate::scripts::helpers;
+use sha2::{Digest, Sha256};
+use std::fmt::Write as _;
+
+/// 打包进安装脚本的一个脚本
+pub struct BundledScript {
+    /// 解压后的文件名
+    pub name: String,
+    pub content: String,
+    /// 指定的解释器命令 (如 `python3 -u`)，为空时按 shebang 直接执行
+    pub interpreter: Option<String>,
+}
+
+/// 生成自解压的 POSIX sh 安装脚本：在临时目录解出 gt-helpers 函数库 (通过 `GT_LIB` 导出)
+/// 和 `scripts`，按顺序运行，最后一个是主脚本并接收安装脚本的参数；任一脚本失败即停止。
+/// 运行只需要 sh 和脚本自身的解释器，不需要 geektools
+pub fn render(title: &str, scripts: &[BundledScript]) -> String {
+    let library = helpers::library();
+    let mut contents = vec![library.as_str()];
+    contents.extend(scripts.iter().map(|s| s.content.as_str()));
+    let delimiter = heredoc_delimiter(&contents);
+
+    let mut out = String::new();
+    let _ = writeln!(out, "#!/bin/sh");
+    let _ = writeln!(out, "# {} - self-extracting installer generated by geektools {}", title, env!("CARGO_PKG_VERSION"));
+    let names: Vec<&str> = scripts.iter().map(|s| s.name.as_str()).collect();
+    let _ = writeln!(out, "# Runs: {}", names.join(", "));
+    out.push_str(
+        "set -e\n\
+         GT_DIR=$(mktemp -d \"${TMPDIR:-/tmp}/geektools-installer.XXXXXX\")\n\
+         trap 'rm -rf \"$GT_DIR\"' EXIT\n\
+         trap 'exit 130' INT TERM\n\
+         GT_LIB=\"$GT_DIR/geektools.sh\"\n\
+         GEEKTOOLS_ARTIFACTS=\"$GT_DIR/artifacts\"\n\
+         export GT_LIB GEEKTOOLS_ARTIFACTS\n\
+         mkdir \"$GEEKTOOLS_ARTIFACTS\"\n",
+    );
+    write_file(&mut out, "\"$GT_LIB\"", &library, &delimiter);
+    for script in scripts {
+        let path = format!("\"$GT_DIR\"/{}", quote(&script.name));
+        write_file(&mut out, &path, &script.content, &delimiter);
+        let _ = writeln!(out, "chmod +x {}", path);
+    }
+    out.push('\n');
+    for (i, script) in scripts.iter().enumerate() {
+        let mut command: Vec<String> = script
+            .interpreter
+            .as_deref()
+            .map(|i| i.split_whitespace().map(quote).collect())
+            .unwrap_or_default();
+        command.push(format!("\"$GT_DIR\"/{}", quote(&script.name)));
+        if i + 1 == scripts.len() {
+            command.push("\"$@\"".to_string());
+        }
+        let _ = writeln!(out, "{}", command.join(" "));
+    }
+    out
+}
+
+/// 用带引号的 here-document 原样写出文件内容，不做变量展开
+fn write_file(out: &mut String, path: &str, content: &str, delimiter: &str) {
+    let _ = writeln!(out, "\ncat > {} <<'{}'", path, delimiter);
+    out.push_str(content);
+    if !content.ends_with('\n') {
+        out.push('\n');
+    }
+    let _ = writeln!(out, "{}", delimiter);
+}
+
+/// 选一个不会作为整行出现在任何内容中的 here-document 结束标记
+fn heredoc_delimiter(contents: &[&str]) -> String {
+    let mut hasher = Sha256::new();
+    for content in contents {
+        hasher.update(content.as_bytes());
+    }
+    let digest = hasher.finalize();
+    let base = format!("GEEKTOOLS_EOF_{:02x}{:02x}{:02x}{:02x}", digest[0], digest[1], digest[2], digest[3]);
+    let taken = |candidate: &str| contents.iter().any(|c| c.lines().any(|line| line == candidate));
+    let mut delimiter = base.clone();
+    let mut n = 1;
+    while taken(&delimiter) {
+        delimiter = format!("{}_{}", base, n);
+        n += 1;
+    }
+    delimiter
+}
+
+/// 单引号转义，用于命令行中的文件名和解释器参数
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    fn script(name: &str, content: &str) -> BundledScript {
+        BundledScript { name: name.to_string(), content: content.to_string(), interpreter: None }
+    }
+
+    fn run(installer: &str, args: &[&str]) -> (bool, String) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("installer.sh");
+        std::fs::write(&path, installer).unwrap();
+        let output = Command::new("sh").arg(&path).args(args).env("TMPDIR", dir.path()).output().unwrap();
+        // 临时目录在退出时被清理，只剩安装脚本本身
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+        (output.status.success(), String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    #[test]
+    fn test_runs_dependencies_then_main_with_args() {
+        let installer = render(
+            "deploy",
+            &[
+                script("dep.sh", "#!/bin/sh\necho dep\n"),
+                script("deploy's main.sh", "#!/bin/sh\n. \"$GT_LIB\"\ngt_info \"main $1 $2\"\n[ -d \"$GEEKTOOLS_ARTIFACTS\" ]"),
+            ],
+        );
+        let (ok, stdout) = run(&installer, &["a b", "c"]);
+        assert!(ok);
+        assert_eq!(stdout, "dep\nmain a b c\n");
+    }
+
+    #[test]
+    fn test_failure_stops_installer() {
+        let installer = render("x", &[script("dep.sh", "#!/bin/sh\nexit 3\n"), script("main.sh", "#!/bin/sh\necho main\n")]);
+        let (ok, stdout) = run(&installer, &[]);
+        assert!(!ok);
+        assert!(stdout.is_empty());
+    }
+
+    #[test]
+    fn test_content_is_written_verbatim() {
+        let content = "#!/bin/sh\ncat <<'GEEKTOOLS_EOF'\n$HOME `id` \\n\nGEEKTOOLS_EOF\n";
+        let mut bundled = script("main", content);
+        bundled.interpreter = Some("sh".to_string());
+        let (ok, stdout) = run(&render("x", &[bundled]), &[]);
+        assert!(ok);
+        assert_eq!(stdout, "$HOME `id` \\n\n");
+    }
+
+    #[test]
+    fn test_delimiter_avoids_content_lines() {
+        let first = heredoc_delimiter(&["echo hi\n"]);
+        let content = format!("echo hi\n{}\n", first);
+        let second = heredoc_delimiter(&[&content]);
+        assert!(!content.lines().any(|line| line == second));
+    }
+}
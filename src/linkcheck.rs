@@ -0,0 +1,146 @@
+use crate::config::Config;
+use crate::errors::Result;
+use crate::network;
+use crate::scripts;
+
+/// 被检查链接的来源，决定给出的修复建议
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinkSource {
+    /// 内置 `.link` 脚本
+    Builtin(String),
+    /// 来自 URL 的自定义脚本 (名称)
+    CustomScript(String),
+    /// 插件市场 API
+    #[cfg(feature = "marketplace")]
+    Marketplace,
+}
+
+/// 一个待检查的链接
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkTarget {
+    pub source: LinkSource,
+    pub url: String,
+}
+
+/// 链接检查结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinkHealth {
+    Ok,
+    /// 404 或 410：目标已不存在
+    Dead(u16),
+    /// 其他错误状态码 (如 403、5xx)，可能只是暂时的
+    Failing(u16),
+    /// 连接失败 (DNS、TLS、超时等)
+    Unreachable(String),
+}
+
+/// 收集所有保存的链接：内置 `.link` 脚本、自定义脚本的 URL，以及插件市场的健康检查端点
+pub fn collect(config: &Config) -> Vec<LinkTarget> {
+    let mut targets: Vec<LinkTarget> = scripts::links()
+        .into_iter()
+        .map(|(name, url)| LinkTarget { source: LinkSource::Builtin(name), url })
+        .collect();
+    targets.extend(config.custom_scripts.iter().filter_map(|script| {
+        Some(LinkTarget { source: LinkSource::CustomScript(script.name.clone()), url: script.url.clone()? })
+    }));
+    #[cfg(feature = "marketplace")]
+    targets.push(LinkTarget {
+        source: LinkSource::Marketplace,
+        url: config.marketplace_config.endpoint_url("/health"),
+    });
+    targets
+}
+
+/// HEAD 检查一个链接
+pub fn check(url: &str) -> LinkHealth {
+    classify(network::probe_url(url))
+}
+
+fn classify(result: Result<u16>) -> LinkHealth {
+    match result {
+        Ok(status) if status < 400 => LinkHealth::Ok,
+        Ok(status @ (404 | 410)) => LinkHealth::Dead(status),
+        Ok(status) => LinkHealth::Failing(status),
+        Err(e) => LinkHealth::Unreachable(e.to_string()),
+    }
+}
+
+impl LinkTarget {
+    /// 报告中显示的名称
+    pub fn label(&self) -> String {
+        match &self.source {
+            LinkSource::Builtin(name) => format!("内置脚本 {}", name),
+            LinkSource::CustomScript(name) => format!("自定义脚本 {}", name),
+            #[cfg(feature = "marketplace")]
+            LinkSource::Marketplace => "插件市场".to_string(),
+        }
+    }
+
+    /// 链接有问题时的处理建议
+    pub fn suggestion(&self, health: &LinkHealth) -> &'static str {
+        let temporary = matches!(health, LinkHealth::Failing(_) | LinkHealth::Unreachable(_));
+        match (&self.source, temporary) {
+            (LinkSource::Builtin(_), true) => "可能只是暂时无法访问，稍后再试；持续失败时在设置中刷新脚本目录",
+            (LinkSource::Builtin(_), false) => "在设置中刷新脚本目录获取新地址；仍然失效时请向项目报告",
+            (LinkSource::CustomScript(_), true) => "可能只是暂时无法访问，稍后再试；持续失败时替换为新的 URL 或删除该脚本",
+            (LinkSource::CustomScript(_), false) => "替换为新的 URL，或删除该脚本",
+            #[cfg(feature = "marketplace")]
+            (LinkSource::Marketplace, _) => "检查插件市场设置中的 API 地址和端口，或稍后再试",
+        }
+    }
+}
+
+impl std::fmt::Display for LinkHealth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LinkHealth::Ok => write!(f, "OK"),
+            LinkHealth::Dead(status) => write!(f, "已失效 (HTTP {})", status),
+            LinkHealth::Failing(status) => write!(f, "HTTP {}", status),
+            LinkHealth::Unreachable(error) => write!(f, "无法连接: {}", error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::CustomScript;
+    use crate::errors::GeekToolsError;
+
+    #[test]
+    fn test_classify_status() {
+        assert_eq!(classify(Ok(200)), LinkHealth::Ok);
+        assert_eq!(classify(Ok(301)), LinkHealth::Ok);
+        assert_eq!(classify(Ok(404)), LinkHealth::Dead(404));
+        assert_eq!(classify(Ok(410)), LinkHealth::Dead(410));
+        assert_eq!(classify(Ok(503)), LinkHealth::Failing(503));
+        let error = GeekToolsError::ConfigError { message: "timeout".to_string() };
+        assert!(matches!(classify(Err(error)), LinkHealth::Unreachable(e) if e.contains("timeout")));
+    }
+
+    #[test]
+    fn test_collect_includes_builtin_and_custom_links() {
+        let mut config = Config::default();
+        config.custom_scripts.push(CustomScript {
+            name: "remote".to_string(),
+            description: None,
+            descriptions: Default::default(),
+            url: Some("https://example.com/remote.sh".to_string()),
+            file_path: Some("/tmp/remote.sh".to_string()),
+            enabled: true,
+            last_updated: None,
+            interpreter: None,
+            working_dir: None,
+            keep_artifacts: false,
+        });
+        let targets = collect(&config);
+        assert!(targets.iter().any(|t| matches!(&t.source, LinkSource::Builtin(n) if n == "cleanmac.link")
+            && t.url.starts_with("https://")));
+        assert!(targets.contains(&LinkTarget {
+            source: LinkSource::CustomScript("remote".to_string()),
+            url: "https://example.com/remote.sh".to_string(),
+        }));
+        #[cfg(feature = "marketplace")]
+        assert!(targets.iter().any(|t| t.source == LinkSource::Marketplace && t.url.ends_with("/api/v1/health")));
+    }
+}
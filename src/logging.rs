@@ -1,16 +1,25 @@
-use log::{Level, Record};
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime};
 use serde_json::{json, Value};
-use std::io::Write;
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
-use std::fs::{File, Metadata};
+use std::fs::{File, OpenOptions};
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::{Arc, Mutex};
+use flate2::read::GzDecoder;
 use flate2::write::GzEncoder;
 use flate2::Compression;
 use crate::errors::{GeekToolsError, Result};
 use serde::{Deserialize, Serialize};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::filter::{filter_fn, LevelFilter};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{self, FmtContext, FormatEvent, FormatFields, MakeWriter};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum LogLevel {
     Error = 1,
     Warn = 2,
@@ -19,14 +28,14 @@ pub enum LogLevel {
     Trace = 5,
 }
 
-impl From<Level> for LogLevel {
-    fn from(level: Level) -> Self {
-        match level {
-            Level::Error => LogLevel::Error,
-            Level::Warn => LogLevel::Warn,
-            Level::Info => LogLevel::Info,
-            Level::Debug => LogLevel::Debug,
-            Level::Trace => LogLevel::Trace,
+impl From<&Level> for LogLevel {
+    fn from(level: &Level) -> Self {
+        match *level {
+            Level::ERROR => LogLevel::Error,
+            Level::WARN => LogLevel::Warn,
+            Level::INFO => LogLevel::Info,
+            Level::DEBUG => LogLevel::Debug,
+            _ => LogLevel::Trace,
         }
     }
 }
@@ -34,11 +43,35 @@ impl From<Level> for LogLevel {
 impl From<LogLevel> for Level {
     fn from(level: LogLevel) -> Self {
         match level {
-            LogLevel::Error => Level::Error,
-            LogLevel::Warn => Level::Warn,
-            LogLevel::Info => Level::Info,
-            LogLevel::Debug => Level::Debug,
-            LogLevel::Trace => Level::Trace,
+            LogLevel::Error => Level::ERROR,
+            LogLevel::Warn => Level::WARN,
+            LogLevel::Info => Level::INFO,
+            LogLevel::Debug => Level::DEBUG,
+            LogLevel::Trace => Level::TRACE,
+        }
+    }
+}
+
+impl LogLevel {
+    /// 不区分大小写，接受 WARNING 作为 WARN 的别名
+    pub fn parse(text: &str) -> Option<Self> {
+        match text.to_ascii_uppercase().as_str() {
+            "ERROR" => Some(Self::Error),
+            "WARN" | "WARNING" => Some(Self::Warn),
+            "INFO" => Some(Self::Info),
+            "DEBUG" => Some(Self::Debug),
+            "TRACE" => Some(Self::Trace),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Error => "ERROR",
+            Self::Warn => "WARN",
+            Self::Info => "INFO",
+            Self::Debug => "DEBUG",
+            Self::Trace => "TRACE",
         }
     }
 }
@@ -49,6 +82,8 @@ pub struct LogEntry {
     pub timestamp: DateTime<Local>,
     pub level: LogLevel,
     pub module: String,
+    /// 写入时的关联 ID，见 [`begin_action`]
+    pub action: Option<String>,
     pub message: String,
     pub metadata: Option<Value>,
 }
@@ -65,6 +100,7 @@ impl LogEntry {
                 LogLevel::Trace => "TRACE",
             },
             "module": self.module,
+            "action": self.action,
             "message": self.message,
             "metadata": self.metadata
         })
@@ -72,7 +108,7 @@ impl LogEntry {
     
     pub fn to_formatted_string(&self) -> String {
         format!(
-            "[{}] {} [{}] {}{}",
+            "[{}] {} [{}] {}{}{}",
             self.timestamp.format("%Y-%m-%d %H:%M:%S%.3f"),
             match self.level {
                 LogLevel::Error => "ERROR",
@@ -82,6 +118,7 @@ impl LogEntry {
                 LogLevel::Trace => "TRACE",
             },
             self.module,
+            self.action.as_ref().map(|id| format!("#{} ", id)).unwrap_or_default(),
             self.message,
             self.metadata.as_ref()
                 .map(|m| format!(" {}", m))
@@ -113,7 +150,9 @@ impl Default for LogRotationConfig {
 pub struct LoggingConfig {
     pub level: String,           // "ERROR", "WARN", "INFO", "DEBUG", "TRACE"
     pub file_enabled: bool,      // 是否启用文件日志
-    pub console_enabled: bool,   // 是否启用控制台日志
+    pub console_enabled: bool,   // 是否启用控制台日志 (只显示警告和错误)
+    #[serde(default)]
+    pub json_enabled: bool,      // 是否另外写一份 JSON Lines 日志
     pub rotation: LogRotationConfig,
 }
 
@@ -123,6 +162,7 @@ impl Default for LoggingConfig {
             level: "INFO".to_string(),
             file_enabled: true,
             console_enabled: true,
+            json_enabled: false,
             rotation: LogRotationConfig::default(),
         }
     }
@@ -157,16 +197,21 @@ impl RotatingLogger {
                 source: e,
             })?;
         }
-        File::create(base_path).map_err(|e| GeekToolsError::FileOperationError {
+        OpenOptions::new().create(true).append(true).open(base_path).map_err(|e| GeekToolsError::FileOperationError {
             path: base_path.display().to_string(),
             source: e,
         })
     }
     
     pub fn write(&self, entry: &LogEntry) -> Result<()> {
-        let formatted = entry.to_formatted_string();
-        let bytes = formatted.as_bytes();
-        
+        self.write_line(&entry.to_formatted_string())
+    }
+
+    pub fn write_line(&self, line: &str) -> Result<()> {
+        self.append(format!("{}\n", line).as_bytes())
+    }
+
+    fn append(&self, bytes: &[u8]) -> Result<()> {
         {
             let size = self.current_size.lock().unwrap();
             if *size + bytes.len() as u64 > self.config.max_file_size {
@@ -182,10 +227,6 @@ impl RotatingLogger {
                     path: self.base_path.display().to_string(),
                     source: e,
                 })?;
-                file.write_all(b"\n").map_err(|e| GeekToolsError::FileOperationError {
-                    path: self.base_path.display().to_string(),
-                    source: e,
-                })?;
                 file.flush().map_err(|e| GeekToolsError::FileOperationError {
                     path: self.base_path.display().to_string(),
                     source: e,
@@ -195,7 +236,7 @@ impl RotatingLogger {
         
         {
             let mut size = self.current_size.lock().unwrap();
-            *size += bytes.len() as u64 + 1;
+            *size += bytes.len() as u64;
         }
         
         Ok(())
@@ -210,7 +251,7 @@ impl RotatingLogger {
         
         // 重命名文件
         let timestamp = Local::now().format("%Y%m%d_%H%M%S").to_string();
-        let rotated_path = self.base_path.with_extension(format!("log.{}", timestamp));
+        let rotated_path = PathBuf::from(format!("{}.{}", self.base_path.display(), timestamp));
         std::fs::rename(&self.base_path, &rotated_path).map_err(|e| GeekToolsError::FileOperationError {
             path: self.base_path.display().to_string(),
             source: e,
@@ -240,7 +281,7 @@ impl RotatingLogger {
             path: path.display().to_string(),
             source: e,
         })?;
-        let compressed_path = path.with_extension("log.gz");
+        let compressed_path = PathBuf::from(format!("{}.gz", path.display()));
         
         let file = File::create(&compressed_path).map_err(|e| GeekToolsError::FileOperationError {
             path: compressed_path.display().to_string(),
@@ -282,18 +323,16 @@ impl RotatingLogger {
                 })?;
                 
                 let path = entry.path();
-                if let Some(file_name) = path.file_name() {
-                    let file_name_str = file_name.to_string_lossy();
-                    if file_name_str.starts_with(&format!("{}.log.", base_name)) {
-                        if let Ok(metadata) = entry.metadata() {
-                            log_files.push((path, metadata.modified().unwrap_or(std::time::UNIX_EPOCH)));
-                        }
-                    }
+                if let Some(file_name) = path.file_name()
+                    && file_name.to_string_lossy().starts_with(&format!("{}.", base_name))
+                    && let Ok(metadata) = entry.metadata()
+                {
+                    log_files.push((path, metadata.modified().unwrap_or(std::time::UNIX_EPOCH)));
                 }
             }
             
             // 按修改时间排序，最新的在前
-            log_files.sort_by(|a, b| b.1.cmp(&a.1));
+            log_files.sort_by_key(|(_, modified)| std::cmp::Reverse(*modified));
             
             // 删除超过最大数量的文件
             if log_files.len() > self.config.max_files {
@@ -318,46 +357,326 @@ impl RotatingLogger {
     }
 }
 
-// 保留兼容性的同时添加新的日志功能
-#[macro_export]
-macro_rules! log_with_metadata {
-    ($level:ident, $msg:expr) => {
-        log::$level!("{}", $msg);
-    };
-    ($level:ident, $msg:expr, $($key:expr => $value:expr),+) => {
-        log::$level!("{} [{}]", $msg, 
-            vec![$(format!("{}={}", $key, $value)),+].join(", ")
-        );
+// ──────────────────────────────── tracing 订阅者 ────────────────────────────
+
+/// `log_only!` 事件的 target：只写文件，不显示在控制台
+pub const FILE_ONLY: &str = "file_only";
+
+/// fmt 层的事件格式：控制台和文本文件用 [`LogEntry::to_formatted_string`]，JSON 层用 [`LogEntry::to_json`]
+#[derive(Debug, Clone, Copy)]
+pub enum EntryFormat {
+    Console,
+    Text,
+    Json,
+}
+
+impl<S, N> FormatEvent<S, N> for EntryFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(&self, _ctx: &FmtContext<'_, S, N>, mut writer: Writer<'_>, event: &Event<'_>) -> std::fmt::Result {
+        let entry = LogEntry::from_event(event);
+        match self {
+            Self::Console => writeln!(writer, "{}", crate::ui::theme::render(&entry.to_formatted_string())),
+            Self::Text => writeln!(writer, "{}", entry.to_formatted_string()),
+            Self::Json => writeln!(writer, "{}", entry.to_json()),
+        }
+    }
+}
+
+impl LogEntry {
+    /// `message` 作为消息，`category` 作为模块名，其余字段放进 metadata；
+    /// 关联 ID 优先取事件的 `action` 字段 (如代替发起动作的线程记录)，否则来自当前动作
+    fn from_event(event: &Event<'_>) -> Self {
+        let mut fields = EventFields::default();
+        event.record(&mut fields);
+        Self {
+            timestamp: Local::now(),
+            level: LogLevel::from(event.metadata().level()),
+            module: fields.category.unwrap_or_else(|| event.metadata().target().to_string()),
+            action: Some(fields.action.unwrap_or_else(action_id)),
+            message: fields.message,
+            metadata: (!fields.extra.is_empty()).then_some(Value::Object(fields.extra)),
+        }
+    }
+}
+
+#[derive(Default)]
+struct EventFields {
+    message: String,
+    category: Option<String>,
+    action: Option<String>,
+    extra: serde_json::Map<String, Value>,
+}
+
+impl Visit for EventFields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "message" => self.message = value.to_string(),
+            "category" => self.category = Some(value.to_string()),
+            "action" => self.action = Some(value.to_string()),
+            name => {
+                self.extra.insert(name.to_string(), json!(value));
+            }
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        match field.name() {
+            "message" => self.message = format!("{:?}", value),
+            name => {
+                self.extra.insert(name.to_string(), json!(format!("{:?}", value)));
+            }
+        }
+    }
+}
+
+/// 让 fmt 层直接写入滚动日志，每个事件一次写入
+impl<'a> MakeWriter<'a> for RotatingLogger {
+    type Writer = &'a RotatingLogger;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self
+    }
+}
+
+impl Write for &RotatingLogger {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.append(buf).map_err(|e| std::io::Error::other(e.to_string()))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// 组装订阅者：控制台层 (只显示警告和错误，跳过 `log_only!`)、滚动文本文件层，
+/// 以及可选的 JSON 层 (与文本文件同名，扩展名 .jsonl)
+pub fn build_subscriber(config: &LoggingConfig, log_file_path: Option<PathBuf>) -> Result<impl Subscriber + Send + Sync> {
+    let console = config.console_enabled.then(|| {
+        fmt::layer()
+            .event_format(EntryFormat::Console)
+            .with_writer(std::io::stderr)
+            .with_filter(filter_fn(|metadata| {
+                LogLevel::from(metadata.level()) <= LogLevel::Warn && metadata.target() != FILE_ONLY
+            }))
+    });
+    let (file, json) = match log_file_path.filter(|_| config.file_enabled) {
+        Some(path) => {
+            let json = match config.json_enabled {
+                true => Some(
+                    fmt::layer()
+                        .event_format(EntryFormat::Json)
+                        .with_writer(RotatingLogger::new(path.with_extension("jsonl"), config.rotation.clone())?),
+                ),
+                false => None,
+            };
+            let file = fmt::layer()
+                .event_format(EntryFormat::Text)
+                .with_writer(RotatingLogger::new(path, config.rotation.clone())?);
+            (Some(file), json)
+        }
+        None => (None, None),
     };
+
+    let level = LogLevel::parse(&config.level).unwrap_or(LogLevel::Info);
+    Ok(tracing_subscriber::registry()
+        .with(LevelFilter::from_level(level.into()))
+        .with(console)
+        .with(file)
+        .with(json))
 }
 
+/// 安装全局订阅者，见 [`build_subscriber`]
 pub fn init_logging(config: &LoggingConfig, log_file_path: Option<PathBuf>) -> Result<()> {
-    let log_level = match config.level.as_str() {
-        "ERROR" => log::LevelFilter::Error,
-        "WARN" => log::LevelFilter::Warn,
-        "INFO" => log::LevelFilter::Info,
-        "DEBUG" => log::LevelFilter::Debug,
-        "TRACE" => log::LevelFilter::Trace,
-        _ => log::LevelFilter::Info,
-    };
-    
-    // 初始化 env_logger 用于控制台输出
-    if config.console_enabled {
-        env_logger::Builder::from_default_env()
-            .filter_level(log_level)
-            .init();
+    tracing::subscriber::set_global_default(build_subscriber(config, log_file_path)?)
+        .map_err(|e| GeekToolsError::ConfigError { message: e.to_string() })
+}
+
+// ──────────────────────────────── 关联 ID ──────────────────────────────────
+
+/// 当前用户动作 (一次菜单选择、一个批处理步骤或一次命令行调用) 的关联 ID；
+/// 全局而非线程局部，动作中派生的线程写的日志也带同一个 ID
+static ACTION: AtomicU32 = AtomicU32::new(0);
+
+/// 开始新的用户动作，之后的 `log_only!` 条目都带上返回的 ID
+pub fn begin_action() -> String {
+    ACTION.store(rand::random(), Ordering::Relaxed);
+    action_id()
+}
+
+/// 当前动作的关联 ID，8 位十六进制
+pub fn action_id() -> String {
+    format!("{:08x}", ACTION.load(Ordering::Relaxed))
+}
+
+// ──────────────────────────────── 日志查询 ─────────────────────────────────
+
+/// 日志目录：~/.geektools/logs，每次会话一个 `.logs` 文件，轮转后的 `.log.*` 和 `.log.gz` 也在这里
+pub fn log_dir() -> PathBuf {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".into());
+    PathBuf::from(home).join(".geektools").join("logs")
+}
+
+/// 查询的时间范围，两端都包含；None 表示不限
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TimeRange {
+    pub since: Option<NaiveDateTime>,
+    pub until: Option<NaiveDateTime>,
+}
+
+impl TimeRange {
+    pub fn contains(&self, time: NaiveDateTime) -> bool {
+        self.since.is_none_or(|since| time >= since) && self.until.is_none_or(|until| time <= until)
     }
-    
-    // 如果启用文件日志且提供了路径，初始化文件日志
-    if config.file_enabled {
-        if let Some(path) = log_file_path {
-            let _rotating_logger = RotatingLogger::new(path, config.rotation.clone())?;
-            // 注意：这里需要实现一个自定义的 Log trait 实现来同时写入文件和控制台
-            // 目前先保持简单实现
+}
+
+/// `30s`、`15m`、`1h`、`2d`、`1w` 表示 `now` 之前这么久；
+/// 也接受 `2024-05-01`、`2024-05-01 08:00:00` 和 `2024-05-01T08:00:00`
+pub fn parse_time(text: &str, now: NaiveDateTime) -> Option<NaiveDateTime> {
+    let text = text.trim();
+    if let Some(unit) = text.chars().last()
+        && let Ok(amount) = text[..text.len() - unit.len_utf8()].parse::<i64>()
+    {
+        let span = match unit {
+            's' => chrono::Duration::try_seconds(amount),
+            'm' => chrono::Duration::try_minutes(amount),
+            'h' => chrono::Duration::try_hours(amount),
+            'd' => chrono::Duration::try_days(amount),
+            'w' => chrono::Duration::try_weeks(amount),
+            _ => None,
+        }?;
+        return now.checked_sub_signed(span);
+    }
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+        .or_else(|| NaiveDate::parse_from_str(text, "%Y-%m-%d").ok()?.and_hms_opt(0, 0, 0))
+}
+
+/// 日志文件中的一行
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogLine {
+    pub timestamp: NaiveDateTime,
+    pub level: LogLevel,
+    pub category: String,
+    /// `log_only!` 写入的关联 ID，见 [`begin_action`]
+    pub action: Option<String>,
+    pub message: String,
+}
+
+impl LogLine {
+    /// 解析旧版 `log_only!` 直接写入会话日志的 `LEVEL 2024-05-01 08:00:00 CATEGORY #关联ID message`
+    /// 和 [`LogEntry::to_formatted_string`] 的 `[2024-05-01 08:00:00.000] LEVEL [module] #关联ID message`，
+    /// 关联 ID 可以没有；
+    /// 菜单回显等其他行返回 None
+    pub fn parse(line: &str) -> Option<Self> {
+        if let Some(rest) = line.strip_prefix('[') {
+            let (time, rest) = rest.split_once("] ")?;
+            let (level, rest) = rest.trim_start().split_once(' ')?;
+            let (category, message) = rest.trim_start().strip_prefix('[')?.split_once(']')?;
+            let (action, message) = split_action(message.strip_prefix(' ').unwrap_or(message));
+            return Some(Self {
+                timestamp: NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M:%S%.f").ok()?,
+                level: LogLevel::parse(level)?,
+                category: category.to_string(),
+                action,
+                message: message.to_string(),
+            });
         }
+        let mut parts = line.splitn(5, ' ');
+        let level = LogLevel::parse(parts.next()?)?;
+        let time = format!("{} {}", parts.next()?, parts.next()?);
+        let category = parts.next()?.to_string();
+        let (action, message) = split_action(parts.next().unwrap_or_default());
+        Some(Self {
+            timestamp: NaiveDateTime::parse_from_str(&time, "%Y-%m-%d %H:%M:%S").ok()?,
+            level,
+            category,
+            action,
+            message: message.to_string(),
+        })
     }
-    
-    Ok(())
+}
+
+/// 消息开头的 `#关联ID`
+fn split_action(message: &str) -> (Option<String>, &str) {
+    let is_tag = |word: &str| word.len() == 9 && word.starts_with('#') && word[1..].chars().all(|c| c.is_ascii_hexdigit());
+    match message.split_once(' ') {
+        Some((id, rest)) if is_tag(id) => (Some(id[1..].to_string()), rest),
+        _ if is_tag(message) => (Some(message[1..].to_string()), ""),
+        _ => (None, message),
+    }
+}
+
+impl std::fmt::Display for LogLine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:<5} {} {}", self.level.as_str(), self.timestamp.format("%Y-%m-%d %H:%M:%S"), self.category)?;
+        if let Some(action) = &self.action {
+            write!(f, " #{}", action)?;
+        }
+        write!(f, " {}", self.message)
+    }
+}
+
+/// 在 [`log_dir`] 下的所有日志 (含轮转和 gz 压缩的) 中查询，按时间排序；
+/// `level` 为最低严重程度，`category` 不区分大小写完全匹配，`text` 不区分大小写匹配消息内容
+pub fn query(range: &TimeRange, level: Option<LogLevel>, category: Option<&str>, text: Option<&str>) -> Vec<LogLine> {
+    query_dir(&log_dir(), range, level, category, text)
+}
+
+pub fn query_dir(
+    dir: &Path,
+    range: &TimeRange,
+    level: Option<LogLevel>,
+    category: Option<&str>,
+    text: Option<&str>,
+) -> Vec<LogLine> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|entry| {
+            // 最后修改早于起始时间的文件不可能包含范围内的条目
+            let modified = entry.metadata().and_then(|m| m.modified()).ok();
+            match (range.since, modified) {
+                (Some(since), Some(modified)) => DateTime::<Local>::from(modified).naive_local() >= since,
+                _ => true,
+            }
+        })
+        .map(|entry| entry.path())
+        .filter(|path| path.file_name().is_some_and(|name| name.to_string_lossy().contains(".log")))
+        .collect();
+    files.sort();
+
+    let text = text.map(str::to_lowercase);
+    let mut lines = Vec::new();
+    for path in files {
+        let Ok(content) = read_log_file(&path) else {
+            continue;
+        };
+        lines.extend(content.lines().filter_map(LogLine::parse).filter(|line| {
+            range.contains(line.timestamp)
+                && level.is_none_or(|level| line.level <= level)
+                && category.is_none_or(|category| line.category.eq_ignore_ascii_case(category))
+                && text.as_ref().is_none_or(|text| line.message.to_lowercase().contains(text.as_str()))
+        }));
+    }
+    lines.sort_by_key(|line| line.timestamp);
+    lines
+}
+
+fn read_log_file(path: &Path) -> std::io::Result<String> {
+    let bytes = std::fs::read(path)?;
+    if path.extension().is_some_and(|ext| ext == "gz") {
+        let mut content = Vec::new();
+        GzDecoder::new(bytes.as_slice()).read_to_end(&mut content)?;
+        return Ok(String::from_utf8_lossy(&content).into_owned());
+    }
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
 }
 
 #[cfg(test)]
@@ -371,6 +690,7 @@ mod tests {
             timestamp: Local::now(),
             level: LogLevel::Info,
             module: "test".to_string(),
+            action: None,
             message: "Test message".to_string(),
             metadata: Some(json!({"key": "value"})),
         };
@@ -401,6 +721,7 @@ mod tests {
                 timestamp: Local::now(),
                 level: LogLevel::Info,
                 module: "test".to_string(),
+                action: None,
                 message: format!("Test message {}", i),
                 metadata: None,
             };
@@ -412,4 +733,82 @@ mod tests {
         let entries: Vec<_> = std::fs::read_dir(parent_dir).unwrap().collect();
         assert!(entries.len() > 1);
     }
+
+    fn at(text: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn test_parse_time() {
+        let now = at("2024-05-01 12:00:00");
+        assert_eq!(parse_time("1h", now), Some(at("2024-05-01 11:00:00")));
+        assert_eq!(parse_time("90s", now), Some(at("2024-05-01 11:58:30")));
+        assert_eq!(parse_time("2d", now), Some(at("2024-04-29 12:00:00")));
+        assert_eq!(parse_time("2024-04-30", now), Some(at("2024-04-30 00:00:00")));
+        assert_eq!(parse_time("2024-04-30T08:15:00", now), Some(at("2024-04-30 08:15:00")));
+        assert_eq!(parse_time("1y", now), None);
+        assert_eq!(parse_time("soon", now), None);
+    }
+
+    #[test]
+    fn test_query_filters_plain_and_compressed_logs() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join("202405011200.logs"),
+            "INFO 2024-05-01 12:00:05 DOWNLOAD GET https://example.com/a.sh\n\
+             === 主菜单 ===\n\
+             ERROR 2024-05-01 12:00:07 RUNTIME #0badf00d docker 失败: exit 1\n",
+        )
+        .unwrap();
+        let mut encoder = GzEncoder::new(File::create(temp_dir.path().join("app.log.gz")).unwrap(), Compression::default());
+        encoder
+            .write_all(b"[2024-04-30 09:00:00.123] WARN  [network] Download timeout\n[2024-05-01 11:59:00.000] ERROR [network] DNS failure\n")
+            .unwrap();
+        encoder.finish().unwrap();
+
+        let all = query_dir(temp_dir.path(), &TimeRange::default(), None, None, None);
+        let messages: Vec<&str> = all.iter().map(|l| l.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec!["Download timeout", "DNS failure", "GET https://example.com/a.sh", "docker 失败: exit 1"]
+        );
+        assert_eq!(all[2].to_string(), "INFO  2024-05-01 12:00:05 DOWNLOAD GET https://example.com/a.sh");
+        assert_eq!(all[3].action.as_deref(), Some("0badf00d"));
+        assert_eq!(all[3].to_string(), "ERROR 2024-05-01 12:00:07 RUNTIME #0badf00d docker 失败: exit 1");
+
+        let range = TimeRange { since: Some(at("2024-05-01 00:00:00")), until: None };
+        let errors = query_dir(temp_dir.path(), &range, Some(LogLevel::Error), None, None);
+        assert_eq!(errors.iter().map(|l| l.category.as_str()).collect::<Vec<_>>(), vec!["network", "RUNTIME"]);
+        let warnings = query_dir(temp_dir.path(), &TimeRange::default(), Some(LogLevel::Warn), Some("NETWORK"), Some("timeout"));
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].level, LogLevel::Warn);
+    }
+
+    #[test]
+    fn test_subscriber_routes_events_to_layers() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = LoggingConfig { console_enabled: false, json_enabled: true, ..LoggingConfig::default() };
+        let subscriber = build_subscriber(&config, Some(temp_dir.path().join("app.log"))).unwrap();
+
+        // 显式给出关联 ID，不读取其他测试 (如菜单) 同时修改的全局动作
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::warn!(target: FILE_ONLY, category = "DOWNLOAD", action = "0badf00d", "timeout after {}s", 30);
+            tracing::info!(attempt = 2, "retrying");
+            tracing::debug!("filtered out by level");
+        });
+
+        let text = std::fs::read_to_string(temp_dir.path().join("app.log")).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let parsed = LogLine::parse(lines[0]).unwrap();
+        assert_eq!((parsed.level, parsed.category.as_str()), (LogLevel::Warn, "DOWNLOAD"));
+        assert_eq!(parsed.action.as_deref(), Some("0badf00d"));
+        assert_eq!(parsed.message, "timeout after 30s");
+        assert!(lines[1].ends_with(r#"retrying {"attempt":"2"}"#));
+
+        let json_lines = std::fs::read_to_string(temp_dir.path().join("app.jsonl")).unwrap();
+        let first: Value = serde_json::from_str(json_lines.lines().next().unwrap()).unwrap();
+        assert_eq!(first["module"], "DOWNLOAD");
+        assert_eq!(first["action"], "0badf00d");
+    }
 }
\ No newline at end of file
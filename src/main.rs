@@ -1,32 +1,71 @@
-mod fileio;
-mod i18n;
-mod scripts;
-mod plugins;
-mod errors;
-mod recovery;
-mod logging;
-mod config;
+// 模块为 pub(crate)，benches/perf.rs 通过 #[path] 引入本文件后在其根模块重新导出
+pub(crate) mod fileio;
+pub(crate) mod i18n;
+pub(crate) mod scripts;
+pub(crate) mod plugins;
+pub(crate) mod errors;
+pub(crate) mod recovery;
+pub(crate) mod logging;
+pub(crate) mod config;
+pub(crate) mod network;
+pub(crate) mod executor;
+pub(crate) mod cli;
+pub(crate) mod notify;
+pub(crate) mod watch;
+pub(crate) mod envdiff;
+pub(crate) mod configdiff;
+pub(crate) mod history;
+#[cfg(feature = "network")]
+pub(crate) mod httpcache;
+pub(crate) mod runtime;
+pub(crate) mod remote;
+pub(crate) mod container;
+#[cfg(feature = "scheduler")]
+pub(crate) mod service;
+pub(crate) mod startup;
+pub(crate) mod terminal;
+pub(crate) mod ui;
+pub(crate) mod batch;
+pub(crate) mod yaml;
+pub(crate) mod apply;
+pub(crate) mod search;
+pub(crate) mod stats;
+pub(crate) mod metrics;
+pub(crate) mod signature;
+pub(crate) mod ssh;
+pub(crate) mod pkg;
+pub(crate) mod updater;
+pub(crate) mod installer;
+#[cfg(feature = "network")]
+pub(crate) mod gist;
+#[cfg(feature = "network")]
+pub(crate) mod linkcheck;
+#[cfg(unix)]
+pub(crate) mod rpc;
+pub(crate) mod deeplink;
 
+#[cfg(feature = "plugins")]
 use plugins::{PluginManager, MarketplaceConfig};
 use errors::{GeekToolsError, Result};
 use recovery::{RecoveryHandler, RetryConfig, execute_with_recovery};
-use logging::{LoggingConfig, init_logging};
-use config::{Config, ConfigManager, CustomScript};
+use logging::init_logging;
+use config::{load_user_config, Config, ConfigManager, CustomScript};
+use executor::{RunOptions, Verbosity, execute_script};
 
 use chrono::Local;
 use once_cell::sync::Lazy;
-#[cfg(feature = "network")]
-use reqwest::blocking::Client;
+#[cfg(any(feature = "self-update", feature = "plugins"))]
 use serde::Deserialize;
 use serde_json::{self, Value};
 use std::process::exit;
 use std::{
+    collections::BTreeMap,
     env,
     fs::File,
-    io::{self, Write},
+    io::{self, IsTerminal, Read, Write},
     path::Path,
     path::PathBuf,
-    process::{self, Command},
+    process,
     sync::Mutex,
 };
 // 读取build tag
@@ -38,12 +77,14 @@ const BUILD_TAG: &str = include_str!("./buildtag.env");
 // ────────────────────────────────────────────────────────────────────────────
 // 1️⃣ 统一的调试宏：只在 DEBUG 文ce开启时打印
 // ────────────────────────────────────────────────────────────────────────────
+#[cfg(any(feature = "self-update", all(feature = "plugins", feature = "network")))]
 static DEBUG_ENABLED: Lazy<bool> = Lazy::new(|| {
     fileio::compat::read_compat("DEBUG")
         .map(|s| s.trim() == "DEBUG=true")
         .unwrap_or(false)
 });
 
+#[cfg(any(feature = "self-update", all(feature = "plugins", feature = "network")))]
 macro_rules! debug_log {
     ($($arg:tt)*) => {
         if *DEBUG_ENABLED {
@@ -67,14 +108,17 @@ static CUSTOM_SCRIPTS_DIR: Lazy<PathBuf> = Lazy::new(|| {
     PathBuf::from(home).join(".geektools").join("custom_scripts")
 });
 
+/// 下载过的发行版缓存目录：~/.geektools/cache/releases/<tag>/
+#[cfg(feature = "self-update")]
+static RELEASE_CACHE_DIR: Lazy<PathBuf> = Lazy::new(|| {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".into());
+    PathBuf::from(home).join(".geektools").join("cache").join("releases")
+});
+
 /// 日志文件路径：~/.geektools/logs/YYYYMMDDHHMM.logs
 static LOG_FILE_PATH: Lazy<PathBuf> = Lazy::new(|| {
-    let home = std::env::var("HOME").unwrap_or_else(|_| ".".into());
     let ts = Local::now().format("%Y%m%d%H%M").to_string();
-    PathBuf::from(home)
-        .join(".geektools")
-        .join("logs")
-        .join(format!("{ts}.logs"))
+    logging::log_dir().join(format!("{ts}.logs"))
 });
 
 pub static LOG_FILE: Lazy<Mutex<File>> = Lazy::new(|| {
@@ -94,13 +138,17 @@ pub static LOG_FILE: Lazy<Mutex<File>> = Lazy::new(|| {
     Mutex::new(file)
 });
 
+// 菜单输出经过 terminal 模块，测试中可以捕获
 macro_rules! log_println {
+    () => {
+        log_println!("")
+    };
     ($($arg:tt)*) => {{
         use std::io::Write;
         if let Ok(mut f) = LOG_FILE.lock() {
             let _ = writeln!(f, $($arg)*);
         }
-        println!($($arg)*);
+        terminal::write_str(&format!("{}\n", format_args!($($arg)*)));
     }};
 }
 
@@ -111,7 +159,7 @@ macro_rules! log_print {
             let _ = write!(f, $($arg)*);
             let _ = f.flush();
         }
-        print!($($arg)*);
+        terminal::write_str(&format!($($arg)*));
     }};
 }
 
@@ -121,18 +169,21 @@ macro_rules! log_eprintln {
         if let Ok(mut f) = LOG_FILE.lock() {
             let _ = writeln!(f, $($arg)*);
         }
-        eprintln!($($arg)*);
+        eprintln!("{}", ui::theme::render(&format!($($arg)*)));
     }};
 }
 
-// 仅记录到日志文件的宏（不输出到控制台）
+// 仅记录到日志文件的宏（不输出到控制台），带当前动作的关联 ID；
+// 迁移期间保留原来的写法，实际发出 tracing 事件，由 logging 模块的订阅者写入滚动日志
 #[macro_export]
 macro_rules! log_only {
     ($level:expr, $category:expr, $($arg:tt)*) => {{
-        use std::io::Write;
-        if let Ok(mut f) = LOG_FILE.lock() {
-            let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
-            let _ = writeln!(f, "{} {} {} {}", $level, timestamp, $category, format!($($arg)*));
+        let message = format!($($arg)*);
+        match $level {
+            "ERROR" => tracing::error!(target: $crate::logging::FILE_ONLY, category = $category, "{}", message),
+            "WARN" => tracing::warn!(target: $crate::logging::FILE_ONLY, category = $category, "{}", message),
+            "DEBUG" => tracing::debug!(target: $crate::logging::FILE_ONLY, category = $category, "{}", message),
+            _ => tracing::info!(target: $crate::logging::FILE_ONLY, category = $category, "{}", message),
         }
     }};
 }
@@ -140,6 +191,8 @@ macro_rules! log_only {
 /// 应用程序状态 - 精简版，移除未使用字段
 struct AppState {
     current_language: Language,
+    /// 脚本运行的默认输出级别，来自命令行参数
+    verbosity: Verbosity,
 }
 
 impl AppState {
@@ -149,27 +202,29 @@ impl AppState {
         
         Ok(Self {
             current_language,
+            verbosity: Verbosity::Normal,
         })
     }
     
     /// 快速语言检测，避免复杂的配置加载
     fn detect_language_fast() -> Language {
-        // 1. 检查现有配置文件（轻量级）
-        if let Ok(content) = std::fs::read_to_string(&*CONFIG_PATH) {
-            // 匹配实际保存的格式
-            if content.contains("\"language\":\"zh\"") || 
-               content.contains("\"language\":\"Chinese\"") ||
-               content.contains("\"language\": \"zh\"") || 
-               content.contains("\"language\": \"Chinese\"") {
-                return Language::Chinese;
-            }
+        // 1. 检查现有配置文件（轻量级，只取 language 字段）
+        #[derive(serde::Deserialize)]
+        struct LanguageOnly {
+            language: Option<String>,
         }
-        
+        if let Ok(content) = std::fs::read_to_string(&*CONFIG_PATH)
+            && let Ok(LanguageOnly { language: Some(code) }) = serde_json::from_str(&content)
+            && let Some(language) = Language::from_code(&code)
+        {
+            return language;
+        }
+
         // 2. 检查环境变量作为备选
-        if let Ok(lang) = env::var("LANG") {
-            if lang.starts_with("zh") || lang.contains("CN") {
-                return Language::Chinese;
-            }
+        if let Ok(lang) = env::var("LANG")
+            && let Some(language) = Language::from_locale(&lang)
+        {
+            return language;
         }
         
         // 3. 默认英文
@@ -196,97 +251,43 @@ impl AppState {
         t(key_path, &params, self.current_language)
     }
 
-    // 主菜单文本
-    fn get_menu_text(&self) -> String {
-        format!(
-            "\n{}\n1. {}\n2. {}\n3. {}\n4. {}\n5. {}\n6. {}\n{}",
-            self.get_translation("menu.title"),
-            self.get_translation("menu.run_existing_script"),
-            self.get_translation("menu.run_script_from_network"),
-            self.get_translation("menu.custom_scripts"),
-            self.get_translation("menu.plugin_management"),
-            self.get_translation("menu.settings"),
-            self.get_translation("menu.exit"),
-            self.get_translation("menu.prompt_extended")
-        )
-    }
-
-    // 语言切换菜单
-    fn get_language_menu_text(&self) -> String {
-        format!(
-            "\n{}\n1. {}\n2. {}\n{}",
-            self.get_translation("language_menu.title"),
-            self.get_translation("language_menu.english"),
-            self.get_translation("language_menu.chinese"),
-            self.get_translation("language_menu.prompt")
-        )
+    // 输出本地化错误信息和恢复建议
+    fn print_error(&self, error: &GeekToolsError) {
+        log_only!("ERROR", "APP", "{}", error);
+        log_println!("❌ {}", error.user_friendly_message(self.current_language));
+        for suggestion in error.recovery_suggestions(self.current_language) {
+            log_println!("   💡 {}", suggestion);
+        }
     }
 
-    // 设置菜单
-    fn get_settings_menu_text(&self) -> String {
-        format!(
-            "\n{}\n1. {}\n2. {}\n3. {}\n4. {}\n{}",
-            self.get_translation("settings_menu.title"),
-            self.get_translation("settings_menu.change_language"),
-            self.get_translation("settings_menu.change_version"),
-            self.get_translation("settings_menu.clear_personalization"),
-            self.get_translation("settings_menu.back"),
-            self.get_translation("settings_menu.prompt")
-        )
+    // 把一组翻译键转为菜单项
+    fn menu_items(&self, keys: &[&str]) -> Vec<String> {
+        keys.iter().map(|key| self.get_translation(key)).collect()
     }
 
-    // 插件管理菜单
-    fn get_plugin_menu_text(&self) -> String {
-        format!(
-            "\n{}\n1. {}\n2. {}\n3. {}\n4. {}\n5. {}\n6. {}\n7. {}\n{}",
-            self.get_translation("plugin_menu.title"),
-            self.get_translation("plugin_menu.marketplace"),
-            self.get_translation("plugin_menu.local_scan"),
-            self.get_translation("plugin_menu.install"),
-            self.get_translation("plugin_menu.list"),
-            self.get_translation("plugin_menu.uninstall"),
-            self.get_translation("plugin_menu.toggle"),
-            self.get_translation("plugin_menu.back"),
-            self.get_translation("plugin_menu.prompt_extended")
-        )
+    // 进入菜单，面包屑显示为该翻译键的文本
+    fn enter_menu(&self, name_key: &str) -> ui::menu::Crumb {
+        ui::menu::enter(&self.get_translation(name_key))
     }
 
-    // 自定义脚本管理菜单
-    fn get_custom_scripts_menu_text(&self) -> String {
-        format!(
-            "\n{}\n1. {}\n2. {}\n3. {}\n4. {}\n{}",
-            self.get_translation("custom_script_menu.title"),
-            self.get_translation("custom_script_menu.add"),
-            self.get_translation("custom_script_menu.list"),
-            self.get_translation("custom_script_menu.remove"),
-            self.get_translation("custom_script_menu.back"),
-            self.get_translation("custom_script_menu.prompt")
-        )
+    // 显示编号菜单，返回选中项的下标；None 表示离开当前菜单 (返回、回到主菜单或退出)
+    fn choose(&self, title_key: &str, item_keys: &[&str]) -> Option<usize> {
+        ui::menu::choose(&self.get_translation(title_key), &self.menu_items(item_keys), self.current_language)
     }
 }
 
-/// 查询 IP-API 的返回结构
-#[derive(Deserialize)]
-struct IpApiResp {
-    #[serde(rename = "countryCode")]
-    country_code: String,
-}
-
-/// 加载或初始化用户语言 (legacy function for backward compatibility)
-fn load_or_init_language() -> Language {
-    AppState::detect_language_fast()
-}
-
-
+#[cfg(any(feature = "self-update", feature = "plugins"))]
 #[derive(Deserialize)]
 struct GhAsset {
     browser_download_url: String,
     name: String,
 }
 
+#[cfg(any(feature = "self-update", feature = "plugins"))]
 #[derive(Deserialize)]
 struct GhRelease {
     tag_name: String,
+    #[cfg(feature = "self-update")]
     prerelease: bool,
     assets: Vec<GhAsset>,
 }
@@ -295,6 +296,7 @@ struct GhRelease {
 // ────────────────────────────────────────────────────────────────────────────
 // 2️⃣ fetch_releases 内全部 println! → debug_log!
 // ────────────────────────────────────────────────────────────────────────────
+#[cfg(feature = "self-update")]
 fn fetch_releases() -> std::result::Result<Vec<GhRelease>, GeekToolsError> {
     #[cfg(not(feature = "network"))]
     return Err(GeekToolsError::ConfigError {
@@ -307,26 +309,7 @@ fn fetch_releases() -> std::result::Result<Vec<GhRelease>, GeekToolsError> {
         let url = format!("https://api.github.com/repos/{repo}/releases");
         debug_log!("[DEBUG] 即将请求 GitHub API: {url}");
 
-        let client = reqwest::blocking::Client::builder()
-            .user_agent(format!(
-                "geektools/{} (+{})",
-                env!("CARGO_PKG_VERSION"),
-                "PeterFujiyu/geektools"
-            ))
-            .build()?;
-
-        let resp = client
-            .get(&url)
-            .send()?;
-        debug_log!("[DEBUG] 收到响应，状态码: {}", resp.status());
-
-        if !resp.status().is_success() {
-            return Err(GeekToolsError::ConfigError {
-                message: format!("HTTP non-success status: {}", resp.status()),
-            });
-        }
-
-        let text = resp.text()?;
+        let text = network::github_get(&url)?;
         debug_log!("[DEBUG] 响应体长度: {}", text.len());
 
         let releases: Vec<GhRelease> = serde_json::from_str(&text)?;
@@ -336,58 +319,80 @@ fn fetch_releases() -> std::result::Result<Vec<GhRelease>, GeekToolsError> {
     }
 }
 
-fn asset_name() -> Option<&'static str> {
-    match (env::consts::OS, env::consts::ARCH) {
-        ("macos", _) => Some("geektools-macos-universal"),
-        ("linux", "x86_64") => Some("geektools-linux-x64"),
-        ("linux", "aarch64") => Some("geektools-linux-arm64"),
-        _ => None,
-    }
+/// 版本缓存中的二进制路径，tag 或文件名不安全时返回 None
+#[cfg(feature = "self-update")]
+fn release_cache_path(tag: &str, name: &str) -> Option<PathBuf> {
+    let safe = |part: &str| !part.is_empty() && part != "." && part != ".." && !part.contains(['/', '\\']);
+    (safe(tag) && safe(name)).then(|| RELEASE_CACHE_DIR.join(tag).join(name))
 }
 
-fn download_and_replace(url: &str) -> std::result::Result<(), GeekToolsError> {
-    #[cfg(not(feature = "network"))]
-    return Err(GeekToolsError::ConfigError {
-        message: "Network functionality disabled".to_string(),
-    });
-    
-    #[cfg(feature = "network")]
-    {
-        let resp = reqwest::blocking::get(url)?;
-        let bytes = resp.bytes()?;
-        let exe = env::current_exe()?;
-        let mut tmp = exe.clone();
-        tmp.set_extension("tmp");
-        fileio::write_bytes(&tmp, &bytes)?;
-        #[cfg(unix)]
-        {
-            let _ = fileio::set_executable(&tmp);
+// 下载 (或取用缓存的) 发行版二进制并替换当前程序；给出 sha256 时校验不一致的文件会被删除
+#[cfg(feature = "self-update")]
+fn download_and_replace(
+    url: &str,
+    cached: &Path,
+    sha256: Option<&str>,
+    app_state: &AppState,
+) -> std::result::Result<(), GeekToolsError> {
+    if cached.exists() {
+        log_println!("{}", app_state.get_translation("update_menu.using_cache"));
+    } else {
+        // 未完成的下载保留为 .part，下次从断点继续
+        let mut printer = network::ProgressPrinter::new();
+        let result = network::download_to_file(url, cached, &mut |done, total| printer.update(done, total));
+        printer.finish();
+        result?;
+    }
+    if let Some(expected) = sha256 {
+        let actual = network::sha256_file(cached)?;
+        if !network::checksum_matches(&actual, expected) {
+            let _ = fileio::remove_file(cached);
+            log_only!("WARN", "UPDATE", "校验失败 {}: expected={} actual={}", url, expected, actual);
+            return Err(GeekToolsError::ValidationError {
+                field: "sha256".to_string(),
+                message: format!("checksum mismatch for {} (expected {}, got {})", url, expected, actual),
+            });
         }
-        fileio::rename(&tmp, &exe)?;
-        Ok(())
     }
+
+    let exe = env::current_exe()?;
+    let mut tmp = exe.clone();
+    tmp.set_extension("tmp");
+    std::fs::copy(cached, &tmp).map_err(|e| GeekToolsError::FileOperationError {
+        path: format!("{} -> {}", cached.display(), tmp.display()),
+        source: e,
+    })?;
+    #[cfg(unix)]
+    {
+        let _ = fileio::set_executable(&tmp);
+    }
+    fileio::rename(&tmp, &exe)?;
+    Ok(())
 }
 
+#[cfg(feature = "self-update")]
 fn update_to_release(release: &GhRelease, app_state: &AppState) {
-    let name = match asset_name() {
-        Some(n) => n,
-        None => {
-            log_println!("{}", app_state.get_translation("update_menu.not_found"));
-            return;
-        }
+    let Some(name) = updater::wanted_asset(&load_user_config().update) else {
+        log_println!("{}", app_state.get_translation("update_menu.not_found"));
+        return;
     };
-    let asset = match release.assets.iter().find(|a| a.name == name) {
-        Some(a) => a,
-        None => {
-            log_println!("{}", app_state.get_translation("update_menu.not_found"));
-            return;
-        }
+    let Some(asset) = release.assets.iter().find(|a| a.name == name) else {
+        log_println!(
+            "{}",
+            app_state.get_formatted_translation("update_menu.asset_missing", &[&release.tag_name, &name])
+        );
+        return;
+    };
+    log_println!("{}", app_state.get_formatted_translation("update_menu.asset", &[&asset.name]));
+    let Some(cached) = release_cache_path(&release.tag_name, &asset.name) else {
+        log_println!("{}", app_state.get_translation("update_menu.not_found"));
+        return;
     };
     log_println!(
         "{}",
         app_state.get_formatted_translation("update_menu.downloading", &[&release.tag_name])
     );
-    match download_and_replace(&asset.browser_download_url) {
+    match download_and_replace(&asset.browser_download_url, &cached, None, app_state) {
         Ok(_) => log_println!("{}", app_state.get_translation("update_menu.success")),
         Err(e) => log_println!(
             "{}",
@@ -396,6 +401,7 @@ fn update_to_release(release: &GhRelease, app_state: &AppState) {
     }
 }
 
+#[cfg(feature = "self-update")]
 fn update_to_latest(prerelease: bool, app_state: &AppState) {
     match fetch_releases() {
         Ok(releases) => {
@@ -405,6 +411,7 @@ fn update_to_latest(prerelease: bool, app_state: &AppState) {
                 log_println!("{}", app_state.get_translation("update_menu.no_release"));
             }
         }
+        Err(e @ GeekToolsError::RateLimitError { .. }) => app_state.print_error(&e),
         Err(e) => log_println!(
             "{}",
             app_state.get_formatted_translation("update_menu.download_failed", &[&e.to_string()])
@@ -418,6 +425,7 @@ fn update_to_latest(prerelease: bool, app_state: &AppState) {
 // ────────────────────────────────────────────────────────────────────────────
 // 3️⃣ choose_other 开头同样替换
 // ────────────────────────────────────────────────────────────────────────────
+#[cfg(feature = "self-update")]
 fn choose_other(app_state: &AppState) {
     debug_log!("[DEBUG] 进入 choose_other()");
     match fetch_releases() {
@@ -446,22 +454,8 @@ fn choose_other(app_state: &AppState) {
                 &[&releases.len().to_string()],
             );
 
-            loop {
-                log_print!("{}", prompt);
-                let _ = io::stdout().flush();
-
-                let mut input = String::new();
-                if io::stdin().read_line(&mut input).is_err() {
-                    log_println!("{}", app_state.get_translation("main.invalid_choice"));
-                    continue;
-                }
-
-                let trimmed = input.trim();
-                if trimmed.eq_ignore_ascii_case("exit") {
-                    return;
-                }
-
-                if let Ok(idx) = trimmed.parse::<usize>() {
+            while let Some(input) = ui::menu::prompt(&prompt) {
+                if let Ok(idx) = input.parse::<usize>() {
                     if (1..=releases.len()).contains(&idx) {
                         let rel = &releases[idx - 1];
                         update_to_release(rel, app_state);
@@ -472,6 +466,7 @@ fn choose_other(app_state: &AppState) {
                 log_println!("{}", app_state.get_translation("main.invalid_choice"));
             }
         }
+        Err(e @ GeekToolsError::RateLimitError { .. }) => app_state.print_error(&e),
         Err(e) => {
             log_eprintln!("[DEBUG] fetch_releases() 失败: {e}");
             log_println!(
@@ -482,37 +477,62 @@ fn choose_other(app_state: &AppState) {
     }
 }
 
-fn change_version(app_state: &AppState) {
-    loop {
-        log_println!(
-            "\n{}\n1. {}\n2. {}\n3. {}",
-            app_state.get_translation("update_menu.title"),
-            app_state.get_translation("update_menu.latest"),
-            app_state.get_translation("update_menu.latest_dev"),
-            app_state.get_translation("update_menu.other")
-        );
-        log_print!("{}", app_state.get_translation("update_menu.prompt"));
-        let _ = io::stdout().flush();
-        let mut input = String::new();
-        if io::stdin().read_line(&mut input).is_err() {
-            log_println!("{}", app_state.get_translation("main.invalid_choice"));
-            continue;
-        }
-        match input.trim() {
-            "1" => {
-                update_to_latest(false, app_state);
-                break;
-            }
-            "2" => {
-                update_to_latest(true, app_state);
-                break;
-            }
-            "3" => {
-                choose_other(app_state);
-                break;
-            }
-            _ => log_println!("{}", app_state.get_translation("main.invalid_choice")),
+// 按 `update.nightly_feed` 清单切换到夜间构建；当前平台没有夜间构建时退回最新正式版
+#[cfg(feature = "self-update")]
+fn update_to_nightly(app_state: &AppState) {
+    let config = load_user_config().update;
+    let Some(feed_url) = config.nightly_feed.as_deref() else {
+        log_println!("{}", app_state.get_translation("update_menu.nightly_unconfigured"));
+        return;
+    };
+    let feed: updater::NightlyFeed = match network::get_json(feed_url) {
+        Ok(feed) => feed,
+        Err(e) => {
+            log_println!(
+                "{}",
+                app_state.get_formatted_translation("update_menu.download_failed", &[&e.to_string()])
+            );
+            return;
         }
+    };
+    log_println!(
+        "{}",
+        app_state.get_formatted_translation("update_menu.nightly_build", &[&feed.build_tag, BUILD_TAG.trim()])
+    );
+    if feed.build_tag == BUILD_TAG.trim() {
+        log_println!("{}", app_state.get_translation("update_menu.nightly_current"));
+        return;
+    }
+    let Some(asset) = updater::wanted_asset(&config).and_then(|name| feed.asset(&name)) else {
+        log_println!("{}", app_state.get_translation("update_menu.nightly_fallback"));
+        update_to_latest(false, app_state);
+        return;
+    };
+    let Some(cached) = release_cache_path(&format!("nightly-{}", feed.build_tag), &asset.name) else {
+        log_println!("{}", app_state.get_translation("update_menu.not_found"));
+        return;
+    };
+    log_println!("{}", app_state.get_formatted_translation("update_menu.asset", &[&asset.name]));
+    log_println!("{}", app_state.get_formatted_translation("update_menu.downloading", &[&feed.build_tag]));
+    match download_and_replace(&asset.url, &cached, Some(&asset.sha256), app_state) {
+        Ok(_) => log_println!("{}", app_state.get_translation("update_menu.success")),
+        Err(e) => log_println!(
+            "{}",
+            app_state.get_formatted_translation("update_menu.replace_failed", &[&e.to_string()])
+        ),
+    }
+}
+
+#[cfg(feature = "self-update")]
+fn change_version(app_state: &AppState) {
+    let _crumb = app_state.enter_menu("settings_menu.change_version");
+    let options = ["update_menu.latest", "update_menu.latest_dev", "update_menu.nightly", "update_menu.other"];
+    match app_state.choose("update_menu.title", &options) {
+        Some(0) => update_to_latest(false, app_state),
+        Some(1) => update_to_latest(true, app_state),
+        Some(2) => update_to_nightly(app_state),
+        Some(_) => choose_other(app_state),
+        None => {}
     }
 }
 
@@ -528,8 +548,8 @@ static TMP_DIR: Lazy<PathBuf> = Lazy::new(|| {
 // ──────────────────────────────── 运行本地脚本 ─────────────────────────────
 fn run_existing_script(app_state: &AppState) {
     // 性能优化：不再每次都删除临时目录，使用全局复用
-    // 1. 读取 info.json（已打包进二进制）
-    let data = match scripts::get_string("info.json") {
+    // 1. 读取脚本描述（build.rs 合并各语言后打包进二进制）
+    let data = match scripts::script_info() {
         Some(s) => s,
         None => {
             log_println!(
@@ -567,8 +587,7 @@ fn run_existing_script(app_state: &AppState) {
     let custom_scripts: Vec<(usize, &CustomScript)> = config.custom_scripts.iter().enumerate().collect();
 
     // 2.5. 加载插件脚本
-    let plugin_manager = PluginManager::new();
-    let plugin_scripts = plugin_manager.get_enabled_scripts();
+    let plugin_scripts = enabled_plugin_scripts();
 
     // 3. 计算总脚本数量
     let total_scripts = map.len() + custom_scripts.len() + plugin_scripts.len();
@@ -586,133 +605,101 @@ fn run_existing_script(app_state: &AppState) {
         app_state.get_translation("script_execution.available_scripts")
     );
 
-    // 内置脚本
+    // 内置脚本、自定义脚本、插件脚本依次编号，按显示宽度对齐
+    let lang = app_state.current_language.code();
     let names: Vec<&String> = map.keys().collect();
-    for (i, name) in names.iter().enumerate() {
-        let desc = map
-            .get(*name)
-            .and_then(|v| {
-                v.get(match app_state.current_language {
-                    Language::English => "English",
-                    Language::Chinese => "Chinese",
-                })
-            })
-            .and_then(Value::as_str)
-            .unwrap_or("");
-        log_println!("{}. {} - {}", i + 1, name, desc);
+    let mut rows: Vec<Vec<String>> = names
+        .iter()
+        .map(|name| {
+            let desc = map.get(*name).and_then(|v| scripts::catalog::localized(v, lang)).unwrap_or("");
+            vec![name.to_string(), desc.to_string()]
+        })
+        .collect();
+    for (_, script) in &custom_scripts {
+        let description = script.localized_description(lang).unwrap_or("无描述");
+        rows.push(vec![script.name.clone(), format!("{} [自定义]", description)]);
     }
-
-    // 自定义脚本
-    for (i, (_, script)) in custom_scripts.iter().enumerate() {
-        log_println!("{}. {} - {} [自定义]", names.len() + i + 1, script.name, script.description.as_deref().unwrap_or("无描述"));
+    for (name, description, _, _) in &plugin_scripts {
+        rows.push(vec![name.to_string(), format!("{} [插件]", description)]);
     }
-
-    // 插件脚本
-    for (i, (name, description, _)) in plugin_scripts.iter().enumerate() {
-        log_println!("{}. {} - {} [插件]", names.len() + custom_scripts.len() + i + 1, name, description);
+    for line in ui::layout::numbered(&rows, 1) {
+        log_println!("{}", line);
     }
 
+    log_println!("{}", app_state.get_translation("script_execution.run_as_hint"));
+
     // 5. 处理用户选择
     let prompt = app_state
         .get_formatted_translation("script_execution.run_prompt", &[&total_scripts.to_string()]);
     loop {
-        log_print!("{}", prompt);
-        let _ = io::stdout().flush();
-        let mut input = String::new();
-        if io::stdin().read_line(&mut input).is_err() {
-            log_println!("{}", app_state.get_translation("main.invalid_choice"));
-            continue;
-        }
-        let input = input.trim();
-        if input.eq_ignore_ascii_case("exit") {
+        let Some(line) = ui::menu::prompt(&prompt) else {
             log_println!(
                 "{}",
                 app_state.get_translation("script_execution.returning")
             );
             return;
-        }
-        if let Ok(idx) = input.parse::<usize>() {
+        };
+        let input = line.as_str();
+        // u<编号> 表示先选择运行用户，d<编号> 表示运行后显示环境变化，h<编号> 表示在主机组上运行，
+        // c<编号> 表示在 Docker 容器中运行，可组合
+        let index_input = input.trim_start_matches(['u', 'U', 'd', 'D', 'h', 'H', 'c', 'C']);
+        let prefix = input[..input.len() - index_input.len()].to_ascii_lowercase();
+        let (choose_user, env_diff) = (prefix.contains('u'), prefix.contains('d'));
+        let (on_hosts, in_container) = (prefix.contains('h'), prefix.contains('c'));
+        let index_input = index_input.trim();
+        if let Ok(idx) = index_input.parse::<usize>() {
             if (1..=total_scripts).contains(&idx) {
-                if idx <= names.len() {
-                    // 内置脚本
-                    let script_name = names[idx - 1];
-                    log_println!(
-                        "{}",
-                        app_state.get_formatted_translation(
-                            "script_execution.running_script",
-                            &[script_name]
-                        )
-                    );
-
-                    if script_name.ends_with(".link") {
-                        // .link 文件仍使用原有逻辑
-                        let script_path = match scripts::materialize(script_name) {
-                            Ok(p) => p,
-                            Err(e) => {
-                                log_println!(
-                                    "{}",
-                                    app_state.get_formatted_translation(
-                                        "script_execution.failed_read_info",
-                                        &[&e.to_string()]
-                                    )
-                                );
-                                return;
-                            }
-                        };
-                        run_link_script(&script_path, app_state);
+                let display_name: &str = if idx <= names.len() {
+                    names[idx - 1]
+                } else if idx <= names.len() + custom_scripts.len() {
+                    &custom_scripts[idx - names.len() - 1].1.name
+                } else {
+                    &plugin_scripts[idx - names.len() - custom_scripts.len() - 1].0
+                };
+                let options = match resolve_run_options(app_state, display_name, choose_user) {
+                    Some(options) => options.with_env_diff(env_diff),
+                    None => return,
+                };
+
+                if on_hosts || in_container {
+                    let target = if idx <= names.len() {
+                        let script_name = names[idx - 1];
+                        if script_name.ends_with(".link") {
+                            Err("链接脚本不支持在远程主机或容器中运行".to_string())
+                        } else {
+                            scripts::materialize_with_deps(script_name)
+                                .map(|paths| (paths, None))
+                                .map_err(|e| e.to_string())
+                        }
+                    } else if idx <= names.len() + custom_scripts.len() {
+                        let (_, custom_script) = custom_scripts[idx - names.len() - 1];
+                        match &custom_script.file_path {
+                            Some(file_path) => Ok((vec![PathBuf::from(file_path)], custom_script.interpreter.as_deref())),
+                            None => Err("脚本没有保存的文件路径".to_string()),
+                        }
                     } else {
-                        // .sh 文件使用新的依赖解析逻辑
-                        match scripts::materialize_with_deps(script_name) {
-                            Ok(script_paths) => {
-                                run_sh_scripts_with_deps(&script_paths, app_state);
-                            }
-                            Err(e) => {
-                                log_println!(
-                                    "{}",
-                                    app_state.get_formatted_translation(
-                                        "script_execution.failed_read_info",
-                                        &[&e.to_string()]
-                                    )
-                                );
-                                return;
-                            }
+                        let (_, _, script_path, entry) = &plugin_scripts[idx - names.len() - custom_scripts.len() - 1];
+                        Ok((vec![script_path.clone()], entry.interpreter.as_deref()))
+                    };
+                    match target {
+                        Ok((paths, interpreter)) if on_hosts => {
+                            run_on_host_group(app_state, &paths, &options.with_interpreter(interpreter));
                         }
-                    }
-                } else if idx <= names.len() + custom_scripts.len() {
-                    // 自定义脚本
-                    let custom_idx = idx - names.len() - 1;
-                    let (_, custom_script) = custom_scripts[custom_idx];
-                    log_println!(
-                        "{}",
-                        app_state.get_formatted_translation(
-                            "script_execution.running_script",
-                            &[&custom_script.name]
-                        )
-                    );
-                    match &custom_script.file_path {
-                        Some(file_path) => run_custom_script_from_file(file_path, app_state),
-                        None => {
-                            if let Some(url) = &custom_script.url {
-                                log_println!("⚠️  脚本没有保存的文件路径，正在从URL重新下载...");
-                                run_custom_script_from_url(url, app_state);
-                            } else {
-                                log_println!("❌ 脚本既没有文件路径也没有URL，无法执行");
-                            }
+                        Ok((paths, interpreter)) => {
+                            run_in_container(app_state, &paths, &options.with_interpreter(interpreter));
                         }
+                        Err(e) => log_println!("❌ {}", e),
                     }
+                    return;
+                }
+
+                if idx <= names.len() {
+                    run_builtin_script(app_state, names[idx - 1], &options);
+                } else if idx <= names.len() + custom_scripts.len() {
+                    run_custom_script(app_state, custom_scripts[idx - names.len() - 1].1, options);
                 } else {
-                    // 插件脚本
-                    let plugin_idx = idx - names.len() - custom_scripts.len() - 1;
-                    let (name, _, script_path) = &plugin_scripts[plugin_idx];
-                    log_println!(
-                        "{}",
-                        app_state.get_formatted_translation(
-                            "script_execution.running_script",
-                            &[name]
-                        )
-                    );
-                    log_println!("正在执行插件脚本: {}", script_path.file_name().unwrap_or_default().to_string_lossy());
-                    run_sh_script(script_path, app_state);
+                    let (name, _, script_path, entry) = &plugin_scripts[idx - names.len() - custom_scripts.len() - 1];
+                    run_plugin_script(app_state, name, script_path, plugin_run_options(options, entry));
                 }
                 return;
             }
@@ -727,76 +714,1281 @@ fn run_existing_script(app_state: &AppState) {
     }
 }
 
-// 根据脚本的 shebang 选择解释器执行脚本
-fn execute_script(path: &Path) -> io::Result<process::ExitStatus> {
-    if let Ok(content) = fileio::read(path) {
-        if let Some(first_line) = content.lines().next() {
-            if let Some(stripped) = first_line.strip_prefix("#!") {
-                let parts: Vec<&str> = stripped.trim().split_whitespace().collect();
-                if let Some(program) = parts.first() {
-                    let mut cmd = Command::new(program);
-                    for arg in &parts[1..] {
-                        cmd.arg(arg);
-                    }
-                    return cmd.arg(path).status();
-                }
+// 运行内置脚本：.link 脚本按链接运行，.sh 脚本连同依赖一起运行
+fn run_builtin_script(app_state: &AppState, script_name: &str, options: &RunOptions) {
+    log_println!(
+        "{}",
+        app_state.get_formatted_translation(
+            "script_execution.running_script",
+            &[script_name]
+        )
+    );
+
+    let result = if script_name.ends_with(".link") {
+        // .link 文件仍使用原有逻辑
+        scripts::materialize(script_name).map(|script_path| run_link_script(&script_path, app_state, options))
+    } else {
+        // .sh 文件使用新的依赖解析逻辑
+        scripts::materialize_with_deps(script_name)
+            .map(|script_paths| run_sh_scripts_with_deps(&script_paths, app_state, options))
+    };
+    if let Err(e) = result {
+        log_println!(
+            "{}",
+            app_state.get_formatted_translation(
+                "script_execution.failed_read_info",
+                &[&e.to_string()]
+            )
+        );
+    }
+}
+
+// 运行自定义脚本；没有保存文件时从 URL 重新下载
+fn run_custom_script(app_state: &AppState, custom_script: &CustomScript, options: RunOptions) {
+    let options = options
+        .with_interpreter(custom_script.interpreter.as_deref())
+        .with_workspace(custom_script.working_dir.as_deref(), custom_script.keep_artifacts);
+    log_println!(
+        "{}",
+        app_state.get_formatted_translation(
+            "script_execution.running_script",
+            &[&custom_script.name]
+        )
+    );
+    match &custom_script.file_path {
+        Some(file_path) => run_custom_script_from_file(file_path, app_state, &options),
+        None => {
+            if let Some(url) = &custom_script.url {
+                log_println!("⚠️  脚本没有保存的文件路径，正在从URL重新下载...");
+                run_custom_script_from_url(url, app_state, &options);
+            } else {
+                log_println!("❌ 脚本既没有文件路径也没有URL，无法执行");
             }
         }
     }
-    Command::new("sh").arg(path).status()
 }
 
-// 直接执行 .sh
-fn run_sh_script(path: &Path, app_state: &AppState) {
-    match execute_script(path) {
-        Ok(status) if !status.success() => log_println!(
-            "{}",
-            app_state.get_formatted_translation("url_script.failed_status", &[&status.to_string()])
-        ),
-        Err(e) => log_println!(
-            "{}",
-            app_state.get_formatted_translation("url_script.failed_execute", &[&e.to_string()])
-        ),
-        _ => {}
-    }
+// 已启用插件中的脚本：(显示名, 描述, 脚本路径, 脚本条目)
+#[cfg(feature = "plugins")]
+fn enabled_plugin_scripts() -> Vec<(String, String, PathBuf, plugins::ScriptEntry)> {
+    PluginManager::new().get_enabled_scripts()
 }
 
-// 运行自定义脚本（从文件）
-fn run_custom_script_from_file(file_path: &str, app_state: &AppState) {
-    let script_path = Path::new(file_path);
-    
-    if !script_path.exists() {
-        log_println!("❌ 脚本文件不存在: {}", file_path);
-        log_println!("   提示：请尝试重新添加此脚本");
+// 未编译插件功能时没有插件脚本
+#[cfg(not(feature = "plugins"))]
+fn enabled_plugin_scripts() -> Vec<(String, String, PathBuf, plugins::ScriptEntry)> {
+    Vec::new()
+}
+
+// 插件脚本条目中的解释器和工作目录设置
+fn plugin_run_options(options: RunOptions, entry: &plugins::ScriptEntry) -> RunOptions {
+    options
+        .with_interpreter(entry.interpreter.as_deref())
+        .with_workspace(entry.working_dir.as_deref(), entry.keep_artifacts)
+}
+
+// 运行已启用插件中的脚本
+fn run_plugin_script(app_state: &AppState, name: &str, script_path: &Path, options: RunOptions) {
+    log_println!(
+        "{}",
+        app_state.get_formatted_translation(
+            "script_execution.running_script",
+            &[name]
+        )
+    );
+    log_println!("正在执行插件脚本: {}", script_path.file_name().unwrap_or_default().to_string_lossy());
+    run_sh_script(script_path, app_state, &options);
+}
+
+// 全局搜索：同时查询内置脚本、自定义脚本、插件脚本和插件市场，合并显示，选中后直接运行或安装
+fn global_search(app_state: &AppState) {
+    let Some(query) = ui::menu::prompt(&app_state.get_translation("search.query_prompt")) else {
+        return;
+    };
+    if query.trim().is_empty() {
         return;
     }
-    
-    log_println!("正在执行自定义脚本: {}", script_path.file_name().unwrap_or_default().to_string_lossy());
-    match execute_script(script_path) {
-        Ok(status) if status.success() => {
-            log_println!("{}", app_state.get_translation("url_script.success"));
+
+    let config = load_user_config();
+    let builtin: Vec<(String, String)> = scripts::script_info()
+        .and_then(|data| serde_json::from_str::<Value>(&data).ok())
+        .and_then(|info| info.as_object().cloned())
+        .map(|map| {
+            let lang = app_state.current_language.code();
+            map.iter()
+                .map(|(name, v)| (name.clone(), scripts::catalog::localized(v, lang).unwrap_or("").to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+    let plugin_scripts = enabled_plugin_scripts();
+
+    let local_hits = || {
+        let lang = app_state.current_language.code();
+        let mut hits = Vec::new();
+        for (i, (name, description)) in builtin.iter().enumerate() {
+            hits.extend(search::Hit::matching(&query, search::Source::Builtin, i, name, description));
         }
-        Ok(status) => {
-            log_println!("❌ 自定义脚本执行失败，退出码: {}", status);
+        for (i, script) in config.custom_scripts.iter().enumerate() {
+            let description = script.localized_description(lang).unwrap_or("");
+            hits.extend(search::Hit::matching(&query, search::Source::Custom, i, &script.name, description));
+        }
+        for (i, (name, description, _, _)) in plugin_scripts.iter().enumerate() {
+            hits.extend(search::Hit::matching(&query, search::Source::Plugin, i, name, description));
         }
+        hits
+    };
+    // 市场查询走网络，与本地搜索同时进行
+    #[cfg(feature = "marketplace")]
+    let (mut hits, market) = std::thread::scope(|scope| {
+        let market = scope.spawn(|| -> Result<Vec<plugins::MarketplacePlugin>> {
+            Ok(plugins::MarketplaceClient::new(config.marketplace_config.clone())?.search_plugins(&query)?.plugins)
+        });
+        (local_hits(), market.join().unwrap_or_else(|_| Ok(Vec::new())))
+    });
+    #[cfg(not(feature = "marketplace"))]
+    let hits = local_hits();
+
+    #[cfg(feature = "marketplace")]
+    let market = match market {
+        Ok(market) => market,
         Err(e) => {
-            log_println!("❌ 自定义脚本执行出错: {}", e);
+            log_println!("{}", app_state.get_formatted_translation("search.marketplace_failed", &[&e.to_string()]));
+            Vec::new()
+        }
+    };
+    #[cfg(feature = "marketplace")]
+    {
+        // 已安装的插件以插件脚本的形式出现
+        let installed: Vec<String> = PluginManager::new().list_installed_plugins().iter().map(|p| p.info.id.clone()).collect();
+        for (i, plugin) in market.iter().enumerate() {
+            if !installed.contains(&plugin.id) {
+                hits.push(search::Hit::remote(&query, search::Source::Marketplace, i, &plugin.name, &plugin.description));
+            }
+        }
+    }
+    let hits = search::rank(hits);
+
+    if hits.is_empty() {
+        log_println!("{}", app_state.get_formatted_translation("search.no_results", &[&query]));
+        return;
+    }
+    log_println!("{}", app_state.get_formatted_translation("search.results", &[&hits.len().to_string(), &query]));
+    let rows: Vec<Vec<String>> = hits
+        .iter()
+        .map(|hit| {
+            let label = format!("[{}]", app_state.get_translation(hit.source.label_key()));
+            vec![label, hit.name.to_string(), hit.description.to_string()]
+        })
+        .collect();
+    for line in ui::layout::numbered(&rows, 1) {
+        log_println!("{}", line);
+    }
+
+    let prompt = app_state.get_formatted_translation("search.select_prompt", &[&hits.len().to_string()]);
+    while let Some(input) = ui::menu::prompt(&prompt) {
+        let Some(hit) = input.parse::<usize>().ok().filter(|n| (1..=hits.len()).contains(n)).map(|n| &hits[n - 1]) else {
+            log_println!("{}", app_state.get_translation("main.invalid_choice"));
+            continue;
+        };
+        #[cfg(feature = "marketplace")]
+        if hit.source == search::Source::Marketplace {
+            match plugins::MarketplaceClient::new(config.marketplace_config.clone()) {
+                Ok(client) => install_market_plugin(&client, &market[hit.index], &mut PluginManager::new()),
+                Err(e) => app_state.print_error(&e),
+            }
+            return;
+        }
+        let Some(options) = resolve_run_options(app_state, &hit.name, false) else {
+            return;
+        };
+        match hit.source {
+            search::Source::Builtin => run_builtin_script(app_state, &hit.name, &options),
+            search::Source::Custom => run_custom_script(app_state, &config.custom_scripts[hit.index], options),
+            _ => {
+                let (name, _, script_path, entry) = &plugin_scripts[hit.index];
+                run_plugin_script(app_state, name, script_path, plugin_run_options(options, entry));
+            }
         }
+        return;
     }
 }
 
-// 运行自定义脚本（从URL下载，向后兼容）
-fn run_custom_script_from_url(url: &str, _app_state: &AppState) {
-    log_println!("正在从URL下载自定义脚本: {}", url);
-    
-    match download_script_content(url) {
-        Ok(content) => {
-            let file_name = format!("custom_script_{}.sh", rand::random::<u64>());
-            let mut tmp_path = TMP_DIR.clone();
-            tmp_path.push(file_name);
-            
-            if let Err(e) = fileio::write(&tmp_path, &content) {
-                log_println!("❌ 写入脚本失败: {}", e);
+// 选择主机组并在组内所有主机上并发运行脚本
+fn run_on_host_group(app_state: &AppState, scripts: &[PathBuf], options: &RunOptions) {
+    let config = load_user_config();
+    if config.host_groups.is_empty() {
+        log_println!("{}", app_state.get_translation("remote.no_groups"));
+        return;
+    }
+    let mut groups: Vec<(&String, &Vec<String>)> = config.host_groups.iter().collect();
+    groups.sort_by_key(|(name, _)| *name);
+    for (i, (name, hosts)) in groups.iter().enumerate() {
+        log_println!("{}. {} ({})", i + 1, name, hosts.join(", "));
+    }
+    let Some(input) = ui::menu::prompt(&app_state.get_translation("remote.choose_group")) else {
+        return;
+    };
+    let hosts = match input.parse::<usize>() {
+        Ok(i) if (1..=groups.len()).contains(&i) => groups[i - 1].1,
+        _ => match config.host_groups.get(&input) {
+            Some(hosts) => hosts,
+            None => {
+                log_println!("{}", app_state.get_translation("main.invalid_choice"));
+                return;
+            }
+        },
+    };
+
+    log_println!(
+        "{}",
+        app_state.get_formatted_translation("remote.running", &[&hosts.len().to_string()])
+    );
+    let targets = prepare_ssh_targets(app_state, hosts);
+    let results = remote::fan_out(&targets, scripts, options);
+    log_println!("{}", remote::render_summary(&results));
+}
+
+// 连接前的准备：首次连接的主机显示公钥指纹，确认后写入托管 known_hosts (未确认的主机会被 ssh 拒绝)；
+// 密码登录的主机提示输入密码
+fn prepare_ssh_targets(app_state: &AppState, hosts: &[String]) -> Vec<remote::Target> {
+    for scanned in ssh::unknown_hosts(hosts) {
+        let host = match scanned {
+            Ok(host) => host,
+            Err(e) => {
+                log_println!("{}", app_state.get_formatted_translation("ssh.scan_failed", &[&e.to_string()]));
+                continue;
+            }
+        };
+        log_println!("{}", app_state.get_formatted_translation("ssh.new_host", &[&host.host]));
+        for fingerprint in &host.fingerprints {
+            log_println!("   {}", fingerprint);
+        }
+        let trusted = ui::menu::prompt(&app_state.get_translation("ssh.trust_prompt"))
+            .is_some_and(|s| s.eq_ignore_ascii_case("y"));
+        if trusted && let Err(e) = ssh::trust(&host) {
+            log_println!("{}", app_state.get_formatted_translation("ssh.trust_failed", &[&e.to_string()]));
+        }
+    }
+
+    let config = load_user_config().ssh;
+    hosts
+        .iter()
+        .map(|host| {
+            let mut target = remote::Target::new(host, config.auth_for(host));
+            if target.auth.password {
+                log_print!("{}", app_state.get_formatted_translation("ssh.password_prompt", &[host]));
+                let _ = io::stdout().flush();
+                let mut password = String::new();
+                if terminal::read_hidden_line(&mut password).is_ok() {
+                    target.password = Some(password.trim_end_matches(['\r', '\n']).to_string());
+                }
+            }
+            target
+        })
+        .collect()
+}
+
+// 在 Docker 容器中运行脚本：提示输入镜像和挂载的工作目录
+fn run_in_container(app_state: &AppState, scripts: &[PathBuf], options: &RunOptions) {
+    if !container::docker_available() {
+        log_println!("{}", app_state.get_translation("container.docker_unavailable"));
+        return;
+    }
+    let config = load_user_config().container;
+    let prompt = app_state.get_formatted_translation("container.image_prompt", &[&config.default_image]);
+    let Some(image) = ui::menu::prompt_text(&prompt) else {
+        return;
+    };
+    let image = if image.is_empty() { config.default_image } else { image };
+    if !container::is_valid_image(&image) {
+        log_println!("{}", app_state.get_formatted_translation("container.invalid_image", &[&image]));
+        return;
+    }
+
+    let Some(workdir) = ui::menu::prompt_text(&app_state.get_translation("container.workdir_prompt")) else {
+        return;
+    };
+    let workdir = if workdir.is_empty() {
+        let dir = TMP_DIR.join(format!("container_{}", rand::random::<u64>()));
+        if let Err(e) = fileio::create_dir(&dir) {
+            app_state.print_error(&e);
+            return;
+        }
+        dir
+    } else {
+        match Path::new(&workdir).canonicalize() {
+            Ok(dir) => dir,
+            Err(e) => {
+                log_println!("❌ {}: {}", workdir, e);
+                return;
+            }
+        }
+    };
+
+    let container_options = container::ContainerOptions {
+        image,
+        workdir,
+        network: config.network,
+    };
+    log_println!(
+        "{}",
+        app_state.get_formatted_translation(
+            "container.running",
+            &[&container_options.image, &container_options.workdir.display().to_string()]
+        )
+    );
+    match container::run(scripts, options, &container_options) {
+        Ok(status) if status.success() => {
+            log_println!("{}", app_state.get_translation("url_script.success"));
+        }
+        Ok(status) => {
+            log_println!(
+                "{}",
+                app_state.get_formatted_translation("url_script.failed_status", &[&status.to_string()])
+            );
+        }
+        Err(e) => {
+            log_println!(
+                "{}",
+                app_state.get_formatted_translation("url_script.failed_execute", &[&e.to_string()])
+            );
+        }
+    }
+}
+
+// 命令行 `--on <组> <脚本>`：返回进程退出码，任一主机失败时为 1
+fn run_fan_out_cli(group: &str, script: &Path, verbosity: Verbosity) -> i32 {
+    let config = load_user_config();
+    let Some(hosts) = config.host_groups.get(group).filter(|hosts| !hosts.is_empty()) else {
+        eprintln!("unknown or empty host group: {}", group);
+        return 2;
+    };
+    if !script.is_file() {
+        eprintln!("script not found: {}", script.display());
+        return 2;
+    }
+    // 没有终端时无法确认主机密钥或输入密码，直接失败而不是等待输入
+    if !io::stdin().is_terminal() {
+        if let Some(host) = hosts.iter().find(|host| config.ssh.auth_for(host).password) {
+            eprintln!("password login to {} needs an interactive terminal", host);
+            return 2;
+        }
+        let unknown: Vec<String> = ssh::unknown_hosts(hosts)
+            .into_iter()
+            .map(|scanned| scanned.map_or_else(|e| e.to_string(), |host| host.host))
+            .collect();
+        if !unknown.is_empty() {
+            eprintln!("untrusted host keys, run once in a terminal to confirm them: {}", unknown.join(", "));
+            return 2;
+        }
+    }
+    let app_state = match AppState::new() {
+        Ok(state) => state,
+        Err(e) => {
+            eprintln!("Failed to initialize application: {}", e);
+            return 1;
+        }
+    };
+    let targets = prepare_ssh_targets(&app_state, hosts);
+    let options = RunOptions::from_config(&config).with_verbosity(verbosity);
+    let results = remote::fan_out(&targets, &[script.to_path_buf()], &options);
+    print!("{}", remote::render_summary(&results));
+    if results.iter().all(|r| r.success) { 0 } else { 1 }
+}
+
+// 命令行 `batch <文件>`：依次执行命令文件中的动作，每步向标准输出写一行 JSON；
+// 文件无法读取或解析时不执行任何动作并返回 2，某一步失败时立即停止并返回 1
+fn run_batch_cli(file: &Path, verbosity: Verbosity) -> i32 {
+    let steps = match fileio::read(file).map_err(|e| (0, e)).and_then(|content| batch::parse(&content)) {
+        Ok(steps) => steps,
+        Err((line, e)) => {
+            println!("{}", batch::event(line, None, &Err(e)));
+            return 2;
+        }
+    };
+    let config = load_user_config();
+    network::apply_security_config(&config.security);
+    network::set_github_token(config.github_token);
+
+    for step in &steps {
+        logging::begin_action();
+        log_only!("INFO", "BATCH", "第 {} 行: {} {}", step.line, step.action.name(), step.action.target());
+        let outcome = run_batch_action(&step.action, verbosity);
+        println!("{}", batch::event(step.line, Some(&step.action), &outcome));
+        if outcome.is_err() {
+            return 1;
+        }
+    }
+    0
+}
+
+// 执行一个 batch 动作，命令文件和控制接口共用
+fn run_batch_action(action: &batch::Action, verbosity: Verbosity) -> Result<()> {
+    match action {
+        batch::Action::Run { script, params } => batch_run_script(script, params, verbosity),
+        batch::Action::InstallPlugin { source, sha256 } => batch_install_plugin(source, sha256.as_deref()),
+        batch::Action::InstallPackage { name } => batch_install_package(name),
+        batch::Action::Set { key, value } => {
+            batch::set_config_value(&load_user_config(), key, value).and_then(|config| save_user_config(&config))
+        }
+    }
+}
+
+// 命令行 `serve --socket <路径>`：在 unix socket 上提供 JSON-RPC 控制接口，直到进程被中断；
+// 无法监听时返回 1
+#[cfg(unix)]
+fn run_serve_cli(socket: &Path, verbosity: Verbosity) -> i32 {
+    let config = load_user_config();
+    network::apply_security_config(&config.security);
+    network::set_github_token(config.github_token);
+    eprintln!("geektools 控制接口正在 {} 上监听 (JSON-RPC 2.0，每行一个请求)", socket.display());
+    let handler = move |call: &rpc::Call| -> Result<Value> {
+        logging::begin_action();
+        match call {
+            rpc::Call::List => Ok(serde_json::json!({ "scripts": rpc_script_list() })),
+            rpc::Call::Action(action) => {
+                log_only!("INFO", "RPC", "{} {}", action.name(), action.target());
+                run_batch_action(action, verbosity).map(|()| serde_json::json!({ "status": "ok" }))
+            }
+        }
+    };
+    match rpc::serve(socket, handler) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("❌ {}: {}", socket.display(), e);
+            1
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn run_serve_cli(_socket: &Path, _verbosity: Verbosity) -> i32 {
+    eprintln!("❌ serve 需要 unix socket，当前平台不支持");
+    1
+}
+
+// 命令行 `open <链接>`：桌面环境打开 `geektools://run/<脚本>` 链接时调用。
+// 像 `run` 一样显示主脚本并确认后运行；链接无效或脚本不存在返回 2，失败或取消返回 1
+fn run_open_cli(link: &str, verbosity: Verbosity) -> i32 {
+    let code = open_deep_link(link, verbosity);
+    // 链接在新的终端窗口中打开，进程退出后窗口可能随之关闭，先让用户看到结果
+    log_print!("\n按回车键关闭...");
+    let _ = io::stdout().flush();
+    let _ = terminal::read_line(&mut String::new());
+    code
+}
+
+fn open_deep_link(link: &str, verbosity: Verbosity) -> i32 {
+    let link = match deeplink::parse(link) {
+        Ok(link) => link,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return 2;
+        }
+    };
+    log_only!("INFO", "DEEPLINK", "打开链接: {} 参数={:?}", link.script, link.params);
+    let mut app_state = match AppState::new() {
+        Ok(state) => state,
+        Err(e) => {
+            eprintln!("Failed to initialize application: {}", e);
+            return 1;
+        }
+    };
+    app_state.verbosity = verbosity;
+    let config = load_user_config();
+    network::apply_security_config(&config.security);
+    network::set_github_token(config.github_token.clone());
+
+    let options = RunOptions::from_config(&config);
+    let (paths, options) = match resolve_named_script(&link.script, options) {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return 2;
+        }
+    };
+    let content = match paths.last().map(fileio::read) {
+        Some(Ok(content)) => content,
+        Some(Err(e)) => {
+            eprintln!("❌ {}", e);
+            return 2;
+        }
+        None => {
+            eprintln!("❌ script not found: {}", link.script);
+            return 2;
+        }
+    };
+    let notice = (!link.params.is_empty()).then(|| {
+        let params: Vec<String> = link.params.iter().map(|(name, value)| format!("{}={}", name, value)).collect();
+        app_state.get_formatted_translation("deep_link.params", &[&params.join(" ")])
+    });
+    let Some(verbosity) = confirm_script(&app_state, &link.script, &content, notice.as_deref()) else {
+        return 1;
+    };
+    match run_resolved_script(&link.script, &paths, options.with_verbosity(verbosity), &link.params) {
+        Ok(()) => {
+            log_println!("{}", app_state.get_translation("url_script.success"));
+            0
+        }
+        Err(e) => {
+            app_state.print_error(&e);
+            1
+        }
+    }
+}
+
+// 命令行 `register-url-handler`：把当前可执行文件注册为 `geektools://` 链接的处理程序
+fn run_register_url_handler_cli() -> i32 {
+    let exe = match env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => {
+            log_eprintln!("❌ {}", e);
+            return 1;
+        }
+    };
+    match deeplink::register(&exe) {
+        Ok(path) => {
+            log_only!("INFO", "DEEPLINK", "已注册链接处理程序: {}", path.display());
+            log_println!("✅ 已注册 {}:// 链接处理程序: {}", deeplink::SCHEME, path.display());
+            log_println!("   例如 {}://run/<脚本名>?参数=值 会在终端中确认后运行该脚本", deeplink::SCHEME);
+            0
+        }
+        Err(e) => {
+            log_eprintln!("❌ 注册链接处理程序失败: {}", e);
+            1
+        }
+    }
+}
+
+// 控制接口 `list` 的结果：内置、自定义和插件脚本，name 可直接用于 `run`
+#[cfg(unix)]
+fn rpc_script_list() -> Vec<Value> {
+    let config = load_user_config();
+    let lang = config.language.as_str();
+    let info: Value = scripts::script_info().and_then(|data| serde_json::from_str(&data).ok()).unwrap_or_default();
+    let mut list: Vec<Value> = info
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(name, descriptions)| {
+            let description = scripts::catalog::localized(descriptions, lang).unwrap_or("");
+            serde_json::json!({ "name": name, "kind": "builtin", "description": description })
+        })
+        .collect();
+    list.extend(config.custom_scripts.iter().filter(|script| script.enabled).map(|script| {
+        let description = script.localized_description(lang).unwrap_or("");
+        serde_json::json!({ "name": script.name, "kind": "custom", "description": description })
+    }));
+    list.extend(enabled_plugin_scripts().into_iter().map(|(name, description, _, _)| {
+        serde_json::json!({ "name": name, "kind": "plugin", "description": description })
+    }));
+    list
+}
+
+// 按名称查找要运行的脚本：依次尝试文件路径、自定义脚本、插件脚本和内置脚本，
+// 返回执行顺序和带上脚本自身设置 (解释器、工作目录) 的运行选项
+fn resolve_batch_script(name: &str, options: RunOptions) -> Result<(Vec<PathBuf>, RunOptions)> {
+    if Path::new(name).is_file() {
+        return Ok((vec![PathBuf::from(name)], options));
+    }
+    resolve_named_script(name, options)
+}
+
+// 同 resolve_batch_script，但不把名称当作文件路径 (链接只能指向已有的脚本)
+fn resolve_named_script(name: &str, options: RunOptions) -> Result<(Vec<PathBuf>, RunOptions)> {
+    let config = load_user_config();
+    if let Some(custom) = config.custom_scripts.iter().find(|s| s.name == name) {
+        return match &custom.file_path {
+            Some(file_path) => Ok((
+                vec![PathBuf::from(file_path)],
+                options
+                    .with_interpreter(custom.interpreter.as_deref())
+                    .with_workspace(custom.working_dir.as_deref(), custom.keep_artifacts),
+            )),
+            None => Err(GeekToolsError::ValidationError {
+                field: "script".to_string(),
+                message: format!("custom script {} has no saved file", name),
+            }),
+        };
+    }
+    // 插件脚本的显示名为 "脚本名 - 插件名"，两种写法都接受
+    let plugin_script = enabled_plugin_scripts()
+        .into_iter()
+        .find(|(display, _, _, _)| display == name || display.split(" - ").next() == Some(name));
+    if let Some((_, _, path, entry)) = plugin_script {
+        return Ok((vec![path], plugin_run_options(options, &entry)));
+    }
+    if name.ends_with(".link") {
+        return Err(GeekToolsError::ValidationError {
+            field: "script".to_string(),
+            message: "link scripts are interactive and cannot run in batch mode".to_string(),
+        });
+    }
+    if scripts::get_string(name).is_some() {
+        return Ok((scripts::materialize_with_deps(name)?, options));
+    }
+    Err(GeekToolsError::ValidationError {
+        field: "script".to_string(),
+        message: format!("script not found: {}", name),
+    })
+}
+
+// 运行脚本及其依赖，参数按脚本声明的 `# Param:` 顺序作为位置参数传给主脚本
+fn batch_run_script(name: &str, params: &[(String, String)], verbosity: Verbosity) -> Result<()> {
+    let options = RunOptions::from_config(&load_user_config()).with_verbosity(verbosity);
+    let (paths, options) = resolve_batch_script(name, options)?;
+    run_resolved_script(name, &paths, options, params)
+}
+
+// 按顺序运行已解析的依赖和主脚本 (最后一项)，任一脚本失败即停止
+fn run_resolved_script(name: &str, paths: &[PathBuf], options: RunOptions, params: &[(String, String)]) -> Result<()> {
+    let Some(main_script) = paths.last() else {
+        return Err(GeekToolsError::ValidationError {
+            field: "script".to_string(),
+            message: format!("script not found: {}", name),
+        });
+    };
+    let declared = scripts::metadata::parse(&fileio::read(main_script)?).params;
+    let args = scripts::metadata::positional_args(&declared, params)
+        .map_err(|message| GeekToolsError::ValidationError { field: "params".to_string(), message })?;
+
+    for path in paths {
+        let options = if path == main_script { options.clone().with_args(args.clone()) } else { options.clone() };
+        let script_name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        match execute_script(path, &options) {
+            Ok(status) if status.success() => {}
+            Ok(status) => {
+                return Err(GeekToolsError::ScriptExecutionError {
+                    script_name,
+                    exit_code: status.code(),
+                    source: io::Error::other(format!("script exited with {}", status)),
+                });
+            }
+            Err(e) => return Err(GeekToolsError::ScriptExecutionError { script_name, exit_code: None, source: e }),
+        }
+    }
+    Ok(())
+}
+
+// 用系统包管理器安装软件包，已安装时不做任何事
+fn batch_install_package(name: &str) -> Result<()> {
+    let Some(manager) = pkg::detect() else {
+        return Err(GeekToolsError::ValidationError {
+            field: "package".to_string(),
+            message: "no supported package manager found (brew, apt, dnf, pacman, apk)".to_string(),
+        });
+    };
+    if manager.is_installed(name) {
+        log_only!("INFO", "PKG", "{} 已安装，跳过", name);
+        return Ok(());
+    }
+    let script_name = format!("{} install {}", manager.name(), name);
+    match manager.install(&[name.to_string()]) {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(GeekToolsError::ScriptExecutionError {
+            script_name,
+            exit_code: status.code(),
+            source: io::Error::other(format!("package manager exited with {}", status)),
+        }),
+        Err(e) => Err(GeekToolsError::ScriptExecutionError { script_name, exit_code: None, source: e }),
+    }
+}
+
+// 从本地路径或 URL 安装插件；给出 sha256 时先比对校验值
+#[cfg(feature = "plugins")]
+fn batch_install_plugin(source: &str, sha256: Option<&str>) -> Result<()> {
+    // 下载到私有临时目录中的随机文件，离开作用域时删除
+    let download;
+    let package = if source.starts_with("http://") || source.starts_with("https://") {
+        download = fileio::private_temp_file("plugin_", ".tar.gz")?;
+        network::download_to_file(source, download.path(), &mut |_, _| {})?;
+        download.path().to_path_buf()
+    } else {
+        PathBuf::from(source)
+    };
+
+    verify_package_checksum(&package, source, sha256)
+        .and_then(|()| PluginManager::new().install_plugin(&package).map(|_| ()))
+}
+
+// 未编译插件功能时无法安装插件
+#[cfg(not(feature = "plugins"))]
+fn batch_install_plugin(_source: &str, _sha256: Option<&str>) -> Result<()> {
+    Err(GeekToolsError::ConfigError {
+        message: "plugin support is not compiled into this build (cargo feature `plugins`)".to_string(),
+    })
+}
+
+#[cfg(feature = "plugins")]
+fn verify_package_checksum(package: &Path, source: &str, expected: Option<&str>) -> Result<()> {
+    let Some(expected) = expected else {
+        return Ok(());
+    };
+    let actual = network::sha256_file(package)?;
+    if network::checksum_matches(&actual, expected) {
+        Ok(())
+    } else {
+        Err(GeekToolsError::PluginPackageError {
+            package: source.to_string(),
+            message: format!("SHA-256 mismatch: expected {}, got {}", expected, actual),
+        })
+    }
+}
+
+// 命令行 `apply <状态文件>`：把配置、插件和自定义脚本收敛到文件声明的状态，已满足的项不做改动，
+// 最后输出变更摘要；文件无法读取或解析时返回 2，某一项失败时停止并返回 1
+fn run_apply_cli(file: &Path) -> i32 {
+    let state = match fileio::read(file).and_then(|content| apply::parse(&content)) {
+        Ok(state) => state,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return 2;
+        }
+    };
+    let mut outcomes = Vec::new();
+    let result = apply_state(&state, &mut outcomes);
+    print!("{}", apply::render_summary(&outcomes));
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            1
+        }
+    }
+}
+
+// `geektools logs`：按条件输出日志后退出，没有匹配时退出码为 1
+fn run_logs_cli(filter: &cli::LogsArgs) -> i32 {
+    let lines = filter.query();
+    for line in &lines {
+        println!("{}", line);
+    }
+    if lines.is_empty() { 1 } else { 0 }
+}
+
+// `geektools export-installer`：把脚本、它导入的脚本和 gt-helpers 函数库打包成自解压的 .sh，
+// 在没有 geektools 的机器上运行；找不到脚本时返回 2，读写失败时返回 1
+fn run_export_installer_cli(args: &cli::ExportInstaller) -> i32 {
+    let (paths, options) = match resolve_batch_script(&args.script, RunOptions::default()) {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            log_eprintln!("❌ {}", e);
+            return 2;
+        }
+    };
+    let app_state = match AppState::new() {
+        Ok(state) => state,
+        Err(e) => {
+            log_eprintln!("Failed to initialize application: {}", e);
+            return 1;
+        }
+    };
+    let mut bundled = Vec::new();
+    for (i, path) in paths.iter().enumerate() {
+        let content = match fileio::read(path) {
+            Ok(content) => content,
+            Err(e) => {
+                log_eprintln!("❌ {}", e);
+                return 1;
+            }
+        };
+        let main = i + 1 == paths.len();
+        // 不同插件或目录中的依赖可能同名，加上序号避免解压时互相覆盖
+        let file_name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        bundled.push(installer::BundledScript {
+            name: format!("{:02}-{}", i + 1, file_name),
+            content,
+            interpreter: if main { options.interpreter.clone() } else { None },
+        });
+    }
+    let stem = Path::new(&args.script)
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| args.script.clone());
+    let output = args.output.clone().unwrap_or_else(|| format!("{}-installer.sh", stem));
+    let result = fileio::write(&output, &installer::render(&args.script, &bundled));
+    #[cfg(unix)]
+    let result = result.and_then(|_| fileio::set_executable(&output));
+    match result {
+        Ok(()) => {
+            log_println!("{}", app_state.get_formatted_translation("installer.generated", &[&output, &bundled.len().to_string()]));
+            log_println!("{}", app_state.get_formatted_translation("installer.run_hint", &[&output]));
+            0
+        }
+        Err(e) => {
+            log_eprintln!("❌ {}", e);
+            1
+        }
+    }
+}
+
+// `geektools plugin new`：在当前目录生成插件骨架；`geektools plugin lint`：检查 info.json，有错误时返回 1
+#[cfg(feature = "plugins")]
+fn run_plugin_cli(command: &cli::PluginCommand) -> i32 {
+    let config = load_user_config();
+    network::apply_security_config(&config.security);
+    network::set_github_token(config.github_token);
+    match command {
+        cli::PluginCommand::New { name, lang } => match plugins::scaffold::create(Path::new("."), name, *lang) {
+            Ok(dir) => {
+                log_println!("✅ 已创建插件骨架: {}", dir.display());
+                log_println!("   编辑 info.json 和 scripts/ 后打包: sh {}", dir.join("pack.sh").display());
+                log_println!("   开发时可在插件管理中链接该目录 (开发模式)，无需每次打包");
+                0
+            }
+            Err(e) => {
+                log_eprintln!("❌ {}", e);
+                1
+            }
+        },
+        cli::PluginCommand::Lint { dir } => match PluginManager::validate_manifest(Path::new(dir)) {
+            Ok(issues) => {
+                for issue in &issues {
+                    log_println!("{}", issue);
+                }
+                let errors = issues.iter().filter(|i| i.severity == plugins::lint::Severity::Error).count();
+                log_println!("{} error(s), {} warning(s)", errors, issues.len() - errors);
+                if errors > 0 { 1 } else { 0 }
+            }
+            Err(e) => {
+                log_eprintln!("❌ {}", e);
+                2
+            }
+        },
+        cli::PluginCommand::Publish { file, token, org } => publish_plugin_cli(Path::new(file), token.as_deref(), org.as_deref()),
+    }
+}
+
+#[cfg(not(feature = "plugins"))]
+fn run_plugin_cli(_command: &cli::PluginCommand) -> i32 {
+    log_eprintln!("❌ 此版本编译时未启用插件功能 (cargo feature `plugins`)");
+    2
+}
+
+// 命令行 `plugin publish`：检查包内的 info.json 后上传；令牌依次取 --token、GEEKTOOLS_TOKEN 和已登录的账号，
+// 供 CI 无交互发布。无法读取插件包时返回 2，上传失败返回 1
+#[cfg(feature = "marketplace")]
+fn publish_plugin_cli(file: &Path, token: Option<&str>, org: Option<&str>) -> i32 {
+    let info = match plugins::read_package_manifest(file) {
+        Ok(info) => info,
+        Err(e) => {
+            log_eprintln!("❌ {}", e);
+            return 2;
+        }
+    };
+    let mut config = load_user_config().marketplace_config;
+    if let Some(token) = token.map(str::to_string).or_else(|| std::env::var("GEEKTOOLS_TOKEN").ok()).filter(|t| !t.is_empty()) {
+        config.access_token = Some(token);
+    }
+    let result = plugins::MarketplaceClient::new(config).and_then(|client| client.upload_plugin(file, org));
+    match result {
+        Ok(uploaded) => {
+            log_println!("✅ 已发布 {} v{}", uploaded.plugin_id, uploaded.version);
+            0
+        }
+        Err(e) => {
+            log_eprintln!("❌ 发布 {} v{} 失败: {}", info.id, info.version, e);
+            1
+        }
+    }
+}
+
+#[cfg(all(feature = "plugins", not(feature = "marketplace")))]
+fn publish_plugin_cli(_file: &Path, _token: Option<&str>, _org: Option<&str>) -> i32 {
+    log_eprintln!("❌ 此版本编译时未启用插件市场 (cargo feature `marketplace`)");
+    2
+}
+
+// 命令行 `run`：与 URL 脚本相同的预览和确认后运行；`-` 从标准输入读取脚本，此时确认改从控制终端读取；
+// `--url` 下载的内容摘要不匹配时拒绝运行。无法读取脚本时返回 2，校验不符、运行失败或取消时返回 1
+fn run_script_cli(source: &cli::RunSource, verbosity: Verbosity) -> i32 {
+    let mut app_state = match AppState::new() {
+        Ok(state) => state,
+        Err(e) => {
+            log_eprintln!("Failed to initialize application: {}", e);
+            return 1;
+        }
+    };
+    app_state.verbosity = verbosity;
+    let config = load_user_config();
+    let max_size = config.security.max_script_download_bytes;
+    let (label, content) = match source {
+        cli::RunSource::File(file) if file == "-" => ("stdin", read_stdin_script(max_size)),
+        cli::RunSource::File(file) => (file.as_str(), fileio::read(file).map_err(|e| e.to_string())),
+        cli::RunSource::Url { url, sha256 } => {
+            network::apply_security_config(&config.security);
+            let content = match network::fetch_script(url, max_size) {
+                Ok(script) => script.content,
+                Err(e) => {
+                    log_eprintln!("❌ {}", e);
+                    return 2;
+                }
+            };
+            let actual = network::sha256_str(&content);
+            if !network::checksum_matches(&actual, sha256) {
+                log_only!("WARN", "DOWNLOAD", "校验值不匹配，拒绝运行 URL={} 期望={} 实际={}", url, sha256, actual);
+                log_eprintln!("❌ SHA-256 mismatch, refusing to run {}", url);
+                log_eprintln!("   expected: {}", sha256);
+                log_eprintln!("   actual:   {}", actual);
+                return 1;
+            }
+            log_println!("✅ SHA-256 verified: {}", actual);
+            (url.as_str(), Ok(content))
+        }
+    };
+    let content = match content {
+        Ok(content) if !content.trim().is_empty() => content,
+        Ok(_) => {
+            log_eprintln!("❌ empty script");
+            return 2;
+        }
+        Err(e) => {
+            log_eprintln!("❌ {}", e);
+            return 2;
+        }
+    };
+    if matches!(source, cli::RunSource::File(file) if file == "-")
+        && let Err(e) = terminal::use_tty()
+    {
+        log_eprintln!("❌ no terminal to confirm the script on: {}", e);
+        return 2;
+    }
+    let notice = match source {
+        cli::RunSource::Url { url, .. } => match check_remote_signature(&app_state, url, &content) {
+            Ok(notice) => notice,
+            Err(message) => {
+                log_eprintln!("{}", message);
+                return 1;
+            }
+        },
+        cli::RunSource::File(_) => None,
+    };
+    match preview_and_run_script(&app_state, label, &content, notice.as_deref()) {
+        Some(true) => 0,
+        _ => 1,
+    }
+}
+
+// 读取标准输入中的脚本，和 URL 脚本一样受 `max_script_download_bytes` 限制
+fn read_stdin_script(max_size: u64) -> std::result::Result<String, String> {
+    let mut content = String::new();
+    io::stdin()
+        .take(max_size.saturating_add(1))
+        .read_to_string(&mut content)
+        .map_err(|e| format!("failed to read script from stdin: {}", e))?;
+    if content.len() as u64 > max_size {
+        return Err(format!("script exceeds the {} byte limit", max_size));
+    }
+    Ok(content)
+}
+
+// 依次收敛配置 (之后的下载会用到其中的安全和市场设置)、插件和自定义脚本
+fn apply_state(state: &apply::State, outcomes: &mut Vec<apply::Outcome>) -> Result<()> {
+    let (config, config_outcomes) = apply::apply_config(&load_user_config(), &state.config)?;
+    if config_outcomes.iter().any(|o| o.change != apply::Change::Unchanged) {
+        save_user_config(&config)?;
+    }
+    outcomes.extend(config_outcomes);
+    network::apply_security_config(&config.security);
+    network::set_github_token(config.github_token.clone());
+    apply_plugins(&state.plugins, &config, outcomes)?;
+
+    let mut config = load_user_config();
+    let mut scripts_changed = false;
+    for script in &state.custom_scripts {
+        let index = config.custom_scripts.iter().position(|s| s.name == script.name);
+        let existing = index.map(|i| &config.custom_scripts[i]);
+        let desired = desired_custom_script(script, existing)?;
+        let change = apply::script_change(existing, &desired);
+        let detail = desired.file_path.clone().unwrap_or_default();
+        match (change, index) {
+            (apply::Change::Unchanged, _) => {}
+            (_, Some(i)) => config.custom_scripts[i] = desired,
+            (_, None) => config.custom_scripts.push(desired),
+        }
+        scripts_changed |= change != apply::Change::Unchanged;
+        outcomes.push(apply::Outcome::new("script", &script.name, change, detail));
+    }
+    if scripts_changed {
+        save_user_config(&config)?;
+    }
+    Ok(())
+}
+
+// 安装或升级状态文件中声明的插件，已是期望版本的不做改动
+#[cfg(feature = "plugins")]
+fn apply_plugins(plugins: &[apply::PluginState], config: &Config, outcomes: &mut Vec<apply::Outcome>) -> Result<()> {
+    let mut plugin_manager = PluginManager::new();
+    for plugin in plugins {
+        let installed = plugin_manager
+            .list_installed_plugins()
+            .into_iter()
+            .find(|p| p.info.id == plugin.id)
+            .map(|p| p.info.version.clone());
+        let change = apply::plugin_change(installed.as_deref(), &plugin.version);
+        let detail = match &installed {
+            Some(old) if change == apply::Change::Updated => format!("{} -> {}", old, plugin.version),
+            _ => plugin.version.clone(),
+        };
+        if change != apply::Change::Unchanged {
+            apply_plugin(&mut plugin_manager, plugin, &config.marketplace_config, installed.is_some())?;
+        }
+        outcomes.push(apply::Outcome::new("plugin", &plugin.id, change, detail));
+    }
+    Ok(())
+}
+
+// 未编译插件功能时，状态文件中声明了插件则报错
+#[cfg(not(feature = "plugins"))]
+fn apply_plugins(plugins: &[apply::PluginState], _config: &Config, _outcomes: &mut Vec<apply::Outcome>) -> Result<()> {
+    if plugins.is_empty() {
+        return Ok(());
+    }
+    Err(GeekToolsError::ConfigError {
+        message: "plugin support is not compiled into this build (cargo feature `plugins`)".to_string(),
+    })
+}
+
+// 取得期望版本的插件包 (指定的来源或插件市场)，核对清单后替换已安装的版本
+#[cfg(feature = "plugins")]
+fn apply_plugin(
+    plugin_manager: &mut PluginManager,
+    plugin: &apply::PluginState,
+    marketplace: &MarketplaceConfig,
+    replace: bool,
+) -> Result<()> {
+    let source = match &plugin.source {
+        Some(source) => source.clone(),
+        None => marketplace_package_url(plugin, marketplace)?,
+    };
+
+    let download;
+    let package = if source.starts_with("http://") || source.starts_with("https://") {
+        download = fileio::private_temp_file("plugin_", ".tar.gz")?;
+        network::download_to_file(&source, download.path(), &mut |_, _| {})?;
+        download.path().to_path_buf()
+    } else {
+        PathBuf::from(&source)
+    };
+    verify_package_checksum(&package, &source, plugin.sha256.as_deref())
+        .and_then(|()| plugins::read_package_manifest(&package))
+        .and_then(|info| {
+            if info.id != plugin.id || info.version != plugin.version {
+                return Err(GeekToolsError::PluginPackageError {
+                    package: source.clone(),
+                    message: format!("package contains {} {}, expected {} {}", info.id, info.version, plugin.id, plugin.version),
+                });
+            }
+            if replace {
+                plugin_manager.upgrade_plugin(&package).map(|_| ())
+            } else {
+                plugin_manager.install_plugin(&package).map(|_| ())
+            }
+        })
+}
+
+// 状态文件没有给出来源时，在插件市场中查找期望版本的下载地址
+#[cfg(feature = "marketplace")]
+fn marketplace_package_url(plugin: &apply::PluginState, marketplace: &MarketplaceConfig) -> Result<String> {
+    let client = plugins::MarketplaceClient::new(marketplace.clone())?;
+    let found = client.search_plugins(&plugin.id)?.plugins.into_iter().find(|p| p.id == plugin.id);
+    match found {
+        Some(p) if p.version == plugin.version => Ok(p.file_url),
+        Some(p) => Err(GeekToolsError::PluginError {
+            plugin_name: plugin.id.clone(),
+            message: format!(
+                "marketplace offers version {}, not {}; set `source` to install other versions",
+                p.version, plugin.version
+            ),
+        }),
+        None => Err(GeekToolsError::PluginError {
+            plugin_name: plugin.id.clone(),
+            message: "not found in the marketplace".to_string(),
+        }),
+    }
+}
+
+#[cfg(all(feature = "plugins", not(feature = "marketplace")))]
+fn marketplace_package_url(plugin: &apply::PluginState, _marketplace: &MarketplaceConfig) -> Result<String> {
+    Err(GeekToolsError::PluginError {
+        plugin_name: plugin.id.clone(),
+        message: "marketplace support is not compiled into this build; set `source` to install".to_string(),
+    })
+}
+
+// 由状态文件中的声明构造自定义脚本记录；URL 脚本下载到固定文件名，已下载过同一 URL 时不再下载
+fn desired_custom_script(script: &apply::ScriptState, existing: Option<&CustomScript>) -> Result<CustomScript> {
+    let file_path = match (&script.file, &script.url) {
+        (Some(file), _) => {
+            if !Path::new(file).is_file() {
+                return Err(GeekToolsError::ValidationError {
+                    field: format!("custom_scripts.{}", script.name),
+                    message: format!("script file not found: {}", file),
+                });
+            }
+            file.clone()
+        }
+        (None, Some(url)) => {
+            let path = CUSTOM_SCRIPTS_DIR.join(format!(
+                "custom_{}.{}",
+                &network::sha256_str(url)[..16],
+                runtime::extension_for(url)
+            ));
+            let downloaded = existing.is_some_and(|s| s.url.as_deref() == Some(url)) && path.is_file();
+            if !downloaded {
+                fileio::write(&path, &download_script_content(url)?)?;
+                #[cfg(unix)]
+                fileio::set_executable(&path)?;
+            }
+            path.to_string_lossy().into_owned()
+        }
+        (None, None) => unreachable!("apply::parse requires file or url"),
+    };
+    Ok(CustomScript {
+        name: script.name.clone(),
+        description: script.description.clone(),
+        descriptions: BTreeMap::new(),
+        url: script.url.clone(),
+        file_path: Some(file_path),
+        enabled: true,
+        last_updated: Some(chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string()),
+        interpreter: script.interpreter.clone(),
+        working_dir: script.working_dir.clone(),
+        keep_artifacts: script.keep_artifacts,
+    })
+}
+
+// 确定脚本的运行用户；choose_user 为 true 时提示输入并记住到配置中
+fn resolve_run_options(app_state: &AppState, script_name: &str, choose_user: bool) -> Option<RunOptions> {
+    let mut config = load_user_config();
+    if choose_user {
+        let current = config.run_as_users.get(script_name).cloned();
+        log_print!(
+            "{}",
+            app_state.get_formatted_translation(
+                "script_execution.run_as_prompt",
+                &[script_name, current.as_deref().unwrap_or("-")]
+            )
+        );
+        let _ = io::stdout().flush();
+        let mut input = String::new();
+        if terminal::read_line(&mut input).is_err() {
+            return None;
+        }
+        match input.trim() {
+            "" => {}
+            "-" => {
+                config.run_as_users.remove(script_name);
+            }
+            user if executor::is_valid_username(user) => {
+                config.run_as_users.insert(script_name.to_string(), user.to_string());
+            }
+            user => {
+                log_println!(
+                    "{}",
+                    app_state.get_formatted_translation("script_execution.run_as_invalid", &[user])
+                );
+                return None;
+            }
+        }
+        if let Err(e) = save_user_config(&config) {
+            log_println!(
+                "{}",
+                app_state.get_formatted_translation("script_execution.run_as_saved_failed", &[&e.to_string()])
+            );
+        }
+    }
+
+    let user = config.run_as_users.get(script_name).map(String::as_str);
+    if let Some(user) = user {
+        log_println!(
+            "{}",
+            app_state.get_formatted_translation("script_execution.running_as", &[user])
+        );
+    }
+    Some(
+        RunOptions::from_config(&config)
+            .with_run_as(user)
+            .with_verbosity(app_state.verbosity),
+    )
+}
+
+// 直接执行 .sh
+fn run_sh_script(path: &Path, app_state: &AppState, options: &RunOptions) {
+    match execute_script(path, options) {
+        Ok(status) if !status.success() => log_println!(
+            "{}",
+            app_state.get_formatted_translation("url_script.failed_status", &[&status.to_string()])
+        ),
+        Err(e) => log_println!(
+            "{}",
+            app_state.get_formatted_translation("url_script.failed_execute", &[&e.to_string()])
+        ),
+        _ => {}
+    }
+}
+
+// 运行自定义脚本（从文件）
+fn run_custom_script_from_file(file_path: &str, app_state: &AppState, options: &RunOptions) {
+    let script_path = Path::new(file_path);
+    
+    if !script_path.exists() {
+        log_println!("❌ 脚本文件不存在: {}", file_path);
+        log_println!("   提示：请尝试重新添加此脚本");
+        return;
+    }
+
+    // 先运行 `#@import` 导入的内置、插件或其他自定义脚本；解释器和参数只属于自定义脚本本身
+    let dependencies = match scripts::materialize_imports(script_path) {
+        Ok(dependencies) => dependencies,
+        Err(e) => {
+            log_println!("❌ 无法准备导入的脚本: {}", e);
+            return;
+        }
+    };
+    let dependency_options = RunOptions { interpreter: None, args: Vec::new(), ..options.clone() };
+    for dependency in &dependencies {
+        let name = dependency.file_name().unwrap_or_default().to_string_lossy();
+        log_println!("正在执行导入的脚本: {}", name);
+        match execute_script(dependency, &dependency_options) {
+            Ok(status) if status.success() => {}
+            Ok(status) => {
+                log_println!("❌ 导入的脚本 {} 执行失败，退出码: {}", name, status);
+                return;
+            }
+            Err(e) => {
+                log_println!("❌ 导入的脚本 {} 执行出错: {}", name, e);
+                return;
+            }
+        }
+    }
+    
+    log_println!("正在执行自定义脚本: {}", script_path.file_name().unwrap_or_default().to_string_lossy());
+    match execute_script(script_path, options) {
+        Ok(status) if status.success() => {
+            log_println!("{}", app_state.get_translation("url_script.success"));
+        }
+        Ok(status) => {
+            log_println!("❌ 自定义脚本执行失败，退出码: {}", status);
+        }
+        Err(e) => {
+            log_println!("❌ 自定义脚本执行出错: {}", e);
+        }
+    }
+}
+
+// 运行自定义脚本（从URL下载，向后兼容）
+fn run_custom_script_from_url(url: &str, _app_state: &AppState, options: &RunOptions) {
+    log_println!("正在从URL下载自定义脚本: {}", url);
+    
+    match download_script_content(url) {
+        Ok(content) => {
+            let file_name = format!("custom_script_{}.{}", rand::random::<u64>(), runtime::extension_for(url));
+            let mut tmp_path = TMP_DIR.clone();
+            tmp_path.push(file_name);
+            
+            if let Err(e) = fileio::write(&tmp_path, &content) {
+                log_println!("❌ 写入脚本失败: {}", e);
                 return;
             }
             
@@ -806,7 +1998,7 @@ fn run_custom_script_from_url(url: &str, _app_state: &AppState) {
             }
             
             log_println!("正在执行自定义脚本...");
-            match execute_script(&tmp_path) {
+            match execute_script(&tmp_path, options) {
                 Ok(status) if status.success() => {
                     log_println!("✅ 自定义脚本执行成功");
                 }
@@ -827,7 +2019,7 @@ fn run_custom_script_from_url(url: &str, _app_state: &AppState) {
 }
 
 // 按顺序执行多个 .sh 脚本（支持依赖关系）
-fn run_sh_scripts_with_deps(paths: &[PathBuf], app_state: &AppState) {
+fn run_sh_scripts_with_deps(paths: &[PathBuf], app_state: &AppState, options: &RunOptions) {
     if paths.is_empty() {
         log_println!("{}", app_state.get_translation("script_execution.no_scripts"));
         return;
@@ -847,7 +2039,7 @@ fn run_sh_scripts_with_deps(paths: &[PathBuf], app_state: &AppState) {
             );
         }
         
-        match execute_script(path) {
+        match execute_script(path, options) {
             Ok(status) if status.success() => {
                 if paths.len() > 1 {
                     log_println!("✅ {} 执行成功", script_name);
@@ -879,59 +2071,114 @@ fn run_sh_scripts_with_deps(paths: &[PathBuf], app_state: &AppState) {
     }
 }
 
-// 处理 .link —— 下载远程脚本后执行
-fn run_link_script(path: &Path, app_state: &AppState) {
-    // 性能优化：使用全局复用的临时目录
-
-    // 1. 读取 URL
-    let url = match fileio::read(path) {
-        Ok(s) => s.trim().to_string(),
-        Err(e) => {
-            log_println!(
-                "{}",
-                app_state.get_formatted_translation("link_script.failed_read", &[&e.to_string()])
-            );
-            return;
-        }
-    };
-    log_println!(
-        "{}",
-        app_state.get_formatted_translation("link_script.downloading", &[&url])
-    );
-
-    // 2. 下载
-    #[cfg(not(feature = "network"))]
-    {
-        log_println!("❌ 网络功能已禁用，无法下载脚本");
-        return;
+// 下载 .link 指向的脚本内容，失败时已输出提示
+#[cfg(feature = "network")]
+fn download_link_script(url: &str, app_state: &AppState) -> Option<String> {
+    if let Err(e) = network::ensure_secure_url(url) {
+        app_state.print_error(&e);
+        return None;
     }
-    
-    #[cfg(feature = "network")]
-    let resp = match reqwest::blocking::get(&url) {
+    let resp = match network::build_client(None).and_then(|client| client.get(url).send().map_err(GeekToolsError::from)) {
         Ok(r) => r,
         Err(e) => {
             log_println!(
                 "{}",
                 app_state.get_formatted_translation("url_script.failed_fetch", &[&e.to_string()])
             );
-            return;
+            return None;
         }
     };
-    #[cfg(feature = "network")]
-    let content = match resp.text() {
-        Ok(t) => t,
+    match resp.text() {
+        Ok(t) => Some(t),
         Err(e) => {
             log_println!(
                 "{}",
                 app_state
                     .get_formatted_translation("url_script.failed_read_content", &[&e.to_string()])
             );
+            None
+        }
+    }
+}
+
+#[cfg(not(feature = "network"))]
+fn download_link_script(_url: &str, _app_state: &AppState) -> Option<String> {
+    log_println!("❌ 网络功能已禁用，无法下载脚本");
+    None
+}
+
+// 处理 .link —— 下载远程脚本后执行
+fn run_link_script(path: &Path, app_state: &AppState, options: &RunOptions) {
+    // 性能优化：使用全局复用的临时目录
+
+    // 1. 读取链接 (一行 URL 或带校验值和元数据的 JSON)
+    let spec = match fileio::read(path).map_err(|e| e.to_string()).and_then(|s| scripts::link::LinkSpec::parse(&s)) {
+        Ok(spec) => spec,
+        Err(e) => {
+            log_println!(
+                "{}",
+                app_state.get_formatted_translation("link_script.failed_read", &[&e])
+            );
             return;
         }
     };
+    if let Some(description) = spec.description(app_state.current_language.code()) {
+        log_println!("📝 {}", description);
+    }
+    if !spec.platforms.is_empty() {
+        log_println!("{}", app_state.get_formatted_translation("link_script.platforms", &[&spec.platforms.join(", ")]));
+        if !spec.supports_platform(env::consts::OS) {
+            log_println!("⚠️  {}", app_state.get_formatted_translation("link_script.unsupported_platform", &[env::consts::OS]));
+            if !ui::menu::prompt("(y/N): ").is_some_and(|s| s.eq_ignore_ascii_case("y")) {
+                return;
+            }
+        }
+    }
+
+    // 2. 依次尝试主地址和镜像，链接给出校验值时内容必须匹配
+    let mut downloaded = None;
+    for (i, url) in spec.urls().enumerate() {
+        let key = if i == 0 { "link_script.downloading" } else { "link_script.trying_mirror" };
+        log_println!("{}", app_state.get_formatted_translation(key, &[url]));
+        let Some(content) = download_link_script(url, app_state) else {
+            continue;
+        };
+        if let Some(expected) = &spec.sha256 {
+            let actual = network::sha256_str(&content);
+            if !network::checksum_matches(&actual, expected) {
+                log_only!("WARN", "DOWNLOAD", "校验值不匹配，拒绝运行 URL={} 期望={} 实际={}", url, expected, actual);
+                log_println!("❌ {}", app_state.get_translation("link_script.checksum_mismatch"));
+                log_println!("   期望: {}", expected);
+                log_println!("   实际: {}", actual);
+                continue;
+            }
+            log_println!("✅ {}", app_state.get_translation("link_script.checksum_verified"));
+        }
+        downloaded = Some((url.to_string(), content));
+        break;
+    }
+    let Some((url, content)) = downloaded else {
+        if !spec.mirrors.is_empty() {
+            log_println!("❌ {}", app_state.get_translation("link_script.all_mirrors_failed"));
+        }
+        return;
+    };
+    if url != spec.url {
+        log_only!("INFO", "DOWNLOAD", "链接脚本 {} 使用镜像 {}", spec.url, url);
+        log_println!("{}", app_state.get_formatted_translation("link_script.using_mirror", &[&url]));
+    }
+    // 目录中的脚本同样校验签名，没有确认步骤，直接显示签名者
+    match check_remote_signature(app_state, &url, &content) {
+        Ok(Some(notice)) => log_println!("{}", notice),
+        Ok(None) => {}
+        Err(message) => {
+            log_println!("{}", message);
+            return;
+        }
+    }
 
     // 3. 写入临时文件
-    let file_name = format!("script_{}.sh", rand::random::<u64>());
+    let file_name = format!("script_{}.{}", rand::random::<u64>(), runtime::extension_for(&url));
     let mut tmp_path = TMP_DIR.clone();
     tmp_path.push(file_name);
     if let Err(e) = fileio::write(&tmp_path, &content) {
@@ -955,7 +2202,7 @@ fn run_link_script(path: &Path, app_state: &AppState) {
 
     // 5. 执行
     log_println!("{}", app_state.get_translation("url_script.executing"));
-    match execute_script(&tmp_path) {
+    match execute_script(&tmp_path, options) {
         Ok(status) if status.success() => {
             log_println!("{}", app_state.get_translation("url_script.success"));
         }
@@ -978,101 +2225,342 @@ fn run_link_script(path: &Path, app_state: &AppState) {
     }
 }
 
-// ──────────────────────────────── 手动输入脚本 URL ─────────────────────────
-fn run_script_from_url(app_state: &AppState) {
-    log_print!("{}", app_state.get_translation("url_script.enter_url"));
-    let _ = io::stdout().flush();
+// ──────────────────────────────── 使用统计 ─────────────────────────────────
+fn show_stats(app_state: &AppState) {
+    let stats = history::stats();
+    if stats.is_empty() {
+        log_println!("{}", app_state.get_translation("stats.no_runs"));
+        return;
+    }
+    log_println!("{}", app_state.get_translation("stats.title"));
+    log_print!("{}", stats::render(&stats, &load_user_config().stats));
+}
 
-    let mut url = String::new();
-    if io::stdin().read_line(&mut url).is_err() {
-        log_println!("{}", app_state.get_translation("main.invalid_choice"));
+// ──────────────────────────────── 撤销上次运行 ─────────────────────────────
+fn undo_last_run(app_state: &AppState) {
+    let records = history::load();
+    let Some(last) = records.last() else {
+        log_println!("{}", app_state.get_translation("undo.no_history"));
+        return;
+    };
+    log_println!(
+        "{}",
+        app_state.get_formatted_translation(
+            "undo.last_run",
+            &[&last.script, &last.started_at, if last.success { "✅" } else { "❌" }]
+        )
+    );
+    if last.rolled_back {
+        log_println!("{}", app_state.get_translation("undo.already_rolled_back"));
+        return;
+    }
+    let Some(rollback) = &last.rollback else {
+        log_println!("{}", app_state.get_translation("undo.no_rollback"));
+        return;
+    };
+
+    log_println!("{}\n{}", app_state.get_translation("undo.commands"), rollback);
+    log_print!("{}", app_state.get_translation("undo.confirm"));
+    let _ = io::stdout().flush();
+    let mut confirm = String::new();
+    if terminal::read_line(&mut confirm).is_err() || !confirm.trim().eq_ignore_ascii_case("y") {
+        log_println!("{}", app_state.get_translation("script_execution.returning"));
         return;
     }
-    let url_trimmed = url.trim();
-    if url_trimmed.eq_ignore_ascii_case("exit") {
+
+    // 回滚脚本写到私有临时目录中的随机文件名，离开作用域时删除
+    let script = fileio::private_temp_file(&format!("rollback_{}_", last.id), ".sh").and_then(|file| {
+        fileio::write(file.path(), &format!("#!/bin/sh\nset -e\n{}", rollback))?;
+        Ok(file)
+    });
+    let script = match script {
+        Ok(script) => script,
+        Err(e) => {
+            log_println!(
+                "{}",
+                app_state.get_formatted_translation("url_script.failed_write", &[&e.to_string()])
+            );
+            return;
+        }
+    };
+
+    let mut options = RunOptions::from_config(&load_user_config()).with_run_as(last.run_as.as_deref());
+    options.record_history = false;
+    match execute_script(script.path(), &options) {
+        Ok(status) if status.success() => {
+            if let Err(e) = history::mark_rolled_back(last.id) {
+                log_only!("WARN", "HISTORY", "标记回滚失败: {}", e);
+            }
+            log_println!("{}", app_state.get_translation("undo.success"));
+        }
+        Ok(status) => log_println!(
+            "{}",
+            app_state.get_formatted_translation("url_script.failed_status", &[&status.to_string()])
+        ),
+        Err(e) => log_println!(
+            "{}",
+            app_state.get_formatted_translation("url_script.failed_execute", &[&e.to_string()])
+        ),
+    }
+}
+
+// ──────────────────────────────── 手动输入脚本 URL ─────────────────────────
+fn run_script_from_url(app_state: &AppState) {
+    let Some(url) = ui::menu::prompt(&app_state.get_translation("url_script.enter_url")) else {
         log_println!(
             "{}",
             app_state.get_translation("script_execution.returning")
         );
         return;
+    };
+    let url_trimmed = url.as_str();
+
+    let final_url = match confirm_redirect_chain(app_state, url_trimmed) {
+        Some(final_url) => final_url,
+        None => {
+            log_println!("{}", app_state.get_translation("script_execution.returning"));
+            return;
+        }
+    };
+
+    let max_size = load_user_config().security.max_script_download_bytes;
+    let script = match network::fetch_script(&final_url, max_size) {
+        Ok(script) => script,
+        Err(e) => {
+            log_println!(
+                "{}",
+                app_state.get_formatted_translation("url_script.failed_fetch", &[&e.to_string()])
+            );
+            return;
+        }
+    };
+
+    log_println!(
+        "{}",
+        app_state.get_formatted_translation(
+            "url_script.response_info",
+            &[&script.status, script.content_type.as_deref().unwrap_or("-"), &script.content.len().to_string()]
+        )
+    );
+    let notice = match check_remote_signature(app_state, &final_url, &script.content) {
+        Ok(notice) => notice,
+        Err(message) => {
+            log_println!("{}", message);
+            return;
+        }
+    };
+    preview_and_run_script(app_state, &final_url, &script.content, notice.as_deref());
+}
+
+// 获取并校验远程脚本的 `<url>.sig`，返回确认前显示的签名状态 (未配置签名校验时为 None)；
+// 签名无效、签名者不受信任，或要求签名而脚本没有签名时返回拒绝运行的提示
+fn check_remote_signature(app_state: &AppState, url: &str, content: &str) -> std::result::Result<Option<String>, String> {
+    let config = load_user_config().signatures;
+    if !config.enabled() {
+        return Ok(None);
+    }
+    let verification = match network::fetch_signature(url) {
+        Ok(Some(sig)) => signature::verify(content.as_bytes(), &sig, &config.trusted_keys),
+        Ok(None) => signature::Verification::Unsigned,
+        Err(e) => signature::Verification::Invalid(e.to_string()),
+    };
+    log_only!("INFO", "SIGNATURE", "URL={} 结果={:?}", url, verification);
+    match verification {
+        signature::Verification::Verified(signer) => {
+            Ok(Some(app_state.get_formatted_translation("signature.verified", &[&signer])))
+        }
+        signature::Verification::Unsigned if config.require => Err(app_state.get_translation("signature.required")),
+        signature::Verification::Unsigned => Ok(Some(app_state.get_formatted_translation("signature.unsigned", &[url]))),
+        signature::Verification::Invalid(reason) => {
+            Err(app_state.get_formatted_translation("signature.invalid", &[&reason]))
+        }
     }
+}
 
-    #[cfg(not(feature = "network"))]
-    {
-        log_println!("❌ 网络功能已禁用，无法下载脚本");
-        return;
+// 显示脚本内容和签名状态并等待确认；返回选择的输出级别，取消时返回 None
+fn confirm_script(app_state: &AppState, source: &str, content: &str, notice: Option<&str>) -> Option<Verbosity> {
+    log_println!(
+        "{}",
+        app_state.get_formatted_translation(
+            "url_script.script_content",
+            &[source, content]
+        )
+    );
+    if let Some(notice) = notice {
+        log_println!("{}", notice);
     }
-    
-    #[cfg(feature = "network")]
-    match reqwest::blocking::get(url_trimmed) {
-        Ok(response) => match response.text() {
-            Ok(script_content) => {
-                log_println!(
-                    "{}",
-                    app_state.get_formatted_translation(
-                        "url_script.script_content",
-                        &[url_trimmed, &script_content]
-                    )
-                );
 
-                // 落盘 → chmod → 执行
-                let file_name = format!("script_{}.sh", rand::random::<u64>());
-                let mut tmp_path = TMP_DIR.clone();
-                tmp_path.push(file_name);
-                if let Err(e) = fileio::write(&tmp_path, &script_content) {
-                    log_println!(
-                        "{}",
-                        app_state.get_formatted_translation(
-                            "url_script.failed_write",
-                            &[&e.to_string()]
-                        )
-                    );
-                    return;
-                }
-                #[cfg(unix)]
-                {
-                    let _ = fileio::set_executable(&tmp_path);
-                }
+    log_print!("{}", app_state.get_translation("url_script.confirm_execute"));
+    let _ = io::stdout().flush();
+    let mut confirm = String::new();
+    if terminal::read_line(&mut confirm).is_err() {
+        log_println!("{}", app_state.get_translation("script_execution.returning"));
+        return None;
+    }
+    // y 按默认级别运行，q/v 分别以安静/详细模式运行
+    match confirm.trim().to_ascii_lowercase().as_str() {
+        "y" => Some(app_state.verbosity),
+        "q" => Some(Verbosity::Quiet),
+        "v" => Some(Verbosity::Verbose),
+        _ => {
+            log_println!("{}", app_state.get_translation("script_execution.returning"));
+            None
+        }
+    }
+}
 
-                let status = execute_script(&tmp_path);
-                match status {
-                    Ok(s) if s.success() => {
-                        log_println!("{}", app_state.get_translation("url_script.success"))
-                    }
-                    Ok(s) => log_println!(
-                        "{}",
-                        app_state.get_formatted_translation(
-                            "url_script.failed_status",
-                            &[&s.to_string()]
-                        )
-                    ),
-                    Err(e) => log_println!(
-                        "{}",
-                        app_state.get_formatted_translation(
-                            "url_script.failed_execute",
-                            &[&e.to_string()]
-                        )
-                    ),
-                }
+// 显示脚本内容和签名状态，确认后落盘执行；取消时返回 None，否则返回是否运行成功
+fn preview_and_run_script(app_state: &AppState, source: &str, content: &str, notice: Option<&str>) -> Option<bool> {
+    let verbosity = confirm_script(app_state, source, content, notice)?;
 
-                let _ = fileio::remove_file(&tmp_path);
-            }
-            Err(e) => log_println!(
-                "{}",
-                app_state
-                    .get_formatted_translation("url_script.failed_read_content", &[&e.to_string()])
-            ),
-        },
-        Err(e) => log_println!(
+    // 落盘 → chmod → 执行
+    let file_name = format!("script_{}.sh", rand::random::<u64>());
+    let mut tmp_path = TMP_DIR.clone();
+    tmp_path.push(file_name);
+    if let Err(e) = fileio::write(&tmp_path, content) {
+        log_println!(
             "{}",
-            app_state.get_formatted_translation("url_script.failed_fetch", &[&e.to_string()])
-        ),
+            app_state.get_formatted_translation(
+                "url_script.failed_write",
+                &[&e.to_string()]
+            )
+        );
+        return Some(false);
+    }
+    #[cfg(unix)]
+    {
+        let _ = fileio::set_executable(&tmp_path);
+    }
+
+    let options = RunOptions::from_config(&load_user_config()).with_verbosity(verbosity);
+    let status = execute_script(&tmp_path, &options);
+    let success = match status {
+        Ok(s) if s.success() => {
+            log_println!("{}", app_state.get_translation("url_script.success"));
+            true
+        }
+        Ok(s) => {
+            log_println!(
+                "{}",
+                app_state.get_formatted_translation(
+                    "url_script.failed_status",
+                    &[&s.to_string()]
+                )
+            );
+            false
+        }
+        Err(e) => {
+            log_println!(
+                "{}",
+                app_state.get_formatted_translation(
+                    "url_script.failed_execute",
+                    &[&e.to_string()]
+                )
+            );
+            false
+        }
+    };
+
+    let _ = fileio::remove_file(&tmp_path);
+    Some(success)
+}
+
+// 解析并显示重定向链；最终域名与输入不同时需要确认。返回最终 URL，取消时返回 None
+fn confirm_redirect_chain(app_state: &AppState, url: &str) -> Option<String> {
+    let chain = match network::resolve_redirects(url) {
+        Ok(chain) => chain,
+        Err(e) => {
+            app_state.print_error(&e);
+            return None;
+        }
+    };
+    let final_url = chain.last().cloned().unwrap_or_else(|| url.to_string());
+    if chain.len() == 1 {
+        return Some(final_url);
+    }
+
+    log_println!("{}", app_state.get_translation("url_script.redirect_chain"));
+    for (i, hop) in chain.iter().enumerate() {
+        log_println!("  {}. {}", i + 1, hop);
     }
+    let final_host = network::url_host(&final_url).unwrap_or_default();
+    log_println!("{}", app_state.get_formatted_translation("url_script.redirect_final", &[&final_url, &final_host]));
+
+    if network::url_host(url).unwrap_or_default() != final_host {
+        log_print!("{}", app_state.get_formatted_translation("url_script.redirect_confirm", &[&final_host]));
+        let _ = io::stdout().flush();
+        let mut input = String::new();
+        if terminal::read_line(&mut input).is_err() || !input.trim().eq_ignore_ascii_case("y") {
+            return None;
+        }
+    }
+    Some(final_url)
 }
 
 // ─────────────────────────────────── 主函数 ───────────────────────────────
 
-fn main() {
+fn main() {
+    // ssh 以 askpass 方式调用本程序时只回答密码提示
+    if let Some(code) = ssh::run_askpass() {
+        std::process::exit(code);
+    }
+    let mut profile = startup::StartupProfile::start();
+    // 命令行调用本身算一个动作；交互模式下每次菜单选择再开始新的动作
+    logging::begin_action();
+    let cli_args = match cli::parse(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    if cli_args.show_help {
+        print!("{}", cli::USAGE);
+        return;
+    }
+    // 命令行子命令也要写日志，先于分派安装订阅者
+    let startup_config = load_user_config();
+    ui::theme::set_plain(startup_config.ui.plain_output);
+    if let Err(e) = init_logging(&startup_config.logging, Some(logging::log_dir().join("geektools.log"))) {
+        eprintln!("⚠️  {}", e);
+    }
+    fileio::configure_cache(&startup_config.cache);
+    #[cfg(feature = "plugins")]
+    plugins::configure_archives(&startup_config.plugins.archives);
+    #[cfg(feature = "network")]
+    network::configure_rate_limit(&startup_config.network);
+    profile.mark("config + logging");
+    if let Some((group, script)) = &cli_args.fan_out {
+        std::process::exit(run_fan_out_cli(group, Path::new(script), cli_args.verbosity));
+    }
+    if let Some(file) = &cli_args.batch {
+        std::process::exit(run_batch_cli(Path::new(file), cli_args.verbosity));
+    }
+    if let Some(file) = &cli_args.apply {
+        std::process::exit(run_apply_cli(Path::new(file)));
+    }
+    if let Some(filter) = &cli_args.logs {
+        std::process::exit(run_logs_cli(filter));
+    }
+    if let Some(source) = &cli_args.run {
+        std::process::exit(run_script_cli(source, cli_args.verbosity));
+    }
+    if let Some(command) = &cli_args.plugin {
+        std::process::exit(run_plugin_cli(command));
+    }
+    if let Some(args) = &cli_args.export_installer {
+        std::process::exit(run_export_installer_cli(args));
+    }
+    if let Some(socket) = &cli_args.serve {
+        std::process::exit(run_serve_cli(Path::new(socket), cli_args.verbosity));
+    }
+    if let Some(link) = &cli_args.open {
+        std::process::exit(run_open_cli(link, cli_args.verbosity));
+    }
+    if cli_args.register_url_handler {
+        std::process::exit(run_register_url_handler_cli());
+    }
+
     let mut app_state = match AppState::new() {
         Ok(state) => state,
         Err(e) => {
@@ -1080,7 +2568,13 @@ fn main() {
             std::process::exit(1);
         }
     };
+    app_state.verbosity = cli_args.verbosity;
+    profile.mark("language");
     log_println!("{}", app_state.get_translation("main.welcome"));
+    profile.mark("session log");
+    network::apply_security_config(&startup_config.security);
+    network::set_github_token(startup_config.github_token);
+    profile.mark("network setup");
 
     log_println!(
         "{}",
@@ -1107,78 +2601,104 @@ fn main() {
             ]
         )
     );
-    loop {
-        log_print!("{}", app_state.get_menu_text());
-        let _ = io::stdout().flush();
-
-        let mut choice = String::new();
-        if io::stdin().read_line(&mut choice).is_err() {
-            log_println!("{}", app_state.get_translation("main.invalid_choice"));
-            continue;
+    // 插件、市场和网络检查都在打开对应菜单时才初始化
+    if cli_args.profile_startup {
+        profile.mark("banner");
+        eprint!("{}", profile.render());
+    }
+    // 加载配置时迁移或合并了新默认值，可选查看差异
+    if let Some(report) = config::take_update_report() {
+        let count = report.changes.len() + report.conflicts.len();
+        let prompt = app_state.get_formatted_translation("config_update.prompt", &[&count.to_string()]);
+        if ui::menu::prompt(&prompt).is_some_and(|answer| answer.eq_ignore_ascii_case("y")) {
+            log_print!("{}", report.render());
         }
-
-        match choice.trim() {
-            "1" => run_existing_script(&app_state),
-            "2" => run_script_from_url(&app_state),
-            "3" => show_custom_scripts_menu(&app_state),
-            "4" => show_plugin_menu(&app_state),
-            "5" => show_settings_menu(&mut app_state),
-            "6" => {
-                log_println!("{}", app_state.get_translation("main.exit_message"));
-                process::exit(0);
-            }
-            _ => log_println!("{}", app_state.get_translation("main.invalid_choice")),
+    }
+    let _home = app_state.enter_menu("menu_nav.home");
+    // 未编译的功能不出现在菜单中
+    let keys: Vec<&str> = [
+        "menu.run_existing_script",
+        "menu.run_script_from_network",
+        "menu.custom_scripts",
+        "menu.plugin_management",
+        "menu.settings",
+        "menu.undo_last_run",
+        "menu.services",
+        "menu.search",
+        "menu.stats",
+    ]
+    .into_iter()
+    .filter(|key| match *key {
+        "menu.plugin_management" => cfg!(feature = "plugins"),
+        "menu.services" => cfg!(feature = "scheduler"),
+        _ => true,
+    })
+    .collect();
+    // 命令名不随菜单顺序变化；update 直接进入设置中的版本切换
+    let mut commands: Vec<(&str, usize)> = [
+        ("run", "menu.run_existing_script"),
+        ("plugins", "menu.plugin_management"),
+        ("settings", "menu.settings"),
+        ("search", "menu.search"),
+        ("stats", "menu.stats"),
+    ]
+    .into_iter()
+    .filter_map(|(command, key)| Some((command, keys.iter().position(|k| *k == key)?)))
+    .collect();
+    if cfg!(feature = "self-update") {
+        commands.push(("update", keys.len()));
+    }
+    loop {
+        // 设置中可能切换了语言，每次重新取翻译
+        let title = app_state.get_translation("menu.title");
+        let items = app_state.menu_items(&keys);
+        let Some(choice) = ui::menu::choose_with_commands(&title, &items, &commands, app_state.current_language) else {
+            break;
+        };
+        match keys.get(choice).copied() {
+            Some("menu.run_existing_script") => run_existing_script(&app_state),
+            Some("menu.run_script_from_network") => run_script_from_url(&app_state),
+            Some("menu.custom_scripts") => show_custom_scripts_menu(&app_state),
+            #[cfg(feature = "plugins")]
+            Some("menu.plugin_management") => show_plugin_menu(&app_state),
+            Some("menu.settings") => show_settings_menu(&mut app_state),
+            Some("menu.undo_last_run") => undo_last_run(&app_state),
+            #[cfg(feature = "scheduler")]
+            Some("menu.services") => show_service_menu(&app_state),
+            Some("menu.search") => global_search(&app_state),
+            Some("menu.stats") => show_stats(&app_state),
+            #[cfg(feature = "self-update")]
+            None => change_version(&app_state),
+            _ => {}
         }
 
         log_println!(); // 空行，美观
     }
+    log_println!("{}", app_state.get_translation("main.exit_message"));
 }
 
 // 显示设置菜单
 fn show_settings_menu(app_state: &mut AppState) {
-    loop {
-        log_print!("{}", app_state.get_settings_menu_text());
-        let _ = io::stdout().flush();
-
-        let mut choice = String::new();
-        if io::stdin().read_line(&mut choice).is_err() {
-            log_println!("{}", app_state.get_translation("main.invalid_choice"));
-            continue;
-        }
-
-        match choice.trim() {
-            "1" => {
-                // 语言设置
-                log_print!("{}", app_state.get_language_menu_text());
-                let _ = io::stdout().flush();
-
-                let mut lang_choice = String::new();
-                if io::stdin().read_line(&mut lang_choice).is_err() {
-                    log_println!("{}", app_state.get_translation("main.invalid_choice"));
-                    continue;
-                }
-                match lang_choice.trim() {
-                    "1" => {
-                        app_state.update_language(Language::English);
-                        if let Err(e) = save_language_to_config(Language::English) {
-                            log_println!("Failed to save language setting: {}", e);
-                        } else {
-                            log_println!("{}", app_state.get_translation("settings_menu.language_saved"));
-                        }
-                    }
-                    "2" => {
-                        app_state.update_language(Language::Chinese);
-                        if let Err(e) = save_language_to_config(Language::Chinese) {
-                            log_println!("Failed to save language setting: {}", e);
-                        } else {
-                            log_println!("{}", app_state.get_translation("settings_menu.language_saved"));
-                        }
-                    }
-                    _ => log_println!("{}", app_state.get_translation("main.invalid_choice")),
-                }
-            }
-            "2" => change_version(app_state),
-            "3" => {
+    let _crumb = app_state.enter_menu("menu.settings");
+    // 未编译自更新时不显示版本切换，其余编号前移
+    let skip = usize::from(!cfg!(feature = "self-update"));
+    let items = [
+        "settings_menu.change_language",
+        "settings_menu.change_version",
+        "settings_menu.clear_personalization",
+        "settings_menu.refresh_catalog",
+        "settings_menu.plain_output",
+        "settings_menu.view_logs",
+        "settings_menu.flush_cache",
+        #[cfg(feature = "network")]
+        "settings_menu.check_links",
+    ];
+    while let Some(choice) = app_state.choose("settings_menu.title", &[&items[..1], &items[1 + skip..]].concat()) {
+        match if choice == 0 { 0 } else { choice + skip } {
+            0 => change_language(app_state),
+            #[cfg(feature = "self-update")]
+            1 => change_version(app_state),
+            2 => {
                 // 清理个性化设置
                 if let Err(e) = fileio::remove_file(&*CONFIG_PATH) {
                     // Only show error if it's not a "file not found" error
@@ -1198,15 +2718,132 @@ fn show_settings_menu(app_state: &mut AppState) {
                 );
                 exit(0);
             }
-            "4" => return, // 返回主菜单
-            _ => log_println!("{}", app_state.get_translation("main.invalid_choice")),
+            3 => refresh_script_catalog(app_state),
+            4 => toggle_plain_output(app_state),
+            5 => show_logs(app_state),
+            6 => {
+                let cleared = fileio::clear_cache();
+                log_println!("{}", app_state.get_formatted_translation("settings_menu.cache_flushed", &[&cleared.to_string()]));
+            }
+            #[cfg(feature = "network")]
+            7 => check_stored_links(app_state),
+            _ => {}
         }
 
         log_println!(); // 空行，美观
     }
 }
 
+// 切换纯文本输出 (无 emoji 和制表符)，立即生效并保存到配置
+fn toggle_plain_output(app_state: &AppState) {
+    let mut config = load_user_config();
+    config.ui.plain_output = !config.ui.plain_output;
+    ui::theme::set_plain(config.ui.plain_output);
+    match save_user_config(&config) {
+        Ok(()) => {
+            let key = if config.ui.plain_output { "settings_menu.plain_output_on" } else { "settings_menu.plain_output_off" };
+            log_println!("{}", app_state.get_translation(key));
+        }
+        Err(e) => app_state.print_error(&e),
+    }
+}
+
+// 应用内日志查看器：过滤语法与 `geektools logs` 相同，只显示最近的条目
+fn show_logs(app_state: &AppState) {
+    const SHOWN: usize = 200;
+    let Some(input) = ui::menu::prompt_text(&app_state.get_translation("logs.filter_prompt")) else {
+        return;
+    };
+    let now = Local::now().naive_local();
+    let mut filter = match cli::parse_logs(input.split_whitespace(), now) {
+        Ok(filter) => filter,
+        Err(e) => {
+            log_println!("❌ {}", e);
+            return;
+        }
+    };
+    if input.trim().is_empty() {
+        filter.range.since = logging::parse_time("1d", now);
+    }
+    let lines = filter.query();
+    if lines.is_empty() {
+        log_println!("{}", app_state.get_translation("logs.no_entries"));
+        return;
+    }
+    // 查看器的输出不写回日志文件，否则每次查看都会让日志翻倍
+    for line in &lines[lines.len().saturating_sub(SHOWN)..] {
+        terminal::write_str(&format!("{}\n", line));
+    }
+    if lines.len() > SHOWN {
+        log_println!(
+            "{}",
+            app_state.get_formatted_translation("logs.showing", &[&SHOWN.to_string(), &lines.len().to_string()])
+        );
+    }
+}
+
+// 语言设置
+fn change_language(app_state: &mut AppState) {
+    let _crumb = app_state.enter_menu("settings_menu.change_language");
+    let language = match app_state.choose("language_menu.title", &Language::ALL.map(Language::menu_key)) {
+        Some(index) => Language::ALL[index],
+        None => return,
+    };
+    // slim-i18n 构建只内置英文，其他语言询问后下载语言包
+    if !language.is_available() {
+        let confirmed = ui::menu::prompt(&app_state.get_translation("language_menu.not_installed"))
+            .is_some_and(|answer| answer.eq_ignore_ascii_case("y"));
+        if !confirmed {
+            log_println!("{}", app_state.get_translation("main.invalid_language"));
+            return;
+        }
+        log_println!("{}", app_state.get_formatted_translation("language_menu.downloading", &[language.file_name()]));
+        if let Err(e) = i18n::download_pack(language) {
+            log_println!("{}", app_state.get_formatted_translation("language_menu.download_failed", &[&e.to_string()]));
+            return;
+        }
+    }
+    app_state.update_language(language);
+    if let Err(e) = save_language_to_config(language) {
+        log_println!("Failed to save language setting: {}", e);
+    } else {
+        log_println!("{}", app_state.get_translation("settings_menu.language_saved"));
+    }
+}
+
+// 检查远程脚本目录，有新版本时更新描述和链接 (不需要更新程序)
+fn refresh_script_catalog(app_state: &AppState) {
+    let mut config = load_user_config();
+    log_println!(
+        "{}",
+        app_state.get_formatted_translation("catalog.checking", &[&config.catalog.version.to_string()])
+    );
+    match scripts::catalog::check_for_update(&config.catalog) {
+        Ok(Some(catalog)) => {
+            if let Err(e) = scripts::catalog::save(&catalog) {
+                app_state.print_error(&e);
+                return;
+            }
+            config.catalog.version = catalog.version;
+            if let Err(e) = save_user_config(&config) {
+                app_state.print_error(&e);
+                return;
+            }
+            log_println!(
+                "{}",
+                app_state.get_formatted_translation(
+                    "catalog.updated",
+                    &[&catalog.version.to_string(), &catalog.scripts.len().to_string()]
+                )
+            );
+        }
+        Ok(None) => log_println!("{}", app_state.get_translation("catalog.up_to_date")),
+        Err(e) => app_state.print_error(&e),
+    }
+}
+
 // 从 Cargo.toml 读取 repository 信息
+#[cfg(feature = "self-update")]
 fn repo_path_from_cargo() -> std::result::Result<String, GeekToolsError> {
     // 在编译时直接获取 repository 字段
     Ok(env!("CARGO_PKG_REPOSITORY").to_string())
@@ -1222,15 +2859,11 @@ fn show_security_warning(app_state: &AppState) -> bool {
     log_println!("{}", app_state.get_translation("security.responsibility"));
     
     loop {
-        log_print!("\n{}", app_state.get_translation("security.confirm_prompt"));
-        let _ = io::stdout().flush();
-        
-        let mut input = String::new();
-        if io::stdin().read_line(&mut input).is_err() {
-            continue;
-        }
+        let Some(input) = ui::menu::prompt(&format!("\n{}", app_state.get_translation("security.confirm_prompt"))) else {
+            return false;
+        };
         
-        match input.trim().to_lowercase().as_str() {
+        match input.to_lowercase().as_str() {
             "y" | "yes" | "是" | "确认" => return true,
             "n" | "no" | "否" | "取消" => return false,
             _ => log_println!("{}", app_state.get_translation("main.invalid_choice")),
@@ -1240,44 +2873,18 @@ fn show_security_warning(app_state: &AppState) -> bool {
 
 /// 从URL下载脚本内容
 fn download_script_content(url: &str) -> std::result::Result<String, GeekToolsError> {
-    #[cfg(not(feature = "network"))]
-    return Err(GeekToolsError::ConfigError {
-        message: "Network functionality disabled".to_string(),
-    });
-    
-    #[cfg(feature = "network")]
-    {
-        let resp = reqwest::blocking::get(url)?;
-        
-        if !resp.status().is_success() {
-            return Err(GeekToolsError::ConfigError {
-                message: format!("HTTP error: {}", resp.status()),
-            });
-        }
-        
-        resp.text().map_err(GeekToolsError::from)
-    }
+    let max_size = load_user_config().security.max_script_download_bytes;
+    network::fetch_script(url, max_size).map(|script| script.content)
 }
 
-/// 解析脚本内容获取描述信息
-fn parse_script_info(content: &str, default_name: &str) -> (String, String) {
-    let mut name = default_name.to_string();
-    let mut description = "无描述".to_string();
-    
-    for line in content.lines().take(20) { // 只检查前20行
-        let line = line.trim();
-        if line.starts_with("# Name:") || line.starts_with("#Name:") {
-            name = line.split(':').nth(1).unwrap_or("").trim().to_string();
-        } else if line.starts_with("# Description:") || line.starts_with("#Description:") {
-            description = line.split(':').nth(1).unwrap_or("").trim().to_string();
-        } else if line.starts_with("# 名称:") || line.starts_with("#名称:") {
-            name = line.split(':').nth(1).unwrap_or("").trim().to_string();
-        } else if line.starts_with("# 描述:") || line.starts_with("#描述:") {
-            description = line.split(':').nth(1).unwrap_or("").trim().to_string();
-        }
-    }
-    
-    (name, description)
+/// 解析脚本头部获取名称、当前语言的描述和按语言的描述 (`# Description[zh]:`)
+fn parse_script_info(content: &str, default_name: &str, app_state: &AppState) -> (String, String, BTreeMap<String, String>) {
+    let meta = scripts::metadata::parse(content);
+    let name = meta.name.clone().unwrap_or_else(|| default_name.to_string());
+    let description = meta.description(Some(app_state.current_language.code())).unwrap_or("无描述").to_string();
+    let mut descriptions = meta.descriptions;
+    descriptions.remove("");
+    (name, description, descriptions)
 }
 
 /// 添加自定义脚本
@@ -1287,17 +2894,11 @@ fn add_custom_script(app_state: &AppState) {
         return;
     }
     
-    log_print!("{}", app_state.get_translation("custom_script.enter_url"));
-    let _ = io::stdout().flush();
-    
-    let mut url = String::new();
-    if io::stdin().read_line(&mut url).is_err() {
-        log_println!("{}", app_state.get_translation("main.invalid_choice"));
+    let Some(url) = ui::menu::prompt_text(&app_state.get_translation("custom_script.enter_url")) else {
         return;
-    }
-    
-    let url = url.trim();
-    if url.is_empty() || url.eq_ignore_ascii_case("exit") {
+    };
+    let url = url.as_str();
+    if url.is_empty() {
         return;
     }
     
@@ -1306,7 +2907,7 @@ fn add_custom_script(app_state: &AppState) {
     match download_script_content(url) {
         Ok(content) => {
             let script_id = format!("custom_{}", rand::random::<u64>());
-            let (name, description) = parse_script_info(&content, &script_id);
+            let (name, description, mut descriptions) = parse_script_info(&content, &script_id, app_state);
             
             log_println!("📝 检测到脚本信息:");
             log_println!("   名称: {}", name);
@@ -1316,14 +2917,14 @@ fn add_custom_script(app_state: &AppState) {
             let _ = io::stdout().flush();
             
             let mut edit_choice = String::new();
-            let _ = io::stdin().read_line(&mut edit_choice);
+            let _ = terminal::read_line(&mut edit_choice);
             
-            let (final_name, final_desc) = if edit_choice.trim().to_lowercase().starts_with("y") {
+            let (final_name, final_desc, final_interpreter) = if edit_choice.trim().to_lowercase().starts_with("y") {
                 // 编辑名称
                 log_print!("输入脚本名称 (留空保持'{}'): ", name);
                 let _ = io::stdout().flush();
                 let mut new_name = String::new();
-                let _ = io::stdin().read_line(&mut new_name);
+                let _ = terminal::read_line(&mut new_name);
                 let new_name = new_name.trim();
                 let final_name = if new_name.is_empty() { name } else { new_name.to_string() };
                 
@@ -1331,13 +2932,23 @@ fn add_custom_script(app_state: &AppState) {
                 log_print!("输入脚本描述 (留空保持'{}'): ", description);
                 let _ = io::stdout().flush();
                 let mut new_desc = String::new();
-                let _ = io::stdin().read_line(&mut new_desc);
+                let _ = terminal::read_line(&mut new_desc);
                 let new_desc = new_desc.trim();
-                let final_desc = if new_desc.is_empty() { description } else { new_desc.to_string() };
+                let final_desc = if new_desc.is_empty() {
+                    description
+                } else {
+                    // 手动输入的描述作为当前语言的描述
+                    descriptions.insert(app_state.current_language.code().to_string(), new_desc.to_string());
+                    new_desc.to_string()
+                };
+                
+                // 指定解释器
+                let interpreter = ui::menu::prompt_text("输入解释器 (如 bash、python3，留空按 shebang 自动选择): ")
+                    .filter(|s| !s.is_empty());
                 
-                (final_name, final_desc)
+                (final_name, final_desc, interpreter)
             } else {
-                (name, description)
+                (name, description, None)
             };
             
             // 创建自定义脚本目录
@@ -1349,7 +2960,7 @@ fn add_custom_script(app_state: &AppState) {
             }
             
             // 保存脚本内容到文件
-            let script_file_name = format!("{}.sh", script_id);
+            let script_file_name = format!("{}.{}", script_id, runtime::extension_for(url));
             let script_file_path = CUSTOM_SCRIPTS_DIR.join(&script_file_name);
             
             if let Err(e) = fileio::write(&script_file_path, &content) {
@@ -1368,10 +2979,14 @@ fn add_custom_script(app_state: &AppState) {
             let custom_script = CustomScript {
                 name: final_name.clone(),
                 description: Some(final_desc.clone()),
+                descriptions,
                 url: Some(url.to_string()),
                 file_path: Some(script_file_path.to_string_lossy().to_string()),
                 enabled: true,
                 last_updated: Some(chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string()),
+                interpreter: final_interpreter,
+                working_dir: None,
+                keep_artifacts: false,
             };
             
             let mut config = load_user_config();
@@ -1405,13 +3020,166 @@ fn list_custom_scripts(app_state: &AppState) {
     log_println!("{}", app_state.get_translation("custom_script.list_title"));
     for (idx, script) in config.custom_scripts.iter().enumerate() {
         log_println!("📜 {} ({})", script.name, idx + 1);
-        log_println!("   描述: {}", script.description.as_deref().unwrap_or("无描述"));
+        log_println!("   描述: {}", script.localized_description(app_state.current_language.code()).unwrap_or("无描述"));
         log_println!("   URL: {}", script.url.as_deref().unwrap_or("本地文件"));
         log_println!("   更新时间: {}", script.last_updated.as_deref().unwrap_or("未知"));
         log_println!();
     }
 }
 
+/// 检查来自 URL 的自定义脚本是否有更新：重新下载并比较哈希，列出有变化的脚本和差异，更新选中的脚本
+fn check_custom_script_updates(app_state: &AppState) {
+    // 每个脚本最多显示的差异行数
+    const DIFF_PREVIEW_LINES: usize = 12;
+
+    let mut config = load_user_config();
+    let sources: Vec<(usize, String, String)> = config
+        .custom_scripts
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, s)| Some((idx, s.url.clone()?, s.file_path.clone()?)))
+        .collect();
+    if sources.is_empty() {
+        log_println!("{}", app_state.get_translation("custom_script.no_url_scripts"));
+        return;
+    }
+
+    log_println!("🔍 正在检查 {} 个脚本...", sources.len());
+    let mut changed: Vec<(usize, String, String)> = Vec::new();
+    for (idx, url, file_path) in sources {
+        let name = &config.custom_scripts[idx].name;
+        let current = fileio::read(&file_path).unwrap_or_default();
+        match download_script_content(&url) {
+            Ok(latest) if network::sha256_str(&latest) != network::sha256_str(&current) => {
+                changed.push((idx, current, latest))
+            }
+            Ok(_) => {}
+            Err(e) => log_println!("⚠️  {}: {}", name, e),
+        }
+    }
+    if changed.is_empty() {
+        log_println!("✅ {}", app_state.get_translation("custom_script.all_up_to_date"));
+        return;
+    }
+
+    log_println!("\n{} 个脚本有更新:", changed.len());
+    for (i, (idx, current, latest)) in changed.iter().enumerate() {
+        let lines = |text: &str| text.lines().map(str::to_string).collect::<Vec<_>>();
+        let change = envdiff::line_diff(&lines(current), &lines(latest));
+        log_println!("{}. {} (+{} -{})", i + 1, config.custom_scripts[*idx].name, change.added.len(), change.removed.len());
+        let diff: Vec<String> = change
+            .removed
+            .iter()
+            .map(|line| format!("    - {}", line))
+            .chain(change.added.iter().map(|line| format!("    + {}", line)))
+            .collect();
+        for line in diff.iter().take(DIFF_PREVIEW_LINES) {
+            log_println!("{}", line);
+        }
+        if diff.len() > DIFF_PREVIEW_LINES {
+            log_println!("    ... 另有 {} 行变化", diff.len() - DIFF_PREVIEW_LINES);
+        }
+    }
+
+    let Some(input) = ui::menu::prompt("选择要更新的脚本 (如 1,3、2-4 或 all，直接回车取消): ").filter(|s| !s.is_empty()) else {
+        return;
+    };
+    let Some(selected) = parse_multi_select(&input, changed.len()) else {
+        log_println!("{}", app_state.get_translation("main.invalid_choice"));
+        return;
+    };
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let mut updated = 0;
+    for i in selected {
+        let (idx, _, latest) = &changed[i];
+        let script = &mut config.custom_scripts[*idx];
+        let Some(file_path) = &script.file_path else {
+            continue;
+        };
+        match fileio::write(file_path, latest) {
+            Ok(()) => {
+                script.last_updated = Some(now.clone());
+                updated += 1;
+                log_println!("✅ 已更新: {}", script.name);
+            }
+            Err(e) => log_println!("❌ {}: {}", script.name, e),
+        }
+    }
+    if updated > 0
+        && let Err(e) = save_user_config(&config)
+    {
+        log_println!("❌ 保存配置失败: {}", e);
+    }
+}
+
+/// 检查所有保存的链接 (内置 .link 脚本、自定义脚本 URL、插件市场)，报告失效的链接和处理建议；
+/// 失效的自定义脚本可以当场替换 URL 或删除
+#[cfg(feature = "network")]
+fn check_stored_links(app_state: &AppState) {
+    let mut config = load_user_config();
+    let targets = linkcheck::collect(&config);
+    log_println!("🔍 正在检查 {} 个链接...", targets.len());
+    let mut problems = Vec::new();
+    for target in targets {
+        let health = linkcheck::check(&target.url);
+        if health == linkcheck::LinkHealth::Ok {
+            log_println!("  ✅ {}", target.label());
+        } else {
+            log_println!("  ❌ {}: {}", target.label(), health);
+            problems.push((target, health));
+        }
+    }
+    if problems.is_empty() {
+        log_println!("✅ {}", app_state.get_translation("settings_menu.links_ok"));
+        return;
+    }
+
+    log_println!("\n{} 个链接有问题:", problems.len());
+    let mut changed = false;
+    for (target, health) in &problems {
+        log_println!("• {} ({})", target.label(), health);
+        log_println!("  {}", target.url);
+        log_println!("  💡 {}", target.suggestion(health));
+        let linkcheck::LinkSource::CustomScript(name) = &target.source else {
+            continue;
+        };
+        let Some(idx) = config.custom_scripts.iter().position(|s| &s.name == name && s.url.as_ref() == Some(&target.url)) else {
+            continue;
+        };
+        let Some(input) = ui::menu::prompt_text("  输入新 URL 替换，输入 d 删除该脚本，直接回车跳过: ").filter(|s| !s.is_empty()) else {
+            continue;
+        };
+        if input.eq_ignore_ascii_case("d") {
+            let script = config.custom_scripts.remove(idx);
+            if let Some(file_path) = &script.file_path {
+                let _ = fileio::remove_file(file_path);
+            }
+            log_println!("  🗑️  已删除: {}", script.name);
+            changed = true;
+            continue;
+        }
+        match download_script_content(&input) {
+            Ok(content) => {
+                let script = &mut config.custom_scripts[idx];
+                if let Some(file_path) = &script.file_path
+                    && let Err(e) = fileio::write(file_path, &content)
+                {
+                    log_println!("  ❌ {}", e);
+                    continue;
+                }
+                script.url = Some(input);
+                script.last_updated = Some(chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string());
+                log_println!("  ✅ 已替换为新的 URL: {}", script.name);
+                changed = true;
+            }
+            Err(e) => log_println!("  ❌ 新 URL 下载失败，未修改: {}", e),
+        }
+    }
+    if changed && let Err(e) = save_user_config(&config) {
+        log_println!("❌ 保存配置失败: {}", e);
+    }
+}
+
 /// 删除自定义脚本
 fn remove_custom_script(app_state: &AppState) {
     let mut config = load_user_config();
@@ -1428,18 +3196,9 @@ fn remove_custom_script(app_state: &AppState) {
         log_println!("{}. {}", i + 1, script.name);
     }
     
-    log_print!("选择要删除的脚本编号 (1-{}, 或输入 exit 退出): ", scripts.len());
-    let _ = io::stdout().flush();
-    
-    let mut input = String::new();
-    if io::stdin().read_line(&mut input).is_err() {
-        return;
-    }
-    
-    let input = input.trim();
-    if input.eq_ignore_ascii_case("exit") {
+    let Some(input) = ui::menu::prompt(&format!("选择要删除的脚本编号 (1-{}, 或输入 b 返回): ", scripts.len())) else {
         return;
-    }
+    };
     
     if let Ok(idx) = input.parse::<usize>() {
         if (1..=scripts.len()).contains(&idx) {
@@ -1450,7 +3209,7 @@ fn remove_custom_script(app_state: &AppState) {
             let _ = io::stdout().flush();
             
             let mut confirm = String::new();
-            let _ = io::stdin().read_line(&mut confirm);
+            let _ = terminal::read_line(&mut confirm);
             
             if confirm.trim().to_lowercase().starts_with("y") {
                 // 删除脚本文件（如果存在）
@@ -1477,89 +3236,467 @@ fn remove_custom_script(app_state: &AppState) {
     }
 }
 
+/// 从模板新建自定义脚本，用 $EDITOR 打开后注册
+fn create_script_from_template(app_state: &AppState) {
+    use scripts::templates::{self, TemplateKind};
+
+    let Some(name) = ui::menu::prompt_text("脚本名称: ").filter(|s| !s.is_empty()) else {
+        return;
+    };
+    let description = ui::menu::prompt_text("脚本描述: ").unwrap_or_default();
+
+    for (i, kind) in TemplateKind::ALL.iter().enumerate() {
+        log_println!("{}. {}", i + 1, kind.label());
+    }
+    let kind = match ui::menu::prompt(&format!("选择模板 (1-{}，默认 1): ", TemplateKind::ALL.len()))
+        .as_deref()
+        .unwrap_or("")
+    {
+        "" => TemplateKind::Basic,
+        input => match input.parse::<usize>() {
+            Ok(idx) if (1..=TemplateKind::ALL.len()).contains(&idx) => TemplateKind::ALL[idx - 1],
+            _ => {
+                log_println!("{}", app_state.get_translation("main.invalid_choice"));
+                return;
+            }
+        },
+    };
+
+    let script_id = format!("custom_{}", rand::random::<u64>());
+    let script_path = CUSTOM_SCRIPTS_DIR.join(format!("{}.sh", script_id));
+    if let Err(e) = fileio::write(&script_path, &templates::render(kind, &name, &description)) {
+        log_println!("❌ 保存脚本文件失败: {}", e);
+        return;
+    }
+    #[cfg(unix)]
+    {
+        if let Err(e) = fileio::set_executable(&script_path) {
+            log_println!("⚠️  设置脚本可执行权限失败: {}", e);
+        }
+    }
+
+    // 打开编辑器，EDITOR 可以带参数 (如 "code -w")
+    let editor = env::var("VISUAL").or_else(|_| env::var("EDITOR")).unwrap_or_else(|_| "vi".to_string());
+    let mut parts = editor.split_whitespace();
+    if let Some(program) = parts.next() {
+        log_println!("📝 使用 {} 打开 {}", editor, script_path.display());
+        match process::Command::new(program).args(parts).arg(&script_path).status() {
+            Ok(status) if !status.success() => log_println!("⚠️  编辑器退出码: {}", status),
+            Err(e) => log_println!("⚠️  无法启动编辑器 {}: {}", program, e),
+            _ => {}
+        }
+    }
+
+    // 编辑后重新读取头部，名称和描述以文件为准
+    let content = fileio::read(&script_path).unwrap_or_default();
+    let (final_name, final_desc, descriptions) = parse_script_info(&content, &name, app_state);
+    let mut config = load_user_config();
+    config.custom_scripts.push(CustomScript {
+        name: final_name.clone(),
+        description: Some(final_desc),
+        descriptions,
+        url: None,
+        file_path: Some(script_path.to_string_lossy().to_string()),
+        enabled: true,
+        last_updated: Some(chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string()),
+        interpreter: None,
+        working_dir: None,
+        keep_artifacts: false,
+    });
+    match save_user_config(&config) {
+        Ok(_) => log_println!("✅ 自定义脚本 '{}' 已创建: {}", final_name, script_path.display()),
+        Err(e) => log_println!("❌ 保存配置失败: {}", e),
+    }
+}
+
+/// 监视文件变化并重新运行自定义脚本，按回车停止
+fn watch_custom_script(app_state: &AppState) {
+    let config = load_user_config();
+    let scripts: Vec<&CustomScript> = config.custom_scripts.iter().filter(|s| s.file_path.is_some()).collect();
+    if scripts.is_empty() {
+        log_println!("{}", app_state.get_translation("custom_script.no_scripts"));
+        return;
+    }
+
+    for (i, script) in scripts.iter().enumerate() {
+        log_println!("{}. {}", i + 1, script.name);
+    }
+    let Some(input) = ui::menu::prompt(&format!("选择要监视运行的脚本编号 (1-{}, 或输入 b 返回): ", scripts.len())) else {
+        return;
+    };
+    let script = match input.parse::<usize>() {
+        Ok(idx) if (1..=scripts.len()).contains(&idx) => scripts[idx - 1],
+        _ => {
+            log_println!("{}", app_state.get_translation("main.invalid_choice"));
+            return;
+        }
+    };
+    let Some(file_path) = script.file_path.as_deref() else {
+        return;
+    };
+
+    let Some(input) = ui::menu::prompt_text("要监视的文件或目录 (多个用空格分隔，默认当前目录): ") else {
+        return;
+    };
+    let paths: Vec<PathBuf> = if input.is_empty() {
+        vec![env::current_dir().unwrap_or_else(|_| PathBuf::from("."))]
+    } else {
+        input.split_whitespace().map(PathBuf::from).collect()
+    };
+    if let Some(missing) = paths.iter().find(|p| !p.exists()) {
+        log_println!("❌ 路径不存在: {}", missing.display());
+        return;
+    }
+
+    let mut options = watch::WatchOptions::new(paths);
+    options.clear_screen = ui::menu::prompt("每次运行前清屏? (y/N): ").is_some_and(|s| s.eq_ignore_ascii_case("y"));
+
+    let Some(run_options) = resolve_run_options(app_state, &script.name, false) else {
+        return;
+    };
+    let run_options = run_options
+        .with_interpreter(script.interpreter.as_deref())
+        .with_workspace(script.working_dir.as_deref(), script.keep_artifacts);
+
+    // 监视期间按配置提供 Prometheus 指标，离开时 drop 关闭端点
+    let metrics_config = load_user_config().metrics;
+    let _exporter = if metrics_config.enabled {
+        match metrics::serve(metrics_config.port) {
+            Ok(exporter) => {
+                log_println!("📈 指标: http://{}/metrics", exporter.addr());
+                Some(exporter)
+            }
+            Err(e) => {
+                log_println!("⚠️  无法在端口 {} 上提供指标: {}", metrics_config.port, e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let watcher = match watch::Watch::start(&options) {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            log_println!("❌ 无法监视: {}", e);
+            return;
+        }
+    };
+
+    log_println!("👀 正在监视 {} 个路径，按回车停止", options.paths.len());
+    run_custom_script_from_file(file_path, app_state, &run_options);
+    // 监视在后台线程中运行，主线程读取回车；监视结束时不会留下继续读取标准输入的线程
+    let stop = std::sync::atomic::AtomicBool::new(false);
+    std::thread::scope(|s| {
+        s.spawn(|| {
+            watcher.run(&options, &stop, |changed| {
+                for path in changed.iter().take(5) {
+                    log_println!("🔄 变化: {}", path.display());
+                }
+                if changed.len() > 5 {
+                    log_println!("🔄 ... 共 {} 个文件变化", changed.len());
+                }
+                run_custom_script_from_file(file_path, app_state, &run_options);
+                log_println!("👀 等待变化中，按回车停止");
+            })
+        });
+        let mut line = String::new();
+        let _ = terminal::read_line(&mut line);
+        stop.store(true, std::sync::atomic::Ordering::Relaxed);
+    });
+    log_println!("⏹️  已停止监视");
+}
+
+/// 把自定义脚本发布为 GitHub Gist，并把 raw URL 复制到剪贴板，别人可以"从 URL 添加"
+#[cfg(feature = "network")]
+fn publish_custom_script_gist(app_state: &AppState) {
+    let config = load_user_config();
+    let scripts: Vec<&CustomScript> = config.custom_scripts.iter().filter(|s| s.file_path.is_some()).collect();
+    if scripts.is_empty() {
+        log_println!("{}", app_state.get_translation("custom_script.no_scripts"));
+        return;
+    }
+
+    for (i, script) in scripts.iter().enumerate() {
+        log_println!("{}. {}", i + 1, script.name);
+    }
+    let Some(input) = ui::menu::prompt(&app_state.get_formatted_translation("custom_script.gist_choose", &[&scripts.len().to_string()])) else {
+        return;
+    };
+    let script = match input.parse::<usize>() {
+        Ok(idx) if (1..=scripts.len()).contains(&idx) => scripts[idx - 1],
+        _ => {
+            log_println!("{}", app_state.get_translation("main.invalid_choice"));
+            return;
+        }
+    };
+    // 私密 Gist 不出现在个人主页和搜索中，但知道链接的人都能访问
+    let public = ui::menu::prompt(&app_state.get_translation("custom_script.gist_public_prompt"))
+        .is_some_and(|s| s.eq_ignore_ascii_case("y"));
+
+    log_println!("{}", app_state.get_translation("custom_script.publishing_gist"));
+    match gist::publish(script, public) {
+        Ok(url) => {
+            log_println!("✅ {}", app_state.get_translation("custom_script.gist_published"));
+            log_println!("   {}", url);
+            if terminal::copy_to_clipboard(&url) {
+                log_println!("📋 {}", app_state.get_translation("custom_script.url_copied"));
+            }
+            if !ui::theme::is_plain()
+                && ui::menu::prompt(&app_state.get_translation("custom_script.gist_qr_prompt"))
+                    .is_some_and(|s| s.eq_ignore_ascii_case("y"))
+            {
+                show_qr_code(&url);
+            }
+        }
+        Err(e) => {
+            log_println!("❌ {}", e);
+            if matches!(e, GeekToolsError::PermissionError { .. } | GeekToolsError::ConfigError { .. }) {
+                log_println!("{}", app_state.get_translation("custom_script.gist_token_hint"));
+            }
+        }
+    }
+}
+
 // 显示自定义脚本管理菜单
 fn show_custom_scripts_menu(app_state: &AppState) {
-    loop {
-        log_print!("{}", app_state.get_custom_scripts_menu_text());
-        let _ = io::stdout().flush();
-
-        let mut choice = String::new();
-        if io::stdin().read_line(&mut choice).is_err() {
-            log_println!("{}", app_state.get_translation("main.invalid_choice"));
-            continue;
+    let _crumb = app_state.enter_menu("menu.custom_scripts");
+    while let Some(choice) = app_state.choose(
+        "custom_script_menu.title",
+        &[
+            "custom_script_menu.add",
+            "custom_script_menu.list",
+            "custom_script_menu.remove",
+            "custom_script_menu.watch",
+            "custom_script_menu.new_from_template",
+            "custom_script_menu.check_updates",
+            #[cfg(feature = "network")]
+            "custom_script_menu.publish_gist",
+        ],
+    ) {
+        match choice {
+            0 => add_custom_script(app_state),
+            1 => list_custom_scripts(app_state),
+            2 => remove_custom_script(app_state),
+            3 => watch_custom_script(app_state),
+            4 => create_script_from_template(app_state),
+            5 => check_custom_script_updates(app_state),
+            #[cfg(feature = "network")]
+            6 => publish_custom_script_gist(app_state),
+            _ => {}
         }
 
-        match choice.trim() {
-            "1" => add_custom_script(app_state),
-            "2" => list_custom_scripts(app_state),
-            "3" => remove_custom_script(app_state),
-            "4" => return, // 返回主菜单
-            _ => log_println!("{}", app_state.get_translation("main.invalid_choice")),
+        log_println!(); // 空行，美观
+    }
+}
+
+// 显示脚本服务菜单 (systemd 用户单元 / launchd LaunchAgent)
+#[cfg(feature = "scheduler")]
+fn show_service_menu(app_state: &AppState) {
+    let Some(backend) = service::Backend::current() else {
+        log_println!("{}", app_state.get_translation("service.unsupported"));
+        return;
+    };
+    let _crumb = app_state.enter_menu("menu.services");
+    while let Some(choice) = app_state.choose(
+        "service_menu.title",
+        &[
+            "service_menu.install",
+            "service_menu.status",
+            "service_menu.start",
+            "service_menu.stop",
+            "service_menu.uninstall",
+        ],
+    ) {
+        let result = match choice {
+            0 => install_script_service(app_state, backend),
+            1 => choose_service(app_state, backend).map(|id| service::status(backend, &id).map(|_| ())),
+            2 => choose_service(app_state, backend).map(|id| service::start(backend, &id)),
+            3 => choose_service(app_state, backend).map(|id| service::stop(backend, &id)),
+            _ => choose_service(app_state, backend).map(|id| service::uninstall(backend, &id)),
+        };
+        match result {
+            Some(Ok(())) => log_println!("{}", app_state.get_translation("service.done")),
+            Some(Err(e)) => log_println!(
+                "{}",
+                app_state.get_formatted_translation("service.failed", &[&e.to_string()])
+            ),
+            None => {}
         }
 
         log_println!(); // 空行，美观
     }
 }
 
-// 显示插件管理菜单
-fn show_plugin_menu(app_state: &AppState) {
-    let mut plugin_manager = PluginManager::new();
-    
-    loop {
-        log_print!("{}", app_state.get_plugin_menu_text());
-        let _ = io::stdout().flush();
+// 选择已安装的服务，没有服务或取消时返回 None
+#[cfg(feature = "scheduler")]
+fn choose_service(app_state: &AppState, backend: service::Backend) -> Option<String> {
+    let ids = service::list_installed(backend);
+    if ids.is_empty() {
+        log_println!("{}", app_state.get_translation("service.no_services"));
+        return None;
+    }
+    for (i, id) in ids.iter().enumerate() {
+        log_println!("{}. {}", i + 1, id);
+    }
+    let input = ui::menu::prompt(
+        &app_state.get_formatted_translation("service.choose_service", &[&ids.len().to_string()]),
+    )?;
+    match input.parse::<usize>() {
+        Ok(idx) if (1..=ids.len()).contains(&idx) => Some(ids[idx - 1].clone()),
+        _ => {
+            log_println!("{}", app_state.get_translation("main.invalid_choice"));
+            None
+        }
+    }
+}
+
+// 把自定义脚本或插件脚本安装为开机 (登录) 自启的服务
+#[cfg(feature = "scheduler")]
+fn install_script_service(app_state: &AppState, backend: service::Backend) -> Option<io::Result<()>> {
+    let config = load_user_config();
+    let mut candidates: Vec<(String, PathBuf, Option<String>)> = config
+        .custom_scripts
+        .iter()
+        .filter_map(|s| Some((s.name.clone(), PathBuf::from(s.file_path.as_ref()?), s.interpreter.clone())))
+        .collect();
+    candidates.extend(
+        enabled_plugin_scripts()
+            .into_iter()
+            .map(|(name, _, path, entry)| (name, path, entry.interpreter)),
+    );
+    if candidates.is_empty() {
+        log_println!("{}", app_state.get_translation("service.no_scripts"));
+        return None;
+    }
 
-        let mut choice = String::new();
-        if io::stdin().read_line(&mut choice).is_err() {
+    for (i, (name, path, _)) in candidates.iter().enumerate() {
+        log_println!("{}. {} ({})", i + 1, name, path.display());
+    }
+    let input = ui::menu::prompt(
+        &app_state.get_formatted_translation("service.choose_script", &[&candidates.len().to_string()]),
+    )?;
+    let (name, path, interpreter) = match input.parse::<usize>() {
+        Ok(idx) if (1..=candidates.len()).contains(&idx) => &candidates[idx - 1],
+        _ => {
             log_println!("{}", app_state.get_translation("main.invalid_choice"));
-            continue;
+            return None;
+        }
+    };
+    let path = match path.canonicalize() {
+        Ok(path) => path,
+        Err(e) => return Some(Err(e)),
+    };
+
+    let home = PathBuf::from(env::var("HOME").unwrap_or_else(|_| ".".to_string()));
+    let prompt = app_state.get_formatted_translation("service.workdir_prompt", &[&home.display().to_string()]);
+    let workdir = ui::menu::prompt_text(&prompt)?;
+    let working_dir = if workdir.is_empty() { home } else { PathBuf::from(workdir) };
+    let restart_on_failure = ui::menu::prompt(&app_state.get_translation("service.restart_prompt"))
+        .is_some_and(|s| s.eq_ignore_ascii_case("y"));
+
+    let options = RunOptions::default().with_interpreter(interpreter.as_deref());
+    let mut command = executor::resolve_interpreter(&path, &options);
+    command.push(path.to_string_lossy().into_owned());
+    let spec = service::ServiceSpec {
+        id: service::service_id(name),
+        description: name.clone(),
+        command,
+        working_dir,
+        restart_on_failure,
+    };
+    if backend.unit_file(&spec.id).exists() {
+        log_println!("{}", app_state.get_formatted_translation("service.overwrite", &[&spec.id]));
+    }
+    Some(service::install(backend, &spec).map(|unit| {
+        log_println!(
+            "{}",
+            app_state.get_formatted_translation(
+                "service.installed",
+                &[&unit.display().to_string(), &spec.log_file().display().to_string()]
+            )
+        );
+        if !service::enable_linger(backend) {
+            log_println!("{}", app_state.get_translation("service.linger_hint"));
         }
+    }))
+}
 
-        match choice.trim() {
-            "1" => {
+// 显示插件管理菜单
+#[cfg(feature = "plugins")]
+fn show_plugin_menu(app_state: &AppState) {
+    let mut plugin_manager = PluginManager::new();
+    let _crumb = app_state.enter_menu("menu.plugin_management");
+    // 未编译插件市场时不显示第一项，其余编号后移
+    let skip = usize::from(!cfg!(feature = "marketplace"));
+    let items = [
+        "plugin_menu.marketplace",
+        "plugin_menu.local_scan",
+        "plugin_menu.install",
+        "plugin_menu.list",
+        "plugin_menu.uninstall",
+        "plugin_menu.toggle",
+        "plugin_menu.rollback",
+        "plugin_menu.cached",
+        "plugin_menu.link_dev",
+    ];
+    while let Some(choice) = app_state.choose("plugin_menu.title", &items[skip..]) {
+        match choice + skip {
+            #[cfg(feature = "marketplace")]
+            0 => {
                 // 插件市场管理
                 show_marketplace_menu(app_state, &mut plugin_manager);
             }
-            "2" => {
+            1 => {
                 // 本地插件扫描和导入
                 show_local_scan_menu(app_state, &mut plugin_manager);
             }
-            "3" => {
-                // 安装插件
-                log_print!("请输入插件包路径 (.tar.gz 文件): ");
-                let _ = io::stdout().flush();
-                
-                let mut path_input = String::new();
-                if io::stdin().read_line(&mut path_input).is_err() {
-                    log_println!("{}", app_state.get_translation("main.invalid_choice"));
+            2 => {
+                // 安装插件（本地路径、URL 或 GitHub 仓库）
+                let Some(path_input) = ui::menu::prompt_text(
+                    "请输入插件包路径 (.tar.gz 文件)、https:// 下载地址或 GitHub 仓库 (user/repo[@tag]): ",
+                ) else {
+                    continue;
+                };
+                let plugin_path = path_input.as_str();
+                if plugin_path.is_empty() {
                     continue;
                 }
                 
-                let plugin_path = path_input.trim();
-                if plugin_path.is_empty() || plugin_path.eq_ignore_ascii_case("exit") {
+                if network::is_url(plugin_path) {
+                    install_plugin_from_url(plugin_path, &mut plugin_manager);
                     continue;
                 }
                 
+                if !Path::new(plugin_path).exists() {
+                    if let Some((repo, tag)) = parse_github_shorthand(plugin_path) {
+                        install_plugin_from_github(&repo, tag.as_deref(), &mut plugin_manager);
+                        continue;
+                    }
+                }
+                
                 match plugin_manager.install_plugin(Path::new(plugin_path)) {
                     Ok(plugin_id) => {
                         log_println!("✅ 插件安装成功！插件 ID: {}", plugin_id);
                     }
-                    Err(e) => {
-                        log_println!("❌ 插件安装失败: {}", e);
-                    }
+                    Err(e) => app_state.print_error(&e),
                 }
             }
-            "4" => {
+            3 => {
                 // 列出插件
                 let plugins = plugin_manager.list_installed_plugins();
                 if plugins.is_empty() {
                     log_println!("📋 暂无已安装的插件");
                 } else {
                     log_println!("📋 已安装的插件:");
-                    for plugin in plugins {
+                    for (i, plugin) in plugins.iter().enumerate() {
                         let status = if plugin.enabled { "✅ 已启用" } else { "❌ 已禁用" };
-                        log_println!("  📦 {} ({})", plugin.info.name, plugin.info.id);
+                        let dev = if plugin.dev { " [dev]" } else { "" };
+                        log_println!("{}. 📦 {} ({}){}", i + 1, plugin.info.name, plugin.info.id, dev);
                         log_println!("     版本: {} | 状态: {}", plugin.info.version, status);
+                        if let Some(previous) = &plugin.previous_version {
+                            log_println!("     上一版本: {} (可回滚)", previous);
+                        }
                         log_println!("     描述: {}", plugin.info.description);
                         log_println!("     作者: {} | 安装时间: {}", plugin.info.author, plugin.installed_at);
                         if !plugin.info.scripts.is_empty() {
@@ -1570,9 +3707,39 @@ fn show_plugin_menu(app_state: &AppState) {
                         }
                         log_println!();
                     }
+
+                    // 批量操作
+                    let Some(input) =
+                        ui::menu::prompt("批量操作：输入插件编号 (如 1,3,5、2-4 或 all)，直接回车返回: ")
+                    else {
+                        continue;
+                    };
+                    let input = input.as_str();
+                    if input.is_empty() {
+                        continue;
+                    }
+
+                    let selected = match parse_multi_select(input, plugins.len()) {
+                        Some(indices) => indices.iter().map(|&i| plugins[i].info.id.clone()).collect::<Vec<_>>(),
+                        None => {
+                            log_println!("{}", app_state.get_translation("main.invalid_choice"));
+                            continue;
+                        }
+                    };
+
+                    log_print!("选择操作: e - 启用 | d - 禁用 | u - 卸载: ");
+                    let _ = io::stdout().flush();
+                    let mut action = String::new();
+                    let _ = terminal::read_line(&mut action);
+                    match action.trim() {
+                        "e" => set_plugins_enabled_batch(&mut plugin_manager, &selected, true),
+                        "d" => set_plugins_enabled_batch(&mut plugin_manager, &selected, false),
+                        "u" => uninstall_plugins_batch(&mut plugin_manager, &selected),
+                        _ => log_println!("{}", app_state.get_translation("main.invalid_choice")),
+                    }
                 }
             }
-            "5" => {
+            4 => {
                 // 卸载插件
                 let plugins = plugin_manager.list_installed_plugins();
                 if plugins.is_empty() {
@@ -1585,43 +3752,34 @@ fn show_plugin_menu(app_state: &AppState) {
                     log_println!("{}. {} ({})", i + 1, plugin.info.name, plugin.info.id);
                 }
                 
-                log_print!("输入插件编号 (1-{}, 或输入 exit 退出): ", plugins.len());
-                let _ = io::stdout().flush();
-                
-                let mut input = String::new();
-                if io::stdin().read_line(&mut input).is_err() {
-                    continue;
-                }
-                
-                let input = input.trim();
-                if input.eq_ignore_ascii_case("exit") {
+                let prompt = format!("输入插件编号 (1-{}，支持 1,3,5 或 all，输入 b 返回): ", plugins.len());
+                let Some(input) = ui::menu::prompt(&prompt) else {
                     continue;
-                }
+                };
+                let input = input.as_str();
                 
-                if let Ok(idx) = input.parse::<usize>() {
-                    if (1..=plugins.len()).contains(&idx) {
-                        let plugin = &plugins[idx - 1];
-                        let plugin_name = plugin.info.name.clone();
-                        let plugin_id = plugin.info.id.clone();
+                match parse_multi_select(input, plugins.len()) {
+                    Some(indices) => {
+                        let selected: Vec<String> = indices.iter().map(|&i| plugins[i].info.id.clone()).collect();
+                        let names: Vec<String> = indices.iter().map(|&i| plugins[i].info.name.clone()).collect();
                         
-                        log_print!("确认卸载插件 '{}' 吗? (y/N): ", plugin_name);
+                        log_print!("确认卸载插件 '{}' 吗? (y/N): ", names.join("', '"));
                         let _ = io::stdout().flush();
                         
                         let mut confirm = String::new();
-                        let _ = io::stdin().read_line(&mut confirm);
+                        let _ = terminal::read_line(&mut confirm);
                         
                         if confirm.trim().to_lowercase().starts_with("y") {
-                            match plugin_manager.uninstall_plugin(&plugin_id) {
-                                Ok(_) => log_println!("✅ 插件 '{}' 卸载成功", plugin_name),
-                                Err(e) => log_println!("❌ 卸载失败: {}", e),
-                            }
+                            uninstall_plugins_batch(&mut plugin_manager, &selected);
                         }
-                    } else {
-                        log_println!("{}", app_state.get_translation("main.invalid_choice"));
                     }
+                    None => log_println!("{}", app_state.get_translation("main.invalid_choice")),
                 }
             }
-            "6" => {
+            6 => rollback_plugin_menu(app_state, &mut plugin_manager),
+            7 => install_cached_plugin_menu(app_state, &mut plugin_manager),
+            8 => link_dev_plugin_menu(app_state, &mut plugin_manager),
+            _ => {
                 // 启用/禁用插件
                 let plugins = plugin_manager.list_installed_plugins();
                 if plugins.is_empty() {
@@ -1630,27 +3788,26 @@ fn show_plugin_menu(app_state: &AppState) {
                 }
                 
                 log_println!("📋 选择要切换状态的插件:");
-                for (i, plugin) in plugins.iter().enumerate() {
-                    let status = if plugin.enabled { "✅ 已启用" } else { "❌ 已禁用" };
-                    log_println!("{}. {} ({}) - {}", i + 1, plugin.info.name, plugin.info.id, status);
-                }
-                
-                log_print!("输入插件编号 (1-{}, 或输入 exit 退出): ", plugins.len());
-                let _ = io::stdout().flush();
-                
-                let mut input = String::new();
-                if io::stdin().read_line(&mut input).is_err() {
-                    continue;
+                let rows: Vec<Vec<String>> = plugins
+                    .iter()
+                    .map(|plugin| {
+                        let status = if plugin.enabled { "✅ 已启用" } else { "❌ 已禁用" };
+                        vec![plugin.info.name.clone(), format!("({})", plugin.info.id), status.to_string()]
+                    })
+                    .collect();
+                for line in ui::layout::numbered(&rows, 1) {
+                    log_println!("{}", line);
                 }
                 
-                let input = input.trim();
-                if input.eq_ignore_ascii_case("exit") {
+                let prompt = format!("输入插件编号 (1-{}，支持 1,3,5 或 all，输入 b 返回): ", plugins.len());
+                let Some(input) = ui::menu::prompt(&prompt) else {
                     continue;
-                }
+                };
+                let input = input.as_str();
                 
-                if let Ok(idx) = input.parse::<usize>() {
-                    if (1..=plugins.len()).contains(&idx) {
-                        let plugin = &plugins[idx - 1];
+                match parse_multi_select(input, plugins.len()) {
+                    Some(indices) if indices.len() == 1 => {
+                        let plugin = &plugins[indices[0]];
                         let plugin_id = plugin.info.id.clone();
                         let plugin_name = plugin.info.name.clone();
                         let new_status = !plugin.enabled;
@@ -1660,53 +3817,447 @@ fn show_plugin_menu(app_state: &AppState) {
                             Ok(_) => log_println!("✅ 插件 '{}' 已{}", plugin_name, status_text),
                             Err(e) => log_println!("❌ 操作失败: {}", e),
                         }
-                    } else {
-                        log_println!("{}", app_state.get_translation("main.invalid_choice"));
                     }
+                    Some(indices) => {
+                        let selected: Vec<String> = indices.iter().map(|&i| plugins[i].info.id.clone()).collect();
+                        
+                        log_print!("已选择 {} 个插件，e - 全部启用 | d - 全部禁用: ", selected.len());
+                        let _ = io::stdout().flush();
+                        let mut action = String::new();
+                        let _ = terminal::read_line(&mut action);
+                        match action.trim() {
+                            "e" => set_plugins_enabled_batch(&mut plugin_manager, &selected, true),
+                            "d" => set_plugins_enabled_batch(&mut plugin_manager, &selected, false),
+                            _ => log_println!("{}", app_state.get_translation("main.invalid_choice")),
+                        }
+                    }
+                    None => log_println!("{}", app_state.get_translation("main.invalid_choice")),
                 }
             }
-            "7" => return, // 返回主菜单
-            _ => log_println!("{}", app_state.get_translation("main.invalid_choice")),
         }
 
         log_println!(); // 空行，美观
     }
 }
 
+// 把升级过的插件回滚到上一版本 (使用缓存的插件包)
+#[cfg(feature = "plugins")]
+fn rollback_plugin_menu(app_state: &AppState, plugin_manager: &mut PluginManager) {
+    let candidates: Vec<(String, String, String, String)> = plugin_manager
+        .list_installed_plugins()
+        .into_iter()
+        .filter_map(|p| {
+            let previous = p.previous_version.clone()?;
+            Some((p.info.id.clone(), p.info.name.clone(), p.info.version.clone(), previous))
+        })
+        .collect();
+    if candidates.is_empty() {
+        log_println!("📋 没有可回滚的插件 (只有升级过的插件可以回滚)");
+        return;
+    }
+
+    log_println!("📋 选择要回滚的插件:");
+    for (i, (_, name, version, previous)) in candidates.iter().enumerate() {
+        log_println!("{}. {} v{} → v{}", i + 1, name, version, previous);
+    }
+    let prompt = format!("输入插件编号 (1-{}，输入 b 返回): ", candidates.len());
+    let Some(input) = ui::menu::prompt(&prompt) else {
+        return;
+    };
+    let Some((id, name, _, _)) = input.parse::<usize>().ok().and_then(|n| candidates.get(n.wrapping_sub(1))) else {
+        log_println!("{}", app_state.get_translation("main.invalid_choice"));
+        return;
+    };
+    match plugin_manager.rollback_plugin(id) {
+        Ok(version) => log_println!("✅ 插件 '{}' 已回滚到 v{}", name, version),
+        Err(e) => log_println!("❌ 回滚失败: {}", e),
+    }
+}
+
+// 从本地缓存的插件包重新安装 (卸载后重装或切换版本，无需联网)
+#[cfg(feature = "plugins")]
+fn install_cached_plugin_menu(app_state: &AppState, plugin_manager: &mut PluginManager) {
+    let installed: std::collections::HashMap<String, String> = plugin_manager
+        .list_installed_plugins()
+        .into_iter()
+        .map(|p| (p.info.id.clone(), p.info.version.clone()))
+        .collect();
+    let archives: Vec<plugins::CachedArchive> = plugin_manager
+        .cached_archives()
+        .into_iter()
+        .filter(|a| installed.get(&a.info.id) != Some(&a.info.version))
+        .collect();
+    if archives.is_empty() {
+        log_println!("📋 没有可安装的缓存插件包");
+        return;
+    }
+
+    log_println!("📋 缓存的插件包:");
+    for (i, archive) in archives.iter().enumerate() {
+        let status = match installed.get(&archive.info.id) {
+            Some(version) => format!("已安装 v{}", version),
+            None => "未安装".to_string(),
+        };
+        log_println!("{}. {} v{} ({})", i + 1, archive.info.name, archive.info.version, status);
+    }
+    let prompt = format!("输入编号 (1-{}，输入 b 返回): ", archives.len());
+    let Some(input) = ui::menu::prompt(&prompt) else {
+        return;
+    };
+    let Some(archive) = input.parse::<usize>().ok().and_then(|n| archives.get(n.wrapping_sub(1))) else {
+        log_println!("{}", app_state.get_translation("main.invalid_choice"));
+        return;
+    };
+    match plugin_manager.install_cached(archive) {
+        Ok(_) => log_println!("✅ 已安装 {} v{}", archive.info.name, archive.info.version),
+        Err(e) => app_state.print_error(&e),
+    }
+}
+
+// 开发模式：链接插件的工作目录，修改脚本后无需重新打包安装
+#[cfg(feature = "plugins")]
+fn link_dev_plugin_menu(app_state: &AppState, plugin_manager: &mut PluginManager) {
+    let Some(input) = ui::menu::prompt_text("请输入插件工作目录 (包含 info.json 和 scripts/): ") else {
+        return;
+    };
+    if input.is_empty() {
+        return;
+    }
+    match plugin_manager.link_dev_plugin(Path::new(&input)) {
+        Ok(plugin_id) => {
+            log_println!("✅ 已链接开发插件: {}", plugin_id);
+            log_println!("   脚本修改立即生效，info.json 的修改在下次打开插件管理时读取");
+        }
+        Err(e) => app_state.print_error(&e),
+    }
+}
+
+// 解析 `user/repo[@tag]` 形式的 GitHub 仓库简写
+#[cfg(feature = "plugins")]
+fn parse_github_shorthand(input: &str) -> Option<(String, Option<String>)> {
+    let (repo, tag) = match input.split_once('@') {
+        Some((repo, tag)) if !tag.is_empty() => (repo, Some(tag.to_string())),
+        Some(_) => return None,
+        None => (input, None),
+    };
+
+    let (owner, name) = repo.split_once('/')?;
+    let valid = |part: &str| {
+        !part.is_empty()
+            && part != "."
+            && part != ".."
+            && part.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    };
+    if valid(owner) && valid(name) {
+        Some((format!("{}/{}", owner, name), tag))
+    } else {
+        None
+    }
+}
+
+// 查询 GitHub 仓库的指定 release（未指定 tag 时取最新正式版）
+#[cfg(feature = "plugins")]
+fn fetch_github_release(repo: &str, tag: Option<&str>) -> std::result::Result<GhRelease, GeekToolsError> {
+    #[cfg(not(feature = "network"))]
+    {
+        let _ = (repo, tag);
+        return Err(GeekToolsError::ConfigError {
+            message: "Network functionality disabled".to_string(),
+        });
+    }
+
+    #[cfg(feature = "network")]
+    {
+        let url = match tag {
+            Some(tag) => format!("https://api.github.com/repos/{}/releases/tags/{}", repo, urlencoding::encode(tag)),
+            None => format!("https://api.github.com/repos/{}/releases/latest", repo),
+        };
+        debug_log!("[DEBUG] 查询插件 release: {url}");
+
+        let release: GhRelease = serde_json::from_str(&network::github_get(&url)?)?;
+        Ok(release)
+    }
+}
+
+// 从 GitHub release 中挑选 .tar.gz 插件包下载并安装
+#[cfg(feature = "plugins")]
+fn install_plugin_from_github(repo: &str, tag: Option<&str>, plugin_manager: &mut PluginManager) {
+    log_println!("正在查询 GitHub 仓库 {} 的发布版本...", repo);
+    let release = match fetch_github_release(repo, tag) {
+        Ok(release) => release,
+        Err(e) => {
+            log_println!("❌ 获取发布信息失败: {}", e);
+            return;
+        }
+    };
+
+    let assets: Vec<&GhAsset> = release.assets.iter().filter(|a| a.name.ends_with(".tar.gz")).collect();
+    let asset = match assets.as_slice() {
+        [] => {
+            log_println!("❌ 发布版本 {} 中没有 .tar.gz 插件包", release.tag_name);
+            return;
+        }
+        [only] => *only,
+        _ => {
+            log_println!("📦 发布版本 {} 包含多个插件包:", release.tag_name);
+            for (i, asset) in assets.iter().enumerate() {
+                log_println!("{}. {}", i + 1, asset.name);
+            }
+            let Some(input) = ui::menu::prompt(&format!("选择要安装的插件包 (1-{}, 或输入 b 返回): ", assets.len())) else {
+                return;
+            };
+            match input.parse::<usize>() {
+                Ok(idx) if (1..=assets.len()).contains(&idx) => assets[idx - 1],
+                _ => return,
+            }
+        }
+    };
+
+    log_println!("📦 {} @ {}: {}", repo, release.tag_name, asset.name);
+    install_plugin_from_url(&asset.browser_download_url, plugin_manager);
+}
+
+// 从 URL 下载插件包，校验 SHA-256 后交给本地安装流程
+#[cfg(feature = "plugins")]
+fn install_plugin_from_url(url: &str, plugin_manager: &mut PluginManager) {
+    // 下载到私有临时目录中的随机文件，离开作用域时删除
+    let download = match fileio::private_temp_file("plugin_", ".tar.gz") {
+        Ok(file) => file,
+        Err(e) => {
+            log_println!("❌ 下载失败: {}", e);
+            return;
+        }
+    };
+
+    log_println!("正在下载插件包: {}", url);
+    let mut printer = network::ProgressPrinter::new();
+    let result = network::download_to_file(url, download.path(), &mut |done, total| printer.update(done, total));
+    printer.finish();
+    match result {
+        Ok(size) => log_println!("✅ 下载完成 ({} 字节)", size),
+        Err(e) => {
+            log_println!("❌ 下载失败: {}", e);
+            return;
+        }
+    }
+
+    if !confirm_download_checksum(download.path()) {
+        return;
+    }
+
+    match plugin_manager.install_plugin(download.path()) {
+        Ok(plugin_id) => log_println!("✅ 插件安装成功！插件 ID: {}", plugin_id),
+        Err(e) => log_println!("❌ 插件安装失败: {}", e),
+    }
+}
+
+// 显示下载文件的 SHA-256 并让用户输入期望值进行比对，返回是否继续
+#[cfg(feature = "plugins")]
+fn confirm_download_checksum(path: &Path) -> bool {
+    let actual = match network::sha256_file(path) {
+        Ok(hash) => hash,
+        Err(e) => {
+            log_println!("❌ 计算校验值失败: {}", e);
+            return false;
+        }
+    };
+
+    log_println!("🔐 SHA-256: {}", actual);
+    log_print!("输入期望的 SHA-256 校验值进行比对 (直接回车跳过): ");
+    let _ = io::stdout().flush();
+
+    let mut expected = String::new();
+    if terminal::read_line(&mut expected).is_err() {
+        return false;
+    }
+    let expected = expected.trim();
+    if expected.is_empty() {
+        log_println!("⚠️  已跳过校验值比对");
+        return true;
+    }
+
+    if network::checksum_matches(&actual, expected) {
+        log_println!("✅ 校验值匹配");
+        true
+    } else {
+        log_println!("❌ 校验值不匹配，已取消安装");
+        log_println!("   期望: {}", expected);
+        log_println!("   实际: {}", actual);
+        false
+    }
+}
+
+// 解析多选输入：支持 `1,3,5`、`2-4` 以及 `all`，返回去重后的 0 基索引
+fn parse_multi_select(input: &str, max: usize) -> Option<Vec<usize>> {
+    let input = input.trim();
+    if input.eq_ignore_ascii_case("all") {
+        return if max > 0 { Some((0..max).collect()) } else { None };
+    }
+
+    let mut selected = Vec::new();
+    for part in input.split([',', '，', ' ']).map(str::trim).filter(|p| !p.is_empty()) {
+        let (start, end) = match part.split_once('-') {
+            Some((a, b)) => (a.trim().parse::<usize>().ok()?, b.trim().parse::<usize>().ok()?),
+            None => {
+                let n = part.parse::<usize>().ok()?;
+                (n, n)
+            }
+        };
+        if start == 0 || start > end || end > max {
+            return None;
+        }
+        for n in start..=end {
+            if !selected.contains(&(n - 1)) {
+                selected.push(n - 1);
+            }
+        }
+    }
+
+    if selected.is_empty() { None } else { Some(selected) }
+}
+
+// 批量卸载插件并逐个输出结果
+#[cfg(feature = "plugins")]
+fn uninstall_plugins_batch(plugin_manager: &mut PluginManager, plugin_ids: &[String]) {
+    if let [plugin_id] = plugin_ids {
+        match plugin_manager.uninstall_plugin(plugin_id) {
+            Ok(_) => log_println!("✅ 插件 '{}' 卸载成功", plugin_id),
+            Err(e) => log_println!("❌ 卸载失败: {}", e),
+        }
+        return;
+    }
+
+    let results = plugin_manager.uninstall_plugins(plugin_ids);
+    let succeeded = results.iter().filter(|(_, r)| r.is_ok()).count();
+    for (plugin_id, result) in &results {
+        match result {
+            Ok(_) => log_println!("✅ 插件 '{}' 卸载成功", plugin_id),
+            Err(e) => log_println!("❌ 插件 '{}' 卸载失败: {}", plugin_id, e),
+        }
+    }
+    log_println!("📋 共卸载 {}/{} 个插件", succeeded, results.len());
+}
+
+// 批量启用/禁用插件
+#[cfg(feature = "plugins")]
+fn set_plugins_enabled_batch(plugin_manager: &mut PluginManager, plugin_ids: &[String], enabled: bool) {
+    let status_text = if enabled { "启用" } else { "禁用" };
+    match plugin_manager.set_plugins_enabled(plugin_ids, enabled) {
+        Ok(updated) => log_println!("✅ 已{} {} 个插件（共选择 {} 个）", status_text, updated, plugin_ids.len()),
+        Err(e) => log_println!("❌ 操作失败: {}", e),
+    }
+}
+
 // 显示插件市场管理菜单
+#[cfg(feature = "marketplace")]
 fn show_marketplace_menu(app_state: &AppState, plugin_manager: &mut PluginManager) {
-    loop {
-        log_println!("\n{}", app_state.get_translation("marketplace_menu.title"));
-        log_println!("1. {}", app_state.get_translation("marketplace_menu.configure"));
-        log_println!("2. {}", app_state.get_translation("marketplace_menu.browse"));
-        log_println!("3. {}", app_state.get_translation("marketplace_menu.search"));
-        log_println!("4. {}", app_state.get_translation("marketplace_menu.test_connection"));
-        log_println!("5. {}", app_state.get_translation("marketplace_menu.scan_local"));
-        log_println!("6. {}", app_state.get_translation("marketplace_menu.back"));
-        log_print!("{}", app_state.get_translation("marketplace_menu.prompt"));
-        let _ = io::stdout().flush();
+    let _crumb = app_state.enter_menu("plugin_menu.marketplace");
+    while let Some(choice) = app_state.choose(
+        "marketplace_menu.title",
+        &[
+            "marketplace_menu.configure",
+            "marketplace_menu.browse",
+            "marketplace_menu.search",
+            "marketplace_menu.test_connection",
+            "marketplace_menu.scan_local",
+            "marketplace_menu.my_plugins",
+            "marketplace_menu.offline_install",
+        ],
+    ) {
+        match choice {
+            0 => configure_marketplace(app_state),
+            1 => browse_marketplace(app_state, plugin_manager),
+            2 => search_marketplace(app_state, plugin_manager),
+            3 => test_marketplace_connection(app_state),
+            4 => show_local_scan_menu(app_state, plugin_manager),
+            5 => show_my_plugins_menu(),
+            _ => install_from_offline_index(plugin_manager),
+        }
 
-        let mut choice = String::new();
-        if io::stdin().read_line(&mut choice).is_err() {
-            log_println!("{}", app_state.get_translation("main.invalid_choice"));
-            continue;
+        log_println!();
+    }
+}
+
+// 从离线索引安装：读取带签名的索引 (U 盘等)，核对插件包的 SHA-256 后安装，全程不联网
+#[cfg(feature = "marketplace")]
+fn install_from_offline_index(plugin_manager: &mut PluginManager) {
+    let mut config = load_user_config();
+    let default = config.marketplace_config.offline_index.clone().unwrap_or_default();
+    let Some(input) = ui::menu::prompt_text(&format!("离线索引文件 (plugin-index.json) [{}]: ", default)) else {
+        return;
+    };
+    let path = if input.is_empty() { default } else { input };
+    if path.is_empty() {
+        return;
+    }
+
+    let loaded = match plugins::offline::load(Path::new(&path), &config.signatures.trusted_keys) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            log_println!("❌ 无法使用离线索引: {}", e);
+            if config.signatures.trusted_keys.is_empty() {
+                log_println!("   请先在配置的 signatures.trusted_keys 中添加插件市场的 minisign 公钥");
+            }
+            return;
+        }
+    };
+    if config.marketplace_config.offline_index.as_deref() != Some(path.as_str()) {
+        config.marketplace_config.offline_index = Some(path);
+        if let Err(e) = save_user_config(&config) {
+            log_println!("⚠️  保存配置失败: {}", e);
         }
+    }
 
-        match choice.trim() {
-            "1" => configure_marketplace(app_state),
-            "2" => browse_marketplace(app_state, plugin_manager),
-            "3" => search_marketplace(app_state, plugin_manager),
-            "4" => test_marketplace_connection(app_state),
-            "5" => show_local_scan_menu(app_state, plugin_manager),
-            "6" => return,
-            _ => log_println!("{}", app_state.get_translation("main.invalid_choice")),
+    log_println!("\n✅ 离线索引签名有效: {}", loaded.signer);
+    log_println!("   生成时间: {} | 共 {} 个插件", loaded.index.generated_at, loaded.index.plugins.len());
+    let installed: std::collections::HashMap<String, String> = plugin_manager
+        .list_installed_plugins()
+        .into_iter()
+        .map(|p| (p.info.id.clone(), p.info.version.clone()))
+        .collect();
+    for (i, entry) in loaded.index.plugins.iter().enumerate() {
+        let plugin = &entry.plugin;
+        let status = match installed.get(&plugin.id) {
+            Some(version) if *version == plugin.version => " [已安装]".to_string(),
+            Some(version) => format!(" [已安装 v{}]", version),
+            None => String::new(),
+        };
+        log_println!("{}. {} v{} - {}{}", i + 1, plugin.name, plugin.version, plugin.publisher(), status);
+        log_println!("   {}", plugin.description);
+    }
+
+    let Some(entry) = ui::menu::prompt("输入要安装的插件编号 (回车返回): ")
+        .and_then(|input| input.parse::<usize>().ok())
+        .and_then(|num| loaded.index.plugins.get(num.wrapping_sub(1)))
+    else {
+        return;
+    };
+    let archive = match loaded.verify_archive(entry) {
+        Ok(archive) => archive,
+        Err(e) => {
+            log_println!("❌ {}", e);
+            return;
         }
-        
-        log_println!();
+    };
+    let result = match installed.get(&entry.plugin.id) {
+        Some(version) if *version == entry.plugin.version => {
+            log_println!("✅ 插件 {} v{} 已安装", entry.plugin.name, version);
+            return;
+        }
+        Some(_) => plugin_manager.upgrade_plugin(&archive).map(|previous| {
+            log_println!("🎉 插件已升级到 v{}，如有问题可回滚到 v{}", entry.plugin.version, previous);
+        }),
+        None => plugin_manager.install_plugin(&archive).map(|plugin_id| {
+            log_println!("🎉 插件安装成功！插件ID: {}", plugin_id);
+        }),
+    };
+    if let Err(e) = result {
+        log_println!("❌ 插件安装失败: {}", e);
     }
 }
 
 // 配置插件市场URL和端口
+#[cfg(feature = "marketplace")]
 fn configure_marketplace(app_state: &AppState) {
     let mut config = load_user_config();
     
@@ -1717,28 +4268,22 @@ fn configure_marketplace(app_state: &AppState) {
     log_println!("{}", app_state.get_formatted_translation("marketplace_config.timeout_label", &[&config.marketplace_config.timeout_seconds.to_string()]));
     
     // 配置URL
-    log_print!("\n{}", app_state.get_translation("marketplace_config.url_prompt"));
-    let _ = io::stdout().flush();
-    let mut url_input = String::new();
-    if io::stdin().read_line(&mut url_input).is_ok() {
-        let url_input = url_input.trim();
-        if !url_input.is_empty() && !url_input.eq_ignore_ascii_case("exit") {
-            config.marketplace_config.api_url = url_input.to_string();
-        }
+    let Some(url_input) = ui::menu::prompt_text(&format!("\n{}", app_state.get_translation("marketplace_config.url_prompt"))) else {
+        return;
+    };
+    if !url_input.is_empty() {
+        config.marketplace_config.api_url = url_input;
     }
     
     // 配置端口
-    log_print!("{}", app_state.get_translation("marketplace_config.port_prompt"));
-    let _ = io::stdout().flush();
-    let mut port_input = String::new();
-    if io::stdin().read_line(&mut port_input).is_ok() {
-        let port_input = port_input.trim();
-        if !port_input.is_empty() && !port_input.eq_ignore_ascii_case("exit") {
-            if let Ok(port) = port_input.parse::<u16>() {
-                config.marketplace_config.api_port = port;
-            } else {
-                log_println!("{}", app_state.get_translation("marketplace_config.invalid_port"));
-            }
+    let Some(port_input) = ui::menu::prompt_text(&app_state.get_translation("marketplace_config.port_prompt")) else {
+        return;
+    };
+    if !port_input.is_empty() {
+        if let Ok(port) = port_input.parse::<u16>() {
+            config.marketplace_config.api_port = port;
+        } else {
+            log_println!("{}", app_state.get_translation("marketplace_config.invalid_port"));
         }
     }
     
@@ -1753,6 +4298,7 @@ fn configure_marketplace(app_state: &AppState) {
 }
 
 // 测试市场连接
+#[cfg(feature = "marketplace")]
 fn test_marketplace_connection(app_state: &AppState) {
     let config = load_user_config();
     log_println!("{}", app_state.get_formatted_translation("marketplace_config.testing_connection", &[&config.marketplace_config.api_url, &config.marketplace_config.api_port.to_string()]));
@@ -1761,20 +4307,202 @@ fn test_marketplace_connection(app_state: &AppState) {
         Ok(client) => {
             match client.test_connection() {
                 Ok(_) => log_println!("{}", app_state.get_translation("marketplace_config.connection_success")),
-                Err(e) => log_println!("{}", app_state.get_formatted_translation("marketplace_config.connection_failed", &[&e.to_string()])),
+                Err(e) => app_state.print_error(&e),
+            }
+        }
+        Err(e) => app_state.print_error(&e),
+    }
+}
+
+// 登录市场账号并保存令牌
+#[cfg(feature = "marketplace")]
+fn login_marketplace(config: &mut Config) -> bool {
+    let client = match plugins::MarketplaceClient::new(config.marketplace_config.clone()) {
+        Ok(client) => client,
+        Err(e) => {
+            log_println!("❌ {}", e);
+            return false;
+        }
+    };
+
+    log_println!("\n=== 登录插件市场 ===");
+    let email = match ui::menu::prompt_text("邮箱: ") {
+        Some(email) if !email.is_empty() => email,
+        _ => return false,
+    };
+
+    match client.send_login_code(&email) {
+        Ok(Some(code)) => log_println!("📨 验证码已生成 (开发模式): {}", code),
+        Ok(None) => log_println!("📨 验证码已发送到 {}", email),
+        Err(e) => {
+            log_println!("❌ 发送验证码失败: {}", e);
+            return false;
+        }
+    }
+    let code = ui::menu::prompt_text("验证码: ").unwrap_or_default();
+
+    match client.verify_login_code(&email, &code) {
+        Ok(token) => {
+            config.marketplace_config.access_token = Some(token);
+            config.marketplace_config.account_email = Some(email.clone());
+            if let Err(e) = save_user_config(config) {
+                log_println!("⚠️  令牌保存失败，本次会话仍可使用: {}", e);
+            }
+            log_println!("✅ 已登录为 {}", email);
+            true
+        }
+        Err(e) => {
+            log_println!("❌ 登录失败: {}", e);
+            false
+        }
+    }
+}
+
+// 我的已发布插件：列表、统计、编辑元数据、撤回版本
+#[cfg(feature = "marketplace")]
+fn show_my_plugins_menu() {
+    let mut config = load_user_config();
+    if config.marketplace_config.access_token.is_none() && !login_marketplace(&mut config) {
+        return;
+    }
+
+    loop {
+        let client = match plugins::MarketplaceClient::new(config.marketplace_config.clone()) {
+            Ok(client) => client,
+            Err(e) => {
+                log_println!("❌ {}", e);
+                return;
+            }
+        };
+
+        log_println!("\n=== 我的已发布插件 ({}) ===", config.marketplace_config.account_email.as_deref().unwrap_or("?"));
+        let my_plugins = match client.get_my_plugins() {
+            Ok(response) => response.plugins,
+            Err(e) => {
+                log_println!("❌ 获取插件列表失败: {}", e);
+                Vec::new()
+            }
+        };
+
+        if my_plugins.is_empty() {
+            log_println!("   暂无已发布的插件");
+        }
+        let rows: Vec<Vec<String>> = my_plugins
+            .iter()
+            .map(|plugin| {
+                vec![
+                    plugin.name.clone(),
+                    format!("v{}", plugin.version),
+                    format!("下载量: {}", plugin.download_count),
+                    format!("评分: {:.1}", plugin.rating),
+                ]
+            })
+            .collect();
+        for line in ui::layout::numbered(&rows, 1) {
+            log_println!("{}", line);
+        }
+
+        log_println!("\n操作选项:");
+        log_println!("  数字 - 管理插件 | l - 重新登录 | o - 退出登录 | 回车 - 返回");
+        let input = match ui::menu::prompt("请输入选择: ") {
+            Some(input) => input,
+            None => return,
+        };
+
+        match input.as_str() {
+            "" => return,
+            "l" => {
+                login_marketplace(&mut config);
+            }
+            "o" => {
+                config.marketplace_config.access_token = None;
+                config.marketplace_config.account_email = None;
+                match save_user_config(&config) {
+                    Ok(_) => log_println!("✅ 已退出登录"),
+                    Err(e) => log_println!("❌ 保存配置失败: {}", e),
+                }
+                return;
+            }
+            num_str => match num_str.parse::<usize>() {
+                Ok(num) if (1..=my_plugins.len()).contains(&num) => {
+                    manage_published_plugin(&client, &my_plugins[num - 1]);
+                }
+                _ => log_println!("❌ 无效选择"),
+            },
+        }
+    }
+}
+
+// 管理单个已发布插件
+#[cfg(feature = "marketplace")]
+fn manage_published_plugin(client: &plugins::MarketplaceClient, plugin: &plugins::MarketplacePlugin) {
+    log_println!("\n=== {} ({}) ===", plugin.name, plugin.id);
+    log_println!("1. 查看统计");
+    log_println!("2. 编辑名称/描述/标签");
+    log_println!("3. 撤回版本");
+    let choice = ui::menu::prompt("请选择 (回车返回): ").unwrap_or_default();
+
+    match choice.as_str() {
+        "1" => match client.get_plugin_stats(&plugin.id) {
+            Ok(stats) => {
+                log_println!("📊 总下载量: {}", stats.total_downloads);
+                log_println!("   近 7 天: {} | 近 30 天: {}", stats.downloads_last_7_days, stats.downloads_last_30_days);
+                log_println!("   评分: {:.1} ({} 人评分)", stats.rating, stats.rating_count);
+                print_download_trend(&stats);
+            }
+            Err(e) => log_println!("❌ 获取统计失败: {}", e),
+        },
+        "2" => {
+            log_println!("留空表示保持不变");
+            let mut update = plugins::PluginMetadataUpdate::default();
+            let name = ui::menu::prompt_text(&format!("名称 [{}]: ", plugin.name)).unwrap_or_default();
+            if !name.is_empty() {
+                update.name = Some(name);
+            }
+            let description = ui::menu::prompt_text(&format!("描述 [{}]: ", plugin.description)).unwrap_or_default();
+            if !description.is_empty() {
+                update.description = Some(description);
+            }
+            let tags = ui::menu::prompt_text(&format!("标签，逗号分隔 [{}]: ", plugin.tags.join(", "))).unwrap_or_default();
+            if !tags.is_empty() {
+                update.tags = Some(tags.split(',').map(|t| t.trim().to_string()).filter(|t| !t.is_empty()).collect());
+            }
+
+            if update.is_empty() {
+                log_println!("未做任何修改");
+                return;
+            }
+            match client.update_plugin_metadata(&plugin.id, &update) {
+                Ok(_) => log_println!("✅ 插件信息已更新"),
+                Err(e) => log_println!("❌ 更新失败: {}", e),
+            }
+        }
+        "3" => {
+            let version = ui::menu::prompt_text(&format!("要撤回的版本 [{}]: ", plugin.version)).unwrap_or_default();
+            let version = if version.is_empty() { plugin.version.clone() } else { version };
+            let reason = ui::menu::prompt_text("撤回原因: ").unwrap_or_default();
+            let confirm = ui::menu::prompt(&format!("确认撤回 {} v{}？新用户将无法下载该版本 (y/N): ", plugin.name, version)).unwrap_or_default();
+            if !confirm.eq_ignore_ascii_case("y") {
+                log_println!("已取消");
+                return;
+            }
+            match client.yank_version(&plugin.id, &version, &reason) {
+                Ok(_) => log_println!("✅ 已撤回 v{}", version),
+                Err(e) => log_println!("❌ 撤回失败: {}", e),
             }
         }
-        Err(e) => log_println!("{}", app_state.get_formatted_translation("marketplace_config.connection_failed", &[&e.to_string()])),
+        _ => {}
     }
 }
 
 // 浏览插件市场
+#[cfg(feature = "marketplace")]
 fn browse_marketplace(app_state: &AppState, plugin_manager: &mut PluginManager) {
     let config = load_user_config();
     let client = match plugins::MarketplaceClient::new(config.marketplace_config.clone()) {
         Ok(client) => client,
         Err(e) => {
-            log_println!("{}", app_state.get_formatted_translation("marketplace_config.connection_failed", &[&e.to_string()]));
+            app_state.print_error(&e);
             return;
         }
     };
@@ -1796,7 +4524,7 @@ fn browse_marketplace(app_state: &AppState, plugin_manager: &mut PluginManager)
 
                     for (i, plugin) in response.plugins.iter().enumerate() {
                         log_println!("{}", app_state.get_formatted_translation("marketplace_browse.plugin_info", &[&(i + 1).to_string(), &plugin.name, &plugin.version]));
-                        log_println!("{}", app_state.get_formatted_translation("marketplace_browse.plugin_details", &[&plugin.author, &plugin.download_count.to_string(), &format!("{:.1}", plugin.rating)]));
+                        log_println!("{}", app_state.get_formatted_translation("marketplace_browse.plugin_details", &[&plugin.publisher(), &plugin.download_count.to_string(), &format!("{:.1}", plugin.rating)]));
                         log_println!("{}", app_state.get_formatted_translation("marketplace_browse.plugin_description", &[&plugin.description]));
                         if !plugin.tags.is_empty() {
                             log_println!("   标签: {}", plugin.tags.join(", "));
@@ -1805,25 +4533,21 @@ fn browse_marketplace(app_state: &AppState, plugin_manager: &mut PluginManager)
                     }
 
                     log_println!("操作选项:");
-                    log_println!("  n - 下一页 | p - 上一页 | s - 排序 | i - 安装插件");
-                    log_println!("  数字 - 查看详情 | exit - 返回");
-                    log_print!("请输入选择: ");
-                    let _ = io::stdout().flush();
-
-                    let mut input = String::new();
-                    if io::stdin().read_line(&mut input).is_ok() {
-                        let input = input.trim();
-                        match input {
-                            "n" if current_page < response.total_pages => current_page += 1,
-                            "p" if current_page > 1 => current_page -= 1,
-                            "s" => current_sort = select_sort_method(),
-                            "i" | "d" => download_plugin_from_market(&client, &response.plugins, plugin_manager),
-                            "exit" => return,
-                            num_str => {
-                                if let Ok(num) = num_str.parse::<usize>() {
-                                    if (1..=response.plugins.len()).contains(&num) {
-                                        show_plugin_details(&response.plugins[num - 1]);
-                                    }
+                    log_println!("  n - 下一页 | p - 上一页 | s - 排序 | f - 推荐 | i - 安装插件");
+                    log_println!("  数字 - 查看详情 | b - 返回");
+                    let Some(input) = ui::menu::prompt("请输入选择: ") else {
+                        return;
+                    };
+                    match input.as_str() {
+                        "n" if current_page < response.total_pages => current_page += 1,
+                        "p" if current_page > 1 => current_page -= 1,
+                        "s" => current_sort = select_sort_method(),
+                        "f" => show_recommended_plugins(&client, plugin_manager),
+                        "i" | "d" => download_plugin_from_market(&client, &response.plugins, plugin_manager),
+                        num_str => {
+                            if let Ok(num) = num_str.parse::<usize>() {
+                                if (1..=response.plugins.len()).contains(&num) {
+                                    show_plugin_details(&client, &response.plugins[num - 1]);
                                 }
                             }
                         }
@@ -1838,7 +4562,60 @@ fn browse_marketplace(app_state: &AppState, plugin_manager: &mut PluginManager)
     }
 }
 
+// 推荐页：编辑推荐 + 近 7 天热门
+#[cfg(feature = "marketplace")]
+fn show_recommended_plugins(client: &plugins::MarketplaceClient, plugin_manager: &mut PluginManager) {
+    let mut recommended: Vec<plugins::MarketplacePlugin> = Vec::new();
+
+    let sections = [
+        ("⭐ 编辑推荐", client.get_featured()),
+        ("🔥 近 7 天热门", client.get_trending(10)),
+    ];
+    for (title, result) in sections {
+        log_println!("\n{}", title);
+        match result {
+            Ok(response) if response.plugins.is_empty() => log_println!("   暂无插件"),
+            Ok(response) => {
+                for plugin in response.plugins {
+                    // 同一插件可能同时出现在两个分区，只保留一次
+                    if recommended.iter().any(|p| p.id == plugin.id) {
+                        continue;
+                    }
+                    log_println!("{}. {} v{} - {}", recommended.len() + 1, plugin.name, plugin.version, plugin.publisher());
+                    log_println!("   {} | 下载量: {} | 评分: {:.1}", plugin.description, plugin.download_count, plugin.rating);
+                    recommended.push(plugin);
+                }
+            }
+            Err(e) => log_println!("   ❌ 获取失败: {}", e),
+        }
+    }
+
+    if recommended.is_empty() {
+        return;
+    }
+
+    log_println!("\n操作选项:");
+    log_println!("  数字 - 查看详情 | i - 安装插件 | 回车 - 返回");
+    log_print!("请输入选择: ");
+    let _ = io::stdout().flush();
+
+    let mut input = String::new();
+    if terminal::read_line(&mut input).is_ok() {
+        match input.trim() {
+            "i" => download_plugin_from_market(client, &recommended, plugin_manager),
+            num_str => {
+                if let Ok(num) = num_str.parse::<usize>() {
+                    if (1..=recommended.len()).contains(&num) {
+                        show_plugin_details(client, &recommended[num - 1]);
+                    }
+                }
+            }
+        }
+    }
+}
+
 // 选择排序方式
+#[cfg(feature = "marketplace")]
 fn select_sort_method() -> plugins::SortBy {
     log_println!("\n选择排序方式:");
     log_println!("1. 按名称排序");
@@ -1850,7 +4627,7 @@ fn select_sort_method() -> plugins::SortBy {
     let _ = io::stdout().flush();
 
     let mut input = String::new();
-    if io::stdin().read_line(&mut input).is_ok() {
+    if terminal::read_line(&mut input).is_ok() {
         if let Ok(choice) = input.trim().parse::<usize>() {
             return plugins::SortBy::from_choice(choice).unwrap_or(plugins::SortBy::Rating);
         }
@@ -1858,15 +4635,22 @@ fn select_sort_method() -> plugins::SortBy {
     plugins::SortBy::Rating
 }
 
-// 显示插件详情
-fn show_plugin_details(plugin: &plugins::MarketplacePlugin) {
+// 显示插件详情，可举报插件
+#[cfg(feature = "marketplace")]
+fn show_plugin_details(client: &plugins::MarketplaceClient, plugin: &plugins::MarketplacePlugin) {
     log_println!("\n=== 插件详情 ===");
     log_println!("名称: {}", plugin.name);
     log_println!("版本: {}", plugin.version);
     log_println!("作者: {}", plugin.author);
+    if let Some(org) = &plugin.organization {
+        log_println!("发布组织: {} ({})", org.name, if org.verified { "✔ 已认证" } else { "未认证" });
+    }
     log_println!("描述: {}", plugin.description);
     log_println!("评分: {:.1}/5.0", plugin.rating);
     log_println!("下载量: {}", plugin.download_count);
+    if let Ok(stats) = client.get_plugin_stats(&plugin.id) {
+        print_download_trend(&stats);
+    }
     log_println!("文件大小: {} 字节", plugin.file_size);
     log_println!("创建时间: {}", plugin.created_at);
     log_println!("更新时间: {}", plugin.updated_at);
@@ -1874,77 +4658,183 @@ fn show_plugin_details(plugin: &plugins::MarketplacePlugin) {
         log_println!("标签: {}", plugin.tags.join(", "));
     }
     log_println!("下载URL: {}", plugin.file_url);
+
+    match ui::menu::prompt("\nr - 举报此插件 | c - 显示下载链接的二维码 | 回车 - 返回: ").as_deref() {
+        Some("r") => report_plugin(client, plugin),
+        Some("c") => show_qr_code(&plugin.file_url),
+        _ => {}
+    }
+}
+
+// 在终端画出链接的二维码，SSH 登录的机器上也能用手机扫码拿到链接；不写入会话日志
+#[cfg(feature = "network")]
+fn show_qr_code(url: &str) {
+    match ui::qr::render(url) {
+        Some(qr) => terminal::write_str(&qr),
+        None if ui::theme::is_plain() => log_println!("纯文本模式下不显示二维码"),
+        None => log_println!("⚠️  链接太长或终端太窄，无法显示二维码"),
+    }
+}
+
+// 近 30 天每日下载量的迷你趋势图，服务端没有每日数据时不显示
+#[cfg(feature = "marketplace")]
+fn print_download_trend(stats: &plugins::marketplace::PluginStats) {
+    let (Some(first), Some(last)) = (stats.daily_downloads.first(), stats.daily_downloads.last()) else {
+        return;
+    };
+    log_println!("近 30 天下载: {} 共 {} ({} ~ {})",
+        ui::theme::sparkline(&stats.daily_counts()), stats.downloads_last_30_days, first.date, last.date);
+}
+
+// 举报插件：选择原因并填写说明，提交到市场的审核队列
+#[cfg(feature = "marketplace")]
+fn report_plugin(client: &plugins::MarketplaceClient, plugin: &plugins::MarketplacePlugin) {
+    if !client.is_logged_in() {
+        log_println!("❌ 请先登录插件市场账号");
+        return;
+    }
+    log_println!("\n举报原因:");
+    for (i, reason) in plugins::ReportReason::ALL.iter().enumerate() {
+        log_println!("{}. {}", i + 1, reason.label());
+    }
+    let Some(reason) = ui::menu::prompt("请选择: ")
+        .and_then(|input| input.parse::<usize>().ok())
+        .and_then(|num| plugins::ReportReason::ALL.get(num.wrapping_sub(1)).copied())
+    else {
+        log_println!("已取消");
+        return;
+    };
+    let details = ui::menu::prompt_text("补充说明 (可选): ").unwrap_or_default();
+    match client.report_plugin(&plugin.id, reason, &details) {
+        Ok(_) => log_println!("✅ 已提交举报，管理员审核后会处理"),
+        Err(e) => log_println!("❌ 举报失败: {}", e),
+    }
 }
 
 // 从市场下载并安装插件
+#[cfg(feature = "marketplace")]
 fn download_plugin_from_market(client: &plugins::MarketplaceClient, plugins_list: &[plugins::MarketplacePlugin], plugin_manager: &mut PluginManager) {
-    log_print!("输入要下载的插件编号: ");
-    let _ = io::stdout().flush();
+    let Some(input) = ui::menu::prompt("输入要下载的插件编号: ") else {
+        return;
+    };
+    if let Ok(num) = input.parse::<usize>() {
+        if (1..=plugins_list.len()).contains(&num) {
+            install_market_plugin(client, &plugins_list[num - 1], plugin_manager);
+        }
+    }
+}
 
-    let mut input = String::new();
-    if io::stdin().read_line(&mut input).is_ok() {
-        if let Ok(num) = input.trim().parse::<usize>() {
-            if (1..=plugins_list.len()).contains(&num) {
-                let plugin = &plugins_list[num - 1];
-                
-                // 显示插件信息和免责声明
-                log_println!("\n📦 准备安装插件：");
-                log_println!("   名称: {}", plugin.name);
-                log_println!("   版本: {}", plugin.version);
-                log_println!("   作者: {}", plugin.author);
-                log_println!("   描述: {}", plugin.description);
-                log_println!("   评分: {:.1}/5.0 | 下载量: {}", plugin.rating, plugin.download_count);
-                
-                // 显示安全免责声明
-                if !show_plugin_marketplace_disclaimer() {
-                    log_println!("❌ 安装已取消");
-                    return;
+// 显示插件信息和免责声明，确认后下载并安装；已安装其他版本时显示更新说明并升级
+#[cfg(feature = "marketplace")]
+fn install_market_plugin(client: &plugins::MarketplaceClient, plugin: &plugins::MarketplacePlugin, plugin_manager: &mut PluginManager) {
+    let installed_version = plugin_manager
+        .list_installed_plugins()
+        .into_iter()
+        .find(|p| p.info.id == plugin.id)
+        .map(|p| p.info.version.clone());
+    if installed_version.as_deref() == Some(plugin.version.as_str()) {
+        log_println!("✅ 插件 {} v{} 已安装", plugin.name, plugin.version);
+        return;
+    }
+
+    match &installed_version {
+        Some(current) => log_println!("\n⬆️  准备升级插件：v{} → v{}", current, plugin.version),
+        None => log_println!("\n📦 准备安装插件："),
+    }
+    log_println!("   名称: {}", plugin.name);
+    log_println!("   版本: {}", plugin.version);
+    log_println!("   作者: {}", plugin.author);
+    if let Some(org) = &plugin.organization {
+        log_println!("   发布组织: {} ({})", org.name, if org.verified { "✔ 已认证" } else { "未认证" });
+    }
+    log_println!("   描述: {}", plugin.description);
+    log_println!("   评分: {:.1}/5.0 | 下载量: {}", plugin.rating, plugin.download_count);
+    if let Some(current) = &installed_version {
+        show_plugin_changelog(client, &plugin.id, current);
+    }
+    
+    // 显示安全免责声明
+    if !show_plugin_marketplace_disclaimer() {
+        log_println!("❌ 安装已取消");
+        return;
+    }
+    
+    let download_path = env::temp_dir().join(format!("{}-{}.tar.gz", plugin.name, plugin.version));
+    
+    log_println!("正在下载 {} v{}...", plugin.name, plugin.version);
+    
+    // 如果没有file_url，尝试构建下载URL
+    let download_url = if plugin.file_url.is_empty() {
+        let config = load_user_config();
+        format!("{}:{}/api/v1/plugins/{}/download", 
+            config.marketplace_config.api_url, 
+            config.marketplace_config.api_port,
+            plugin.id)
+    } else {
+        plugin.file_url.clone()
+    };
+    
+    let mut printer = network::ProgressPrinter::new();
+    let result = client.download_plugin(&download_url, &download_path, &mut |done, total| printer.update(done, total));
+    printer.finish();
+    match result {
+        Ok(_) => {
+            log_println!("✅ 下载完成，正在安装...");
+            
+            // 直接安装下载的插件
+            let result = match &installed_version {
+                Some(_) => plugin_manager.upgrade_plugin(&download_path).map(|previous| {
+                    log_println!("🎉 插件已升级到 v{}！", plugin.version);
+                    log_println!("   如有问题可在插件管理中回滚到 v{}", previous);
+                }),
+                None => plugin_manager.install_plugin(&download_path).map(|plugin_id| {
+                    log_println!("🎉 插件安装成功！");
+                    log_println!("   插件ID: {}", plugin_id);
+                    log_println!("   插件已启用，可在脚本列表中使用");
+                }),
+            };
+            match result {
+                Ok(()) => {
+                    // 清理临时文件
+                    let _ = std::fs::remove_file(&download_path);
                 }
-                
-                let download_path = env::temp_dir().join(format!("{}-{}.tar.gz", plugin.name, plugin.version));
-                
-                log_println!("正在下载 {} v{}...", plugin.name, plugin.version);
-                
-                // 如果没有file_url，尝试构建下载URL
-                let download_url = if plugin.file_url.is_empty() {
-                    let config = load_user_config();
-                    format!("{}:{}/api/v1/plugins/{}/download", 
-                        config.marketplace_config.api_url, 
-                        config.marketplace_config.api_port,
-                        plugin.id)
-                } else {
-                    plugin.file_url.clone()
-                };
-                
-                match client.download_plugin(&download_url, &download_path) {
-                    Ok(_) => {
-                        log_println!("✅ 下载完成，正在安装...");
-                        
-                        // 直接安装下载的插件
-                        match plugin_manager.install_plugin(&download_path) {
-                            Ok(plugin_id) => {
-                                log_println!("🎉 插件安装成功！");
-                                log_println!("   插件ID: {}", plugin_id);
-                                log_println!("   插件已启用，可在脚本列表中使用");
-                                
-                                // 清理临时文件
-                                let _ = std::fs::remove_file(&download_path);
-                            }
-                            Err(e) => {
-                                log_println!("❌ 插件安装失败: {}", e);
-                                log_println!("   下载文件保留在: {:?}", download_path);
-                                log_println!("   您可以稍后手动安装");
-                            }
-                        }
-                    }
-                    Err(e) => log_println!("❌ 下载失败: {}", e),
+                Err(e) => {
+                    log_println!("❌ 插件安装失败: {}", e);
+                    log_println!("   下载文件保留在: {:?}", download_path);
+                    log_println!("   您可以稍后手动安装");
+                }
+            }
+        }
+        Err(e) => {
+            log_println!("❌ 下载失败: {}", e);
+            log_println!("   已下载的部分会保留，再次安装该插件时将继续下载");
+        }
+    }
+}
+
+// 显示已安装版本之后的更新说明；获取失败不影响升级
+#[cfg(feature = "marketplace")]
+fn show_plugin_changelog(client: &plugins::MarketplaceClient, plugin_id: &str, since: &str) {
+    match client.get_changelog(plugin_id, since) {
+        Ok(entries) if !entries.is_empty() => {
+            log_println!("\n📝 更新说明:");
+            for entry in entries {
+                match &entry.released_at {
+                    Some(date) => log_println!("   v{} ({})", entry.version, date),
+                    None => log_println!("   v{}", entry.version),
+                }
+                for line in entry.notes.lines() {
+                    log_println!("     {}", line);
                 }
             }
         }
+        Ok(_) => log_println!("   (暂无更新说明)"),
+        Err(e) => log_println!("   ⚠️ 获取更新说明失败: {}", e),
     }
 }
 
 // 显示插件市场安装免责声明
+#[cfg(feature = "marketplace")]
 fn show_plugin_marketplace_disclaimer() -> bool {
     log_println!("\n⚠️  插件安装免责声明");
     log_println!("════════════════════════════════════════");
@@ -1958,15 +4848,11 @@ fn show_plugin_marketplace_disclaimer() -> bool {
     log_println!("════════════════════════════════════════");
     
     loop {
-        log_print!("您确认理解上述风险并继续安装吗？(y/N): ");
-        let _ = io::stdout().flush();
-        
-        let mut input = String::new();
-        if io::stdin().read_line(&mut input).is_err() {
-            continue;
-        }
+        let Some(input) = ui::menu::prompt("您确认理解上述风险并继续安装吗？(y/N): ") else {
+            return false;
+        };
         
-        match input.trim().to_lowercase().as_str() {
+        match input.to_lowercase().as_str() {
             "y" | "yes" | "是" | "确认" => return true,
             "n" | "no" | "否" | "取消" | "" => return false,
             _ => log_println!("请输入 y(是) 或 n(否)"),
@@ -1975,26 +4861,22 @@ fn show_plugin_marketplace_disclaimer() -> bool {
 }
 
 // 搜索插件市场
+#[cfg(feature = "marketplace")]
 fn search_marketplace(app_state: &AppState, plugin_manager: &mut PluginManager) {
     let config = load_user_config();
     let client = match plugins::MarketplaceClient::new(config.marketplace_config.clone()) {
         Ok(client) => client,
         Err(e) => {
-            log_println!("{}", app_state.get_formatted_translation("marketplace_config.connection_failed", &[&e.to_string()]));
+            app_state.print_error(&e);
             return;
         }
     };
 
-    log_print!("{}", app_state.get_translation("marketplace_search.query_prompt"));
-    let _ = io::stdout().flush();
-
-    let mut query = String::new();
-    if io::stdin().read_line(&mut query).is_err() {
+    let Some(query) = ui::menu::prompt(&app_state.get_translation("marketplace_search.query_prompt")) else {
         return;
-    }
-
-    let query = query.trim();
-    if query.is_empty() || query.eq_ignore_ascii_case("exit") {
+    };
+    let query = query.as_str();
+    if query.is_empty() {
         return;
     }
 
@@ -2009,26 +4891,21 @@ fn search_marketplace(app_state: &AppState, plugin_manager: &mut PluginManager)
 
                 for (i, plugin) in response.plugins.iter().enumerate() {
                     log_println!("{}", app_state.get_formatted_translation("marketplace_browse.plugin_info", &[(i + 1).to_string().as_str(), &plugin.name, &plugin.version]));
-                    log_println!("{}", app_state.get_formatted_translation("marketplace_browse.plugin_details", &[&plugin.author, &plugin.download_count.to_string(), &format!("{:.1}", plugin.rating)]));
+                    log_println!("{}", app_state.get_formatted_translation("marketplace_browse.plugin_details", &[&plugin.publisher(), &plugin.download_count.to_string(), &format!("{:.1}", plugin.rating)]));
                     log_println!("{}", app_state.get_formatted_translation("marketplace_browse.plugin_description", &[&plugin.description]));
                     log_println!();
                 }
 
                 log_println!("操作选项:");
-                log_println!("  数字 - 查看详情 | i - 安装插件 | exit - 返回");
-                log_print!("请输入选择: ");
-                let _ = io::stdout().flush();
-
-                let mut input = String::new();
-                if io::stdin().read_line(&mut input).is_ok() {
-                    let input = input.trim();
-                    match input {
+                log_println!("  数字 - 查看详情 | i - 安装插件 | b - 返回");
+                if let Some(input) = ui::menu::prompt("请输入选择: ") {
+                    match input.as_str() {
                         "i" => download_plugin_from_market(&client, &response.plugins, plugin_manager),
-                        "exit" | "" => return,
+                        "" => return,
                         num_str => {
                             if let Ok(num) = num_str.parse::<usize>() {
                                 if (1..=response.plugins.len()).contains(&num) {
-                                    show_plugin_details(&response.plugins[num - 1]);
+                                    show_plugin_details(&client, &response.plugins[num - 1]);
                                 }
                             }
                         }
@@ -2041,17 +4918,27 @@ fn search_marketplace(app_state: &AppState, plugin_manager: &mut PluginManager)
 }
 
 // 显示本地扫描菜单
+#[cfg(feature = "plugins")]
 fn show_local_scan_menu(app_state: &AppState, plugin_manager: &mut PluginManager) {
-    let scanner = plugins::LocalPluginScanner::new();
+    let config = load_user_config();
+    let scanner = plugins::LocalPluginScanner::from_config(&config.local_scan);
     
     log_println!("\n{}", app_state.get_translation("local_plugin_scan.title"));
     log_println!("{}", app_state.get_translation("local_plugin_scan.scanning"));
     
-    let local_plugins = scanner.scan_plugins();
+    let scan_results = scanner.scan_by_directory();
+    for result in &scan_results {
+        if result.exists {
+            log_println!("{}", app_state.get_formatted_translation("local_plugin_scan.directory_result", &[&result.directory, &result.plugins.len().to_string()]));
+        } else {
+            log_println!("{}", app_state.get_formatted_translation("local_plugin_scan.directory_missing", &[&result.directory]));
+        }
+    }
+    let local_plugins: Vec<_> = scan_results.into_iter().flat_map(|r| r.plugins).collect();
     
     if local_plugins.is_empty() {
         log_println!("{}", app_state.get_translation("local_plugin_scan.no_plugins_found"));
-        log_println!("{}", app_state.get_translation("local_plugin_scan.scan_directories"));
+        log_println!("{}", app_state.get_formatted_translation("local_plugin_scan.scan_directories", &[&scanner.scan_directories().join(", ")]));
         log_println!("{}", app_state.get_translation("local_plugin_scan.file_format_info"));
         return;
     }
@@ -2064,25 +4951,23 @@ fn show_local_scan_menu(app_state: &AppState, plugin_manager: &mut PluginManager
         log_println!("{}", app_state.get_formatted_translation("local_plugin_scan.plugin_path", &[&format!("{:?}", plugin.file_path)]));
         log_println!("{}", app_state.get_formatted_translation("local_plugin_scan.plugin_size", &[&plugin.file_size.to_string()]));
         log_println!("{}", app_state.get_formatted_translation("local_plugin_scan.plugin_modified", &[&plugin.modified_time]));
-        log_println!("{}", app_state.get_formatted_translation("local_plugin_scan.plugin_estimated_name", &[&plugin.estimated_name]));
-        log_println!("{}", app_state.get_formatted_translation("local_plugin_scan.plugin_estimated_version", &[&plugin.estimated_version]));
+        match &plugin.manifest {
+            Ok(info) => {
+                log_println!("{}", app_state.get_formatted_translation("local_plugin_scan.plugin_manifest", &[&info.name, &info.id, &info.version]));
+                log_println!("{}", app_state.get_formatted_translation("local_plugin_scan.plugin_manifest_description", &[&info.description]));
+                log_println!("{}", app_state.get_formatted_translation("local_plugin_scan.plugin_manifest_author", &[&info.author, &info.scripts.len().to_string()]));
+            }
+            Err(e) => {
+                log_println!("{}", app_state.get_formatted_translation("local_plugin_scan.plugin_manifest_invalid", &[e]));
+                log_println!("{}", app_state.get_formatted_translation("local_plugin_scan.plugin_estimated_name", &[&plugin.estimated_name]));
+                log_println!("{}", app_state.get_formatted_translation("local_plugin_scan.plugin_estimated_version", &[&plugin.estimated_version]));
+            }
+        }
         log_println!();
     }
     
-    loop {
-        log_print!("{}", app_state.get_formatted_translation("local_plugin_scan.install_prompt", &[&local_plugins.len().to_string()]));
-        let _ = io::stdout().flush();
-        
-        let mut input = String::new();
-        if io::stdin().read_line(&mut input).is_err() {
-            continue;
-        }
-        
-        let input = input.trim();
-        if input.eq_ignore_ascii_case("exit") {
-            return;
-        }
-        
+    let prompt = app_state.get_formatted_translation("local_plugin_scan.install_prompt", &[&local_plugins.len().to_string()]);
+    while let Some(input) = ui::menu::prompt(&prompt) {
         if let Ok(num) = input.parse::<usize>() {
             if (1..=local_plugins.len()).contains(&num) {
                 let plugin = &local_plugins[num - 1];
@@ -2107,26 +4992,8 @@ fn show_local_scan_menu(app_state: &AppState, plugin_manager: &mut PluginManager
 }
 
 // Legacy compatibility functions for backward compatibility with older code
-fn load_user_config() -> Config {
-    let config_path = PathBuf::from(env::var("HOME").unwrap_or_else(|_| ".".to_string()))
-        .join(".geektools")
-        .join("config.json");
-    
-    match ConfigManager::new(config_path) {
-        Ok(manager) => {
-            let config = manager.get_config();
-            config.read().unwrap().clone()
-        }
-        Err(_) => Config::default(),
-    }
-}
-
 fn save_user_config(config: &Config) -> std::result::Result<(), GeekToolsError> {
-    let config_path = PathBuf::from(env::var("HOME").unwrap_or_else(|_| ".".to_string()))
-        .join(".geektools")
-        .join("config.json");
-    
-    let manager = ConfigManager::new(config_path)?;
+    let manager = ConfigManager::new(config::user_config_path())?;
     manager.update_config(|cfg| {
         *cfg = config.clone();
         Ok(())
@@ -2134,16 +5001,109 @@ fn save_user_config(config: &Config) -> std::result::Result<(), GeekToolsError>
 }
 
 fn save_language_to_config(language: Language) -> std::result::Result<(), GeekToolsError> {
-    let config_path = PathBuf::from(env::var("HOME").unwrap_or_else(|_| ".".to_string()))
-        .join(".geektools")
-        .join("config.json");
-    
-    let manager = ConfigManager::new(config_path)?;
+    let manager = ConfigManager::new(config::user_config_path())?;
     manager.update_config(|cfg| {
-        cfg.language = match language {
-            Language::Chinese => "zh".to_string(),
-            Language::English => "en".to_string(),
-        };
+        cfg.language = language.code().to_string();
         Ok(())
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(all(feature = "plugins", feature = "network"))]
+    use plugins::mock_marketplace::{self, MockMarketplace};
+    #[cfg(all(feature = "plugins", feature = "network"))]
+    use sha2::{Digest, Sha256};
+    #[cfg(all(feature = "plugins", feature = "network"))]
+    use tempfile::TempDir;
+
+    fn app_state() -> AppState {
+        AppState {
+            current_language: Language::English,
+            verbosity: Verbosity::Normal,
+        }
+    }
+
+    #[test]
+    fn test_parse_multi_select() {
+        assert_eq!(parse_multi_select("1,3", 5), Some(vec![0, 2]));
+        assert_eq!(parse_multi_select("2-4", 5), Some(vec![1, 2, 3]));
+        assert_eq!(parse_multi_select(" 1 ，2-3 5", 5), Some(vec![0, 1, 2, 4]));
+        assert_eq!(parse_multi_select("ALL", 3), Some(vec![0, 1, 2]));
+        assert_eq!(parse_multi_select("all", 0), None);
+    }
+
+    #[test]
+    fn test_parse_multi_select_duplicates() {
+        assert_eq!(parse_multi_select("2,2,1-3", 5), Some(vec![1, 0, 2]));
+        assert_eq!(parse_multi_select("1-2,2-1", 5), None);
+    }
+
+    #[test]
+    fn test_parse_multi_select_out_of_range() {
+        assert_eq!(parse_multi_select("0", 5), None);
+        assert_eq!(parse_multi_select("6", 5), None);
+        assert_eq!(parse_multi_select("4-6", 5), None);
+        assert_eq!(parse_multi_select("1,x", 5), None);
+        assert_eq!(parse_multi_select("", 5), None);
+    }
+
+    #[test]
+    fn test_add_custom_script_declined() {
+        let app_state = app_state();
+        let output = terminal::run_scripted(&["maybe", "n"], || add_custom_script(&app_state));
+        assert!(output.contains(&app_state.get_translation("security.warning_title")));
+        assert!(output.contains(&app_state.get_translation("main.invalid_choice")));
+        assert!(output.ends_with(&format!("{}\n", app_state.get_translation("custom_script.cancelled"))));
+    }
+
+    #[test]
+    fn test_add_custom_script_empty_url() {
+        let app_state = app_state();
+        let output = terminal::run_scripted(&["y", ""], || add_custom_script(&app_state));
+        assert!(output.ends_with(&app_state.get_translation("custom_script.enter_url")));
+    }
+
+    #[cfg(all(feature = "plugins", feature = "network"))]
+    fn install_with_checksum(checksum: &str) -> (String, usize) {
+        let server = MockMarketplace::start(&[("hello-tools", "Hello Tools", "1.0.0")]);
+        let dir = TempDir::new().unwrap();
+        let mut manager = PluginManager::open(dir.path().join("plugins"));
+        let url = server.file_url("hello-tools");
+        let output = terminal::run_scripted(&[checksum], || install_plugin_from_url(&url, &mut manager));
+        (output, manager.list_installed_plugins().len())
+    }
+
+    #[cfg(all(feature = "plugins", feature = "network"))]
+    #[test]
+    fn test_install_plugin_from_url_verifies_checksum() {
+        let package = mock_marketplace::package("hello-tools", "Hello Tools", "1.0.0");
+        let checksum: String = Sha256::digest(&package).iter().map(|b| format!("{:02x}", b)).collect();
+
+        let (output, installed) = install_with_checksum(&checksum);
+        assert!(output.contains("✅ 校验值匹配"), "{}", output);
+        assert!(output.contains("插件 ID: hello-tools"), "{}", output);
+        assert_eq!(installed, 1);
+
+        let (output, installed) = install_with_checksum(&"0".repeat(64));
+        assert!(output.contains("❌ 校验值不匹配"), "{}", output);
+        assert_eq!(installed, 0);
+    }
+
+    #[cfg(feature = "marketplace")]
+    #[test]
+    fn test_plugin_details_shows_qr_code() {
+        let server = MockMarketplace::start(&[("hello-tools", "Hello Tools", "1.0.0")]);
+        let client = plugins::MarketplaceClient::new(server.config()).unwrap();
+        let plugin = client.search_plugins("hello").unwrap().plugins.remove(0);
+
+        let output = terminal::run_scripted(&["c"], || show_plugin_details(&client, &plugin));
+        assert!(output.contains("c - 显示下载链接的二维码"), "{}", output);
+        match ui::qr::render(&plugin.file_url) {
+            Some(qr) => assert!(output.ends_with(&qr), "{}", output),
+            None => assert!(output.contains("二维码"), "{}", output),
+        }
+        assert!(!ui::menu::leaving());
+    }
+}
@@ -0,0 +1,234 @@
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Prometheus 指标导出的设置
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MetricsConfig {
+    /// 监视模式等长时间运行时，在本机端口上提供 `/metrics`
+    pub enabled: bool,
+    /// 只监听 127.0.0.1
+    pub port: u16,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self { enabled: false, port: 9464 }
+    }
+}
+
+#[derive(Default, Clone, Debug, PartialEq)]
+struct ScriptMetrics {
+    runs: u64,
+    failures: u64,
+    seconds: f64,
+}
+
+/// 进程内累计的指标；导出端点关闭时也照常记录
+#[derive(Default, Debug)]
+pub struct Metrics {
+    scripts: BTreeMap<String, ScriptMetrics>,
+    /// 最近一次成功检查更新的 Unix 时间戳
+    last_update_check: Option<i64>,
+}
+
+static METRICS: Lazy<Mutex<Metrics>> = Lazy::new(|| Mutex::new(Metrics::default()));
+
+/// 记录一次脚本运行
+pub fn record_run(script: &str, success: bool, seconds: f64) {
+    if let Ok(mut metrics) = METRICS.lock() {
+        metrics.record_run(script, success, seconds);
+    }
+}
+
+/// 记录一次成功的更新检查
+pub fn record_update_check() {
+    if let Ok(mut metrics) = METRICS.lock() {
+        metrics.last_update_check = Some(chrono::Utc::now().timestamp());
+    }
+}
+
+impl Metrics {
+    fn record_run(&mut self, script: &str, success: bool, seconds: f64) {
+        let entry = self.scripts.entry(script.to_string()).or_default();
+        entry.runs += 1;
+        if !success {
+            entry.failures += 1;
+        }
+        entry.seconds += seconds;
+    }
+
+    /// Prometheus 文本格式 (0.0.4)
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let mut family = |name: &str, kind: &str, help: &str, samples: Vec<(String, String)>| {
+            let _ = writeln!(out, "# HELP {} {}\n# TYPE {} {}", name, help, name, kind);
+            for (series, value) in samples {
+                let _ = writeln!(out, "{} {}", series, value);
+            }
+        };
+        let per_script = |name: &str, value: &dyn Fn(&ScriptMetrics) -> String| -> Vec<(String, String)> {
+            self.scripts
+                .iter()
+                .map(|(script, m)| (format!("{}{{script=\"{}\"}}", name, escape_label(script)), value(m)))
+                .collect()
+        };
+
+        family(
+            "geektools_script_runs_total",
+            "counter",
+            "Script runs executed.",
+            per_script("geektools_script_runs_total", &|m| m.runs.to_string()),
+        );
+        family(
+            "geektools_script_failures_total",
+            "counter",
+            "Script runs that failed.",
+            per_script("geektools_script_failures_total", &|m| m.failures.to_string()),
+        );
+        let mut durations = per_script("geektools_script_duration_seconds_sum", &|m| m.seconds.to_string());
+        durations.extend(per_script("geektools_script_duration_seconds_count", &|m| m.runs.to_string()));
+        family("geektools_script_duration_seconds", "summary", "Time spent in script runs.", durations);
+        family(
+            "geektools_last_update_check_timestamp_seconds",
+            "gauge",
+            "Unix time of the last successful update check.",
+            self.last_update_check
+                .map(|ts| vec![("geektools_last_update_check_timestamp_seconds".to_string(), ts.to_string())])
+                .unwrap_or_default(),
+        );
+        out
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// 正在运行的导出端点，drop 时停止
+pub struct Exporter {
+    addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Exporter {
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Drop for Exporter {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// 在 127.0.0.1:`port` 上提供 `GET /metrics`；端口为 0 时由系统分配
+pub fn serve(port: u16) -> io::Result<Exporter> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    let addr = listener.local_addr()?;
+    // 非阻塞 accept，才能定期检查停止标志
+    listener.set_nonblocking(true)?;
+    let stop = Arc::new(AtomicBool::new(false));
+    let stop_flag = stop.clone();
+    let handle = thread::spawn(move || {
+        while !stop_flag.load(Ordering::Relaxed) {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    let _ = respond(stream);
+                }
+                // 没有连接 (WouldBlock) 或连接出错时稍后再试
+                Err(_) => thread::sleep(Duration::from_millis(100)),
+            }
+        }
+    });
+    Ok(Exporter { addr, stop, handle: Some(handle) })
+}
+
+fn respond(stream: TcpStream) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(2)))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // 读完请求头，避免客户端在发送未完成时收到 RST
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let path = request_line.split_whitespace().nth(1).unwrap_or("");
+    let (status, body) = if path == "/metrics" {
+        let body = METRICS.lock().map(|m| m.render()).unwrap_or_default();
+        ("200 OK", body)
+    } else {
+        ("404 Not Found", "not found\n".to_string())
+    };
+    let mut stream = &stream;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_render_prometheus_text() {
+        let mut metrics = Metrics::default();
+        metrics.record_run("backup.sh", true, 1.5);
+        metrics.record_run("backup.sh", false, 2.0);
+        metrics.record_run("say \"hi\".sh", true, 0.25);
+        metrics.last_update_check = Some(1714550400);
+        let out = metrics.render();
+
+        assert!(out.contains(
+            "# HELP geektools_script_runs_total Script runs executed.\n\
+             # TYPE geektools_script_runs_total counter\n\
+             geektools_script_runs_total{script=\"backup.sh\"} 2\n\
+             geektools_script_runs_total{script=\"say \\\"hi\\\".sh\"} 1\n"
+        ));
+        assert!(out.contains("geektools_script_failures_total{script=\"backup.sh\"} 1\n"));
+        assert!(out.contains("geektools_script_duration_seconds_sum{script=\"backup.sh\"} 3.5\n"));
+        assert!(out.contains("geektools_script_duration_seconds_count{script=\"backup.sh\"} 2\n"));
+        assert!(out.ends_with("geektools_last_update_check_timestamp_seconds 1714550400\n"));
+        // 从未检查过更新时只有 HELP/TYPE，没有样本
+        assert!(!Metrics::default().render().lines().any(|l| l.starts_with("geektools_last_update_check")));
+    }
+
+    #[test]
+    fn test_serve_metrics_endpoint() {
+        record_run("metrics-endpoint-test.sh", false, 0.5);
+        let exporter = serve(0).unwrap();
+        let get = |path: &str| {
+            let mut stream = TcpStream::connect(exporter.addr()).unwrap();
+            write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        };
+
+        let response = get("/metrics");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("geektools_script_failures_total{script=\"metrics-endpoint-test.sh\"} 1\n"));
+        assert!(get("/").starts_with("HTTP/1.1 404"));
+    }
+}
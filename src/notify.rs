@@ -0,0 +1,151 @@
+use crate::executor::ResourceUsage;
+use crate::{log_only, network};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::process::{Command, ExitStatus};
+use std::time::Duration;
+
+/// 长时间运行的脚本结束后的通知设置
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NotificationConfig {
+    pub enabled: bool,
+    /// 运行时间超过该值才通知
+    pub min_duration_seconds: u64,
+    /// 发送桌面通知 (macOS: osascript, Linux: notify-send)
+    pub desktop: bool,
+    /// 以 JSON POST 运行结果的 webhook 地址
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_duration_seconds: 60,
+            desktop: true,
+            webhook_url: None,
+        }
+    }
+}
+
+/// 一次脚本运行的结果摘要
+#[derive(Serialize, Debug)]
+pub struct RunSummary {
+    pub script: String,
+    pub success: bool,
+    pub exit_code: Option<i32>,
+    pub duration_seconds: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<ResourceUsage>,
+}
+
+impl RunSummary {
+    pub fn new(script: &str, status: &io::Result<ExitStatus>, duration: Duration) -> Self {
+        let (success, exit_code) = match status {
+            Ok(status) => (status.success(), status.code()),
+            Err(_) => (false, None),
+        };
+        Self {
+            script: script.to_string(),
+            success,
+            exit_code,
+            duration_seconds: duration.as_secs_f64(),
+            usage: None,
+        }
+    }
+
+    pub fn with_usage(mut self, usage: Option<ResourceUsage>) -> Self {
+        self.usage = usage;
+        self
+    }
+
+    fn title(&self) -> String {
+        let mark = if self.success { "✅" } else { "❌" };
+        format!("{} geektools: {}", mark, self.script)
+    }
+
+    fn message(&self) -> String {
+        let minutes = (self.duration_seconds / 60.0).floor();
+        let seconds = self.duration_seconds - minutes * 60.0;
+        let message = match self.exit_code {
+            Some(code) => format!("exit {} after {}m {:.0}s", code, minutes, seconds),
+            None => format!("terminated after {}m {:.0}s", minutes, seconds),
+        };
+        match &self.usage {
+            Some(usage) => format!("{} ({})", message, usage),
+            None => message,
+        }
+    }
+}
+
+/// 脚本结束时按配置发送桌面通知和/或 webhook，失败只记日志
+pub fn notify_run_finished(config: &NotificationConfig, summary: &RunSummary) {
+    if !config.enabled || summary.duration_seconds < config.min_duration_seconds as f64 {
+        return;
+    }
+
+    if config.desktop {
+        match send_desktop(&summary.title(), &summary.message()) {
+            Ok(status) if status.success() => {}
+            Ok(status) => log_only!("WARN", "NOTIFY", "桌面通知失败: {}", status),
+            Err(e) => log_only!("WARN", "NOTIFY", "桌面通知失败: {}", e),
+        }
+    }
+
+    if let Some(url) = &config.webhook_url {
+        let body = serde_json::to_value(summary).unwrap_or_default();
+        if let Err(e) = network::post_json(url, &body) {
+            log_only!("WARN", "NOTIFY", "webhook 通知失败 URL={}: {}", url, e);
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn send_desktop(title: &str, body: &str) -> io::Result<ExitStatus> {
+    let script = format!(
+        "display notification {} with title {}",
+        applescript_quote(body),
+        applescript_quote(title)
+    );
+    Command::new("osascript").arg("-e").arg(script).status()
+}
+
+#[cfg(not(target_os = "macos"))]
+fn send_desktop(title: &str, body: &str) -> io::Result<ExitStatus> {
+    Command::new("notify-send").arg(title).arg(body).status()
+}
+
+/// 转成 AppleScript 字符串字面量
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn applescript_quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_message() {
+        let summary = RunSummary {
+            script: "install.sh".to_string(),
+            success: false,
+            exit_code: Some(2),
+            duration_seconds: 125.0,
+            usage: None,
+        };
+        assert_eq!(summary.title(), "❌ geektools: install.sh");
+        assert_eq!(summary.message(), "exit 2 after 2m 5s");
+        let usage = ResourceUsage { cpu_seconds: 80.5, peak_memory_kb: 36_045 };
+        assert_eq!(
+            summary.with_usage(Some(usage)).message(),
+            "exit 2 after 2m 5s (CPU 80.50s, peak memory 35.2 MB)"
+        );
+    }
+
+    #[test]
+    fn test_applescript_quote() {
+        assert_eq!(applescript_quote(r#"a "b" \c"#), r#""a \"b\" \\c""#);
+    }
+}
@@ -0,0 +1,147 @@
+use crate::log_only;
+use crate::runtime::find_program;
+use once_cell::sync::Lazy;
+use std::io;
+use std::process::{Command, ExitStatus, Stdio};
+
+/// 支持的系统包管理器
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackageManager {
+    Brew,
+    Apt,
+    Dnf,
+    Pacman,
+    Apk,
+}
+
+impl PackageManager {
+    /// 检测顺序：macOS 上的 brew 优先，其余按常见发行版排列
+    const ALL: [PackageManager; 5] = [Self::Brew, Self::Apt, Self::Dnf, Self::Pacman, Self::Apk];
+
+    /// 对脚本公开的名称 (`GT_PKG_MANAGER`)
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Brew => "brew",
+            Self::Apt => "apt",
+            Self::Dnf => "dnf",
+            Self::Pacman => "pacman",
+            Self::Apk => "apk",
+        }
+    }
+
+    /// 用来判断是否存在的程序
+    fn program(&self) -> &'static str {
+        match self {
+            Self::Apt => "apt-get",
+            other => other.name(),
+        }
+    }
+
+    /// 非交互安装命令，包名追加在后面
+    fn install_args(&self) -> &'static [&'static str] {
+        match self {
+            Self::Brew => &["brew", "install"],
+            Self::Apt => &["apt-get", "install", "-y"],
+            Self::Dnf => &["dnf", "install", "-y"],
+            Self::Pacman => &["pacman", "-S", "--noconfirm", "--needed"],
+            Self::Apk => &["apk", "add"],
+        }
+    }
+
+    /// 查询包是否已安装的命令，已安装时退出码为 0
+    fn query_args(&self) -> &'static [&'static str] {
+        match self {
+            Self::Brew => &["brew", "list", "--versions"],
+            Self::Apt => &["dpkg", "-s"],
+            Self::Dnf => &["rpm", "-q"],
+            Self::Pacman => &["pacman", "-Q"],
+            Self::Apk => &["apk", "info", "-e"],
+        }
+    }
+
+    /// 完整的安装命令前缀：brew 以外需要 root，当前不是 root 且有 sudo 时加上 sudo
+    pub fn install_command(&self) -> Vec<String> {
+        let sudo = *self != Self::Brew && !is_root() && find_program("sudo").is_some();
+        sudo.then_some("sudo")
+            .into_iter()
+            .chain(self.install_args().iter().copied())
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// 包是否已安装
+    pub fn is_installed(&self, package: &str) -> bool {
+        let (program, args) = self.query_args().split_first().expect("query command is not empty");
+        Command::new(program)
+            .args(args)
+            .arg(package)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|s| s.success())
+    }
+
+    /// 安装包，输出直接显示在终端上
+    pub fn install(&self, packages: &[String]) -> io::Result<ExitStatus> {
+        let command = self.install_command();
+        log_only!("INFO", "PKG", "安装软件包: {} {}", command.join(" "), packages.join(" "));
+        Command::new(&command[0]).args(&command[1..]).args(packages).status()
+    }
+}
+
+static DETECTED: Lazy<Option<PackageManager>> =
+    Lazy::new(|| PackageManager::ALL.into_iter().find(|pm| find_program(pm.program()).is_some()));
+
+/// 本机的包管理器 (每个进程检测一次)
+pub fn detect() -> Option<PackageManager> {
+    *DETECTED
+}
+
+/// 传给脚本的环境变量：`GT_PKG_MANAGER` 和 `GT_PKG_INSTALL_CMD` (如 `sudo apt-get install -y`)，
+/// 没有检测到包管理器时为空
+pub fn script_env() -> Vec<(&'static str, String)> {
+    detect()
+        .map(|pm| vec![("GT_PKG_MANAGER", pm.name().to_string()), ("GT_PKG_INSTALL_CMD", pm.install_command().join(" "))])
+        .unwrap_or_default()
+}
+
+#[cfg(unix)]
+fn is_root() -> bool {
+    Command::new("id")
+        .arg("-u")
+        .output()
+        .is_ok_and(|o| String::from_utf8_lossy(&o.stdout).trim() == "0")
+}
+
+#[cfg(not(unix))]
+fn is_root() -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_install_command() {
+        assert_eq!(PackageManager::Brew.install_command(), ["brew", "install"]);
+        let apt = PackageManager::Apt.install_command();
+        assert!(apt.ends_with(&["apt-get".to_string(), "install".to_string(), "-y".to_string()]));
+        assert!(apt.len() == 3 || apt[0] == "sudo");
+        assert_eq!(PackageManager::Apt.name(), "apt");
+        assert_eq!(PackageManager::Apt.program(), "apt-get");
+    }
+
+    #[test]
+    fn test_script_env_matches_detection() {
+        let env = script_env();
+        match detect() {
+            Some(pm) => {
+                assert_eq!(env[0], ("GT_PKG_MANAGER", pm.name().to_string()));
+                assert_eq!(env[1].1, pm.install_command().join(" "));
+                assert!(!pm.is_installed("geektools-no-such-package"));
+            }
+            None => assert!(env.is_empty()),
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use super::PluginInfo;
+use crate::errors::Result;
+use crate::fileio;
+use once_cell::sync::Lazy;
+use serde_json::{Map, Value};
+use std::fmt;
+use std::path::Path;
+
+/// info.json 的 JSON Schema，随程序发布，`plugin lint` 按其中的字段检查清单
+pub const INFO_SCHEMA: &str = include_str!("info.schema.json");
+
+/// Schema 中声明的 (清单字段, 脚本条目字段)
+static KNOWN_FIELDS: Lazy<(Vec<String>, Vec<String>)> = Lazy::new(|| {
+    let schema: Value = serde_json::from_str(INFO_SCHEMA).expect("info.schema.json is valid JSON");
+    let keys = |value: &Value| -> Vec<String> {
+        value["properties"].as_object().map(|p| p.keys().cloned().collect()).unwrap_or_default()
+    };
+    (keys(&schema), keys(&schema["$defs"]["script"]))
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// 安装会失败或插件无法运行
+    Error,
+    /// 可以安装，但很可能不是作者的本意
+    Warning,
+}
+
+/// 清单检查发现的一个问题
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManifestIssue {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl fmt::Display for ManifestIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{}: {}", label, self.message)
+    }
+}
+
+/// 是否为语义化版本 MAJOR.MINOR.PATCH，可带 `-预发布` 和 `+构建` 后缀
+pub fn is_semver(version: &str) -> bool {
+    let (version, build) = match version.split_once('+') {
+        Some((v, b)) => (v, Some(b)),
+        None => (version, None),
+    };
+    let (core, pre) = match version.split_once('-') {
+        Some((c, p)) => (c, Some(p)),
+        None => (version, None),
+    };
+    let suffix_ok = |s: Option<&str>| {
+        s.is_none_or(|s| s.split('.').all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')))
+    };
+    let parts: Vec<&str> = core.split('.').collect();
+    parts.len() == 3
+        && parts.iter().all(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit()) && (p.len() == 1 || !p.starts_with('0')))
+        && suffix_ok(pre)
+        && suffix_ok(build)
+}
+
+/// 检查插件目录 (或其中的 info.json)：未知字段、字段类型、版本格式、脚本文件是否存在及可执行权限；
+/// 无法读取 info.json 时返回错误
+pub fn validate_manifest(path: &Path) -> Result<Vec<ManifestIssue>> {
+    let (plugin_dir, info_path) = if path.is_dir() {
+        (path.to_path_buf(), path.join("info.json"))
+    } else {
+        (path.parent().unwrap_or(Path::new(".")).to_path_buf(), path.to_path_buf())
+    };
+    let content = fileio::read(&info_path)?;
+    let mut issues = Vec::new();
+
+    let value: Value = match serde_json::from_str(&content) {
+        Ok(value) => value,
+        Err(e) => {
+            issues.push(error(format!("info.json is not valid JSON: {}", e)));
+            return Ok(issues);
+        }
+    };
+    let Some(object) = value.as_object() else {
+        issues.push(error("info.json must be a JSON object".to_string()));
+        return Ok(issues);
+    };
+    let mut issues_unknown = unknown_fields(object, &KNOWN_FIELDS.0, "");
+    if let Some(scripts) = object.get("scripts").and_then(Value::as_array) {
+        for (i, script) in scripts.iter().enumerate() {
+            if let Some(script) = script.as_object() {
+                issues_unknown.extend(unknown_fields(script, &KNOWN_FIELDS.1, &format!("scripts[{}].", i)));
+            }
+        }
+    }
+    let info: PluginInfo = match serde_json::from_value(value.clone()) {
+        Ok(info) => info,
+        Err(e) => {
+            issues.push(error(format!("info.json does not match the schema: {}", e)));
+            issues.extend(issues_unknown);
+            return Ok(issues);
+        }
+    };
+
+    if info.id.is_empty() || !info.id.starts_with(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit())
+        || !info.id.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+    {
+        issues.push(error(format!("id '{}' must use lowercase letters, digits, '-' and '_'", info.id)));
+    }
+    if info.name.trim().is_empty() {
+        issues.push(error("name must not be empty".to_string()));
+    }
+    if !is_semver(&info.version) {
+        issues.push(error(format!("version '{}' is not a semantic version (MAJOR.MINOR.PATCH)", info.version)));
+    }
+    if let Some(min) = &info.min_geektools_version
+        && !is_semver(min)
+    {
+        issues.push(error(format!("min_geektools_version '{}' is not a semantic version", min)));
+    }
+
+    let scripts_dir = plugin_dir.join("scripts");
+    if !scripts_dir.is_dir() {
+        issues.push(error("scripts/ directory is missing".to_string()));
+    }
+    for script in &info.scripts {
+        let script_path = scripts_dir.join(&script.file);
+        if Path::new(&script.file).components().any(|c| !matches!(c, std::path::Component::Normal(_))) {
+            issues.push(error(format!("script '{}': file '{}' must be a relative path inside scripts/", script.name, script.file)));
+        } else if !script_path.is_file() {
+            issues.push(error(format!("script '{}': scripts/{} not found", script.name, script.file)));
+        } else if script.executable && !is_executable(&script_path) {
+            issues.push(ManifestIssue {
+                severity: Severity::Warning,
+                message: format!("script '{}': scripts/{} is declared executable but has no execute permission", script.name, script.file),
+            });
+        }
+    }
+    issues.extend(issues_unknown);
+    Ok(issues)
+}
+
+fn error(message: String) -> ManifestIssue {
+    ManifestIssue { severity: Severity::Error, message }
+}
+
+fn unknown_fields(object: &Map<String, Value>, known: &[String], prefix: &str) -> Vec<ManifestIssue> {
+    object
+        .keys()
+        .filter(|key| !known.contains(key))
+        .map(|key| ManifestIssue {
+            severity: Severity::Warning,
+            message: format!("unknown field '{}{}' is ignored", prefix, key),
+        })
+        .collect()
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|m| m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(_path: &Path) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_is_semver() {
+        for ok in ["1.0.0", "0.10.3", "1.2.3-beta.1", "1.2.3+build.5", "1.2.3-rc-1+sha.abc"] {
+            assert!(is_semver(ok), "{}", ok);
+        }
+        for bad in ["1.0", "1.0.0.0", "01.0.0", "v1.0.0", "1.0.0-", "1.0.0-beta..1", ""] {
+            assert!(!is_semver(bad), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_validate_manifest_reports_problems() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        fileio::write(dir.join("info.json"), r#"{
+            "id": "Demo", "name": "Demo", "version": "1.0", "description": "", "author": "",
+            "homepage": "https://example.com",
+            "scripts": [
+                {"name": "a", "file": "a.sh", "description": "", "executable": true, "args": []},
+                {"name": "b", "file": "missing.sh", "description": ""},
+                {"name": "c", "file": "../c.sh", "description": ""}
+            ]
+        }"#).unwrap();
+        fileio::write(dir.join("scripts/a.sh"), "echo a\n").unwrap();
+
+        let messages: Vec<String> = validate_manifest(dir).unwrap().iter().map(|i| i.to_string()).collect();
+        assert_eq!(messages, [
+            "error: id 'Demo' must use lowercase letters, digits, '-' and '_'",
+            "error: version '1.0' is not a semantic version (MAJOR.MINOR.PATCH)",
+            "warning: script 'a': scripts/a.sh is declared executable but has no execute permission",
+            "error: script 'b': scripts/missing.sh not found",
+            "error: script 'c': file '../c.sh' must be a relative path inside scripts/",
+            "warning: unknown field 'homepage' is ignored",
+            "warning: unknown field 'scripts[0].args' is ignored",
+        ]);
+
+        // 脚手架生成的插件没有问题
+        let scaffold = super::super::scaffold::create(dir, "Clean Plugin", true).unwrap();
+        assert_eq!(validate_manifest(&scaffold.join("info.json")).unwrap(), []);
+    }
+
+    #[test]
+    fn test_validate_manifest_type_errors() {
+        let temp_dir = TempDir::new().unwrap();
+        fileio::write(temp_dir.path().join("info.json"), r#"{"id": "x", "scripts": "none"}"#).unwrap();
+        let issues = validate_manifest(temp_dir.path()).unwrap();
+        assert_eq!(issues[0].severity, Severity::Error);
+        assert!(issues[0].message.starts_with("info.json does not match the schema"));
+        assert!(validate_manifest(&temp_dir.path().join("nope")).is_err());
+    }
+}
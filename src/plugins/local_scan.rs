@@ -0,0 +1,276 @@
+use super::{read_package_manifest, LocalScanConfig, PluginInfo};
+use std::path::Path;
+
+/// 单个目录的扫描结果
+#[derive(Debug, Clone)]
+pub struct DirectoryScanResult {
+    pub directory: String,
+    pub exists: bool,
+    pub plugins: Vec<LocalPluginInfo>,
+}
+
+/// 本地插件扫描器
+pub struct LocalPluginScanner {
+    scan_directories: Vec<String>,
+    max_depth: usize,
+    ignore_patterns: Vec<String>,
+}
+
+impl LocalPluginScanner {
+    /// 创建新的本地扫描器
+    pub fn new() -> Self {
+        Self::from_config(&LocalScanConfig::default())
+    }
+
+    /// 根据配置创建扫描器
+    pub fn from_config(config: &LocalScanConfig) -> Self {
+        let home_dir = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+        let scan_directories = if config.directories.is_empty() {
+            vec![
+                format!("{}/Downloads", home_dir),
+                format!("{}/Desktop", home_dir),
+                format!("{}/Documents", home_dir),
+                ".".to_string(), // 当前目录
+            ]
+        } else {
+            config.directories.iter()
+                .map(|dir| match dir.strip_prefix("~/") {
+                    Some(rest) => format!("{}/{}", home_dir, rest),
+                    None => dir.clone(),
+                })
+                .collect()
+        };
+
+        Self {
+            scan_directories,
+            max_depth: config.max_depth,
+            ignore_patterns: config.ignore_patterns.clone(),
+        }
+    }
+
+    /// 添加扫描目录
+    pub fn add_scan_directory(&mut self, directory: String) {
+        if !self.scan_directories.contains(&directory) {
+            self.scan_directories.push(directory);
+        }
+    }
+
+    /// 扫描目录列表
+    pub fn scan_directories(&self) -> &[String] {
+        &self.scan_directories
+    }
+
+    /// 按目录扫描本地插件文件，返回每个目录各自的结果
+    pub fn scan_by_directory(&self) -> Vec<DirectoryScanResult> {
+        self.scan_directories
+            .iter()
+            .map(|dir| {
+                let path = Path::new(dir);
+                let mut plugins = Vec::new();
+                if path.is_dir() {
+                    self.scan_directory(path, 0, &mut plugins);
+                }
+                DirectoryScanResult {
+                    directory: dir.clone(),
+                    exists: path.is_dir(),
+                    plugins,
+                }
+            })
+            .collect()
+    }
+
+    /// 递归扫描单个目录
+    fn scan_directory(&self, dir: &Path, depth: usize, plugins: &mut Vec<LocalPluginInfo>) {
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            if self.is_ignored(&file_name) {
+                continue;
+            }
+
+            // 使用 file_type 而不是 is_dir，避免跟随符号链接导致循环
+            let file_type = match entry.file_type() {
+                Ok(file_type) => file_type,
+                Err(_) => continue,
+            };
+
+            if file_type.is_dir() {
+                if depth < self.max_depth {
+                    self.scan_directory(&path, depth + 1, plugins);
+                }
+            } else if Self::is_plugin_archive(&path)
+                && let Some(plugin_info) = self.analyze_plugin_file(&path)
+            {
+                plugins.push(plugin_info);
+            }
+        }
+    }
+
+    /// 是否为插件包文件
+    fn is_plugin_archive(path: &Path) -> bool {
+        match path.extension() {
+            Some(extension) => extension == "tar" || extension == "gz",
+            None => false,
+        }
+    }
+
+    /// 文件名是否匹配任一忽略模式
+    fn is_ignored(&self, name: &str) -> bool {
+        self.ignore_patterns.iter().any(|pattern| wildcard_match(pattern, name))
+    }
+
+    /// 分析本地插件文件信息
+    fn analyze_plugin_file(&self, path: &Path) -> Option<LocalPluginInfo> {
+        if let Ok(metadata) = std::fs::metadata(path) {
+            let file_name = path.file_name()?.to_string_lossy().to_string();
+            let file_size = metadata.len();
+            let modified_time = metadata.modified().ok()?;
+            
+            // 从文件名推断插件信息
+            let (name, version) = self.parse_filename(&file_name);
+            
+            Some(LocalPluginInfo {
+                file_path: path.to_path_buf(),
+                file_name,
+                file_size,
+                modified_time: format!("{:?}", modified_time),
+                estimated_name: name,
+                estimated_version: version,
+                manifest: read_package_manifest(path).map_err(|e| e.to_string()),
+            })
+        } else {
+            None
+        }
+    }
+
+    /// 从文件名解析插件名称和版本
+    fn parse_filename(&self, filename: &str) -> (String, String) {
+        let base_name = filename
+            .strip_suffix(".tar.gz")
+            .or_else(|| filename.strip_suffix(".tar"))
+            .unwrap_or(filename);
+
+        // 尝试解析版本号 (例如: plugin-name-v1.2.3 或 plugin-name-1.2.3)
+        if let Some(last_dash) = base_name.rfind('-') {
+            let potential_version = &base_name[last_dash + 1..];
+            if potential_version.starts_with('v') || 
+               potential_version.chars().next().is_some_and(|c| c.is_ascii_digit()) {
+                let name = base_name[..last_dash].to_string();
+                let version = potential_version.to_string();
+                return (name, version);
+            }
+        }
+
+        (base_name.to_string(), "unknown".to_string())
+    }
+}
+
+impl Default for LocalPluginScanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 简单通配符匹配：`*` 匹配任意长度字符，`?` 匹配单个字符
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// 本地插件信息
+#[derive(Debug, Clone)]
+pub struct LocalPluginInfo {
+    pub file_path: std::path::PathBuf,
+    pub file_name: String,
+    pub file_size: u64,
+    pub modified_time: String,
+    pub estimated_name: String,
+    pub estimated_version: String,
+    /// 从包内 info.json 读取的真实元数据
+    pub manifest: std::result::Result<PluginInfo, String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match(".*", ".git"));
+        assert!(wildcard_match("node_modules", "node_modules"));
+        assert!(wildcard_match("*.bak", "plugin.tar.gz.bak"));
+        assert!(wildcard_match("v?.tar.gz", "v1.tar.gz"));
+        assert!(!wildcard_match(".*", "plugin.tar.gz"));
+        assert!(!wildcard_match("*.bak", "plugin.tar.gz"));
+    }
+
+    #[test]
+    fn test_scan_respects_depth_and_ignore_patterns() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join("nested/deeper")).unwrap();
+        std::fs::create_dir_all(root.join(".hidden")).unwrap();
+        std::fs::write(root.join("top-1.0.0.tar.gz"), b"").unwrap();
+        std::fs::write(root.join("nested/mid-1.0.0.tar.gz"), b"").unwrap();
+        std::fs::write(root.join("nested/deeper/deep-1.0.0.tar.gz"), b"").unwrap();
+        std::fs::write(root.join(".hidden/secret-1.0.0.tar.gz"), b"").unwrap();
+
+        let scan = |max_depth| {
+            let config = LocalScanConfig {
+                directories: vec![root.display().to_string()],
+                max_depth,
+                ..Default::default()
+            };
+            let mut names: Vec<String> = LocalPluginScanner::from_config(&config)
+                .scan_by_directory()
+                .into_iter()
+                .flat_map(|result| result.plugins)
+                .map(|p| p.estimated_name)
+                .collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(scan(0), vec!["top"]);
+        assert_eq!(scan(1), vec!["mid", "top"]);
+        assert_eq!(scan(5), vec!["deep", "mid", "top"]);
+    }
+
+    #[test]
+    fn test_scan_by_directory_reports_missing_directories() {
+        let config = LocalScanConfig {
+            directories: vec!["/nonexistent/geektools-scan".to_string()],
+            ..Default::default()
+        };
+        let results = LocalPluginScanner::from_config(&config).scan_by_directory();
+        assert_eq!(results.len(), 1);
+        assert!(!results[0].exists);
+        assert!(results[0].plugins.is_empty());
+    }
+}
@@ -0,0 +1,1142 @@
+use crate::errors::{GeekToolsError, Result};
+use crate::fileio;
+use crate::log_only;
+use super::{lint, ArchiveConfig, PluginInfo, ScriptEntry};
+use flate2::read::GzDecoder;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    env,
+    fs::File,
+    io::Read,
+    path::{Component, Path, PathBuf},
+    sync::{Mutex, RwLock},
+    thread,
+    time::SystemTime,
+};
+use tar::Archive;
+
+/// 插件目录：~/.geektools/plugins/
+static PLUGINS_DIR: Lazy<PathBuf> = Lazy::new(|| {
+    let home = env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    let dir = PathBuf::from(home).join(".geektools").join("plugins");
+    // Create directory if it doesn't exist
+    let _ = fileio::create_dir(&dir);
+    dir
+});
+
+/// 已解析的注册表，按文件修改时间判断是否需要重新读取
+struct RegistryCache {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    plugins: HashMap<String, InstalledPlugin>,
+}
+
+/// 进程内只解析一次注册表，多次创建 PluginManager 时复用
+static REGISTRY_CACHE: Lazy<Mutex<Option<RegistryCache>>> = Lazy::new(|| Mutex::new(None));
+
+static ARCHIVE_CONFIG: Lazy<RwLock<ArchiveConfig>> = Lazy::new(|| RwLock::new(ArchiveConfig::default()));
+
+/// 应用插件包缓存的上限，之后创建的 PluginManager 生效
+pub fn configure_archives(config: &ArchiveConfig) {
+    if let Ok(mut current) = ARCHIVE_CONFIG.write() {
+        *current = config.clone();
+    }
+}
+
+/// 缓存中的一个插件包
+#[derive(Debug, Clone)]
+pub struct CachedArchive {
+    pub info: PluginInfo,
+    pub path: PathBuf,
+}
+
+/// 已安装插件的记录
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InstalledPlugin {
+    pub info: PluginInfo,
+    pub install_path: PathBuf,
+    pub installed_at: String,
+    #[serde(default)]
+    pub enabled: bool,
+    /// 升级前的版本，`rollback_plugin` 用其缓存的插件包恢复
+    #[serde(default)]
+    pub previous_version: Option<String>,
+    /// 开发模式：install_path 是指向插件作者工作目录的符号链接，不复制、不缓存插件包
+    #[serde(default)]
+    pub dev: bool,
+}
+
+/// info.json 允许的最大大小，防止读取异常巨大的清单
+const MAX_MANIFEST_SIZE: u64 = 1024 * 1024;
+
+/// 不解压整个插件包，直接从 .tar.gz 中读取并解析 info.json
+pub fn read_package_manifest(plugin_path: &Path) -> Result<PluginInfo> {
+    let file = File::open(plugin_path).map_err(|e| GeekToolsError::FileOperationError {
+        path: plugin_path.display().to_string(),
+        source: e,
+    })?;
+    let mut archive = Archive::new(GzDecoder::new(file));
+
+    let entries = archive.entries()
+        .map_err(|e| package_error(plugin_path, format!("Failed to read plugin package: {}", e)))?;
+
+    for entry in entries {
+        let mut entry = entry
+            .map_err(|e| package_error(plugin_path, format!("Failed to read archive entry: {}", e)))?;
+        let is_manifest = {
+            let path = entry.path()
+                .map_err(|e| package_error(plugin_path, format!("Invalid archive entry path: {}", e)))?;
+            let components: Vec<Component> = path.components()
+                .filter(|c| !matches!(c, Component::CurDir))
+                .collect();
+            matches!(components.as_slice(), [Component::Normal(name)] if *name == "info.json")
+        };
+        if !is_manifest {
+            continue;
+        }
+
+        if entry.size() > MAX_MANIFEST_SIZE {
+            return Err(package_error(plugin_path, "info.json is too large"));
+        }
+        let mut content = String::new();
+        entry.read_to_string(&mut content)
+            .map_err(|e| package_error(plugin_path, format!("Failed to read info.json: {}", e)))?;
+        return serde_json::from_str(&content)
+            .map_err(|e| package_error(plugin_path, format!("Failed to parse info.json: {}", e)));
+    }
+
+    Err(package_error(plugin_path, "Plugin package missing info.json file"))
+}
+
+/// 解压插件包时的资源上限
+pub struct ExtractLimits {
+    /// 解压后的总字节数上限
+    pub max_total_size: u64,
+    /// 条目（文件 + 目录）数量上限
+    pub max_entries: usize,
+}
+
+impl Default for ExtractLimits {
+    fn default() -> Self {
+        Self {
+            max_total_size: 200 * 1024 * 1024,
+            max_entries: 10_000,
+        }
+    }
+}
+
+/// 安全解压 .tar.gz 插件包：只接受普通文件和目录，拒绝绝对路径、`..`、
+/// 符号链接/硬链接，并限制总大小和条目数量
+fn unpack_plugin_archive(reader: impl Read, dest: &Path, limits: &ExtractLimits) -> std::result::Result<(), String> {
+    let mut archive = Archive::new(GzDecoder::new(reader));
+    let entries = archive.entries().map_err(|e| format!("Failed to read plugin package: {}", e))?;
+
+    let mut total_size = 0u64;
+    for (index, entry) in entries.enumerate() {
+        if index >= limits.max_entries {
+            return Err(format!("Plugin package has more than {} entries", limits.max_entries));
+        }
+
+        let mut entry = entry.map_err(|e| format!("Failed to read archive entry: {}", e))?;
+        let path = entry.path()
+            .map_err(|e| format!("Invalid archive entry path: {}", e))?
+            .into_owned();
+
+        if let Some(component) = path.components().find(|c| !matches!(c, Component::Normal(_) | Component::CurDir)) {
+            return Err(format!("Unsafe path in plugin package: {} ({:?})", path.display(), component));
+        }
+
+        let entry_type = entry.header().entry_type();
+        if !(entry_type.is_file() || entry_type.is_dir()) {
+            return Err(format!("Unsupported entry type {:?} in plugin package: {}", entry_type, path.display()));
+        }
+
+        total_size += entry.size();
+        if total_size > limits.max_total_size {
+            return Err(format!("Plugin package exceeds {} bytes when extracted", limits.max_total_size));
+        }
+
+        // unpack_in 会再次确认目标路径位于 dest 之内
+        let unpacked = entry.unpack_in(dest)
+            .map_err(|e| format!("Failed to extract {}: {}", path.display(), e))?;
+        if !unpacked {
+            return Err(format!("Unsafe path in plugin package: {}", path.display()));
+        }
+    }
+
+    Ok(())
+}
+
+fn package_error(package: &Path, message: impl Into<String>) -> GeekToolsError {
+    GeekToolsError::PluginPackageError {
+        package: package.display().to_string(),
+        message: message.into(),
+    }
+}
+
+fn plugin_error(plugin_id: &str, message: impl Into<String>) -> GeekToolsError {
+    GeekToolsError::PluginError {
+        plugin_name: plugin_id.to_string(),
+        message: message.into(),
+    }
+}
+
+/// 插件管理器
+pub struct PluginManager {
+    /// 插件安装目录，注册表为其中的 registry.json
+    plugins_dir: PathBuf,
+    installed_plugins: HashMap<String, InstalledPlugin>,
+    archive_config: ArchiveConfig,
+}
+
+impl Default for PluginManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PluginManager {
+    /// 创建新的插件管理器实例
+    pub fn new() -> Self {
+        Self::open(PLUGINS_DIR.clone())
+    }
+
+    /// 使用指定的插件目录 (测试中为临时目录)
+    pub fn open(plugins_dir: PathBuf) -> Self {
+        let mut manager = Self {
+            plugins_dir,
+            installed_plugins: HashMap::new(),
+            archive_config: ARCHIVE_CONFIG.read().map(|c| c.clone()).unwrap_or_default(),
+        };
+        
+        // 加载已安装的插件
+        if let Err(e) = manager.load_installed_plugins() {
+            eprintln!("Warning: Failed to load installed plugins: {}", e);
+        }
+        manager.refresh_dev_plugins();
+        
+        manager
+    }
+
+    /// 打包前检查插件目录或其中的 info.json，见 [`lint::validate_manifest`]
+    pub fn validate_manifest(path: &Path) -> Result<Vec<lint::ManifestIssue>> {
+        lint::validate_manifest(path)
+    }
+
+    /// 从 .tar.gz 文件安装插件
+    pub fn install_plugin(&mut self, plugin_path: &Path) -> Result<String> {
+        log_only!("INFO", "PLUGIN", "安装 {}", plugin_path.display());
+        let result = self.install_package(plugin_path);
+        match &result {
+            Ok(plugin_id) => log_only!("INFO", "PLUGIN", "已安装 {}", plugin_id),
+            Err(e) => log_only!("ERROR", "PLUGIN", "安装 {} 失败: {}", plugin_path.display(), e),
+        }
+        result
+    }
+
+    fn install_package(&mut self, plugin_path: &Path) -> Result<String> {
+        // 1. 验证文件存在
+        if !plugin_path.exists() {
+            return Err(package_error(plugin_path, "Plugin file does not exist"));
+        }
+
+        // 2. 解压并验证插件包
+        let temp_dir = self.extract_plugin_package(plugin_path)?;
+        let plugin_info = self.validate_plugin_package(&temp_dir, plugin_path)?;
+
+        // 3. 检查是否已安装
+        if self.installed_plugins.contains_key(&plugin_info.id) {
+            return Err(plugin_error(&plugin_info.id, "Plugin is already installed"));
+        }
+
+        // 4. 检查依赖
+        self.check_dependencies(&plugin_info)?;
+
+        // 5. 安装插件到目标目录
+        let install_path = self.plugins_dir.join(&plugin_info.id);
+        if install_path.exists() {
+            fileio::remove_dir(&install_path)?;
+        }
+
+        // 复制插件文件到安装目录
+        self.copy_plugin_files(&temp_dir, &install_path)?;
+
+        // 6. 设置脚本可执行权限
+        self.set_script_permissions(&install_path, &plugin_info)?;
+
+        // 7. 记录已安装插件
+        let installed_plugin = InstalledPlugin {
+            info: plugin_info.clone(),
+            install_path: install_path.clone(),
+            installed_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            enabled: true,
+            previous_version: None,
+            dev: false,
+        };
+
+        self.installed_plugins.insert(plugin_info.id.clone(), installed_plugin);
+        self.save_installed_plugins()?;
+
+        // 8. 缓存插件包并清理临时目录
+        self.archive_package(plugin_path, &plugin_info);
+        let _ = fileio::remove_dir(&temp_dir);
+
+        Ok(plugin_info.id)
+    }
+
+    /// 开发模式：直接链接插件的工作目录 (含 info.json 和 scripts/)，不打包；
+    /// 修改脚本立即生效，info.json 的修改在下次打开插件管理器时读取
+    pub fn link_dev_plugin(&mut self, plugin_dir: &Path) -> Result<String> {
+        let result = self.link_dev_dir(plugin_dir);
+        match &result {
+            Ok(plugin_id) => log_only!("INFO", "PLUGIN", "已链接开发插件 {} -> {}", plugin_id, plugin_dir.display()),
+            Err(e) => log_only!("ERROR", "PLUGIN", "链接开发插件 {} 失败: {}", plugin_dir.display(), e),
+        }
+        result
+    }
+
+    fn link_dev_dir(&mut self, plugin_dir: &Path) -> Result<String> {
+        let source = plugin_dir.canonicalize().map_err(|e| GeekToolsError::FileOperationError {
+            path: plugin_dir.display().to_string(),
+            source: e,
+        })?;
+        let plugin_info = self.validate_plugin_package(&source, plugin_dir)?;
+        // 已链接的开发插件可以重新链接 (如工作目录移动了)
+        if self.installed_plugins.get(&plugin_info.id).is_some_and(|p| !p.dev) {
+            return Err(plugin_error(&plugin_info.id, "Plugin is already installed"));
+        }
+        self.check_dependencies(&plugin_info)?;
+
+        let link = self.plugins_dir.join(&plugin_info.id);
+        if let Ok(metadata) = std::fs::symlink_metadata(&link) {
+            if metadata.file_type().is_symlink() {
+                fileio::remove_file(&link)?;
+            } else {
+                fileio::remove_dir(&link)?;
+            }
+        }
+        fileio::create_dir(&self.plugins_dir)?;
+        symlink_dir(&source, &link).map_err(|e| GeekToolsError::FileOperationError {
+            path: link.display().to_string(),
+            source: e,
+        })?;
+        self.set_script_permissions(&source, &plugin_info)?;
+
+        let enabled = self.installed_plugins.get(&plugin_info.id).is_none_or(|p| p.enabled);
+        self.installed_plugins.insert(plugin_info.id.clone(), InstalledPlugin {
+            info: plugin_info.clone(),
+            install_path: link,
+            installed_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            enabled,
+            previous_version: None,
+            dev: true,
+        });
+        self.save_installed_plugins()?;
+        Ok(plugin_info.id)
+    }
+
+    /// 重新读取开发插件的 info.json，有变化时更新注册表；ID 改变或解析失败时保留原记录
+    fn refresh_dev_plugins(&mut self) {
+        let mut changed = false;
+        for plugin in self.installed_plugins.values_mut().filter(|p| p.dev) {
+            let info_path = plugin.install_path.join("info.json");
+            let info = fileio::read(&info_path)
+                .ok()
+                .and_then(|content| serde_json::from_str::<PluginInfo>(&content).ok());
+            match info {
+                Some(info) if info.id == plugin.info.id => {
+                    if info != plugin.info {
+                        log_only!("INFO", "PLUGIN", "开发插件 {} 的 info.json 已更新", info.id);
+                        plugin.info = info;
+                        changed = true;
+                    }
+                }
+                _ => log_only!("WARN", "PLUGIN", "无法读取开发插件 {} 的 {}", plugin.info.id, info_path.display()),
+            }
+        }
+        if changed && let Err(e) = self.save_installed_plugins() {
+            log_only!("ERROR", "PLUGIN", "保存插件注册表失败: {}", e);
+        }
+    }
+
+    /// 用新版本的插件包升级已安装的插件，保留启用状态并记录原版本以便回滚；返回原版本
+    pub fn upgrade_plugin(&mut self, plugin_path: &Path) -> Result<String> {
+        log_only!("INFO", "PLUGIN", "升级 {}", plugin_path.display());
+        let result = self.replace_package(plugin_path, None);
+        match &result {
+            Ok((plugin_id, previous)) => log_only!("INFO", "PLUGIN", "已升级 {} (原版本 {})", plugin_id, previous),
+            Err(e) => log_only!("ERROR", "PLUGIN", "升级 {} 失败: {}", plugin_path.display(), e),
+        }
+        result.map(|(_, previous)| previous)
+    }
+
+    /// 回滚到升级前的版本 (使用缓存的插件包，无需联网)；返回回滚到的版本
+    pub fn rollback_plugin(&mut self, plugin_id: &str) -> Result<String> {
+        let plugin = self.installed_plugins.get(plugin_id)
+            .ok_or_else(|| plugin_error(plugin_id, "Plugin is not installed"))?;
+        let previous = plugin.previous_version.clone()
+            .ok_or_else(|| plugin_error(plugin_id, "No previous version to roll back to"))?;
+        let archive = self.archive_path(plugin_id, &previous)
+            .filter(|path| path.is_file())
+            .ok_or_else(|| plugin_error(plugin_id, format!("No cached package for version {}", previous)))?;
+
+        self.replace_package(&archive, Some(plugin_id))?;
+        log_only!("INFO", "PLUGIN", "已回滚 {} 到 {}", plugin_id, previous);
+        Ok(previous)
+    }
+
+    /// 用插件包替换已安装的同 ID 插件，返回 (插件 ID, 原版本)
+    fn replace_package(&mut self, plugin_path: &Path, expected_id: Option<&str>) -> Result<(String, String)> {
+        if !plugin_path.exists() {
+            return Err(package_error(plugin_path, "Plugin file does not exist"));
+        }
+        let temp_dir = self.extract_plugin_package(plugin_path)?;
+        let result = self.replace_from_dir(&temp_dir, plugin_path, expected_id);
+        let _ = fileio::remove_dir(&temp_dir);
+        result
+    }
+
+    fn replace_from_dir(&mut self, temp_dir: &Path, plugin_path: &Path, expected_id: Option<&str>) -> Result<(String, String)> {
+        let plugin_info = self.validate_plugin_package(temp_dir, plugin_path)?;
+        if expected_id.is_some_and(|id| id != plugin_info.id) {
+            return Err(package_error(plugin_path, format!("Package contains plugin {}", plugin_info.id)));
+        }
+        let current = self.installed_plugins.get(&plugin_info.id)
+            .ok_or_else(|| plugin_error(&plugin_info.id, "Plugin is not installed"))?;
+        if current.info.version == plugin_info.version {
+            return Err(plugin_error(&plugin_info.id, format!("Version {} is already installed", plugin_info.version)));
+        }
+        if current.dev {
+            return Err(plugin_error(&plugin_info.id, "Plugin is linked in dev mode"));
+        }
+        let previous = current.info.version.clone();
+        let enabled = current.enabled;
+        self.check_dependencies(&plugin_info)?;
+
+        // 新版本先复制到旁边的目录，原版本改名让位后再换入，换入失败时改回，任何一步失败原版本都在
+        let install_path = self.plugins_dir.join(&plugin_info.id);
+        let staging = self.plugins_dir.join(format!("{}.upgrade", plugin_info.id));
+        let old = self.plugins_dir.join(format!("{}.old", plugin_info.id));
+        for leftover in [&staging, &old] {
+            if leftover.exists() {
+                fileio::remove_dir(leftover)?;
+            }
+        }
+        self.copy_plugin_files(temp_dir, &staging)?;
+        self.set_script_permissions(&staging, &plugin_info)?;
+        let had_previous = install_path.exists();
+        if had_previous {
+            fileio::rename(&install_path, &old)?;
+        }
+        if let Err(e) = fileio::rename(&staging, &install_path) {
+            if had_previous {
+                let _ = fileio::rename(&old, &install_path);
+            }
+            return Err(e);
+        }
+        if had_previous && let Err(e) = fileio::remove_dir(&old) {
+            log_only!("WARN", "PLUGIN", "删除旧版本目录 {} 失败: {}", old.display(), e);
+        }
+
+        self.installed_plugins.insert(plugin_info.id.clone(), InstalledPlugin {
+            info: plugin_info.clone(),
+            install_path,
+            installed_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            enabled,
+            previous_version: Some(previous.clone()),
+            dev: false,
+        });
+        self.save_installed_plugins()?;
+        self.archive_package(plugin_path, &plugin_info);
+        Ok((plugin_info.id, previous))
+    }
+
+    /// 缓存的插件包：<插件目录>/_archives/<ID>-<版本>.tar.gz，ID 或版本不能作为文件名时为 None
+    fn archive_path(&self, plugin_id: &str, version: &str) -> Option<PathBuf> {
+        let safe = |part: &str| !part.is_empty() && !part.starts_with('.') && !part.contains(['/', '\\']);
+        (safe(plugin_id) && safe(version))
+            .then(|| self.plugins_dir.join("_archives").join(format!("{}-{}.tar.gz", plugin_id, version)))
+    }
+
+    /// 保存插件包以便回滚；失败只记录日志，不影响安装
+    fn archive_package(&self, plugin_path: &Path, plugin_info: &PluginInfo) {
+        let Some(archive) = self.archive_path(&plugin_info.id, &plugin_info.version) else {
+            return;
+        };
+        if archive == plugin_path {
+            return;
+        }
+        let result = std::fs::read(plugin_path)
+            .map_err(|e| GeekToolsError::FileOperationError { path: plugin_path.display().to_string(), source: e })
+            .and_then(|content| fileio::write_bytes(&archive, &content));
+        if let Err(e) = result {
+            log_only!("WARN", "PLUGIN", "缓存插件包 {} 失败: {}", archive.display(), e);
+        }
+        self.prune_archives();
+    }
+
+    /// 缓存中的插件包，最近缓存的在前；无法读取清单的文件被跳过
+    pub fn cached_archives(&self) -> Vec<CachedArchive> {
+        let mut archives: Vec<(SystemTime, CachedArchive)> = self
+            .archive_files()
+            .into_iter()
+            .filter_map(|(path, modified, _)| {
+                let info = read_package_manifest(&path).ok()?;
+                (self.archive_path(&info.id, &info.version).as_ref() == Some(&path))
+                    .then_some((modified, CachedArchive { info, path }))
+            })
+            .collect();
+        archives.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+        archives.into_iter().map(|(_, archive)| archive).collect()
+    }
+
+    /// 从缓存的插件包安装 (无需联网)；已安装其他版本时按升级处理
+    pub fn install_cached(&mut self, archive: &CachedArchive) -> Result<String> {
+        if self.installed_plugins.contains_key(&archive.info.id) {
+            self.upgrade_plugin(&archive.path).map(|_| archive.info.id.clone())
+        } else {
+            self.install_plugin(&archive.path)
+        }
+    }
+
+    /// _archives 中的 .tar.gz 文件：(路径, 修改时间, 大小)
+    fn archive_files(&self) -> Vec<(PathBuf, SystemTime, u64)> {
+        let Ok(entries) = std::fs::read_dir(self.plugins_dir.join("_archives")) else {
+            return Vec::new();
+        };
+        entries
+            .flatten()
+            .filter(|entry| entry.file_name().to_string_lossy().ends_with(".tar.gz"))
+            .filter_map(|entry| {
+                let meta = entry.metadata().ok().filter(|m| m.is_file())?;
+                Some((entry.path(), meta.modified().unwrap_or(SystemTime::UNIX_EPOCH), meta.len()))
+            })
+            .collect()
+    }
+
+    /// 按数量和总大小上限删除最早缓存的插件包，已安装版本和可回滚版本除外
+    fn prune_archives(&self) {
+        let protected: Vec<PathBuf> = self.installed_plugins.values()
+            .flat_map(|p| {
+                let current = self.archive_path(&p.info.id, &p.info.version);
+                let previous = p.previous_version.as_ref().and_then(|v| self.archive_path(&p.info.id, v));
+                current.into_iter().chain(previous)
+            })
+            .collect();
+        let mut files = self.archive_files();
+        files.sort_by_key(|(_, modified, _)| *modified);
+        let max_bytes = self.archive_config.max_size_mb * 1024 * 1024;
+        let mut count = files.len();
+        let mut total: u64 = files.iter().map(|(_, _, size)| size).sum();
+        for (path, _, size) in files {
+            if count <= self.archive_config.max_count && total <= max_bytes {
+                break;
+            }
+            if protected.contains(&path) {
+                continue;
+            }
+            if fileio::remove_file(&path).is_ok() {
+                log_only!("INFO", "PLUGIN", "清理缓存的插件包 {}", path.display());
+                count -= 1;
+                total -= size;
+            }
+        }
+    }
+
+    /// 卸载插件
+    pub fn uninstall_plugin(&mut self, plugin_id: &str) -> Result<()> {
+        let plugin = self.installed_plugins.get(plugin_id)
+            .ok_or_else(|| plugin_error(plugin_id, "Plugin is not installed"))?;
+
+        // 删除插件目录 (开发模式只删除链接)
+        remove_install_path(plugin)?;
+
+        // 从记录中移除
+        self.installed_plugins.remove(plugin_id);
+        self.save_installed_plugins()?;
+
+        log_only!("INFO", "PLUGIN", "已卸载 {}", plugin_id);
+        Ok(())
+    }
+
+    /// 批量卸载插件，返回每个插件各自的结果（注册表只写一次）
+    pub fn uninstall_plugins(&mut self, plugin_ids: &[String]) -> Vec<(String, Result<()>)> {
+        let mut results = Vec::new();
+        let mut changed = false;
+
+        for plugin_id in plugin_ids {
+            let result = match self.installed_plugins.get(plugin_id) {
+                Some(plugin) => remove_install_path(plugin),
+                None => Err(plugin_error(plugin_id, "Plugin is not installed")),
+            };
+
+            if result.is_ok() {
+                self.installed_plugins.remove(plugin_id);
+                changed = true;
+            }
+            results.push((plugin_id.clone(), result));
+        }
+
+        if changed
+            && let Err(e) = self.save_installed_plugins()
+        {
+            let message = format!("Failed to save plugin registry: {}", e);
+            for (plugin_id, result) in results.iter_mut().filter(|(_, r)| r.is_ok()) {
+                *result = Err(plugin_error(plugin_id, message.clone()));
+            }
+        }
+        for (plugin_id, result) in &results {
+            match result {
+                Ok(()) => log_only!("INFO", "PLUGIN", "已卸载 {}", plugin_id),
+                Err(e) => log_only!("ERROR", "PLUGIN", "卸载 {} 失败: {}", plugin_id, e),
+            }
+        }
+
+        results
+    }
+
+    /// 获取已安装插件列表
+    pub fn list_installed_plugins(&self) -> Vec<&InstalledPlugin> {
+        self.installed_plugins.values().collect()
+    }
+
+
+    /// 启用/禁用插件
+    pub fn toggle_plugin(&mut self, plugin_id: &str, enabled: bool) -> Result<()> {
+        let plugin = self.installed_plugins.get_mut(plugin_id)
+            .ok_or_else(|| plugin_error(plugin_id, "Plugin is not installed"))?;
+
+        plugin.enabled = enabled;
+        self.save_installed_plugins()?;
+        log_only!("INFO", "PLUGIN", "{} {}", if enabled { "已启用" } else { "已禁用" }, plugin_id);
+        Ok(())
+    }
+
+    /// 批量启用/禁用插件，返回实际更新的插件数量
+    pub fn set_plugins_enabled(&mut self, plugin_ids: &[String], enabled: bool) -> Result<usize> {
+        // 先检查全部 ID，避免只更新一部分
+        if let Some(missing) = plugin_ids.iter().find(|id| !self.installed_plugins.contains_key(*id)) {
+            return Err(plugin_error(missing, "Plugin is not installed"));
+        }
+
+        let mut updated = 0;
+        for plugin_id in plugin_ids {
+            if let Some(plugin) = self.installed_plugins.get_mut(plugin_id)
+                && plugin.enabled != enabled
+            {
+                plugin.enabled = enabled;
+                updated += 1;
+            }
+        }
+
+        if updated > 0 {
+            self.save_installed_plugins()?;
+        }
+        Ok(updated)
+    }
+
+    /// 获取所有已启用插件的脚本
+    /// 返回 (显示名称, 描述, 脚本路径, 脚本条目)
+    pub fn get_enabled_scripts(&self) -> Vec<(String, String, PathBuf, ScriptEntry)> {
+        let candidates: Vec<_> = self.installed_plugins.values()
+            .filter(|plugin| plugin.enabled)
+            .flat_map(|plugin| plugin.info.scripts.iter().map(move |script| (
+                format!("{} - {}", script.name, plugin.info.name),
+                script.description.clone(),
+                plugin.install_path.join("scripts").join(&script.file),
+                script.clone(),
+            )))
+            .collect();
+
+        // 只在需要列出脚本时才检查文件，且并行检查
+        let paths: Vec<PathBuf> = candidates.iter().map(|c| c.2.clone()).collect();
+        candidates.into_iter()
+            .zip(paths_exist(&paths))
+            .filter_map(|(candidate, exists)| exists.then_some(candidate))
+            .collect()
+    }
+
+    /// 在已启用的插件中按脚本名或文件名查找脚本，用于 `#@import plugin:<id>/<脚本>`
+    pub fn find_enabled_script(&self, plugin_id: &str, script: &str) -> Option<PathBuf> {
+        let plugin = self.installed_plugins.get(plugin_id).filter(|plugin| plugin.enabled)?;
+        let entry = plugin.info.scripts.iter().find(|entry| entry.name == script || entry.file == script)?;
+        Some(plugin.install_path.join("scripts").join(&entry.file)).filter(|path| path.exists())
+    }
+
+    /// 解压插件包到临时目录
+    fn extract_plugin_package(&self, plugin_path: &Path) -> Result<PathBuf> {
+        let temp_dir = env::temp_dir().join(format!("geektools_plugin_{}", rand::random::<u64>()));
+        
+        // 创建临时目录
+        fileio::create_dir(&temp_dir)?;
+
+        // 打开并解压 .tar.gz 文件
+        let file = File::open(plugin_path).map_err(|e| GeekToolsError::FileOperationError {
+            path: plugin_path.display().to_string(),
+            source: e,
+        })?;
+        
+        if let Err(e) = unpack_plugin_archive(file, &temp_dir, &ExtractLimits::default()) {
+            let _ = fileio::remove_dir(&temp_dir);
+            return Err(package_error(plugin_path, e));
+        }
+
+        Ok(temp_dir)
+    }
+
+    /// 验证插件包结构和元数据
+    fn validate_plugin_package(&self, plugin_dir: &Path, plugin_path: &Path) -> Result<PluginInfo> {
+        // 检查 info.json 文件
+        let info_path = plugin_dir.join("info.json");
+        if !info_path.exists() {
+            return Err(package_error(plugin_path, "Plugin package missing info.json file"));
+        }
+
+        // 读取并解析 info.json
+        let info_content = fileio::read(&info_path)?;
+        
+        let plugin_info: PluginInfo = serde_json::from_str(&info_content)
+            .map_err(|e| package_error(plugin_path, format!("Failed to parse info.json: {}", e)))?;
+
+        // 验证必要字段
+        if plugin_info.id.is_empty() {
+            return Err(package_error(plugin_path, "Plugin ID cannot be empty"));
+        }
+        if plugin_info.name.is_empty() {
+            return Err(package_error(plugin_path, "Plugin name cannot be empty"));
+        }
+        if plugin_info.version.is_empty() {
+            return Err(package_error(plugin_path, "Plugin version cannot be empty"));
+        }
+
+        // 检查 scripts 目录
+        let scripts_dir = plugin_dir.join("scripts");
+        if !scripts_dir.exists() || !scripts_dir.is_dir() {
+            return Err(package_error(plugin_path, "Plugin package missing scripts directory"));
+        }
+
+        // 验证脚本文件是否存在
+        for script in &plugin_info.scripts {
+            let script_path = scripts_dir.join(&script.file);
+            if !script_path.exists() {
+                return Err(package_error(plugin_path, format!("Script file '{}' not found", script.file)));
+            }
+        }
+
+        Ok(plugin_info)
+    }
+
+    /// 检查插件依赖
+    fn check_dependencies(&self, plugin_info: &PluginInfo) -> Result<()> {
+        for dep in &plugin_info.dependencies {
+            if !self.installed_plugins.contains_key(dep) {
+                return Err(plugin_error(&plugin_info.id, format!("Missing dependency: {}", dep)));
+            }
+        }
+        Ok(())
+    }
+
+    /// 复制插件文件到安装目录
+    fn copy_plugin_files(&self, src_dir: &Path, dest_dir: &Path) -> Result<()> {
+        fileio::create_dir(dest_dir)?;
+
+        // 复制所有文件和目录
+        self.copy_directory_recursive(src_dir, dest_dir)
+    }
+
+    /// 递归复制目录
+    fn copy_directory_recursive(&self, src: &Path, dest: &Path) -> Result<()> {
+        let io_error = |path: &Path, e: std::io::Error| GeekToolsError::FileOperationError {
+            path: path.display().to_string(),
+            source: e,
+        };
+
+        if src.is_dir() {
+            if !dest.exists() {
+                fileio::create_dir(dest)?;
+            }
+
+            for entry in src.read_dir().map_err(|e| io_error(src, e))? {
+                let entry = entry.map_err(|e| io_error(src, e))?;
+                let src_path = entry.path();
+                let dest_path = dest.join(entry.file_name());
+                
+                if src_path.is_dir() {
+                    self.copy_directory_recursive(&src_path, &dest_path)?;
+                } else {
+                    let content = fileio::read(&src_path)?;
+                    fileio::write_bytes(&dest_path, content.as_ref())?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 设置脚本文件可执行权限
+    fn set_script_permissions(&self, install_path: &Path, plugin_info: &PluginInfo) -> Result<()> {
+        #[cfg(unix)]
+        {
+            let scripts_dir = install_path.join("scripts");
+            for script in &plugin_info.scripts {
+                if script.executable {
+                    let script_path = scripts_dir.join(&script.file);
+                    if script_path.exists() {
+                        fileio::set_executable(&script_path)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 加载已安装插件记录
+    fn load_installed_plugins(&mut self) -> Result<()> {
+        self.installed_plugins = load_registry(&self.plugins_dir.join("registry.json"))?;
+        Ok(())
+    }
+
+    /// 保存已安装插件记录
+    fn save_installed_plugins(&self) -> Result<()> {
+        let registry_path = self.plugins_dir.join("registry.json");
+        
+        let content = serde_json::to_string_pretty(&self.installed_plugins)?;
+        fileio::write(&registry_path, &content)?;
+        update_registry_cache(&registry_path, &self.installed_plugins);
+        
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn symlink_dir(source: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(source, link)
+}
+
+#[cfg(not(unix))]
+fn symlink_dir(_source: &Path, _link: &Path) -> std::io::Result<()> {
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "dev mode requires symlink support"))
+}
+
+/// 删除插件的安装目录；开发插件只删除链接，不动工作目录
+fn remove_install_path(plugin: &InstalledPlugin) -> Result<()> {
+    let path = &plugin.install_path;
+    if plugin.dev {
+        return match std::fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.file_type().is_symlink() => fileio::remove_file(path),
+            _ => Ok(()),
+        };
+    }
+    if path.exists() {
+        fileio::remove_dir(path)?;
+    }
+    Ok(())
+}
+
+fn registry_modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// 读取注册表；文件未变化时直接返回上次解析的结果
+fn load_registry(path: &Path) -> Result<HashMap<String, InstalledPlugin>> {
+    let modified = registry_modified(path);
+    if modified.is_none() {
+        return Ok(HashMap::new()); // 没有注册表文件是正常的
+    }
+    if let Ok(cache) = REGISTRY_CACHE.lock()
+        && let Some(cache) = cache.as_ref()
+        && cache.path == path
+        && cache.modified == modified
+    {
+        return Ok(cache.plugins.clone());
+    }
+
+    let content = fileio::read(path)?;
+    let plugins: HashMap<String, InstalledPlugin> = serde_json::from_str(&content)
+        .map_err(|e| GeekToolsError::ConfigError {
+            message: format!("Failed to parse plugin registry: {}", e),
+        })?;
+    update_registry_cache(path, &plugins);
+    Ok(plugins)
+}
+
+fn update_registry_cache(path: &Path, plugins: &HashMap<String, InstalledPlugin>) {
+    if let Ok(mut cache) = REGISTRY_CACHE.lock() {
+        *cache = Some(RegistryCache {
+            path: path.to_path_buf(),
+            modified: registry_modified(path),
+            plugins: plugins.clone(),
+        });
+    }
+}
+
+/// 少于该数量的路径直接顺序检查
+const PARALLEL_STAT_THRESHOLD: usize = 16;
+
+/// 并行检查路径是否存在 (SD 卡上逐个 stat 较慢)，结果顺序与输入一致
+fn paths_exist(paths: &[PathBuf]) -> Vec<bool> {
+    let workers = thread::available_parallelism().map_or(1, |n| n.get()).min(8);
+    if paths.len() < PARALLEL_STAT_THRESHOLD || workers < 2 {
+        return paths.iter().map(|p| p.exists()).collect();
+    }
+    let chunk_size = paths.len().div_ceil(workers);
+    thread::scope(|s| {
+        let handles: Vec<_> = paths
+            .chunks(chunk_size)
+            .map(|chunk| s.spawn(move || chunk.iter().map(|p| p.exists()).collect::<Vec<_>>()))
+            .collect();
+        handles
+            .into_iter()
+            .zip(paths.chunks(chunk_size))
+            .flat_map(|(handle, chunk)| handle.join().unwrap_or_else(|_| chunk.iter().map(|p| p.exists()).collect()))
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugins::mock_marketplace;
+    use flate2::{write::GzEncoder, Compression};
+    use tempfile::TempDir;
+
+    /// 构造只包含给定文件的 .tar.gz 插件包
+    fn build_package(dir: &Path, files: &[(&str, &str)]) -> PathBuf {
+        let package_path = dir.join("plugin.tar.gz");
+        let encoder = GzEncoder::new(File::create(&package_path).unwrap(), Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        for (name, content) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, name, content.as_bytes()).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+        package_path
+    }
+
+    /// 构造单条目的恶意包；直接写入 header 名称，绕过 tar::Builder 的路径检查
+    fn build_raw_package(name: &str, entry_type: tar::EntryType, link: Option<&str>) -> Vec<u8> {
+        let mut header = tar::Header::new_old();
+        header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+        header.set_entry_type(entry_type);
+        header.set_mode(0o644);
+        header.set_size(if entry_type.is_file() { 4 } else { 0 });
+        if let Some(link) = link {
+            header.set_link_name(link).unwrap();
+        }
+        header.set_cksum();
+
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        let data: &[u8] = if entry_type.is_file() { b"evil" } else { b"" };
+        builder.append(&header, data).unwrap();
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    #[test]
+    fn test_unpack_rejects_path_traversal() {
+        let temp_dir = TempDir::new().unwrap();
+        let dest = temp_dir.path().join("out");
+        fileio::create_dir(&dest).unwrap();
+
+        for name in ["../evil.sh", "scripts/../../evil.sh", "/tmp/geektools_evil.sh"] {
+            let package = build_raw_package(name, tar::EntryType::Regular, None);
+            let err = unpack_plugin_archive(package.as_slice(), &dest, &ExtractLimits::default()).unwrap_err();
+            assert!(err.contains("Unsafe path"), "{}: {}", name, err);
+        }
+        assert!(!temp_dir.path().join("evil.sh").exists());
+    }
+
+    #[test]
+    fn test_unpack_rejects_links() {
+        let temp_dir = TempDir::new().unwrap();
+
+        for entry_type in [tar::EntryType::Symlink, tar::EntryType::Link] {
+            let package = build_raw_package("scripts/run.sh", entry_type, Some("/etc/passwd"));
+            let err = unpack_plugin_archive(package.as_slice(), temp_dir.path(), &ExtractLimits::default()).unwrap_err();
+            assert!(err.contains("Unsupported entry type"), "{}", err);
+        }
+        assert!(!temp_dir.path().join("scripts/run.sh").exists());
+    }
+
+    #[test]
+    fn test_unpack_enforces_limits() {
+        let temp_dir = TempDir::new().unwrap();
+        let package = build_package(temp_dir.path(), &[
+            ("info.json", INFO_JSON),
+            ("scripts/hello.sh", "#!/bin/sh\necho hello\n"),
+        ]);
+        let dest = temp_dir.path().join("out");
+        fileio::create_dir(&dest).unwrap();
+
+        let too_small = ExtractLimits { max_total_size: 16, max_entries: 100 };
+        let err = unpack_plugin_archive(File::open(&package).unwrap(), &dest, &too_small).unwrap_err();
+        assert!(err.contains("exceeds"), "{}", err);
+
+        let too_few = ExtractLimits { max_total_size: 1024 * 1024, max_entries: 1 };
+        let err = unpack_plugin_archive(File::open(&package).unwrap(), &dest, &too_few).unwrap_err();
+        assert!(err.contains("entries"), "{}", err);
+
+        unpack_plugin_archive(File::open(&package).unwrap(), &dest, &ExtractLimits::default()).unwrap();
+        assert!(dest.join("scripts/hello.sh").exists());
+    }
+
+    const INFO_JSON: &str = r#"{
+        "id": "demo-plugin",
+        "name": "Demo",
+        "version": "1.2.0",
+        "description": "Demo plugin",
+        "author": "tester",
+        "scripts": [{"name": "hello", "file": "hello.sh", "description": "say hello", "executable": true}]
+    }"#;
+
+    /// 写入 60 个插件 (每个 3 个脚本，其中一个缺失) 及其注册表，返回注册表路径
+    fn write_many_plugins(dir: &Path) -> PathBuf {
+        let mut plugins = HashMap::new();
+        for i in 0..60 {
+            let id = format!("plugin-{}", i);
+            let install_path = dir.join(&id);
+            fileio::create_dir(install_path.join("scripts")).unwrap();
+            let mut info: PluginInfo = serde_json::from_str(INFO_JSON).unwrap();
+            info.id = id.clone();
+            info.scripts = (0..3).map(|j| ScriptEntry {
+                name: format!("s{}", j),
+                file: format!("s{}.sh", j),
+                description: String::new(),
+                executable: true,
+                interpreter: None,
+                working_dir: None,
+                keep_artifacts: false,
+            }).collect();
+            for script in &info.scripts[..2] {
+                fileio::write(install_path.join("scripts").join(&script.file), "echo\n").unwrap();
+            }
+            plugins.insert(id, InstalledPlugin {
+                info,
+                install_path,
+                installed_at: String::new(),
+                enabled: true,
+                previous_version: None,
+                dev: false,
+            });
+        }
+        let registry = dir.join("registry.json");
+        fileio::write(&registry, &serde_json::to_string(&plugins).unwrap()).unwrap();
+        registry
+    }
+
+    #[test]
+    fn test_load_many_plugins() {
+        let temp_dir = TempDir::new().unwrap();
+        let registry = write_many_plugins(temp_dir.path());
+
+        let manager = PluginManager::open(temp_dir.path().to_path_buf());
+        assert_eq!(load_registry(&registry).unwrap().len(), 60);
+        let scripts = manager.get_enabled_scripts();
+
+        assert_eq!(manager.installed_plugins.len(), 60);
+        assert_eq!(scripts.len(), 120);
+        assert!(scripts.iter().all(|s| !s.2.ends_with("s2.sh")));
+
+        let found = manager.find_enabled_script("plugin-7", "s1").unwrap();
+        assert!(found.ends_with("plugin-7/scripts/s1.sh"));
+        assert_eq!(manager.find_enabled_script("plugin-7", "s0.sh"), Some(temp_dir.path().join("plugin-7/scripts/s0.sh")));
+        assert_eq!(manager.find_enabled_script("plugin-7", "s2"), None);
+        assert_eq!(manager.find_enabled_script("plugin-99", "s0"), None);
+    }
+
+    #[test]
+    fn test_read_package_manifest() {
+        let temp_dir = TempDir::new().unwrap();
+        let package = build_package(temp_dir.path(), &[
+            ("./info.json", INFO_JSON),
+            ("scripts/hello.sh", "#!/bin/sh\necho hello\n"),
+        ]);
+
+        let info = read_package_manifest(&package).unwrap();
+        assert_eq!(info.id, "demo-plugin");
+        assert_eq!(info.version, "1.2.0");
+        assert_eq!(info.scripts.len(), 1);
+    }
+
+    #[test]
+    fn test_archives_are_bounded_and_reinstall_offline() {
+        let temp_dir = TempDir::new().unwrap();
+        let plugins_dir = temp_dir.path().join("plugins");
+        let mut manager = PluginManager::open(plugins_dir.clone());
+        manager.archive_config = ArchiveConfig { max_count: 2, max_size_mb: 200 };
+
+        let package_path = temp_dir.path().join("download.tar.gz");
+        for (i, version) in ["1.0.0", "1.1.0", "1.2.0"].into_iter().enumerate() {
+            let content = mock_marketplace::package("demo-plugin", "Demo", version);
+            fileio::write_bytes(&package_path, &content).unwrap();
+            if i == 0 {
+                manager.install_plugin(&package_path).unwrap();
+            } else {
+                manager.upgrade_plugin(&package_path).unwrap();
+            }
+            // 修改时间的精度可能不足以区分先后
+            thread::sleep(std::time::Duration::from_millis(20));
+        }
+
+        // 升级换入后不留下新旧版本的临时目录
+        assert!(!plugins_dir.join("demo-plugin.upgrade").exists());
+        assert!(!plugins_dir.join("demo-plugin.old").exists());
+
+        // 1.0.0 既不是已安装版本也不可回滚，超过数量上限时被删除
+        let versions: Vec<String> = manager.cached_archives().into_iter().map(|a| a.info.version).collect();
+        assert_eq!(versions, ["1.2.0", "1.1.0"]);
+
+        std::fs::remove_file(&package_path).unwrap();
+        manager.uninstall_plugin("demo-plugin").unwrap();
+        let archive = manager.cached_archives().remove(0);
+        assert_eq!(manager.install_cached(&archive).unwrap(), "demo-plugin");
+        assert_eq!(manager.list_installed_plugins()[0].info.version, "1.2.0");
+        assert!(plugins_dir.join("demo-plugin/scripts/hello.sh").exists());
+
+        let older = manager.cached_archives().remove(1);
+        manager.install_cached(&older).unwrap();
+        assert_eq!(manager.list_installed_plugins()[0].info.version, "1.1.0");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_dev_plugin_links_working_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        let checkout = temp_dir.path().join("checkout");
+        fileio::write(checkout.join("info.json"), INFO_JSON).unwrap();
+        fileio::write(checkout.join("scripts/hello.sh"), "#!/bin/sh\necho hello\n").unwrap();
+        let plugins_dir = temp_dir.path().join("plugins");
+
+        let mut manager = PluginManager::open(plugins_dir.clone());
+        assert_eq!(manager.link_dev_plugin(&checkout).unwrap(), "demo-plugin");
+        let plugin = manager.list_installed_plugins()[0];
+        assert!(plugin.dev);
+        assert!(std::fs::symlink_metadata(&plugin.install_path).unwrap().file_type().is_symlink());
+        assert_eq!(manager.get_enabled_scripts().len(), 1);
+
+        // info.json 的修改在重新打开时生效
+        fileio::write(checkout.join("info.json"), &INFO_JSON.replace("1.2.0", "1.3.0-dev")).unwrap();
+        let mut manager = PluginManager::open(plugins_dir.clone());
+        assert_eq!(manager.list_installed_plugins()[0].info.version, "1.3.0-dev");
+
+        let package_path = temp_dir.path().join("demo.tar.gz");
+        fileio::write_bytes(&package_path, &mock_marketplace::package("demo-plugin", "Demo", "2.0.0")).unwrap();
+        assert!(manager.upgrade_plugin(&package_path).is_err());
+
+        // 卸载只删除链接
+        manager.uninstall_plugin("demo-plugin").unwrap();
+        assert!(std::fs::symlink_metadata(plugins_dir.join("demo-plugin")).is_err());
+        assert!(checkout.join("scripts/hello.sh").exists());
+    }
+
+    #[test]
+    fn test_read_package_manifest_ignores_nested_info_json() {
+        let temp_dir = TempDir::new().unwrap();
+        let package = build_package(temp_dir.path(), &[("scripts/info.json", INFO_JSON)]);
+
+        assert!(read_package_manifest(&package).is_err());
+    }
+}
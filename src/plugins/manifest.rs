@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+/// 插件元数据文件结构 (info.json)
+#[cfg(feature = "plugins")]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PluginInfo {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    pub author: String,
+    pub scripts: Vec<ScriptEntry>,
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub min_geektools_version: Option<String>,
+}
+
+/// 脚本条目信息
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ScriptEntry {
+    pub name: String,
+    pub file: String,
+    pub description: String,
+    #[serde(default)]
+    pub executable: bool,
+    /// 指定解释器 (如 `bash`、`python3`)，优先于 shebang
+    #[serde(default)]
+    pub interpreter: Option<String>,
+    /// 工作目录，相对路径基于脚本所在目录；为空时沿用启动目录
+    #[serde(default)]
+    pub working_dir: Option<String>,
+    /// 运行结束后保留临时产物目录
+    #[serde(default)]
+    pub keep_artifacts: bool,
+}
@@ -1,11 +1,28 @@
-use crate::{fileio, log_only, LOG_FILE};
-use reqwest::blocking::Client;
-use serde::{Deserialize, Serialize};
+use super::MarketplaceConfig;
+use crate::{fileio, log_only, network};
+use crate::errors::{GeekToolsError, Result};
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
+use reqwest::{Method, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::{
-    path::Path,
+    path::{Path, PathBuf},
+    thread,
     time::Duration,
 };
 
+/// 插件包下载缓存目录；测试中改用进程内的临时目录，不写入真实的 $HOME
+#[cfg(not(test))]
+fn default_cache_dir() -> PathBuf {
+    crate::httpcache::files_dir()
+}
+
+#[cfg(test)]
+fn default_cache_dir() -> PathBuf {
+    static DIR: std::sync::LazyLock<tempfile::TempDir> = std::sync::LazyLock::new(|| tempfile::tempdir().unwrap());
+    DIR.path().join("files")
+}
+
 /// 插件市场插件信息
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct MarketplacePlugin {
@@ -25,6 +42,30 @@ pub struct MarketplacePlugin {
     #[serde(default)]
     pub file_size: i64,    // 可能不存在，默认为0
     pub tags: Vec<String>,
+    /// 以组织身份发布时的组织信息，个人发布时为空
+    #[serde(default)]
+    pub organization: Option<PublisherOrg>,
+}
+
+/// 发布插件的组织
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PublisherOrg {
+    pub id: String,
+    pub name: String,
+    /// 市场已核实组织身份 (域名或代码仓库归属)
+    #[serde(default)]
+    pub verified: bool,
+}
+
+impl MarketplacePlugin {
+    /// 列表中显示的发布者：组织发布时为组织名，已认证的带 ✔
+    pub fn publisher(&self) -> String {
+        match &self.organization {
+            Some(org) if org.verified => format!("{} ✔", org.name),
+            Some(org) => org.name.clone(),
+            None => self.author.clone(),
+        }
+    }
 }
 
 fn default_file_url() -> String {
@@ -34,7 +75,6 @@ fn default_file_url() -> String {
 /// 实际API的分页信息结构
 #[derive(Debug, Deserialize)]
 pub struct PaginationInfo {
-    pub limit: i32,
     pub page: i32,
     pub pages: i32,
     pub total: i32,
@@ -47,23 +87,12 @@ pub struct ApiData {
     pub plugins: Vec<MarketplacePlugin>,
 }
 
-/// 实际API的响应结构
-#[derive(Debug, Deserialize)]
-pub struct ApiResponse {
-    pub data: ApiData,
-    pub success: bool,
-}
-
 /// 标准化的插件列表响应结构
-#[derive(Debug, Deserialize)]
+#[derive(Debug)]
 pub struct PluginListResponse {
     pub plugins: Vec<MarketplacePlugin>,
     pub total: i32,
-    #[serde(default)]
     pub page: i32,
-    #[serde(default)]
-    pub per_page: i32,
-    #[serde(default)]
     pub total_pages: i32,
 }
 
@@ -108,278 +137,444 @@ impl SortBy {
     }
 }
 
-/// 插件市场配置  
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct MarketplaceConfig {
-    pub api_url: String,
-    pub api_port: u16,
+/// 插件的下载和评分统计
+#[derive(Debug, Deserialize)]
+pub struct PluginStats {
+    pub total_downloads: i64,
+    #[serde(default)]
+    pub downloads_last_7_days: i64,
+    #[serde(default)]
+    pub downloads_last_30_days: i64,
+    pub rating: f32,
+    #[serde(default)]
+    pub rating_count: i32,
+    /// 近 30 天每天的下载量，旧的在前，没有下载的日期计 0
+    #[serde(default)]
+    pub daily_downloads: Vec<DailyDownloads>,
+}
+
+/// 某一天的下载量
+#[derive(Debug, Deserialize)]
+pub struct DailyDownloads {
+    pub date: String,
+    pub downloads: i64,
+}
+
+impl PluginStats {
+    /// 每天的下载量，用于画趋势图
+    pub fn daily_counts(&self) -> Vec<i64> {
+        self.daily_downloads.iter().map(|d| d.downloads).collect()
+    }
+}
+
+/// 某个版本的更新说明
+#[derive(Debug, Deserialize, Clone)]
+pub struct ChangelogEntry {
+    pub version: String,
     #[serde(default)]
-    pub timeout_seconds: u64,
+    pub released_at: Option<String>,
+    pub notes: String,
+}
+
+/// 上传成功后服务端返回的插件 ID 和版本
+#[derive(Debug, Deserialize)]
+pub struct UploadResult {
+    pub plugin_id: String,
+    pub version: String,
+}
+
+/// 插件元数据更新，未设置的字段保持不变
+#[derive(Debug, Default, Serialize)]
+pub struct PluginMetadataUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
 }
 
-impl Default for MarketplaceConfig {
-    fn default() -> Self {
-        Self {
-            api_url: "https://market-api.yshsr.org".to_string(),
-            api_port: 443,
-            timeout_seconds: 30,
+impl PluginMetadataUpdate {
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none() && self.description.is_none() && self.tags.is_none()
+    }
+}
+
+/// 举报插件的原因分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportReason {
+    /// 恶意代码：窃取数据、破坏系统、挖矿等
+    Malware,
+    /// 描述与实际行为不符
+    Misleading,
+    /// 侵犯版权或许可证
+    Copyright,
+    /// 垃圾内容或重复发布
+    Spam,
+    Other,
+}
+
+impl ReportReason {
+    pub const ALL: [ReportReason; 5] = [
+        ReportReason::Malware,
+        ReportReason::Misleading,
+        ReportReason::Copyright,
+        ReportReason::Spam,
+        ReportReason::Other,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ReportReason::Malware => "恶意代码",
+            ReportReason::Misleading => "描述与实际行为不符",
+            ReportReason::Copyright => "侵犯版权",
+            ReportReason::Spam => "垃圾内容",
+            ReportReason::Other => "其他",
         }
     }
 }
 
+/// 通用的 API 响应信封
+#[derive(Debug, Deserialize)]
+struct ApiEnvelope<T> {
+    success: bool,
+    #[serde(default)]
+    message: Option<String>,
+    data: Option<T>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SendCodeData {
+    #[serde(default)]
+    code: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LoginData {
+    token: String,
+}
+
+/// 可以安全重发的请求方法
+fn is_idempotent(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS | Method::PUT | Method::DELETE)
+}
+
+/// 一次请求的结果是否值得重试；`status` 为收到的响应状态
+fn should_retry(idempotent: bool, outcome: std::result::Result<Option<StatusCode>, &GeekToolsError>) -> bool {
+    match outcome {
+        Ok(Some(status)) => idempotent && matches!(status.as_u16(), 429 | 502 | 503 | 504),
+        Ok(None) => false,
+        // 连接都未建立时服务器肯定没有收到请求，非幂等请求也可以重发
+        Err(GeekToolsError::NetworkError { source, .. }) => idempotent || source.is_connect(),
+        Err(_) => false,
+    }
+}
+
+/// 市场 API 版本，通过 Accept 头协商
+const API_ACCEPT: &str = "application/vnd.geektools.v1+json";
+
+/// 服务端接受的插件包大小上限，与服务端 `MAX_UPLOAD_BYTES` 默认值一致
+const MAX_UPLOAD_BYTES: u64 = 50 * 1024 * 1024;
+
 /// 插件市场客户端
 pub struct MarketplaceClient {
     config: MarketplaceConfig,
     client: Client,
+    /// 插件包下载缓存目录，默认 ~/.geektools/cache/http/files/
+    cache_dir: PathBuf,
 }
 
 impl MarketplaceClient {
     /// 创建新的市场客户端
-    pub fn new(config: MarketplaceConfig) -> Result<Self, String> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(config.timeout_seconds))
-            .build()
-            .map_err(|e| format!("创建HTTP客户端失败: {}", e))?;
+    pub fn new(config: MarketplaceConfig) -> Result<Self> {
+        network::ensure_secure_url(&config.api_url)?;
+        let mut builder = network::client_builder(Some(Duration::from_secs(config.timeout_seconds)))?;
+        if let Some(fingerprint) = &config.pinned_cert_sha256 {
+            builder = network::pin_certificate(builder, fingerprint)?;
+        }
+        let client = builder.build()?;
+
+        Ok(Self { config, client, cache_dir: default_cache_dir() })
+    }
 
-        Ok(Self { config, client })
+    /// 使用指定的插件包缓存目录 (测试用)
+    #[cfg(test)]
+    pub fn with_cache_dir(mut self, cache_dir: PathBuf) -> Self {
+        self.cache_dir = cache_dir;
+        self
     }
 
     /// 构建API完整URL
     fn build_api_url(&self, endpoint: &str) -> String {
-        format!("{}:{}/api/v1{}", self.config.api_url, self.config.api_port, endpoint)
+        self.config.endpoint_url(endpoint)
     }
 
     /// 获取插件列表（分页）
-    pub fn get_plugins(&self, page: i32, per_page: i32, sort_by: Option<SortBy>) -> Result<PluginListResponse, String> {
-        let mut url = format!("{}/plugins?page={}&per_page={}", 
-            self.build_api_url(""), page, per_page);
+    pub fn get_plugins(&self, page: i32, per_page: i32, sort_by: Option<SortBy>) -> Result<PluginListResponse> {
+        let mut endpoint = format!("/plugins?page={}&per_page={}", page, per_page);
         
         if let Some(sort) = sort_by {
-            url = format!("{}&sort_by={}", url, sort.to_string());
+            endpoint = format!("{}&sort_by={}", endpoint, sort.to_string());
         }
 
-        // 记录API请求信息
-        log_only!("INFO", "API_REQUEST", "插件市场浏览 URL={}", url);
+        self.fetch_plugin_list(&endpoint)
+    }
 
-        let response = self.client
-            .get(&url)
-            .send()
-            .map_err(|e| {
-                log_only!("ERROR", "API_REQUEST", "插件市场请求失败: {}", e);
-                format!("请求失败: {}", e)
-            })?;
+    /// 搜索插件 (使用插件列表端点进行搜索)
+    pub fn search_plugins(&self, query: &str) -> Result<SearchResponse> {
+        let endpoint = format!("/plugins?search={}", urlencoding::encode(query));
+        let plugin_response = self.fetch_plugin_list(&endpoint)?;
 
-        // 记录响应状态
-        log_only!("INFO", "API_RESPONSE", "插件市场响应 status={}", response.status());
+        Ok(SearchResponse {
+            plugins: plugin_response.plugins,
+            total: plugin_response.total,
+            query: query.to_string(),
+        })
+    }
 
-        if !response.status().is_success() {
-            return Err(format!("API请求失败，状态码: {}", response.status()));
-        }
+    /// 获取热门插件（最近 7 天下载量排序）
+    pub fn get_trending(&self, limit: i32) -> Result<PluginListResponse> {
+        self.fetch_plugin_list(&format!("/plugins/trending?days=7&limit={}", limit))
+    }
 
-        // 先获取响应文本用于调试
-        let response_text = response.text()
-            .map_err(|e| {
-                log_only!("ERROR", "API_RESPONSE", "读取响应文本失败: {}", e);
-                format!("读取响应文本失败: {}", e)
-            })?;
-        
-        // 记录响应内容（截取前200字符以避免日志过长）
-        let preview = if response_text.len() > 200 {
-            format!("{}...", &response_text[..200])
-        } else {
-            response_text.clone()
-        };
-        log_only!("INFO", "API_RESPONSE", "插件市场响应内容: {}", preview);
-
-        // 尝试解析为实际的API响应格式
-        let plugin_response: PluginListResponse = match serde_json::from_str::<ApiResponse>(&response_text) {
-            Ok(api_response) => {
-                log_only!("INFO", "API_PARSE", "成功解析插件市场API响应，共 {} 个插件", api_response.data.plugins.len());
-                PluginListResponse {
-                    plugins: api_response.data.plugins,
-                    total: api_response.data.pagination.total,
-                    page: api_response.data.pagination.page,
-                    per_page: api_response.data.pagination.limit,
-                    total_pages: api_response.data.pagination.pages,
-                }
-            }
-            Err(e1) => {
-                log_only!("WARN", "API_PARSE", "API格式解析失败，尝试其他格式: {}", e1);
-                // 尝试解析为简单格式
-                match serde_json::from_str::<PluginListResponse>(&response_text) {
-                    Ok(response) => {
-                        log_only!("INFO", "API_PARSE", "成功解析为简单格式");
-                        response
-                    }
-                    Err(e2) => {
-                        log_only!("WARN", "API_PARSE", "简单格式解析失败，尝试插件数组: {}", e2);
-                        // 最后尝试解析为插件数组
-                        match serde_json::from_str::<Vec<MarketplacePlugin>>(&response_text) {
-                            Ok(plugins) => {
-                                log_only!("INFO", "API_PARSE", "成功解析为插件数组，共 {} 个插件", plugins.len());
-                                let total = plugins.len() as i32;
-                                PluginListResponse {
-                                    plugins,
-                                    total,
-                                    page: 1,
-                                    per_page: total,
-                                    total_pages: 1,
-                                }
-                            }
-                            Err(e3) => {
-                                return Err(format!("所有解析方式都失败:\n1. API格式: {}\n2. 简单格式: {}\n3. 插件数组: {}\n响应内容: {}", e1, e2, e3, response_text));
-                            }
-                        }
-                    }
-                }
-            }
-        };
+    /// 获取编辑推荐插件
+    pub fn get_featured(&self) -> Result<PluginListResponse> {
+        self.fetch_plugin_list("/plugins/featured")
+    }
 
-        Ok(plugin_response)
+    /// 是否已登录
+    pub fn is_logged_in(&self) -> bool {
+        self.config.access_token.is_some()
     }
 
-    /// 搜索插件 (使用插件列表端点进行搜索)
-    pub fn search_plugins(&self, query: &str) -> Result<SearchResponse, String> {
-        // 使用插件列表API进行搜索
-        let url = format!("{}/plugins?search={}", 
-            self.build_api_url(""), urlencoding::encode(query));
-
-        // 记录搜索请求信息
-        log_only!("INFO", "API_REQUEST", "插件搜索 query='{}' URL={}", query, url);
-
-        let response = self.client
-            .get(&url)
-            .send()
-            .map_err(|e| {
-                log_only!("ERROR", "API_REQUEST", "插件搜索请求失败: {}", e);
-                format!("搜索请求失败: {}", e)
-            })?;
-
-        // 记录搜索响应状态
-        log_only!("INFO", "API_RESPONSE", "插件搜索响应 status={}", response.status());
-
-        if !response.status().is_success() {
-            return Err(format!("搜索失败，状态码: {}", response.status()));
+    /// 请求邮箱验证码，开发模式下服务端会直接返回验证码
+    pub fn send_login_code(&self, email: &str) -> Result<Option<String>> {
+        let request = self.request(Method::POST, "/auth/send-code")
+            .json(&serde_json::json!({ "email": email }));
+        let data: Option<SendCodeData> = self.send_json(request, "/auth/send-code")?;
+        Ok(data.and_then(|d| d.code))
+    }
+
+    /// 使用邮箱验证码登录，返回访问令牌
+    pub fn verify_login_code(&self, email: &str, code: &str) -> Result<String> {
+        let endpoint = "/auth/verify-code";
+        let request = self.request(Method::POST, endpoint)
+            .json(&serde_json::json!({ "email": email, "code": code }));
+        let data: LoginData = self.send_json(request, endpoint)?
+            .ok_or_else(|| marketplace_error(endpoint, None, "登录响应缺少令牌"))?;
+        Ok(data.token)
+    }
+
+    /// 获取当前用户发布的插件
+    pub fn get_my_plugins(&self) -> Result<PluginListResponse> {
+        self.require_login("/users/me/plugins")?;
+        self.fetch_plugin_list("/users/me/plugins")
+    }
+
+    /// 更新插件元数据
+    pub fn update_plugin_metadata(&self, plugin_id: &str, update: &PluginMetadataUpdate) -> Result<()> {
+        let endpoint = format!("/plugins/{}", urlencoding::encode(plugin_id));
+        self.require_login(&endpoint)?;
+
+        let request = self.request(Method::PATCH, &endpoint).json(update);
+        self.send_json::<serde_json::Value>(request, &endpoint).map(|_| ())
+    }
+
+    /// 撤回（yank）某个版本，已安装的用户不受影响，但新用户无法再下载该版本
+    pub fn yank_version(&self, plugin_id: &str, version: &str, reason: &str) -> Result<()> {
+        let endpoint = format!("/plugins/{}/versions/{}/yank",
+            urlencoding::encode(plugin_id), urlencoding::encode(version));
+        self.require_login(&endpoint)?;
+
+        let request = self.request(Method::POST, &endpoint)
+            .json(&serde_json::json!({ "reason": reason }));
+        self.send_json::<serde_json::Value>(request, &endpoint).map(|_| ())
+    }
+
+    /// 获取插件的下载和评分统计，不需要登录
+    pub fn get_plugin_stats(&self, plugin_id: &str) -> Result<PluginStats> {
+        let endpoint = format!("/plugins/{}/stats", urlencoding::encode(plugin_id));
+        self.send_json(self.request(Method::GET, &endpoint), &endpoint)?
+            .ok_or_else(|| marketplace_error(&endpoint, None, "统计响应缺少数据"))
+    }
+
+    /// 上传插件包发布新版本，`organization` 为首次发布时所属的组织；
+    /// 已登录的令牌可以是交互登录得到的 JWT，也可以是 CI 使用的 API 密钥
+    pub fn upload_plugin(&self, package_path: &Path, organization: Option<&str>) -> Result<UploadResult> {
+        let endpoint = "/plugins";
+        self.require_login(endpoint)?;
+
+        let size = std::fs::metadata(package_path).map_err(|e| GeekToolsError::FileOperationError {
+            path: package_path.display().to_string(),
+            source: e,
+        })?.len();
+        if size > MAX_UPLOAD_BYTES {
+            return Err(marketplace_error(endpoint, None,
+                &format!("插件包大小 {} 字节超过上限 {} 字节", size, MAX_UPLOAD_BYTES)));
         }
+        let package = std::fs::read(package_path).map_err(|e| GeekToolsError::FileOperationError {
+            path: package_path.display().to_string(),
+            source: e,
+        })?;
+        let file_name = package_path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        let boundary = format!("geektools-{:016x}", rand::random::<u64>());
+        let body = multipart_body(&boundary, &file_name, &package, organization);
+        log_only!("INFO", "API_UPLOAD", "上传 {} ({} 字节)", file_name, package.len());
+
+        let request = self.request(Method::POST, endpoint)
+            .header(CONTENT_TYPE, format!("multipart/form-data; boundary={}", boundary))
+            .body(body);
+        self.send_json(request, endpoint)?
+            .ok_or_else(|| marketplace_error(endpoint, None, "上传响应缺少数据"))
+    }
 
-        // 先获取响应文本用于调试
-        let response_text = response.text()
-            .map_err(|e| format!("读取搜索响应文本失败: {}", e))?;
-        
-        // 记录搜索响应内容（截取前200字符以避免日志过长）
-        let preview = if response_text.len() > 200 {
-            format!("{}...", &response_text[..200])
+    /// 举报插件，进入管理员审核队列；同一用户重复举报同一插件时服务端只保留最新一条
+    pub fn report_plugin(&self, plugin_id: &str, reason: ReportReason, details: &str) -> Result<()> {
+        let endpoint = format!("/plugins/{}/report", urlencoding::encode(plugin_id));
+        self.require_login(&endpoint)?;
+
+        let request = self.request(Method::POST, &endpoint)
+            .json(&serde_json::json!({ "reason": reason, "details": details }));
+        self.send_json::<serde_json::Value>(request, &endpoint).map(|_| ())
+    }
+
+    /// 获取 `since` 之后 (不含) 各版本的更新说明，新版本在前
+    pub fn get_changelog(&self, plugin_id: &str, since: &str) -> Result<Vec<ChangelogEntry>> {
+        let endpoint = format!("/plugins/{}/changelog?since={}", urlencoding::encode(plugin_id), urlencoding::encode(since));
+        Ok(self.send_json(self.request(Method::GET, &endpoint), &endpoint)?.unwrap_or_default())
+    }
+
+    fn require_login(&self, endpoint: &str) -> Result<()> {
+        if self.is_logged_in() {
+            Ok(())
         } else {
-            response_text.clone()
-        };
-        log_only!("INFO", "API_RESPONSE", "插件搜索响应内容: {}", preview);
-
-        // 尝试解析为实际的API响应格式
-        let plugin_response: PluginListResponse = match serde_json::from_str::<ApiResponse>(&response_text) {
-            Ok(api_response) => {
-                log_only!("INFO", "API_PARSE", "成功解析搜索API响应，共 {} 个插件", api_response.data.plugins.len());
-                PluginListResponse {
-                    plugins: api_response.data.plugins,
-                    total: api_response.data.pagination.total,
-                    page: api_response.data.pagination.page,
-                    per_page: api_response.data.pagination.limit,
-                    total_pages: api_response.data.pagination.pages,
-                }
-            }
-            Err(e1) => {
-                log_only!("WARN", "API_PARSE", "搜索API格式解析失败，尝试其他格式: {}", e1);
-                // 尝试解析为简单格式
-                match serde_json::from_str::<PluginListResponse>(&response_text) {
-                    Ok(response) => {
-                        log_only!("INFO", "API_PARSE", "成功解析搜索为简单格式");
-                        response
-                    }
-                    Err(e2) => {
-                        log_only!("WARN", "API_PARSE", "搜索简单格式解析失败，尝试插件数组: {}", e2);
-                        // 最后尝试解析为插件数组
-                        match serde_json::from_str::<Vec<MarketplacePlugin>>(&response_text) {
-                            Ok(plugins) => {
-                                log_only!("INFO", "API_PARSE", "成功解析搜索结果为插件数组，共 {} 个插件", plugins.len());
-                                let total = plugins.len() as i32;
-                                PluginListResponse {
-                                    plugins,
-                                    total,
-                                    page: 1,
-                                    per_page: total,
-                                    total_pages: 1,
-                                }
-                            }
-                            Err(e3) => {
-                                log_only!("ERROR", "API_PARSE", "所有搜索解析方式都失败: API格式={}, 简单格式={}, 插件数组={}", e1, e2, e3);
-                                return Err(format!("搜索解析失败:\n1. API格式: {}\n2. 简单格式: {}\n3. 插件数组: {}\n响应内容: {}", e1, e2, e3, response_text));
-                            }
-                        }
-                    }
-                }
-            }
-        };
+            Err(marketplace_error(endpoint, None, "请先登录插件市场账号"))
+        }
+    }
 
-        // 转换为SearchResponse格式
-        let search_response = SearchResponse {
-            plugins: plugin_response.plugins,
-            total: plugin_response.total,
-            query: query.to_string(),
-        };
+    /// 构建带版本化 Accept 头的请求，已登录时附加 Bearer 令牌
+    fn request(&self, method: Method, endpoint: &str) -> RequestBuilder {
+        let url = self.build_api_url(endpoint);
+        log_only!("INFO", "API_REQUEST", "{} {}", method, url);
 
-        Ok(search_response)
+        let request = self.client.request(method, url).header(ACCEPT, API_ACCEPT);
+        match &self.config.access_token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
     }
 
-    /// 下载插件
-    pub fn download_plugin(&self, download_url: &str, save_path: &Path) -> Result<(), String> {
-        log_only!("INFO", "DOWNLOAD", "插件下载 URL={}", download_url);
-        log_only!("INFO", "DOWNLOAD", "插件保存路径={:?}", save_path);
-        
-        let response = self.client
-            .get(download_url)
-            .send()
-            .map_err(|e| {
-                log_only!("ERROR", "DOWNLOAD", "插件下载请求失败: {}", e);
-                format!("下载请求失败: {}", e)
-            })?;
-
-        log_only!("INFO", "DOWNLOAD", "插件下载响应 status={}", response.status());
-
-        if !response.status().is_success() {
-            log_only!("ERROR", "DOWNLOAD", "插件下载失败，状态码: {}", response.status());
-            return Err(format!("下载失败，状态码: {}", response.status()));
+    /// 按重试策略执行请求，`send` 每次调用都重新发送一次；`status` 取出响应状态用于判断是否重试
+    fn with_retry<R>(
+        &self,
+        method: &Method,
+        endpoint: &str,
+        status: impl Fn(&R) -> Option<StatusCode>,
+        mut send: impl FnMut() -> Result<R>,
+    ) -> Result<R> {
+        let policy = &self.config.retry;
+        let idempotent = is_idempotent(method);
+        let mut attempt = 1;
+        loop {
+            let result = send();
+            let retry = attempt < policy.max_attempts && should_retry(idempotent, result.as_ref().map(&status));
+            if !retry {
+                return result;
+            }
+            let delay = policy.delay(attempt);
+            match &result {
+                Ok(response) => log_only!("WARN", "API_RETRY", "{} {} status={:?}，{:?} 后重试 ({}/{})",
+                    method, endpoint, status(response), delay, attempt, policy.max_attempts),
+                Err(e) => log_only!("WARN", "API_RETRY", "{} {} 失败: {}，{:?} 后重试 ({}/{})",
+                    method, endpoint, e, delay, attempt, policy.max_attempts),
+            }
+            thread::sleep(delay);
+            attempt += 1;
         }
+    }
 
-        let bytes = response.bytes()
-            .map_err(|e| {
-                log_only!("ERROR", "DOWNLOAD", "读取下载内容失败: {}", e);
-                format!("读取下载内容失败: {}", e)
-            })?;
+    /// 请求返回插件列表的端点
+    fn fetch_plugin_list(&self, endpoint: &str) -> Result<PluginListResponse> {
+        let data: ApiData = self.get_json_cached(endpoint)?
+            .ok_or_else(|| marketplace_error(endpoint, None, "响应缺少 data 字段"))?;
+
+        log_only!("INFO", "API_PARSE", "{} 共 {} 个插件", endpoint, data.plugins.len());
+        Ok(PluginListResponse {
+            plugins: data.plugins,
+            total: data.pagination.total,
+            page: data.pagination.page,
+            total_pages: data.pagination.pages,
+        })
+    }
+
+    /// 发送请求并解析响应信封
+    fn send_json<T: DeserializeOwned>(&self, request: RequestBuilder, endpoint: &str) -> Result<Option<T>> {
+        let request = request.build()?;
+        let response = self.with_retry(request.method(), endpoint, |r: &Response| Some(r.status()), || {
+            let attempt = request.try_clone().ok_or_else(|| marketplace_error(endpoint, None, "请求无法重发"))?;
+            network::throttle(attempt.url().as_str());
+            Ok(self.client.execute(attempt)?)
+        })?;
+        let status = response.status();
+        log_only!("INFO", "API_RESPONSE", "{} status={}", endpoint, status);
+
+        let text = response.text()?;
+        parse_envelope(status, &text, endpoint)
+    }
 
-        log_only!("INFO", "DOWNLOAD", "插件下载文件大小: {} bytes", bytes.len());
+    /// GET 请求并解析响应信封，列表未变化时 (304) 使用本地缓存
+    fn get_json_cached<T: DeserializeOwned>(&self, endpoint: &str) -> Result<Option<T>> {
+        let url = self.build_api_url(endpoint);
+        let status = |c: &network::Conditional| match c {
+            network::Conditional::NotModified(_) => None,
+            network::Conditional::Response(response) => Some(response.status()),
+        };
+        let conditional = self.with_retry(&Method::GET, endpoint, status, || {
+            network::send_conditional(self.request(Method::GET, endpoint), &url)
+        })?;
+        match conditional {
+            network::Conditional::NotModified(text) => parse_envelope(StatusCode::OK, &text, endpoint),
+            network::Conditional::Response(response) => {
+                let status = response.status();
+                log_only!("INFO", "API_RESPONSE", "{} status={}", endpoint, status);
+                let text = network::read_and_cache(&url, response)?;
+                parse_envelope(status, &text, endpoint)
+            }
+        }
+    }
 
-        fileio::write_bytes(save_path, &bytes)
-            .map_err(|e| {
-                log_only!("ERROR", "DOWNLOAD", "保存插件文件失败: {}", e);
-                format!("保存插件文件失败: {}", e)
-            })?;
+    /// 下载插件，支持进度回调和断点续传（未完成部分保存在 `<save_path>.part`）
+    pub fn download_plugin(&self, download_url: &str, save_path: &Path, progress: &mut dyn FnMut(u64, Option<u64>)) -> Result<u64> {
+        log_only!("INFO", "DOWNLOAD", "插件下载 URL={}", download_url);
+        log_only!("INFO", "DOWNLOAD", "插件保存路径={:?}", save_path);
 
-        log_only!("INFO", "DOWNLOAD", "插件文件保存成功");
-        Ok(())
+        // 中断的下载从 .part 继续；校验不符时删除文件重新下载
+        let policy = &self.config.retry;
+        let mut attempt = 1;
+        loop {
+            let result = network::download_cached(&self.client, download_url, save_path, &self.cache_dir, &mut *progress)
+                .and_then(|(size, checksum)| verify_download(download_url, save_path, checksum.as_deref()).map(|_| size));
+            match result {
+                Err(e) if attempt < policy.max_attempts && !matches!(e, GeekToolsError::InsecureTransportError { .. }) => {
+                    let delay = policy.delay(attempt);
+                    log_only!("WARN", "DOWNLOAD", "插件下载失败: {}，{:?} 后重试 ({}/{})", e, delay, attempt, policy.max_attempts);
+                    thread::sleep(delay);
+                    attempt += 1;
+                }
+                result => return result.inspect_err(|e| log_only!("ERROR", "DOWNLOAD", "插件下载失败: {}", e)),
+            }
+        }
     }
 
     /// 测试API连接
-    pub fn test_connection(&self) -> Result<(), String> {
-        let url = format!("{}/health", self.build_api_url(""));
-        
-        log_only!("INFO", "API_TEST", "测试API连接 URL={}", url);
-        
-        let response = self.client
-            .get(&url)
-            .send()
-            .map_err(|e| {
-                log_only!("ERROR", "API_TEST", "连接测试失败: {}", e);
-                format!("连接测试失败: {}", e)
-            })?;
+    pub fn test_connection(&self) -> Result<()> {
+        let response = self
+            .with_retry(&Method::GET, "/health", |r: &Response| Some(r.status()), || {
+                network::throttle(&self.build_api_url("/health"));
+                Ok(self.request(Method::GET, "/health").send()?)
+            })
+            .inspect_err(|e| log_only!("ERROR", "API_TEST", "连接测试失败: {}", e))?;
 
         log_only!("INFO", "API_TEST", "连接测试响应 status={}", response.status());
 
@@ -388,119 +583,145 @@ impl MarketplaceClient {
             Ok(())
         } else {
             log_only!("ERROR", "API_TEST", "API服务器响应错误，状态码: {}", response.status());
-            Err(format!("API服务器响应错误，状态码: {}", response.status()))
+            Err(marketplace_error("/health", Some(response.status()), "API服务器响应错误"))
         }
     }
 }
 
-/// 本地插件扫描器
-pub struct LocalPluginScanner {
-    scan_directories: Vec<String>,
+/// 服务器提供了 SHA-256 时校验下载的插件包，不符时删除文件，避免截断的包在安装时报出难懂的错误
+fn verify_download(url: &str, path: &Path, expected: Option<&str>) -> Result<()> {
+    let Some(expected) = expected else {
+        return Ok(());
+    };
+    let actual = network::sha256_file(path)?;
+    if network::checksum_matches(&actual, expected) {
+        log_only!("INFO", "DOWNLOAD", "SHA-256 校验通过: {}", actual);
+        return Ok(());
+    }
+    let _ = fileio::remove_file(path);
+    Err(marketplace_error(url, None, &format!("SHA-256 校验失败: 期望 {}，实际 {}", expected, actual)))
 }
 
-impl LocalPluginScanner {
-    /// 创建新的本地扫描器
-    pub fn new() -> Self {
-        let home_dir = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
-        let scan_directories = vec![
-            format!("{}/Downloads", home_dir),
-            format!("{}/Desktop", home_dir),
-            format!("{}/Documents", home_dir),
-            ".".to_string(), // 当前目录
-        ];
+/// 构造 multipart/form-data 请求体：`plugin_file` 文件字段和可选的 `organization_id` 字段
+fn multipart_body(boundary: &str, file_name: &str, package: &[u8], organization: Option<&str>) -> Vec<u8> {
+    let mut body = Vec::with_capacity(package.len() + 512);
+    if let Some(organization) = organization {
+        body.extend_from_slice(format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"organization_id\"\r\n\r\n{}\r\n",
+            boundary, organization
+        ).as_bytes());
+    }
+    body.extend_from_slice(format!(
+        "--{}\r\nContent-Disposition: form-data; name=\"plugin_file\"; filename=\"{}\"\r\n\
+         Content-Type: application/gzip\r\n\r\n",
+        boundary, file_name.replace('"', "")
+    ).as_bytes());
+    body.extend_from_slice(package);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+    body
+}
 
-        Self { scan_directories }
+fn marketplace_error(endpoint: &str, status: Option<StatusCode>, message: &str) -> GeekToolsError {
+    GeekToolsError::MarketplaceError {
+        endpoint: endpoint.to_string(),
+        status: status.map(|s| s.as_u16()),
+        message: message.to_string(),
     }
+}
 
-    /// 添加扫描目录
-    pub fn add_scan_directory(&mut self, directory: String) {
-        if !self.scan_directories.contains(&directory) {
-            self.scan_directories.push(directory);
-        }
+/// 解析 `{success, message, data}` 响应信封
+fn parse_envelope<T: DeserializeOwned>(status: StatusCode, text: &str, endpoint: &str) -> Result<Option<T>> {
+    if status == StatusCode::UNAUTHORIZED {
+        return Err(marketplace_error(endpoint, Some(status), "登录已失效，请重新登录"));
     }
 
-    /// 扫描本地插件文件
-    pub fn scan_plugins(&self) -> Vec<LocalPluginInfo> {
-        let mut plugins = Vec::new();
-
-        for dir in &self.scan_directories {
-            if let Ok(entries) = std::fs::read_dir(dir) {
-                for entry in entries.flatten() {
-                    let path = entry.path();
-                    if let Some(extension) = path.extension() {
-                        if extension == "tar" || extension == "gz" || 
-                           (extension == "gz" && path.to_string_lossy().ends_with(".tar.gz")) {
-                            
-                            if let Some(plugin_info) = self.analyze_plugin_file(&path) {
-                                plugins.push(plugin_info);
-                            }
-                        }
-                    }
-                }
-            }
-        }
+    let envelope: ApiEnvelope<T> = serde_json::from_str(text).map_err(|e| {
+        log_only!("ERROR", "API_PARSE", "{} 响应解析失败: {}", endpoint, e);
+        // 非 JSON 响应（网关错误页等）优先报告状态码
+        let message = if status.is_success() { format!("响应格式无效: {}", e) } else { status.to_string() };
+        marketplace_error(endpoint, Some(status), &message)
+    })?;
 
-        plugins
-    }
-
-    /// 分析本地插件文件信息
-    fn analyze_plugin_file(&self, path: &Path) -> Option<LocalPluginInfo> {
-        if let Ok(metadata) = std::fs::metadata(path) {
-            let file_name = path.file_name()?.to_string_lossy().to_string();
-            let file_size = metadata.len();
-            let modified_time = metadata.modified().ok()?;
-            
-            // 从文件名推断插件信息
-            let (name, version) = self.parse_filename(&file_name);
-            
-            Some(LocalPluginInfo {
-                file_path: path.to_path_buf(),
-                file_name,
-                file_size,
-                modified_time: format!("{:?}", modified_time),
-                estimated_name: name,
-                estimated_version: version,
-            })
-        } else {
-            None
-        }
+    if !status.is_success() || !envelope.success {
+        let message = envelope.message.unwrap_or_else(|| status.to_string());
+        return Err(marketplace_error(endpoint, Some(status), &message));
+    }
+    Ok(envelope.data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_plugin_list_envelope() {
+        let body = r#"{
+            "success": true,
+            "data": {
+                "plugins": [{
+                    "id": "system_tools",
+                    "name": "系统工具集",
+                    "current_version": "1.0.0",
+                    "description": "常用系统工具",
+                    "author": "GeekTools Team",
+                    "downloads": 1250,
+                    "rating": 4.5,
+                    "created_at": "2024-01-15T10:30:00Z",
+                    "updated_at": "2024-01-20T14:45:00Z",
+                    "tags": ["system"]
+                }, {
+                    "id": "cloud_kit",
+                    "name": "Cloud Kit",
+                    "current_version": "0.3.0",
+                    "description": "",
+                    "author": "alice",
+                    "downloads": 3,
+                    "rating": 0.0,
+                    "created_at": "2024-01-15T10:30:00Z",
+                    "updated_at": "2024-01-20T14:45:00Z",
+                    "tags": [],
+                    "organization": {"id": "acme", "name": "Acme Corp", "verified": true}
+                }],
+                "pagination": {"page": 2, "limit": 20, "total": 21, "pages": 2}
+            }
+        }"#;
+        let data: ApiData = parse_envelope(StatusCode::OK, body, "/plugins").unwrap().unwrap();
+        assert_eq!(data.plugins.len(), 2);
+        assert_eq!(data.plugins[0].version, "1.0.0");
+        assert_eq!(data.plugins[0].publisher(), "GeekTools Team");
+        assert_eq!(data.plugins[1].publisher(), "Acme Corp ✔");
+        assert_eq!(data.pagination.total, 21);
     }
 
-    /// 从文件名解析插件名称和版本
-    fn parse_filename(&self, filename: &str) -> (String, String) {
-        let base_name = filename
-            .strip_suffix(".tar.gz")
-            .or_else(|| filename.strip_suffix(".tar"))
-            .unwrap_or(filename);
-
-        // 尝试解析版本号 (例如: plugin-name-v1.2.3 或 plugin-name-1.2.3)
-        if let Some(last_dash) = base_name.rfind('-') {
-            let potential_version = &base_name[last_dash + 1..];
-            if potential_version.starts_with('v') || 
-               potential_version.chars().next().map_or(false, |c| c.is_ascii_digit()) {
-                let name = base_name[..last_dash].to_string();
-                let version = potential_version.to_string();
-                return (name, version);
+    #[test]
+    fn test_parse_envelope_errors_are_structured() {
+        let err = parse_envelope::<ApiData>(StatusCode::OK, "[]", "/plugins").unwrap_err();
+        assert!(matches!(err, GeekToolsError::MarketplaceError { status: Some(200), .. }));
+
+        let body = r#"{"success": false, "message": "服务维护中"}"#;
+        match parse_envelope::<ApiData>(StatusCode::SERVICE_UNAVAILABLE, body, "/plugins").unwrap_err() {
+            GeekToolsError::MarketplaceError { endpoint, status, message } => {
+                assert_eq!(endpoint, "/plugins");
+                assert_eq!(status, Some(503));
+                assert_eq!(message, "服务维护中");
             }
+            other => panic!("unexpected error: {:?}", other),
         }
 
-        (base_name.to_string(), "unknown".to_string())
+        let err = parse_envelope::<ApiData>(StatusCode::BAD_GATEWAY, "<html>", "/plugins").unwrap_err();
+        assert!(err.is_recoverable());
+        let err = parse_envelope::<ApiData>(StatusCode::UNAUTHORIZED, "", "/users/me/plugins").unwrap_err();
+        assert!(!err.is_recoverable());
     }
-}
 
-impl Default for LocalPluginScanner {
-    fn default() -> Self {
-        Self::new()
+    #[test]
+    fn test_metadata_update_skips_unset_fields() {
+        let update = PluginMetadataUpdate {
+            description: Some("新描述".to_string()),
+            ..Default::default()
+        };
+        assert!(!update.is_empty());
+        assert_eq!(serde_json::to_string(&update).unwrap(), r#"{"description":"新描述"}"#);
+        assert!(PluginMetadataUpdate::default().is_empty());
     }
 }
-
-/// 本地插件信息
-#[derive(Debug, Clone)]
-pub struct LocalPluginInfo {
-    pub file_path: std::path::PathBuf,
-    pub file_name: String,
-    pub file_size: u64,
-    pub modified_time: String,
-    pub estimated_name: String,
-    pub estimated_version: String,
-}
\ No newline at end of file
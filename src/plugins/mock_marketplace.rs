@@ -0,0 +1,583 @@
+use flate2::{write::GzEncoder, Compression};
+use serde_json::json;
+#[cfg(feature = "network")]
+use super::MarketplaceConfig;
+#[cfg(feature = "network")]
+use serde_json::Value;
+#[cfg(feature = "network")]
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+/// 收到的请求：方法、路径 (含查询串)、小写的请求头和请求体
+#[cfg(feature = "network")]
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub target: String,
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+/// 市场中的一个插件及其 .tar.gz 包
+#[cfg(feature = "network")]
+struct MockPlugin {
+    id: String,
+    name: String,
+    version: String,
+    package: Vec<u8>,
+}
+
+/// 本机的模拟插件市场，实现客户端用到的 `/api/v1/plugins` 列表、搜索、更新说明、下载统计、上传、举报和 `/files/<id>.tar.gz` 下载
+#[cfg(feature = "network")]
+#[cfg_attr(not(feature = "marketplace"), allow(dead_code))]
+pub struct MockMarketplace {
+    port: u16,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+    /// 接下来这么多个请求直接返回 502
+    failures: Arc<Mutex<u32>>,
+    /// 接下来这么多次下载返回截断的插件包 (校验头仍是完整包的摘要)
+    corruptions: Arc<Mutex<u32>>,
+}
+
+#[cfg(feature = "network")]
+#[cfg_attr(not(feature = "marketplace"), allow(dead_code))]
+impl MockMarketplace {
+    /// 在随机端口上启动，`plugins` 为 (id, 名称, 版本)
+    pub fn start(plugins: &[(&str, &str, &str)]) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let plugins: Arc<Vec<MockPlugin>> = Arc::new(
+            plugins
+                .iter()
+                .map(|(id, name, version)| MockPlugin {
+                    id: id.to_string(),
+                    name: name.to_string(),
+                    version: version.to_string(),
+                    package: package(id, name, version),
+                })
+                .collect(),
+        );
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let failures = Arc::new(Mutex::new(0));
+        let corruptions = Arc::new(Mutex::new(0));
+
+        let (recorded, failing, corrupting) = (requests.clone(), failures.clone(), corruptions.clone());
+        thread::spawn(move || {
+            for stream in listener.incoming().map_while(Result::ok) {
+                let plugins = plugins.clone();
+                let recorded = recorded.clone();
+                let faults = Faults { failures: failing.clone(), corruptions: corrupting.clone() };
+                thread::spawn(move || handle(stream, port, &plugins, &recorded, &faults));
+            }
+        });
+        Self { port, requests, failures, corruptions }
+    }
+
+    /// 让接下来的 `count` 次下载返回截断的插件包
+    pub fn corrupt_next(&self, count: u32) {
+        *self.corruptions.lock().unwrap() = count;
+    }
+
+    /// 让接下来的 `count` 个请求返回 502 Bad Gateway
+    pub fn fail_next(&self, count: u32) {
+        *self.failures.lock().unwrap() = count;
+    }
+
+    pub fn config(&self) -> MarketplaceConfig {
+        MarketplaceConfig {
+            api_url: "http://127.0.0.1".to_string(),
+            api_port: self.port,
+            timeout_seconds: 5,
+            ..Default::default()
+        }
+    }
+
+    /// 插件包的下载地址
+    pub fn file_url(&self, id: &str) -> String {
+        format!("http://127.0.0.1:{}/files/{}.tar.gz", self.port, id)
+    }
+
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+/// 注入的故障
+#[cfg(feature = "network")]
+struct Faults {
+    failures: Arc<Mutex<u32>>,
+    corruptions: Arc<Mutex<u32>>,
+}
+
+/// 计数大于 0 时减一并返回 true
+#[cfg(feature = "network")]
+fn take(counter: &Mutex<u32>) -> bool {
+    let mut count = counter.lock().unwrap();
+    let hit = *count > 0;
+    if hit {
+        *count -= 1;
+    }
+    hit
+}
+
+/// 生成插件包：info.json 和一个 hello.sh 脚本
+pub fn package(id: &str, name: &str, version: &str) -> Vec<u8> {
+    let info = json!({
+        "id": id,
+        "name": name,
+        "version": version,
+        "description": format!("{} plugin", name),
+        "author": "tester",
+        "scripts": [{"name": "hello", "file": "hello.sh", "description": "say hello", "executable": true}]
+    })
+    .to_string();
+    let script = format!("#!/bin/sh\necho hello from {}\n", id);
+
+    let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    for (path, content) in [("info.json", info.as_str()), ("scripts/hello.sh", script.as_str())] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, path, content.as_bytes()).unwrap();
+    }
+    builder.into_inner().unwrap().finish().unwrap()
+}
+
+#[cfg(feature = "network")]
+fn handle(mut stream: TcpStream, port: u16, plugins: &[MockPlugin], recorded: &Mutex<Vec<RecordedRequest>>, faults: &Faults) {
+    let Some(request) = read_request(&stream) else {
+        return;
+    };
+    recorded.lock().unwrap().push(request.clone());
+    if take(&faults.failures) {
+        respond(&mut stream, "502 Bad Gateway", &[], b"<html>bad gateway</html>");
+        return;
+    }
+
+    let (path, query) = request.target.split_once('?').unwrap_or((&request.target, ""));
+    let query: HashMap<&str, String> = query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(k, v)| (k, urlencoding::decode(v).map(|v| v.into_owned()).unwrap_or_default()))
+        .collect();
+
+    match (request.method.as_str(), path) {
+        ("GET", "/api/v1/plugins") => {
+            let search = query.get("search").map(|s| s.to_lowercase());
+            let matching: Vec<Value> = plugins
+                .iter()
+                .filter(|p| search.as_ref().is_none_or(|s| p.name.to_lowercase().contains(s) || p.id.contains(s)))
+                .map(|p| plugin_json(p, port))
+                .collect();
+            let body = json!({
+                "success": true,
+                "data": {
+                    "plugins": matching,
+                    "pagination": {"page": 1, "limit": 20, "total": matching.len(), "pages": 1}
+                }
+            });
+            respond(&mut stream, "200 OK", &[("Content-Type", "application/json".to_string())], body.to_string().as_bytes());
+        }
+        ("GET", changelog) if changelog.starts_with("/api/v1/plugins/") && changelog.ends_with("/changelog") => {
+            let id = changelog.trim_start_matches("/api/v1/plugins/").trim_end_matches("/changelog");
+            let since = query.get("since").cloned().unwrap_or_default();
+            let entries: Vec<Value> = plugins
+                .iter()
+                .filter(|p| p.id == id && p.version != since)
+                .map(|p| json!({"version": p.version, "released_at": "2024-06-01", "notes": format!("changes in {}", p.version)}))
+                .collect();
+            let body = json!({"success": true, "data": entries});
+            respond(&mut stream, "200 OK", &[("Content-Type", "application/json".to_string())], body.to_string().as_bytes());
+        }
+        ("POST", "/api/v1/plugins") => {
+            let (status, body) = if !request.headers.contains_key("authorization") {
+                ("401 Unauthorized", json!({"success": false, "message": "login required"}))
+            } else if !request.body.contains("name=\"plugin_file\"") {
+                ("400 Bad Request", json!({"success": false, "message": "plugin_file is required"}))
+            } else {
+                ("201 Created", json!({"success": true, "message": "插件上传成功", "data": {"plugin_id": "new-plugin", "version": "1.0.0"}}))
+            };
+            respond(&mut stream, status, &[("Content-Type", "application/json".to_string())], body.to_string().as_bytes());
+        }
+        ("POST", report) if report.starts_with("/api/v1/plugins/") && report.ends_with("/report") => {
+            let id = report.trim_start_matches("/api/v1/plugins/").trim_end_matches("/report");
+            let (status, body) = if !request.headers.contains_key("authorization") {
+                ("401 Unauthorized", json!({"success": false, "message": "login required"}))
+            } else if plugins.iter().any(|p| p.id == id) {
+                ("201 Created", json!({"success": true, "message": "举报已提交", "data": {"report_id": 1}}))
+            } else {
+                ("404 Not Found", json!({"success": false, "message": "plugin not found"}))
+            };
+            respond(&mut stream, status, &[("Content-Type", "application/json".to_string())], body.to_string().as_bytes());
+        }
+        ("GET", stats) if stats.starts_with("/api/v1/plugins/") && stats.ends_with("/stats") => {
+            let id = stats.trim_start_matches("/api/v1/plugins/").trim_end_matches("/stats");
+            let body = json!({
+                "success": true,
+                "data": {
+                    "plugin_id": id, "total_downloads": 12, "downloads_last_7_days": 5, "downloads_last_30_days": 12,
+                    "rating": 5.0, "rating_count": 1,
+                    "daily_downloads": [
+                        {"date": "2024-06-01", "downloads": 0},
+                        {"date": "2024-06-02", "downloads": 7},
+                        {"date": "2024-06-03", "downloads": 5}
+                    ]
+                }
+            });
+            respond(&mut stream, "200 OK", &[("Content-Type", "application/json".to_string())], body.to_string().as_bytes());
+        }
+        ("GET", file) if file.starts_with("/files/") => {
+            let id = file.trim_start_matches("/files/").trim_end_matches(".tar.gz");
+            match plugins.iter().find(|p| p.id == id) {
+                Some(plugin) => {
+                    let corrupt = take(&faults.corruptions);
+                    serve_package(&mut stream, &plugin.package, &request.headers, corrupt)
+                }
+                None => respond(&mut stream, "404 Not Found", &[], b""),
+            }
+        }
+        _ => {
+            let body = json!({"success": false, "message": "not found"}).to_string();
+            respond(&mut stream, "404 Not Found", &[("Content-Type", "application/json".to_string())], body.as_bytes());
+        }
+    }
+}
+
+#[cfg(feature = "network")]
+fn plugin_json(plugin: &MockPlugin, port: u16) -> Value {
+    json!({
+        "id": plugin.id,
+        "name": plugin.name,
+        "current_version": plugin.version,
+        "description": format!("{} plugin", plugin.name),
+        "author": "tester",
+        "downloads": 1,
+        "rating": 5.0,
+        "created_at": "2024-01-01T00:00:00Z",
+        "updated_at": "2024-01-01T00:00:00Z",
+        "file_url": format!("http://127.0.0.1:{}/files/{}.tar.gz", port, plugin.id),
+        "file_size": plugin.package.len(),
+        "tags": []
+    })
+}
+
+/// 支持 `Range: bytes=N-` 断点续传和 `If-None-Match` 条件请求；响应都带完整包的 `X-Checksum-SHA256`
+/// 和 `ETag`，并要求客户端每次使用缓存前重新验证 (`no-cache`)。`corrupt` 时只发送前一半内容
+#[cfg(feature = "network")]
+fn serve_package(stream: &mut TcpStream, package: &[u8], headers: &HashMap<String, String>, corrupt: bool) {
+    let checksum = ("X-Checksum-SHA256", sha256_hex(package));
+    let etag = format!("\"{}\"", &checksum.1[..16]);
+    let served = if corrupt { &package[..package.len() / 2] } else { package };
+    let start = headers.get("range")
+        .and_then(|r| r.strip_prefix("bytes="))
+        .and_then(|r| r.trim_end_matches('-').parse::<usize>().ok());
+    let total = package.len();
+    if !corrupt && start.is_none() && headers.get("if-none-match") == Some(&etag) {
+        respond(stream, "304 Not Modified", &[("ETag", etag), checksum], b"");
+        return;
+    }
+    let cache = [("ETag", etag), ("Cache-Control", "no-cache".to_string())];
+    match start {
+        Some(start) if start >= total => {
+            respond(stream, "416 Range Not Satisfiable", &[("Content-Range", format!("bytes */{}", total)), checksum], b"");
+        }
+        Some(start) => {
+            let range = format!("bytes {}-{}/{}", start, total - 1, total);
+            respond(stream, "206 Partial Content", &[("Content-Range", range), checksum], &package[start..]);
+        }
+        None => respond(stream, "200 OK", &[&[("Content-Type", "application/gzip".to_string()), checksum][..], &cache].concat(), served),
+    }
+}
+
+#[cfg(feature = "network")]
+fn sha256_hex(data: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    Sha256::digest(data).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(feature = "network")]
+fn read_request(stream: &TcpStream) -> Option<RecordedRequest> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line).ok()?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let target = parts.next()?.to_string();
+
+    let mut headers = HashMap::new();
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).ok()? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+        }
+    }
+    let length = headers.get("content-length").and_then(|l| l.parse().ok()).unwrap_or(0);
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).ok()?;
+    Some(RecordedRequest { method, target, headers, body: String::from_utf8_lossy(&body).into_owned() })
+}
+
+#[cfg(feature = "network")]
+fn respond(stream: &mut TcpStream, status: &str, headers: &[(&str, String)], body: &[u8]) {
+    let mut head = format!("HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n", status, body.len());
+    for (name, value) in headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("\r\n");
+    let _ = stream.write_all(head.as_bytes());
+    let _ = stream.write_all(body);
+}
+
+#[cfg(all(test, feature = "marketplace"))]
+mod tests {
+    use super::*;
+    use crate::fileio;
+    use crate::network;
+    use crate::errors::GeekToolsError;
+    use crate::plugins::settings::RetryPolicy;
+    use crate::plugins::{MarketplaceClient, PluginManager, ReportReason};
+    use tempfile::TempDir;
+
+    fn marketplace() -> MockMarketplace {
+        MockMarketplace::start(&[("hello-tools", "Hello Tools", "1.0.0"), ("disk-kit", "Disk Kit", "2.1.0")])
+    }
+
+    #[test]
+    fn test_list_and_search_plugins() {
+        let server = marketplace();
+        let client = MarketplaceClient::new(server.config()).unwrap();
+
+        let list = client.get_plugins(1, 20, None).unwrap();
+        assert_eq!(list.total, 2);
+        assert_eq!(list.plugins[1].version, "2.1.0");
+
+        let found = client.search_plugins("disk").unwrap();
+        assert_eq!(found.plugins.len(), 1);
+        assert_eq!(found.plugins[0].id, "disk-kit");
+
+        let requests = server.requests();
+        assert_eq!(requests[1].target, "/api/v1/plugins?search=disk");
+        assert!(requests.iter().all(|r| r.headers["accept"] == "application/vnd.geektools.v1+json"));
+    }
+
+    #[test]
+    fn test_retries_idempotent_requests_only() {
+        let server = marketplace();
+        let mut config = server.config();
+        config.retry = RetryPolicy { max_attempts: 3, base_delay_ms: 1, max_delay_ms: 5 };
+        let client = MarketplaceClient::new(config).unwrap();
+
+        server.fail_next(2);
+        assert_eq!(client.get_plugins(1, 20, None).unwrap().total, 2);
+        assert_eq!(server.requests().len(), 3);
+
+        // 重试次数用完时返回最后一次的错误
+        server.fail_next(3);
+        let error = client.search_plugins("disk").unwrap_err();
+        assert!(matches!(error, GeekToolsError::MarketplaceError { status: Some(502), .. }), "{:?}", error);
+        assert_eq!(server.requests().len(), 6);
+
+        // POST 收到响应后不重发
+        server.fail_next(1);
+        assert!(client.send_login_code("a@example.com").is_err());
+        assert_eq!(server.requests().len(), 7);
+    }
+
+    #[test]
+    fn test_retry_delay_is_jittered_and_capped() {
+        let policy = RetryPolicy { max_attempts: 5, base_delay_ms: 100, max_delay_ms: 300 };
+        for _ in 0..20 {
+            let first = policy.delay(1).as_millis();
+            assert!((50..=100).contains(&first), "{}", first);
+            let capped = policy.delay(4).as_millis();
+            assert!((150..=300).contains(&capped), "{}", capped);
+        }
+    }
+
+    #[test]
+    fn test_download_verifies_checksum() {
+        let server = marketplace();
+        let mut config = server.config();
+        config.retry = RetryPolicy { max_attempts: 2, base_delay_ms: 1, max_delay_ms: 5 };
+        let client = MarketplaceClient::new(config).unwrap();
+        let url = server.file_url("hello-tools");
+        let expected = package("hello-tools", "Hello Tools", "1.0.0");
+        let dir = TempDir::new().unwrap();
+        let dest = dir.path().join("hello-tools.tar.gz");
+
+        // 第一次收到截断的包，校验失败后重新下载
+        server.corrupt_next(1);
+        assert_eq!(client.download_plugin(&url, &dest, &mut |_, _| {}).unwrap(), expected.len() as u64);
+        assert_eq!(std::fs::read(&dest).unwrap(), expected);
+        std::fs::remove_file(&dest).unwrap();
+
+        server.corrupt_next(2);
+        let error = client.download_plugin(&url, &dest, &mut |_, _| {}).unwrap_err();
+        assert!(error.to_string().contains("SHA-256"), "{}", error);
+        assert!(!dest.exists());
+        assert_eq!(server.requests().iter().filter(|r| r.target.starts_with("/files/")).count(), 4);
+    }
+
+    #[test]
+    fn test_download_revalidates_cached_archive() {
+        let server = marketplace();
+        let dir = TempDir::new().unwrap();
+        let client = MarketplaceClient::new(server.config()).unwrap().with_cache_dir(dir.path().join("cache"));
+        let url = server.file_url("disk-kit");
+        let dest = dir.path().join("disk-kit.tar.gz");
+
+        client.download_plugin(&url, &dest, &mut |_, _| {}).unwrap();
+        std::fs::remove_file(&dest).unwrap();
+        client.download_plugin(&url, &dest, &mut |_, _| {}).unwrap();
+        assert_eq!(std::fs::read(&dest).unwrap(), package("disk-kit", "Disk Kit", "2.1.0"));
+
+        let requests = server.requests();
+        assert!(!requests[0].headers.contains_key("if-none-match"));
+        assert_eq!(requests[1].headers.get("if-none-match").map(|e| e.len()), Some(18));
+        assert!(dir.path().join("cache").read_dir().unwrap().next().is_some());
+    }
+
+    #[test]
+    fn test_download_resumes_partial_file() {
+        let server = marketplace();
+        let client = MarketplaceClient::new(server.config()).unwrap();
+        let plugin = client.search_plugins("hello").unwrap().plugins.remove(0);
+        let expected = package("hello-tools", "Hello Tools", "1.0.0");
+
+        let dir = TempDir::new().unwrap();
+        let dest = dir.path().join("hello-tools.tar.gz");
+        fileio::write_bytes(network::partial_path(&dest), &expected[..10]).unwrap();
+
+        let size = client.download_plugin(&plugin.file_url, &dest, &mut |_, _| {}).unwrap();
+        assert_eq!(size, expected.len() as u64);
+        assert_eq!(std::fs::read(&dest).unwrap(), expected);
+        let download = server.requests().into_iter().find(|r| r.target.starts_with("/files/")).unwrap();
+        assert_eq!(download.headers["range"], "bytes=10-");
+    }
+
+    #[test]
+    fn test_install_plugin_from_marketplace() {
+        let server = marketplace();
+        let client = MarketplaceClient::new(server.config()).unwrap();
+        let plugin = client.get_plugins(1, 20, None).unwrap().plugins.remove(0);
+
+        let dir = TempDir::new().unwrap();
+        let package_path = dir.path().join("download.tar.gz");
+        client.download_plugin(&plugin.file_url, &package_path, &mut |_, _| {}).unwrap();
+
+        let plugins_dir = dir.path().join("plugins");
+        let mut manager = PluginManager::open(plugins_dir.clone());
+        assert_eq!(manager.install_plugin(&package_path).unwrap(), "hello-tools");
+
+        let scripts = manager.get_enabled_scripts();
+        assert_eq!(scripts.len(), 1);
+        assert_eq!(scripts[0].0, "hello - Hello Tools");
+        assert!(scripts[0].2.starts_with(&plugins_dir));
+        assert_eq!(PluginManager::open(plugins_dir).list_installed_plugins().len(), 1);
+    }
+
+    #[test]
+    fn test_upload_plugin_with_api_key() {
+        let server = marketplace();
+        let dir = TempDir::new().unwrap();
+        let package_path = dir.path().join("new-plugin-1.0.0.tar.gz");
+        fileio::write_bytes(&package_path, &package("new-plugin", "New Plugin", "1.0.0")).unwrap();
+        assert!(MarketplaceClient::new(server.config()).unwrap().upload_plugin(&package_path, None).is_err());
+
+        let config = MarketplaceConfig { access_token: Some("gtk_ci_key".to_string()), ..server.config() };
+        let client = MarketplaceClient::new(config).unwrap();
+        let uploaded = client.upload_plugin(&package_path, Some("acme")).unwrap();
+        assert_eq!((uploaded.plugin_id.as_str(), uploaded.version.as_str()), ("new-plugin", "1.0.0"));
+
+        let request = server.requests().pop().unwrap();
+        assert_eq!(request.headers["authorization"], "Bearer gtk_ci_key");
+        assert!(request.headers["content-type"].starts_with("multipart/form-data; boundary=geektools-"));
+        assert!(request.body.contains("name=\"organization_id\"\r\n\r\nacme\r\n"));
+        assert!(request.body.contains("filename=\"new-plugin-1.0.0.tar.gz\""));
+
+        // 超过上限的包在读取前就被拒绝，不会发出请求
+        let oversized = dir.path().join("huge-1.0.0.tar.gz");
+        std::fs::File::create(&oversized).unwrap().set_len(50 * 1024 * 1024 + 1).unwrap();
+        let requests = server.requests().len();
+        assert!(client.upload_plugin(&oversized, None).is_err());
+        assert_eq!(server.requests().len(), requests);
+    }
+
+    #[test]
+    fn test_plugin_stats_without_login() {
+        let server = marketplace();
+        let client = MarketplaceClient::new(server.config()).unwrap();
+        let stats = client.get_plugin_stats("hello-tools").unwrap();
+        assert_eq!(stats.daily_counts(), [0, 7, 5]);
+        assert_eq!(stats.daily_downloads[2].date, "2024-06-03");
+        assert!(!server.requests()[0].headers.contains_key("authorization"));
+    }
+
+    #[test]
+    fn test_report_plugin() {
+        let server = marketplace();
+        let client = MarketplaceClient::new(server.config()).unwrap();
+        assert!(client.report_plugin("hello-tools", ReportReason::Malware, "").is_err());
+        assert!(server.requests().is_empty());
+
+        let config = MarketplaceConfig { access_token: Some("token".to_string()), ..server.config() };
+        let client = MarketplaceClient::new(config).unwrap();
+        client.report_plugin("hello-tools", ReportReason::Malware, "uploads ~/.ssh").unwrap();
+        let request = server.requests().pop().unwrap();
+        assert_eq!((request.method.as_str(), request.target.as_str()), ("POST", "/api/v1/plugins/hello-tools/report"));
+        let body: Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(body, json!({"reason": "malware", "details": "uploads ~/.ssh"}));
+
+        assert!(client.report_plugin("missing", ReportReason::Other, "").is_err());
+    }
+
+    #[test]
+    fn test_upgrade_with_changelog_and_rollback() {
+        let server = marketplace();
+        let client = MarketplaceClient::new(server.config()).unwrap();
+        let dir = TempDir::new().unwrap();
+        let plugins_dir = dir.path().join("plugins");
+        let mut manager = PluginManager::open(plugins_dir.clone());
+
+        let old = dir.path().join("old.tar.gz");
+        fileio::write_bytes(&old, &package("hello-tools", "Hello Tools", "0.9.0")).unwrap();
+        manager.install_plugin(&old).unwrap();
+        manager.toggle_plugin("hello-tools", false).unwrap();
+
+        let changelog = client.get_changelog("hello-tools", "0.9.0").unwrap();
+        assert_eq!(changelog.len(), 1);
+        assert_eq!(changelog[0].notes, "changes in 1.0.0");
+        assert!(client.get_changelog("hello-tools", "1.0.0").unwrap().is_empty());
+        assert!(server.requests().iter().any(|r| r.target == "/api/v1/plugins/hello-tools/changelog?since=0.9.0"));
+
+        let new = dir.path().join("new.tar.gz");
+        client.download_plugin(&server.file_url("hello-tools"), &new, &mut |_, _| {}).unwrap();
+        assert_eq!(manager.upgrade_plugin(&new).unwrap(), "0.9.0");
+        assert!(manager.upgrade_plugin(&new).is_err());
+
+        let reopened = PluginManager::open(plugins_dir.clone());
+        let plugin = reopened.list_installed_plugins()[0];
+        assert_eq!(plugin.info.version, "1.0.0");
+        assert_eq!(plugin.previous_version.as_deref(), Some("0.9.0"));
+        assert!(!plugin.enabled);
+
+        // 回滚只用缓存的插件包，删掉下载的文件也不影响
+        std::fs::remove_file(&old).unwrap();
+        std::fs::remove_file(&new).unwrap();
+        let mut manager = PluginManager::open(plugins_dir);
+        assert_eq!(manager.rollback_plugin("hello-tools").unwrap(), "0.9.0");
+        let plugin = manager.list_installed_plugins()[0];
+        assert_eq!(plugin.info.version, "0.9.0");
+        assert_eq!(plugin.previous_version.as_deref(), Some("1.0.0"));
+        assert!(plugin.install_path.join("scripts/hello.sh").exists());
+    }
+}
@@ -0,0 +1,150 @@
+use super::marketplace::MarketplacePlugin;
+use crate::errors::{GeekToolsError, Result};
+use crate::network;
+use crate::signature::{self, TrustedKey, Verification};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// 当前支持的离线索引格式版本
+pub const FORMAT_VERSION: u32 = 1;
+
+/// 插件市场导出的离线索引：审核通过的插件列表，旁边放 `<索引>.minisig` 签名和各插件包
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OfflineIndex {
+    pub format: u32,
+    pub generated_at: String,
+    pub plugins: Vec<OfflinePlugin>,
+}
+
+/// 离线索引中的一个插件：市场列表中的信息，加上插件包的文件名和 SHA-256
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OfflinePlugin {
+    #[serde(flatten)]
+    pub plugin: MarketplacePlugin,
+    pub file_name: String,
+    pub sha256: String,
+}
+
+/// 已通过签名校验的离线索引
+#[derive(Debug)]
+pub struct LoadedIndex {
+    pub index: OfflineIndex,
+    /// 签名者 (信任公钥的名称和可信注释)
+    pub signer: String,
+    /// 索引所在目录，插件包与索引放在一起
+    pub dir: PathBuf,
+}
+
+/// 签名文件：`<索引>.minisig`
+pub fn signature_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".minisig");
+    PathBuf::from(name)
+}
+
+/// 读取离线索引 (U 盘等)，必须带有信任公钥 (`signatures.trusted_keys`) 的有效签名
+pub fn load(path: &Path, keys: &[TrustedKey]) -> Result<LoadedIndex> {
+    let content = read_bytes(path)?;
+    let signature_path = signature_path(path);
+    if !signature_path.is_file() {
+        return Err(invalid(format!("{} not found, the offline index must be signed", signature_path.display())));
+    }
+    let signer = match signature::verify(&content, &read_bytes(&signature_path)?, keys) {
+        Verification::Verified(signer) => signer,
+        Verification::Unsigned => return Err(invalid("the offline index is not signed".to_string())),
+        Verification::Invalid(reason) => return Err(invalid(format!("invalid signature: {}", reason))),
+    };
+    let index: OfflineIndex = serde_json::from_slice(&content)?;
+    if index.format != FORMAT_VERSION {
+        return Err(invalid(format!("unsupported offline index format {}", index.format)));
+    }
+    let dir = path.parent().unwrap_or(Path::new(".")).to_path_buf();
+    Ok(LoadedIndex { index, signer, dir })
+}
+
+impl LoadedIndex {
+    /// 插件包的位置：索引目录下的同名文件，文件名中的目录部分被忽略
+    pub fn archive_path(&self, plugin: &OfflinePlugin) -> PathBuf {
+        let name = Path::new(&plugin.file_name).file_name().unwrap_or_default();
+        self.dir.join(name)
+    }
+
+    /// 找到插件包并核对 SHA-256，返回可以安装的路径
+    pub fn verify_archive(&self, plugin: &OfflinePlugin) -> Result<PathBuf> {
+        let path = self.archive_path(plugin);
+        if !path.is_file() {
+            return Err(invalid(format!("{} not found next to the offline index", path.display())));
+        }
+        let actual = network::sha256_file(&path)?;
+        if !network::checksum_matches(&actual, &plugin.sha256) {
+            return Err(invalid(format!("{}: SHA-256 mismatch (expected {}, got {})", path.display(), plugin.sha256, actual)));
+        }
+        Ok(path)
+    }
+}
+
+fn read_bytes(path: &Path) -> Result<Vec<u8>> {
+    std::fs::read(path).map_err(|e| GeekToolsError::FileOperationError {
+        path: path.display().to_string(),
+        source: e,
+    })
+}
+
+fn invalid(message: String) -> GeekToolsError {
+    GeekToolsError::ValidationError {
+        field: "offline_index".to_string(),
+        message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fileio;
+    use crate::plugins::mock_marketplace::package;
+    use crate::signature::tests::minisign;
+    use tempfile::TempDir;
+
+    fn index_json(sha256: &str) -> String {
+        serde_json::json!({
+            "format": 1,
+            "generated_at": "2024-06-01T00:00:00Z",
+            "plugins": [{
+                "id": "hello-tools", "name": "Hello Tools", "current_version": "1.0.0",
+                "description": "", "author": "tester", "downloads": 3, "rating": 4.0,
+                "created_at": "2024-01-01T00:00:00Z", "updated_at": "2024-01-01T00:00:00Z", "tags": [],
+                "file_name": "../hello-tools-1.0.0.tar.gz", "sha256": sha256
+            }]
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_load_signed_index_and_verify_archives() {
+        let dir = TempDir::new().unwrap();
+        let archive = package("hello-tools", "Hello Tools", "1.0.0");
+        fileio::write_bytes(dir.path().join("hello-tools-1.0.0.tar.gz"), &archive).unwrap();
+        let sha256 = network::sha256_file(&dir.path().join("hello-tools-1.0.0.tar.gz")).unwrap();
+        let path = dir.path().join("plugin-index.json");
+        let content = index_json(&sha256);
+        fileio::write(&path, &content).unwrap();
+
+        let (key, sig) = minisign(content.as_bytes(), b"ED", "plugin-index");
+        let keys = std::slice::from_ref(&key);
+        assert!(load(&path, keys).unwrap_err().to_string().contains("must be signed"));
+
+        fileio::write(signature_path(&path), &sig).unwrap();
+        let loaded = load(&path, keys).unwrap();
+        assert_eq!(loaded.signer, "Example Maintainers (plugin-index)");
+        let plugin = &loaded.index.plugins[0];
+        assert_eq!(plugin.plugin.version, "1.0.0");
+        assert_eq!(loaded.verify_archive(plugin).unwrap(), dir.path().join("hello-tools-1.0.0.tar.gz"));
+
+        let tampered = OfflinePlugin { sha256: "0".repeat(64), ..plugin.clone() };
+        assert!(loaded.verify_archive(&tampered).unwrap_err().to_string().contains("SHA-256 mismatch"));
+
+        // 索引内容被改动后签名失效
+        fileio::write(&path, &content.replace("Hello Tools", "Evil Tools")).unwrap();
+        assert!(load(&path, keys).unwrap_err().to_string().contains("invalid signature"));
+    }
+}
@@ -0,0 +1,147 @@
+use super::{PluginInfo, ScriptEntry};
+use crate::errors::{GeekToolsError, Result};
+use crate::fileio;
+use std::path::{Path, PathBuf};
+
+/// 新插件的初始版本
+const INITIAL_VERSION: &str = "0.1.0";
+
+/// 示例脚本：带 geektools 元数据头部，启用 `lang/` 时按 `$LANG` 读取对应语言的文字
+fn example_script(name: &str, with_lang: bool) -> String {
+    let mut script = format!(
+        "#!/usr/bin/env bash\n\
+         # Name: {}\n\
+         # Description: Example script of the {} plugin\n\
+         # Description[zh]: {} 插件的示例脚本\n\
+         # Version: {}\n\
+         # Param: TARGET=world - 问候的对象\n\
+         set -euo pipefail\n\n",
+        name, name, name, INITIAL_VERSION
+    );
+    if with_lang {
+        script.push_str(
+            "# 插件根目录下的 lang/<语言>.sh 定义界面文字，系统语言为中文时使用 zh\n\
+             PLUGIN_DIR=\"$(cd \"$(dirname \"$0\")/..\" && pwd)\"\n\
+             case \"${LANG:-}\" in zh*) GT_PLUGIN_LANG=zh ;; *) GT_PLUGIN_LANG=en ;; esac\n\
+             . \"$PLUGIN_DIR/lang/$GT_PLUGIN_LANG.sh\"\n\n\
+             echo \"$MSG_HELLO ${1:-world}\"\n",
+        );
+    } else {
+        script.push_str("echo \"Hello, ${1:-world}\"\n");
+    }
+    script.push_str("# TODO: 在这里编写插件逻辑\n");
+    script
+}
+
+/// 打包脚本：在插件目录中运行，生成 <ID>-<版本>.tar.gz
+fn pack_script(with_lang: bool) -> String {
+    format!(
+        "#!/bin/sh\n\
+         # 打包插件: sh pack.sh，生成的 .tar.gz 可在 geektools 的插件管理中安装\n\
+         set -e\n\
+         cd \"$(dirname \"$0\")\"\n\
+         id=$(sed -n 's/^ *\"id\": *\"\\(.*\\)\",*$/\\1/p' info.json)\n\
+         version=$(sed -n 's/^ *\"version\": *\"\\(.*\\)\",*$/\\1/p' info.json)\n\
+         tar -czf \"$id-$version.tar.gz\" info.json scripts{}\n\
+         echo \"$id-$version.tar.gz\"\n",
+        if with_lang { " lang" } else { "" }
+    )
+}
+
+/// 由插件名称生成 ID：小写字母、数字和短横线
+pub fn plugin_id(name: &str) -> String {
+    let mut id = String::new();
+    for c in name.trim().chars() {
+        if c.is_ascii_alphanumeric() {
+            id.push(c.to_ascii_lowercase());
+        } else if !id.is_empty() && !id.ends_with('-') {
+            id.push('-');
+        }
+    }
+    id.trim_end_matches('-').to_string()
+}
+
+/// 在 `parent` 下创建名为插件 ID 的目录，写入 info.json、带元数据头部的示例脚本、
+/// 打包脚本 pack.sh，以及可选的 lang/en.sh 和 lang/zh.sh；返回插件目录
+pub fn create(parent: &Path, name: &str, with_lang: bool) -> Result<PathBuf> {
+    let id = plugin_id(name);
+    if id.is_empty() {
+        return Err(GeekToolsError::ValidationError {
+            field: "name".to_string(),
+            message: format!("Plugin name must contain letters or digits: {}", name),
+        });
+    }
+    let dir = parent.join(&id);
+    if dir.exists() {
+        return Err(GeekToolsError::ValidationError {
+            field: "name".to_string(),
+            message: format!("{} already exists", dir.display()),
+        });
+    }
+
+    let info = PluginInfo {
+        id: id.clone(),
+        name: name.trim().to_string(),
+        version: INITIAL_VERSION.to_string(),
+        description: format!("{} plugin", name.trim()),
+        author: std::env::var("USER").unwrap_or_default(),
+        scripts: vec![ScriptEntry {
+            name: "hello".to_string(),
+            file: "hello.sh".to_string(),
+            description: "Example script".to_string(),
+            executable: true,
+            interpreter: None,
+            working_dir: None,
+            keep_artifacts: false,
+        }],
+        dependencies: Vec::new(),
+        tags: Vec::new(),
+        min_geektools_version: Some(env!("CARGO_PKG_VERSION").to_string()),
+    };
+    fileio::write(dir.join("info.json"), &serde_json::to_string_pretty(&info)?)?;
+
+    let script = dir.join("scripts").join("hello.sh");
+    fileio::write(&script, &example_script(&info.name, with_lang))?;
+    fileio::set_executable(&script)?;
+    if with_lang {
+        fileio::write(dir.join("lang").join("en.sh"), "MSG_HELLO=\"Hello,\"\n")?;
+        fileio::write(dir.join("lang").join("zh.sh"), "MSG_HELLO=\"你好，\"\n")?;
+    }
+    let pack = dir.join("pack.sh");
+    fileio::write(&pack, &pack_script(with_lang))?;
+    fileio::set_executable(&pack)?;
+    Ok(dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugins::PluginManager;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_plugin_id() {
+        assert_eq!(plugin_id("My Cool Plugin!"), "my-cool-plugin");
+        assert_eq!(plugin_id("  disk_check "), "disk-check");
+        assert_eq!(plugin_id("插件"), "");
+    }
+
+    #[test]
+    fn test_scaffold_packs_and_installs() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = create(temp_dir.path(), "Disk Tools", true).unwrap();
+        assert!(dir.ends_with("disk-tools"));
+        assert!(create(temp_dir.path(), "disk tools", false).is_err());
+
+        let output = Command::new("sh").arg(dir.join("pack.sh")).output().unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "disk-tools-0.1.0.tar.gz");
+
+        let mut manager = PluginManager::open(temp_dir.path().join("plugins"));
+        assert_eq!(manager.install_plugin(&dir.join("disk-tools-0.1.0.tar.gz")).unwrap(), "disk-tools");
+        let (_, _, script, _) = manager.get_enabled_scripts().remove(0);
+        let output = Command::new(&script).arg("geek").env("LANG", "zh_CN.UTF-8").output().unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "你好， geek\n");
+    }
+}
@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "marketplace")]
+use std::time::Duration;
+
+/// 插件包缓存 (`plugins.archives`)：安装过的 .tar.gz 保存在 <插件目录>/_archives/，
+/// 超过上限时先删除最早缓存的，已安装版本和可回滚版本的插件包始终保留
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ArchiveConfig {
+    /// 最多保留的插件包数量
+    pub max_count: usize,
+    /// 插件包的总大小上限 (MB)
+    pub max_size_mb: u64,
+}
+
+impl Default for ArchiveConfig {
+    fn default() -> Self {
+        Self { max_count: 20, max_size_mb: 200 }
+    }
+}
+
+/// 插件市场配置  
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MarketplaceConfig {
+    pub api_url: String,
+    pub api_port: u16,
+    #[serde(default)]
+    pub timeout_seconds: u64,
+    /// 登录后保存的访问令牌
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub access_token: Option<String>,
+    /// 当前登录的邮箱
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_email: Option<String>,
+    /// 固定的服务器证书 SHA-256 指纹，用于自签名证书的自建市场
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pinned_cert_sha256: Option<String>,
+    #[serde(default)]
+    pub retry: RetryPolicy,
+    /// 上次使用的离线索引文件，离线安装时作为默认路径
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offline_index: Option<String>,
+}
+
+impl Default for MarketplaceConfig {
+    fn default() -> Self {
+        Self {
+            api_url: "https://market-api.yshsr.org".to_string(),
+            api_port: 443,
+            timeout_seconds: 30,
+            access_token: None,
+            account_email: None,
+            pinned_cert_sha256: None,
+            retry: RetryPolicy::default(),
+            offline_index: None,
+        }
+    }
+}
+
+impl MarketplaceConfig {
+    /// API 端点的完整地址，如 `endpoint_url("/health")`
+    #[cfg(feature = "marketplace")]
+    pub fn endpoint_url(&self, endpoint: &str) -> String {
+        format!("{}:{}/api/v1{}", self.api_url, self.api_port, endpoint)
+    }
+}
+
+/// 市场请求的重试策略：幂等请求 (GET 等) 在网络错误和 429/502/503/504 时按带抖动的指数退避重试；
+/// POST/PATCH 只在连接未建立时重试，避免重复提交
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// 最多尝试次数 (含第一次)，1 表示不重试
+    pub max_attempts: u32,
+    /// 第一次重试前的基准等待时间，之后每次翻倍
+    pub base_delay_ms: u64,
+    pub max_delay_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self { max_attempts: 3, base_delay_ms: 500, max_delay_ms: 8000 }
+    }
+}
+
+impl RetryPolicy {
+    /// 第 `attempt` 次失败后的等待时间：基准 × 2^(attempt-1)，不超过上限，再在其一半到全部之间随机取值，
+    /// 避免大量客户端在服务恢复时同时重试
+    #[cfg(feature = "marketplace")]
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exp = self.base_delay_ms.saturating_mul(1u64 << attempt.saturating_sub(1).min(20));
+        let cap = exp.min(self.max_delay_ms);
+        Duration::from_millis(cap / 2 + rand::random_range(0..=cap - cap / 2))
+    }
+}
+
+/// 本地插件扫描配置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LocalScanConfig {
+    /// 扫描目录，支持 `~/` 前缀；为空时使用默认目录
+    #[serde(default)]
+    pub directories: Vec<String>,
+    /// 递归深度，0 表示只扫描目录本身
+    #[serde(default)]
+    pub max_depth: usize,
+    /// 忽略的文件/目录名模式，支持 `*` 与 `?` 通配符
+    #[serde(default = "default_ignore_patterns")]
+    pub ignore_patterns: Vec<String>,
+}
+
+fn default_ignore_patterns() -> Vec<String> {
+    vec![".*".to_string(), "node_modules".to_string(), "target".to_string()]
+}
+
+impl Default for LocalScanConfig {
+    fn default() -> Self {
+        Self {
+            directories: Vec::new(),
+            max_depth: 0,
+            ignore_patterns: default_ignore_patterns(),
+        }
+    }
+}
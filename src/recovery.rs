@@ -39,7 +39,7 @@ where
             Err(e) if attempt == config.max_attempts => return Err(e),
             Err(e) if !e.is_recoverable() => return Err(e),
             Err(_) => {
-                log::info!("Attempt {} failed, retrying in {:?}", attempt, delay);
+                tracing::info!("Attempt {} failed, retrying in {:?}", attempt, delay);
                 thread::sleep(delay);
                 delay = std::cmp::min(
                     Duration::from_millis((delay.as_millis() as f64 * config.backoff_factor) as u64),
@@ -80,6 +80,7 @@ impl RecoveryHandler {
                     _ => RecoveryAction::ShowSuggestions(error.recovery_suggestions(self.user_lang))
                 }
             }
+            #[cfg(feature = "network")]
             GeekToolsError::NetworkError { .. } => {
                 RecoveryAction::RetryWithBackoff(self.config.clone())
             }
@@ -118,19 +119,19 @@ where
                 match recovery_handler.handle_error(&error) {
                     RecoveryAction::Retry => {
                         recovery_attempts += 1;
-                        log::info!("Attempting recovery, attempt {}/{}", recovery_attempts, max_recovery_attempts);
+                        tracing::info!("Attempting recovery, attempt {}/{}", recovery_attempts, max_recovery_attempts);
                         continue;
                     }
                     RecoveryAction::RetryWithBackoff(config) => {
                         recovery_attempts += 1;
-                        log::info!("Attempting recovery with backoff, attempt {}/{}", recovery_attempts, max_recovery_attempts);
+                        tracing::info!("Attempting recovery with backoff, attempt {}/{}", recovery_attempts, max_recovery_attempts);
                         
                         return retry_with_backoff(operation, &config);
                     }
                     RecoveryAction::ShowSuggestions(suggestions) => {
-                        log::warn!("Recovery suggestions for error: {}", error);
+                        tracing::warn!("Recovery suggestions for error: {}", error);
                         for suggestion in suggestions {
-                            log::warn!("  - {}", suggestion);
+                            tracing::warn!("  - {}", suggestion);
                         }
                         return Err(error);
                     }
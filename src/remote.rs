@@ -0,0 +1,245 @@
+use crate::executor::{self, RunOptions, Verbosity};
+use crate::fileio;
+use crate::runtime::Language;
+use crate::ssh::{self, HostAuth};
+use crate::ui::layout::{self, Align};
+use crate::log_only;
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Instant;
+
+/// 校验 SSH 目标 (`user@host` 或 ~/.ssh/config 中的别名)，避免被当成 ssh 选项
+pub fn is_valid_host(host: &str) -> bool {
+    !host.is_empty()
+        && !host.starts_with('-')
+        && host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '@' | '.' | '_' | '-' | ':' | '[' | ']'))
+}
+
+/// 要连接的主机及其认证方式
+#[derive(Debug, Clone)]
+pub struct Target {
+    pub host: String,
+    pub auth: HostAuth,
+    /// 密码登录时运行前输入的密码
+    pub password: Option<String>,
+}
+
+impl Target {
+    pub fn new(host: &str, auth: HostAuth) -> Self {
+        Self { host: host.to_string(), auth, password: None }
+    }
+}
+
+/// 单台主机的运行结果
+#[derive(Debug, Clone)]
+pub struct HostResult {
+    pub host: String,
+    pub success: bool,
+    pub exit_code: Option<i32>,
+    pub duration_seconds: f64,
+    /// 无法启动 ssh 等本地错误
+    pub error: Option<String>,
+}
+
+/// 远程解释器：脚本内容通过 stdin 传入 (`sh -s`、`python3 -`)
+fn remote_interpreter(script: &Path, options: &RunOptions) -> Vec<String> {
+    let mut interpreter = executor::resolve_interpreter(script, options);
+    let stdin_flag = match Language::from_interpreter(&interpreter) {
+        Some(Language::Python | Language::Node) => "-",
+        _ => "-s",
+    };
+    interpreter.push(stdin_flag.to_string());
+    interpreter
+}
+
+/// 构造 `ssh <托管 known_hosts 和认证参数> -- host <解释器>`；除密码登录外不会停下来询问
+pub fn ssh_command(target: &Target, interpreter: &[String]) -> Command {
+    let mut cmd = Command::new("ssh");
+    cmd.args(ssh::ssh_args(&target.auth)).args(["--", &target.host]).args(interpreter);
+    cmd
+}
+
+/// 在所有主机上并发运行脚本 (依次运行 scripts，某个失败即停止)，结果顺序与 targets 一致
+pub fn fan_out(targets: &[Target], scripts: &[PathBuf], options: &RunOptions) -> Vec<HostResult> {
+    thread::scope(|s| {
+        let handles: Vec<_> = targets
+            .iter()
+            .map(|target| s.spawn(move || run_on_host(target, scripts, options)))
+            .collect();
+        handles
+            .into_iter()
+            .zip(targets)
+            .map(|(handle, target)| {
+                handle.join().unwrap_or_else(|_| HostResult {
+                    host: target.host.clone(),
+                    success: false,
+                    exit_code: None,
+                    duration_seconds: 0.0,
+                    error: Some("worker thread panicked".to_string()),
+                })
+            })
+            .collect()
+    })
+}
+
+fn run_on_host(target: &Target, scripts: &[PathBuf], options: &RunOptions) -> HostResult {
+    let host = target.host.as_str();
+    let start = Instant::now();
+    let mut result = HostResult {
+        host: host.to_string(),
+        success: true,
+        exit_code: Some(0),
+        duration_seconds: 0.0,
+        error: None,
+    };
+    for script in scripts {
+        match run_script_on_host(target, script, options) {
+            Ok(status) => {
+                result.success = status.success();
+                result.exit_code = status.code();
+            }
+            Err(e) => {
+                result.success = false;
+                result.exit_code = None;
+                result.error = Some(e.to_string());
+            }
+        }
+        if !result.success {
+            break;
+        }
+    }
+    result.duration_seconds = start.elapsed().as_secs_f64();
+    log_only!(
+        "INFO",
+        "REMOTE",
+        "{}: success={} exit_code={:?} error={:?}",
+        host,
+        result.success,
+        result.exit_code,
+        result.error
+    );
+    result
+}
+
+fn run_script_on_host(target: &Target, script: &Path, options: &RunOptions) -> io::Result<std::process::ExitStatus> {
+    let host = target.host.as_str();
+    let content = fileio::read(script).map_err(|e| io::Error::other(e.to_string()))?;
+    let mut cmd = ssh_command(target, &remote_interpreter(script, options));
+    // askpass 服务需要存活到 ssh 退出
+    #[cfg(unix)]
+    let _askpass = target.password.as_deref().map(|password| ssh::set_password(&mut cmd, password)).transpose()?;
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let stdin = child.stdin.take();
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let quiet = options.verbosity == Verbosity::Quiet;
+    thread::scope(|s| {
+        if let Some(mut stdin) = stdin {
+            s.spawn(move || {
+                let _ = stdin.write_all(content.as_bytes());
+            });
+        }
+        if let Some(out) = stdout {
+            s.spawn(move || forward_lines(out, host, quiet, false));
+        }
+        if let Some(err) = stderr {
+            s.spawn(move || forward_lines(err, host, quiet, true));
+        }
+    });
+    child.wait()
+}
+
+/// 逐行转发远程输出，行首加上主机名
+fn forward_lines<R: Read>(reader: R, host: &str, quiet: bool, to_stderr: bool) {
+    for line in BufReader::new(reader).lines().map_while(Result::ok) {
+        if quiet {
+            continue;
+        }
+        if to_stderr {
+            eprintln!("[{}] {}", host, line);
+        } else {
+            println!("[{}] {}", host, line);
+        }
+    }
+}
+
+/// 汇总表：每台主机一行，最后一行为成功/失败数
+pub fn render_summary(results: &[HostResult]) -> String {
+    let width = results.iter().map(|r| layout::width(&r.host)).max().unwrap_or(0);
+    let mut out = String::new();
+    for r in results {
+        let status = if r.success { "✅" } else { "❌" };
+        let detail = match (&r.error, r.exit_code) {
+            (Some(error), _) => error.clone(),
+            (None, Some(code)) => format!("exit {}", code),
+            (None, None) => "killed by signal".to_string(),
+        };
+        let host = layout::pad(&r.host, width, Align::Left);
+        let _ = writeln!(out, "{} {}  {:>7.1}s  {}", status, host, r.duration_seconds, detail);
+    }
+    let failed = results.iter().filter(|r| !r.success).count();
+    let _ = writeln!(out, "{} succeeded, {} failed", results.len() - failed, failed);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsStr;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_is_valid_host() {
+        assert!(is_valid_host("deploy@web-1.example.com"));
+        assert!(is_valid_host("db2"));
+        assert!(!is_valid_host(""));
+        assert!(!is_valid_host("-oProxyCommand=sh"));
+        assert!(!is_valid_host("web1; rm -rf /"));
+    }
+
+    #[test]
+    fn test_ssh_command_reads_script_from_stdin() {
+        let dir = TempDir::new().unwrap();
+        let script = dir.path().join("a.py");
+        std::fs::write(&script, "print(1)\n").unwrap();
+
+        let target = Target::new("web1", HostAuth::default());
+        let cmd = ssh_command(&target, &remote_interpreter(&script, &RunOptions::default()));
+        assert_eq!(cmd.get_program(), "ssh");
+        let args: Vec<&OsStr> = cmd.get_args().collect();
+        assert_eq!(args[args.len() - 4..], ["--", "web1", "python3", "-"]);
+        assert!(args.contains(&OsStr::new("BatchMode=yes")));
+        assert!(args.contains(&OsStr::new("StrictHostKeyChecking=yes")));
+    }
+
+    #[test]
+    fn test_render_summary_counts_failures() {
+        let ok = HostResult {
+            host: "web1".to_string(),
+            success: true,
+            exit_code: Some(0),
+            duration_seconds: 1.2,
+            error: None,
+        };
+        let failed = HostResult {
+            host: "database".to_string(),
+            success: false,
+            exit_code: Some(3),
+            ..ok.clone()
+        };
+        let summary = render_summary(&[ok, failed]);
+        assert!(summary.contains("❌ database"));
+        assert!(summary.contains("exit 3"));
+        assert!(summary.ends_with("1 succeeded, 1 failed\n"));
+    }
+}
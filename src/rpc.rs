@@ -0,0 +1,245 @@
+use crate::batch::Action;
+use crate::errors::Result;
+use crate::log_only;
+use serde_json::{json, Value};
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::sync::Arc;
+use std::thread;
+
+/// JSON-RPC 2.0 的标准错误码
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// 调用本身失败 (脚本退出码非零、安装失败等)，`error.data.code` 是 GeekToolsError 的错误码
+const CALL_FAILED: i64 = -32000;
+
+/// 处理调用的函数，由 main 提供
+type Handler = dyn Fn(&Call) -> Result<Value> + Send + Sync;
+
+/// 控制接口的调用
+///
+/// ```text
+/// {"jsonrpc": "2.0", "id": 1, "method": "list"}
+/// {"jsonrpc": "2.0", "id": 2, "method": "run", "params": {"script": "cleanup.sh", "params": {"DAYS": "3"}}}
+/// {"jsonrpc": "2.0", "id": 3, "method": "install", "params": {"plugin": "https://…/tools.tar.gz", "sha256": "…"}}
+/// {"jsonrpc": "2.0", "id": 4, "method": "install", "params": {"package": "curl"}}
+/// ```
+#[derive(Debug, PartialEq)]
+pub enum Call {
+    /// `list`：可运行的内置、自定义和插件脚本
+    List,
+    /// `run` 和 `install`：与 batch 命令文件中的同名动作相同
+    Action(Action),
+}
+
+/// 处理一行请求，返回一行响应；通知 (没有 `id` 的请求) 照常执行但不回应
+pub fn handle_line(line: &str, handler: &Handler) -> Option<String> {
+    let request: Value = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) => return Some(response(&Value::Null, Err(error(PARSE_ERROR, &e.to_string(), None)))),
+    };
+    let id = request.get("id").cloned();
+    let reply = |outcome| id.as_ref().map(|id| response(id, outcome));
+    let Some(method) = request.get("method").and_then(Value::as_str).filter(|_| request["jsonrpc"] == "2.0") else {
+        let id = id.unwrap_or(Value::Null);
+        return Some(response(&id, Err(error(INVALID_REQUEST, "expected a JSON-RPC 2.0 request object", None))));
+    };
+    let call = match parse_call(method, request.get("params").unwrap_or(&Value::Null)) {
+        Ok(call) => call,
+        Err((code, message)) => return reply(Err(error(code, &message, None))),
+    };
+    reply(handler(&call).map_err(|e| error(CALL_FAILED, &e.to_string(), Some(json!({ "code": e.code() })))))
+}
+
+fn parse_call(method: &str, params: &Value) -> std::result::Result<Call, (i64, String)> {
+    let string = |key: &str| params.get(key).and_then(Value::as_str).map(str::to_string);
+    match method {
+        "list" => Ok(Call::List),
+        "run" => {
+            let script = string("script").ok_or((INVALID_PARAMS, "run requires params.script".to_string()))?;
+            let params = match params.get("params") {
+                None | Some(Value::Null) => Vec::new(),
+                // 数字和布尔值按 JSON 文本传给脚本
+                Some(Value::Object(map)) => map
+                    .iter()
+                    .map(|(name, value)| (name.clone(), value.as_str().map_or_else(|| value.to_string(), str::to_string)))
+                    .collect(),
+                Some(_) => return Err((INVALID_PARAMS, "params.params must be an object".to_string())),
+            };
+            Ok(Call::Action(Action::Run { script, params }))
+        }
+        "install" => match (string("plugin"), string("package")) {
+            (Some(source), None) => Ok(Call::Action(Action::InstallPlugin { source, sha256: string("sha256") })),
+            (None, Some(name)) => Ok(Call::Action(Action::InstallPackage { name })),
+            _ => Err((INVALID_PARAMS, "install requires either params.plugin or params.package".to_string())),
+        },
+        other => Err((METHOD_NOT_FOUND, format!("unknown method: {}", other))),
+    }
+}
+
+fn error(code: i64, message: &str, data: Option<Value>) -> Value {
+    let mut error = json!({ "code": code, "message": message });
+    if let Some(data) = data {
+        error["data"] = data;
+    }
+    error
+}
+
+fn response(id: &Value, outcome: std::result::Result<Value, Value>) -> String {
+    match outcome {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+    }
+    .to_string()
+}
+
+/// 在 unix socket 上提供控制接口直到进程退出：每行一个请求，每个请求回一行响应，每个连接一个线程
+pub fn serve(path: &Path, handler: impl Fn(&Call) -> Result<Value> + Send + Sync + 'static) -> io::Result<()> {
+    let listener = bind(path)?;
+    let handler: Arc<Handler> = Arc::new(handler);
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let handler = handler.clone();
+                thread::spawn(move || {
+                    if let Err(e) = handle_connection(stream, handler.as_ref()) {
+                        log_only!("WARN", "RPC", "连接中断: {}", e);
+                    }
+                });
+            }
+            Err(e) => log_only!("WARN", "RPC", "接受连接失败: {}", e),
+        }
+    }
+    Ok(())
+}
+
+/// 绑定 socket 并只允许当前用户连接 (能连上就能运行脚本)。
+/// 上次未正常退出留下的 socket 文件会被替换；已有服务在监听或路径不是 socket 时报错
+fn bind(path: &Path) -> io::Result<UnixListener> {
+    if let Ok(metadata) = fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} exists and is not a socket", path.display())));
+        }
+        if UnixStream::connect(path).is_ok() {
+            return Err(io::Error::new(io::ErrorKind::AddrInUse, format!("{} is already being served", path.display())));
+        }
+        fs::remove_file(path)?;
+    }
+    // 先在同目录下新建的 0700 私有目录中绑定并 chmod 0600，再改名到目标路径：
+    // 其他用户进不了私有目录，在 chmod 之前也连不上；umask 是进程级的，不能临时修改
+    let parent = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let staging = tempfile::Builder::new().prefix(".geektools-rpc-").tempdir_in(parent)?;
+    let staged = staging.path().join("socket");
+    let listener = UnixListener::bind(&staged)?;
+    fs::set_permissions(&staged, fs::Permissions::from_mode(0o600))?;
+    fs::rename(&staged, path)?;
+    Ok(listener)
+}
+
+fn handle_connection(stream: UnixStream, handler: &Handler) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = handle_line(&line, handler) {
+            writeln!(writer, "{}", response)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::GeekToolsError;
+    use tempfile::TempDir;
+
+    fn handler(call: &Call) -> Result<Value> {
+        match call {
+            Call::List => Ok(json!({ "scripts": [{ "name": "a.sh" }] })),
+            Call::Action(Action::Run { script, .. }) if script == "fail.sh" => Err(GeekToolsError::ScriptExecutionError {
+                script_name: script.clone(),
+                exit_code: Some(1),
+                source: io::Error::other("exit 1"),
+            }),
+            Call::Action(action) => Ok(json!({ "action": action.name(), "target": action.target() })),
+        }
+    }
+
+    fn call(line: &str) -> Value {
+        serde_json::from_str(&handle_line(line, &handler).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_parse_calls() {
+        let run = parse_call("run", &json!({ "script": "x.sh", "params": { "DAYS": 3, "NAME": "a b" } })).unwrap();
+        assert_eq!(
+            run,
+            Call::Action(Action::Run {
+                script: "x.sh".to_string(),
+                params: vec![("DAYS".to_string(), "3".to_string()), ("NAME".to_string(), "a b".to_string())],
+            })
+        );
+        let install = parse_call("install", &json!({ "plugin": "p.tar.gz", "sha256": "ab" })).unwrap();
+        assert_eq!(install, Call::Action(Action::InstallPlugin { source: "p.tar.gz".to_string(), sha256: Some("ab".to_string()) }));
+        assert_eq!(parse_call("install", &json!({ "package": "curl" })).unwrap(), Call::Action(Action::InstallPackage { name: "curl".to_string() }));
+        assert_eq!(parse_call("install", &json!({ "plugin": "p", "package": "curl" })).unwrap_err().0, INVALID_PARAMS);
+        assert_eq!(parse_call("run", &Value::Null).unwrap_err().0, INVALID_PARAMS);
+        assert_eq!(parse_call("set", &Value::Null).unwrap_err().0, METHOD_NOT_FOUND);
+    }
+
+    #[test]
+    fn test_handle_line_responses() {
+        let list = call(r#"{"jsonrpc": "2.0", "id": 1, "method": "list"}"#);
+        assert_eq!(list, json!({ "jsonrpc": "2.0", "id": 1, "result": { "scripts": [{ "name": "a.sh" }] } }));
+
+        let failed = call(r#"{"jsonrpc": "2.0", "id": "r", "method": "run", "params": {"script": "fail.sh"}}"#);
+        assert_eq!(failed["id"], "r");
+        assert_eq!(failed["error"]["code"], CALL_FAILED);
+        assert_eq!(failed["error"]["data"]["code"], "script_execution");
+
+        assert_eq!(call("not json")["error"]["code"], PARSE_ERROR);
+        assert_eq!(call(r#"{"id": 2, "method": "list"}"#)["error"]["code"], INVALID_REQUEST);
+        assert_eq!(call(r#"{"jsonrpc": "2.0", "id": 3, "method": "nope"}"#)["error"]["code"], METHOD_NOT_FOUND);
+        // 通知不回应
+        assert_eq!(handle_line(r#"{"jsonrpc": "2.0", "method": "list"}"#, &handler), None);
+    }
+
+    #[test]
+    fn test_serve_over_socket() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("gt.sock");
+        // 残留的 socket 文件被替换
+        drop(UnixListener::bind(&path).unwrap());
+        let server_path = path.clone();
+        thread::spawn(move || serve(&server_path, handler));
+        let stream = (0..100)
+            .find_map(|_| UnixStream::connect(&path).ok().or_else(|| {
+                thread::sleep(std::time::Duration::from_millis(10));
+                None
+            }))
+            .unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        // 绑定用的私有目录已经删除
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+        assert_eq!(bind(&path).unwrap_err().kind(), io::ErrorKind::AddrInUse);
+
+        let mut writer = stream.try_clone().unwrap();
+        writeln!(writer, r#"{{"jsonrpc": "2.0", "id": 7, "method": "install", "params": {{"package": "curl"}}}}"#).unwrap();
+        let mut line = String::new();
+        BufReader::new(stream).read_line(&mut line).unwrap();
+        let reply: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(reply["result"], json!({ "action": "install-package", "target": "curl" }));
+
+        let file = dir.path().join("plain");
+        fs::write(&file, "").unwrap();
+        assert_eq!(bind(&file).unwrap_err().kind(), io::ErrorKind::AlreadyExists);
+    }
+}
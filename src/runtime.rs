@@ -0,0 +1,252 @@
+use crate::network::sha256_str;
+use crate::scripts::metadata::ScriptMetadata;
+use crate::log_only;
+use once_cell::sync::Lazy;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// 依赖环境的根目录：~/.geektools/runtimes
+static RUNTIMES_DIR: Lazy<PathBuf> = Lazy::new(|| {
+    let home = env::var("HOME").unwrap_or_else(|_| ".".into());
+    PathBuf::from(home).join(".geektools").join("runtimes")
+});
+
+/// 依赖安装成功后写入的标记文件，缺失时下次运行会重新安装
+const READY_MARKER: &str = ".geektools-ready";
+
+/// 脚本语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Shell,
+    Python,
+    Node,
+}
+
+impl Language {
+    /// 按扩展名识别 (`.py`、`.js`/`.mjs`/`.cjs`)，其余视为 shell
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("py") => Language::Python,
+            Some("js" | "mjs" | "cjs") => Language::Node,
+            _ => Language::Shell,
+        }
+    }
+
+    /// 按解释器识别 (支持 `/usr/bin/env python3` 形式)，无法识别时返回 None
+    pub fn from_interpreter(interpreter: &[String]) -> Option<Self> {
+        let program = program_name(interpreter)?;
+        let base = Path::new(program).file_name()?.to_str()?;
+        if base.starts_with("python") {
+            Some(Language::Python)
+        } else if base == "node" || base == "nodejs" {
+            Some(Language::Node)
+        } else {
+            None
+        }
+    }
+
+    /// 没有 shebang 时使用的解释器
+    pub fn default_interpreter(&self) -> &'static str {
+        match self {
+            Language::Shell => "sh",
+            Language::Python => "python3",
+            Language::Node => "node",
+        }
+    }
+}
+
+/// 保存下载的脚本时使用的扩展名：保留 Python/Node 的扩展名，其余为 sh
+pub fn extension_for(source: &str) -> &str {
+    let path = source.split(['?', '#']).next().unwrap_or(source);
+    match Path::new(path).extension().and_then(|e| e.to_str()) {
+        Some(ext @ ("py" | "js" | "mjs" | "cjs")) => ext,
+        _ => "sh",
+    }
+}
+
+/// 解释器中真正执行的程序，`env` 包装时取其后的参数
+pub fn program_name(interpreter: &[String]) -> Option<&str> {
+    let first = interpreter.first()?;
+    if Path::new(first).file_name().is_some_and(|n| n == "env") {
+        interpreter.iter().skip(1).find(|arg| !arg.starts_with('-')).map(String::as_str)
+    } else {
+        Some(first)
+    }
+}
+
+/// 在 PATH 中查找可执行文件，带路径的程序直接检查是否存在
+pub fn find_program(program: &str) -> Option<PathBuf> {
+    if program.contains('/') {
+        let path = PathBuf::from(program);
+        return path.is_file().then_some(path);
+    }
+    env::var_os("PATH").and_then(|paths| {
+        env::split_paths(&paths)
+            .map(|dir| dir.join(program))
+            .find(|candidate| is_executable(candidate))
+    })
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata().is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// 依赖列表的稳定标识，与声明顺序无关，相同依赖的脚本共用一个环境
+pub fn deps_key(deps: &[String]) -> String {
+    let mut sorted: Vec<&str> = deps.iter().map(String::as_str).collect();
+    sorted.sort_unstable();
+    sorted.dedup();
+    sha256_str(&sorted.join("\n"))[..16].to_string()
+}
+
+/// 为脚本准备好的运行环境
+#[derive(Debug, Default, PartialEq)]
+pub struct Prepared {
+    /// 替换原解释器 (如虚拟环境中的 python)
+    pub interpreter: Option<String>,
+    /// 额外设置的环境变量
+    pub env: Vec<(String, PathBuf)>,
+}
+
+/// 按 `#@pip` / `#@npm` 声明准备依赖环境，没有声明依赖时不做任何事
+pub fn prepare(
+    language: Language,
+    interpreter: &[String],
+    meta: &ScriptMetadata,
+    allow_install: bool,
+) -> io::Result<Prepared> {
+    let deps = match language {
+        Language::Python => &meta.pip,
+        Language::Node => &meta.npm,
+        Language::Shell => return Ok(Prepared::default()),
+    };
+    if deps.is_empty() {
+        return Ok(Prepared::default());
+    }
+    if !allow_install {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("dependency install is disabled (set security.allow_dependency_install to true): {}", deps.join(" ")),
+        ));
+    }
+
+    match language {
+        Language::Python => {
+            let python = program_name(interpreter)
+                .filter(|_| Language::from_interpreter(interpreter) == Some(Language::Python))
+                .unwrap_or("python3");
+            prepare_venv(python, deps, &RUNTIMES_DIR.join("venvs"))
+        }
+        _ => prepare_node_modules(deps, &RUNTIMES_DIR.join("node")),
+    }
+}
+
+/// 创建 (或复用) 隔离的虚拟环境，用其中的 python 运行脚本
+fn prepare_venv(python: &str, deps: &[String], root: &Path) -> io::Result<Prepared> {
+    let dir = root.join(deps_key(deps));
+    let venv_python = dir.join("bin").join("python");
+    if !dir.join(READY_MARKER).exists() {
+        crate::terminal::write_str(&format!("📦 创建 Python 虚拟环境并安装依赖: {}\n", deps.join(" ")));
+        run_step(Command::new(python).arg("-m").arg("venv").arg(&dir), "python -m venv")?;
+        run_step(
+            Command::new(&venv_python)
+                .args(["-m", "pip", "install", "--disable-pip-version-check", "-q"])
+                .args(deps),
+            "pip install",
+        )?;
+        fs::write(dir.join(READY_MARKER), deps.join("\n"))?;
+    }
+    Ok(Prepared {
+        interpreter: Some(venv_python.to_string_lossy().into_owned()),
+        env: vec![("VIRTUAL_ENV".to_string(), dir)],
+    })
+}
+
+/// 把依赖安装到独立目录，通过 NODE_PATH 让脚本的 `require` 找到
+fn prepare_node_modules(deps: &[String], root: &Path) -> io::Result<Prepared> {
+    let dir = root.join(deps_key(deps));
+    if !dir.join(READY_MARKER).exists() {
+        if find_program("npm").is_none() {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "npm not found"));
+        }
+        crate::terminal::write_str(&format!("📦 安装 Node 依赖: {}\n", deps.join(" ")));
+        fs::create_dir_all(&dir)?;
+        run_step(
+            Command::new("npm")
+                .arg("install")
+                .arg("--prefix")
+                .arg(&dir)
+                .args(["--no-audit", "--no-fund", "--silent"])
+                .args(deps),
+            "npm install",
+        )?;
+        fs::write(dir.join(READY_MARKER), deps.join("\n"))?;
+    }
+    Ok(Prepared {
+        interpreter: None,
+        env: vec![("NODE_PATH".to_string(), dir.join("node_modules"))],
+    })
+}
+
+fn run_step(cmd: &mut Command, what: &str) -> io::Result<()> {
+    let status = cmd.status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        log_only!("ERROR", "RUNTIME", "{} 失败: {}", what, status);
+        Err(io::Error::other(format!("{} failed: {}", what, status)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argv(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_language_detection() {
+        assert_eq!(Language::from_path(Path::new("tool.py")), Language::Python);
+        assert_eq!(Language::from_path(Path::new("tool.mjs")), Language::Node);
+        assert_eq!(Language::from_path(Path::new("tool.sh")), Language::Shell);
+        assert_eq!(Language::from_interpreter(&argv(&["/usr/bin/env", "python3"])), Some(Language::Python));
+        assert_eq!(Language::from_interpreter(&argv(&["/usr/local/bin/node"])), Some(Language::Node));
+        assert_eq!(Language::from_interpreter(&argv(&["/bin/bash"])), None);
+        assert_eq!(program_name(&argv(&["/usr/bin/env", "-S", "python3", "-u"])), Some("python3"));
+        assert_eq!(extension_for("https://example.com/tools/fetch.py?raw=1"), "py");
+        assert_eq!(extension_for("https://example.com/install"), "sh");
+    }
+
+    #[test]
+    fn test_deps_key_ignores_order() {
+        let a = deps_key(&argv(&["requests", "rich"]));
+        assert_eq!(a, deps_key(&argv(&["rich", "requests", "rich"])));
+        assert_ne!(a, deps_key(&argv(&["requests"])));
+        assert_eq!(a.len(), 16);
+    }
+
+    #[test]
+    fn test_prepare_without_deps_is_noop() {
+        let meta = ScriptMetadata {
+            npm: argv(&["lodash"]),
+            ..ScriptMetadata::default()
+        };
+        let prepared = prepare(Language::Python, &argv(&["python3"]), &meta, false).unwrap();
+        assert_eq!(prepared, Prepared::default());
+        assert!(prepare(Language::Node, &argv(&["node"]), &meta, false).is_err());
+        assert!(find_program("sh").is_some());
+        assert!(find_program("geektools-no-such-program").is_none());
+    }
+}
@@ -0,0 +1,181 @@
+use crate::errors::Result;
+use crate::fileio;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// 下载的脚本目录缓存：~/.geektools/catalog.json
+static CATALOG_CACHE: Lazy<PathBuf> = Lazy::new(|| {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".into());
+    PathBuf::from(home).join(".geektools").join("catalog.json")
+});
+
+/// 脚本目录的来源和已应用的版本，与程序版本分开
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CatalogConfig {
+    /// 默认取仓库 master 分支上的 catalog.json，随脚本更新而不是随程序发布更新
+    pub url: String,
+    /// 已应用的目录版本，0 表示只使用内置的脚本描述
+    #[serde(default)]
+    pub version: u32,
+}
+
+impl Default for CatalogConfig {
+    fn default() -> Self {
+        Self {
+            url: format!(
+                "https://raw.githubusercontent.com/{}/master/catalog.json",
+                env!("CARGO_PKG_REPOSITORY")
+            ),
+            version: 0,
+        }
+    }
+}
+
+/// 远程目录中的一个脚本；`English` / `Chinese` 合并时对应语言代码 `en` / `zh`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CatalogEntry {
+    #[serde(rename = "English", default, skip_serializing_if = "Option::is_none")]
+    pub english: Option<String>,
+    #[serde(rename = "Chinese", default, skip_serializing_if = "Option::is_none")]
+    pub chinese: Option<String>,
+    /// `.link` 脚本的下载地址
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+    /// 新地址内容的 SHA-256，给出时运行前校验
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+/// 远程 `catalog.json`：
+///
+/// ```text
+/// {"version": 3, "scripts": {"cleanmac.link": {"English": "...", "Chinese": "...", "link": "https://..."}}}
+/// ```
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Catalog {
+    pub version: u32,
+    #[serde(default)]
+    pub scripts: BTreeMap<String, CatalogEntry>,
+}
+
+impl Catalog {
+    /// 某个 `.link` 脚本在目录中的新地址和校验值
+    pub fn link_for(&self, name: &str) -> Option<(&str, Option<&str>)> {
+        let entry = self.scripts.get(name)?;
+        Some((entry.link.as_deref()?, entry.sha256.as_deref()))
+    }
+}
+
+/// 读取已下载的目录
+pub fn load_cached() -> Option<Catalog> {
+    load_from(&CATALOG_CACHE)
+}
+
+fn load_from(path: &Path) -> Option<Catalog> {
+    serde_json::from_str(&fileio::read(path).ok()?).ok()
+}
+
+/// 下载远程目录，版本比已应用的版本新时返回
+pub fn check_for_update(config: &CatalogConfig) -> Result<Option<Catalog>> {
+    let catalog: Catalog = crate::network::get_json(&config.url)?;
+    crate::metrics::record_update_check();
+    Ok((catalog.version > config.version).then_some(catalog))
+}
+
+/// 保存下载的目录，之后 [`merged_info`] 会用它覆盖内置描述
+pub fn save(catalog: &Catalog) -> Result<()> {
+    fileio::write(&*CATALOG_CACHE, &serde_json::to_string_pretty(catalog)?)
+}
+
+/// 描述缺少某种语言时的回退语言
+pub const FALLBACK_LANGUAGE: &str = "en";
+
+/// 按 语言代码 → 主语言 (`zh-TW` → `zh`) → 英文 的顺序取脚本描述
+pub fn localized<'a>(entry: &'a Value, code: &str) -> Option<&'a str> {
+    let primary = code.split(['-', '_']).next().unwrap_or(code);
+    [code, primary, FALLBACK_LANGUAGE]
+        .into_iter()
+        .find_map(|code| entry.get(code).and_then(Value::as_str))
+}
+
+/// 用目录覆盖内置的描述；目录中新增的脚本只接受带 link 的 `.link` 脚本
+/// (其余脚本必须随程序内置)
+pub fn merged_info(embedded: &str, catalog: Option<&Catalog>) -> Value {
+    let mut info: Map<String, Value> = serde_json::from_str(embedded).unwrap_or_default();
+    let Some(catalog) = catalog else {
+        return Value::Object(info);
+    };
+    for (name, entry) in &catalog.scripts {
+        let accepted = info.contains_key(name) || (name.ends_with(".link") && entry.link.is_some());
+        if !accepted {
+            continue;
+        }
+        let item = info
+            .entry(name.clone())
+            .or_insert_with(|| Value::Object(Map::new()));
+        if let Some(obj) = item.as_object_mut() {
+            if let Some(english) = &entry.english {
+                obj.insert("en".to_string(), Value::String(english.clone()));
+            }
+            if let Some(chinese) = &entry.chinese {
+                obj.insert("zh".to_string(), Value::String(chinese.clone()));
+            }
+        }
+    }
+    Value::Object(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const EMBEDDED: &str = r#"{"a.sh": {"en": "old", "zh": "旧"}, "b.link": {"en": "b"}}"#;
+
+    fn catalog() -> Catalog {
+        serde_json::from_str(
+            r#"{"version": 2, "scripts": {
+                "a.sh": {"English": "new"},
+                "b.link": {"link": "https://example.com/b.sh"},
+                "c.link": {"English": "c", "link": "https://example.com/c.sh"},
+                "d.sh": {"English": "not embedded"}
+            }}"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_merged_info_overrides_descriptions() {
+        let catalog = catalog();
+        let info = merged_info(EMBEDDED, Some(&catalog));
+        assert_eq!(info["a.sh"]["en"], "new");
+        assert_eq!(info["a.sh"]["zh"], "旧");
+        assert_eq!(info["b.link"]["en"], "b");
+        assert_eq!(info["c.link"]["en"], "c");
+        assert!(info.get("d.sh").is_none());
+        assert_eq!(catalog.link_for("b.link"), Some(("https://example.com/b.sh", None)));
+        assert_eq!(merged_info(EMBEDDED, None)["a.sh"]["en"], "old");
+    }
+
+    #[test]
+    fn test_localized_falls_back_to_english() {
+        let info = merged_info(EMBEDDED, None);
+        assert_eq!(localized(&info["a.sh"], "zh"), Some("旧"));
+        assert_eq!(localized(&info["a.sh"], "zh-TW"), Some("旧"));
+        assert_eq!(localized(&info["b.link"], "zh"), Some("b"));
+        assert_eq!(localized(&info["a.sh"], "ja"), Some("old"));
+        assert_eq!(localized(&Value::Null, "en"), None);
+    }
+
+    #[test]
+    fn test_cached_catalog_roundtrip() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("catalog.json");
+        assert!(load_from(&path).is_none());
+        fileio::write(&path, &serde_json::to_string(&catalog()).unwrap()).unwrap();
+        assert_eq!(load_from(&path), Some(catalog()));
+    }
+}
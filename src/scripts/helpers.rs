@@ -0,0 +1,144 @@
+use crate::errors::Result;
+use crate::fileio;
+use once_cell::sync::Lazy;
+use std::path::{Path, PathBuf};
+
+/// 注入脚本运行环境的 shell 函数库 (POSIX sh)，脚本中用 `. "$GT_LIB"` 加载
+const LIBRARY: &str = r#"
+# 颜色只在输出到终端且未设置 NO_COLOR 时启用
+if [ -t 1 ] && [ -z "${NO_COLOR:-}" ]; then
+    GT_RED='\033[31m' GT_GREEN='\033[32m' GT_YELLOW='\033[33m' GT_BLUE='\033[34m' GT_RESET='\033[0m'
+else
+    GT_RED='' GT_GREEN='' GT_YELLOW='' GT_BLUE='' GT_RESET=''
+fi
+
+gt_info()    { printf '%b%s%b\n' "$GT_BLUE" "$*" "$GT_RESET"; }
+gt_success() { printf '%b✅ %s%b\n' "$GT_GREEN" "$*" "$GT_RESET"; }
+gt_warn()    { printf '%b⚠️  %s%b\n' "$GT_YELLOW" "$*" "$GT_RESET" >&2; }
+gt_error()   { printf '%b❌ %s%b\n' "$GT_RED" "$*" "$GT_RESET" >&2; }
+
+# gt_confirm "问题" [y|n]：第二个参数为直接回车时的默认值 (默认 n)；
+# GT_ASSUME_YES=1 时不询问直接同意。优先从终端读取，脚本的标准输入被占用时也能确认
+gt_confirm() {
+    [ "${GT_ASSUME_YES:-0}" = 1 ] && return 0
+    case "${2:-n}" in y|Y) _gt_hint='[Y/n]' ;; *) _gt_hint='[y/N]' ;; esac
+    printf '%s %s ' "$1" "$_gt_hint"
+    _gt_answer=''
+    { read -r _gt_answer </dev/tty; } 2>/dev/null || read -r _gt_answer || true
+    case "${_gt_answer:-${2:-n}}" in y|Y|yes|YES) return 0 ;; *) return 1 ;; esac
+}
+
+# gt_download URL 目标文件 [次数]：用 curl 或 wget 下载，失败时等待 2s、4s… 后重试 (默认共 3 次)
+gt_download() {
+    _gt_tries="${3:-3}" _gt_n=1
+    while :; do
+        if command -v curl >/dev/null 2>&1; then
+            curl -fsSL -o "$2" "$1" && return 0
+        elif command -v wget >/dev/null 2>&1; then
+            wget -q -O "$2" "$1" && return 0
+        else
+            gt_error "curl or wget is required to download $1"
+            return 1
+        fi
+        [ "$_gt_n" -ge "$_gt_tries" ] && break
+        gt_warn "download failed ($_gt_n/$_gt_tries), retrying in $((_gt_n * 2))s: $1"
+        sleep $((_gt_n * 2))
+        _gt_n=$((_gt_n + 1))
+    done
+    rm -f "$2"
+    gt_error "download failed: $1"
+    return 1
+}
+
+# 输出本机的包管理器 (brew、apt、dnf、yum、pacman、apk、zypper)，没有时返回 1；
+# geektools 已检测到时直接使用 GT_PKG_MANAGER
+gt_detect_pkg_manager() {
+    [ -n "${GT_PKG_MANAGER:-}" ] && echo "$GT_PKG_MANAGER" && return 0
+    for _gt_pm in brew apt-get dnf yum pacman apk zypper; do
+        if command -v "$_gt_pm" >/dev/null 2>&1; then
+            [ "$_gt_pm" = apt-get ] && _gt_pm=apt
+            echo "$_gt_pm"
+            return 0
+        fi
+    done
+    return 1
+}
+"#;
+
+/// 生成的函数库内容，头部注明生成它的程序版本
+pub fn library() -> String {
+    format!(
+        "# geektools.sh - generated by geektools {}\n# 用法: . \"$GT_LIB\"\n{}",
+        env!("CARGO_PKG_VERSION"),
+        LIBRARY
+    )
+}
+
+/// 函数库写到 ~/.geektools/lib/ (每个进程写一次，按版本区分文件名)，失败时为 None。
+/// 目录只有当前用户可访问，避免其他用户在共享的临时目录中预先放置或替换函数库
+static LIBRARY_PATH: Lazy<Option<PathBuf>> = Lazy::new(|| {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".into());
+    write_library(&PathBuf::from(home).join(".geektools").join("lib")).ok()
+});
+
+/// 脚本运行时通过 `GT_LIB` 导出的函数库路径
+pub fn library_path() -> Option<&'static Path> {
+    LIBRARY_PATH.as_deref()
+}
+
+fn write_library(dir: &Path) -> Result<PathBuf> {
+    fileio::create_private_dir(dir)?;
+    let path = dir.join(format!("geektools-{}.sh", env!("CARGO_PKG_VERSION")));
+    let content = library();
+    if std::fs::read_to_string(&path).ok().as_deref() != Some(content.as_str()) {
+        fileio::write_bytes(&path, content.as_bytes())?;
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    fn run_with_library(dir: &Path, script: &str) -> String {
+        let lib = write_library(dir).unwrap();
+        let output = Command::new("sh")
+            .args(["-c", &format!(". \"$GT_LIB\"\n{}", script)])
+            .env("GT_LIB", &lib)
+            .env("GT_ASSUME_YES", "1")
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    #[test]
+    fn test_library_functions() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("src.txt"), "payload").unwrap();
+        let out = run_with_library(
+            dir.path(),
+            "gt_info hello\n\
+             gt_confirm 'go on?' && echo confirmed\n\
+             gt_download \"file://$PWD/src.txt\" copy.txt 1 && cat copy.txt\n\
+             gt_detect_pkg_manager >/dev/null; echo \" rc=$?\"",
+        );
+        // 输出不是终端，不带颜色
+        assert!(out.starts_with("hello\n"));
+        assert!(out.contains("confirmed\n"));
+        assert!(out.contains("payload"));
+        assert!(out.contains(" rc="));
+    }
+
+    #[test]
+    fn test_library_written_once_per_version() {
+        let dir = TempDir::new().unwrap();
+        let path = write_library(dir.path()).unwrap();
+        assert!(path.ends_with(format!("geektools-{}.sh", env!("CARGO_PKG_VERSION"))));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), library());
+        assert_eq!(write_library(dir.path()).unwrap(), path);
+    }
+}
@@ -0,0 +1,144 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// `.link` 脚本的内容。简单格式每行一个 URL (第一行之后的是镜像)；JSON 格式还可附带校验值、
+/// 多语言描述和支持的平台：
+///
+/// ```text
+/// {
+///   "url": "https://raw.githubusercontent.com/user/repo/v1.0/cleanmac.sh",
+///   "mirrors": ["https://cdn.jsdelivr.net/gh/user/repo@v1.0/cleanmac.sh"],
+///   "sha256": "<64 位十六进制>",
+///   "description": {"en": "Clean caches", "zh": "清理缓存"},
+///   "platforms": ["macos"]
+/// }
+/// ```
+///
+/// 下载时依次尝试 `url` 和各个镜像，某个地址被屏蔽时仍能拿到脚本；给出 `sha256` 时内容必须匹配
+/// (所有地址共用一个校验值)，适合指向固定版本 (tag 或提交) 的链接。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LinkSpec {
+    pub url: String,
+    /// 主地址失败时按顺序尝试的镜像地址
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mirrors: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    /// 语言代码 → 描述
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub description: BTreeMap<String, String>,
+    /// 支持的平台 (`std::env::consts::OS` 的取值)，为空表示不限
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub platforms: Vec<String>,
+}
+
+impl LinkSpec {
+    /// 解析 `.link` 内容，两种格式都接受
+    pub fn parse(content: &str) -> Result<LinkSpec, String> {
+        let content = content.trim();
+        if content.starts_with('{') {
+            let spec: LinkSpec = serde_json::from_str(content).map_err(|e| format!("invalid .link JSON: {}", e))?;
+            if spec.url.trim().is_empty() {
+                return Err("`url` is empty".to_string());
+            }
+            if let Some(sha256) = &spec.sha256
+                && (sha256.len() != 64 || !sha256.chars().all(|c| c.is_ascii_hexdigit()))
+            {
+                return Err(format!("`sha256` must be 64 hex digits: {}", sha256));
+            }
+            return Ok(spec);
+        }
+        let mut urls = content.lines().map(str::trim).filter(|line| !line.is_empty()).map(str::to_string);
+        match urls.next() {
+            Some(url) => Ok(LinkSpec { url, mirrors: urls.collect(), ..LinkSpec::default() }),
+            None => Err("empty .link file".to_string()),
+        }
+    }
+
+    /// 按尝试顺序排列的全部地址：主地址在前，之后是镜像
+    pub fn urls(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.url.as_str()).chain(self.mirrors.iter().map(String::as_str))
+    }
+
+    /// 当前语言的描述 (`zh-TW` 没有时取 `zh`)，依次回退到英文和任意语言
+    pub fn description(&self, lang: &str) -> Option<&str> {
+        self.description
+            .get(lang)
+            .or_else(|| self.description.get(lang.split('-').next()?))
+            .or_else(|| self.description.get("en"))
+            .or_else(|| self.description.values().next())
+            .map(String::as_str)
+    }
+
+    pub fn supports_platform(&self, os: &str) -> bool {
+        self.platforms.is_empty() || self.platforms.iter().any(|p| p.eq_ignore_ascii_case(os))
+    }
+
+    /// 用脚本目录中的新地址替换链接；地址变了时旧的镜像和校验值不再适用，改用目录给出的校验值
+    pub fn with_catalog_link(mut self, url: &str, sha256: Option<&str>) -> LinkSpec {
+        if self.url != url {
+            self.url = url.to_string();
+            self.mirrors.clear();
+            self.sha256 = None;
+        }
+        if let Some(sha256) = sha256 {
+            self.sha256 = Some(sha256.to_string());
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bare_url() {
+        let spec = LinkSpec::parse("https://example.com/a.sh\n").unwrap();
+        assert_eq!(spec.url, "https://example.com/a.sh");
+        assert_eq!(spec.sha256, None);
+        assert!(spec.supports_platform("linux"));
+        assert!(LinkSpec::parse("  \n").is_err());
+    }
+
+    #[test]
+    fn test_mirrors_in_order() {
+        let spec = LinkSpec::parse("https://a/x.sh\n\n  https://b/x.sh\nhttps://c/x.sh\n").unwrap();
+        assert_eq!(spec.urls().collect::<Vec<_>>(), ["https://a/x.sh", "https://b/x.sh", "https://c/x.sh"]);
+        let spec = LinkSpec::parse(r#"{"url": "https://a/x.sh", "mirrors": ["https://b/x.sh"]}"#).unwrap();
+        assert_eq!(spec.urls().collect::<Vec<_>>(), ["https://a/x.sh", "https://b/x.sh"]);
+        assert!(spec.clone().with_catalog_link("https://a/x.sh", None).mirrors.len() == 1);
+        assert!(spec.with_catalog_link("https://d/x.sh", None).mirrors.is_empty());
+    }
+
+    #[test]
+    fn test_parse_json() {
+        let spec = LinkSpec::parse(&format!(
+            r#"{{"url": "https://example.com/a.sh", "sha256": "{}", "description": {{"en": "Clean", "zh": "清理"}}, "platforms": ["macos"]}}"#,
+            "ab".repeat(32)
+        ))
+        .unwrap();
+        assert_eq!(spec.sha256.as_deref(), Some("ab".repeat(32).as_str()));
+        assert_eq!(spec.description("zh-TW"), Some("清理"));
+        assert_eq!(spec.description("ja"), Some("Clean"));
+        assert!(spec.supports_platform("macos"));
+        assert!(!spec.supports_platform("linux"));
+
+        assert!(LinkSpec::parse(r#"{"url": "https://example.com/a.sh", "sha256": "xyz"}"#).is_err());
+        assert!(LinkSpec::parse(r#"{"url": ""}"#).is_err());
+        assert!(LinkSpec::parse(r#"{"url": "https://example.com/a.sh", "checksum": "x"}"#).is_err());
+    }
+
+    #[test]
+    fn test_catalog_link_replaces_stale_checksum() {
+        let spec = LinkSpec { url: "https://a/1.sh".to_string(), sha256: Some("0".repeat(64)), ..LinkSpec::default() };
+        assert_eq!(spec.clone().with_catalog_link("https://a/1.sh", None).sha256, Some("0".repeat(64)));
+        let moved = spec.clone().with_catalog_link("https://a/2.sh", None);
+        assert_eq!((moved.url.as_str(), moved.sha256), ("https://a/2.sh", None));
+        let pinned = spec.with_catalog_link("https://a/2.sh", Some(&"1".repeat(64)));
+        assert_eq!(pinned.sha256, Some("1".repeat(64)));
+        let round_trip = serde_json::to_string(&pinned).unwrap();
+        assert_eq!(LinkSpec::parse(&round_trip).unwrap(), pinned);
+    }
+}
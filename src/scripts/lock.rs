@@ -0,0 +1,135 @@
+use super::metadata;
+use crate::errors::Result;
+use crate::{fileio, network};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// 锁文件名，放在主脚本的目录 ~/.geektools/scripts/(脚本名)/ 中
+pub const FILE_NAME: &str = "deps.lock";
+
+/// 主脚本导入的全部依赖 (含间接导入) 在第一次运行时的版本和内容哈希
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct DepsLock {
+    pub script: String,
+    pub dependencies: BTreeMap<String, LockedDependency>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LockedDependency {
+    /// 脚本头部的 `# Version:`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    pub sha256: String,
+}
+
+/// 当前依赖与锁文件的差异
+#[derive(Debug, PartialEq)]
+pub enum Drift {
+    Changed { name: String, locked: LockedDependency, current: LockedDependency },
+    /// 锁定之后新增的导入
+    Added(String),
+    /// 锁定之后不再导入
+    Removed(String),
+}
+
+impl DepsLock {
+    /// 根据已物化的依赖文件生成锁
+    pub fn from_files(script: &str, dependencies: &[(String, PathBuf)]) -> Result<DepsLock> {
+        let mut locked = BTreeMap::new();
+        for (name, path) in dependencies {
+            let version = fileio::read(path).ok().and_then(|content| metadata::parse(&content).version);
+            locked.insert(name.clone(), LockedDependency { version, sha256: network::sha256_file(path)? });
+        }
+        Ok(DepsLock { script: script.to_string(), dependencies: locked })
+    }
+
+    /// 读取锁文件，不存在或无法解析时返回 None
+    pub fn load(path: &Path) -> Option<DepsLock> {
+        fileio::read(path).ok().and_then(|content| serde_json::from_str(&content).ok())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fileio::write(path, &serde_json::to_string_pretty(self)?)
+    }
+
+    /// 与锁定时相比的变化，按依赖名排序
+    pub fn drift(&self, current: &DepsLock) -> Vec<Drift> {
+        let mut drift = Vec::new();
+        for (name, locked) in &self.dependencies {
+            match current.dependencies.get(name) {
+                None => drift.push(Drift::Removed(name.clone())),
+                Some(now) if now.sha256 != locked.sha256 => drift.push(Drift::Changed {
+                    name: name.clone(),
+                    locked: locked.clone(),
+                    current: now.clone(),
+                }),
+                Some(_) => {}
+            }
+        }
+        drift.extend(
+            current
+                .dependencies
+                .keys()
+                .filter(|name| !self.dependencies.contains_key(*name))
+                .map(|name| Drift::Added(name.clone())),
+        );
+        drift
+    }
+}
+
+impl fmt::Display for Drift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let version = |dep: &LockedDependency| dep.version.clone().unwrap_or_else(|| dep.sha256[..12.min(dep.sha256.len())].to_string());
+        match self {
+            Drift::Changed { name, locked, current } => {
+                write!(f, "{} 已改变: {} → {}", name, version(locked), version(current))
+            }
+            Drift::Added(name) => write!(f, "{} 是新增的依赖", name),
+            Drift::Removed(name) => write!(f, "{} 不再被导入", name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_lock_from_files_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let common = dir.path().join("common.sh");
+        std::fs::write(&common, "#!/bin/sh\n# Version: 1.2.0\nhello() { :; }\n").unwrap();
+        let link = dir.path().join("tool.link");
+        std::fs::write(&link, "https://example.com/tool.sh\n").unwrap();
+
+        let lock = DepsLock::from_files("main.sh", &[("common.sh".into(), common), ("tool.link".into(), link)]).unwrap();
+        assert_eq!(lock.dependencies["common.sh"].version.as_deref(), Some("1.2.0"));
+        assert_eq!(lock.dependencies["tool.link"].version, None);
+        assert_eq!(lock.dependencies["tool.link"].sha256, network::sha256_str("https://example.com/tool.sh\n"));
+
+        let path = dir.path().join(FILE_NAME);
+        lock.save(&path).unwrap();
+        assert_eq!(DepsLock::load(&path), Some(lock));
+        assert_eq!(DepsLock::load(&dir.path().join("missing.lock")), None);
+    }
+
+    #[test]
+    fn test_drift() {
+        let dep = |version: &str, sha256: &str| LockedDependency { version: Some(version.into()), sha256: sha256.into() };
+        let lock = |deps: &[(&str, LockedDependency)]| DepsLock {
+            script: "main.sh".into(),
+            dependencies: deps.iter().map(|(n, d)| (n.to_string(), d.clone())).collect(),
+        };
+        let locked = lock(&[("a.sh", dep("1.0", "aa")), ("b.sh", dep("1.0", "bb"))]);
+        assert!(locked.drift(&lock(&[("a.sh", dep("1.0", "aa")), ("b.sh", dep("1.0", "bb"))])).is_empty());
+
+        let drift = locked.drift(&lock(&[("a.sh", dep("1.1", "a2")), ("c.sh", dep("1.0", "cc"))]));
+        assert_eq!(drift.len(), 3);
+        assert_eq!(drift[0].to_string(), "a.sh 已改变: 1.0 → 1.1");
+        assert_eq!(drift[1], Drift::Removed("b.sh".into()));
+        assert_eq!(drift[2], Drift::Added("c.sh".into()));
+    }
+}
@@ -0,0 +1,359 @@
+use std::collections::BTreeMap;
+
+/// 脚本参数声明：`# Param: NAME=默认值 - 说明`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptParam {
+    pub name: String,
+    pub default: Option<String>,
+    pub description: String,
+}
+
+/// 环境变量声明：`# Env: NAME - 说明`，名字后加 `?` 表示可选
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptEnvVar {
+    pub name: String,
+    pub required: bool,
+    pub description: String,
+}
+
+/// 脚本头部的元数据
+///
+/// 头部是脚本开头 (shebang 之后) 连续的注释行，遇到第一行非注释、非空行即结束：
+///
+/// ```text
+/// #!/bin/bash
+/// # Name: 磁盘清理
+/// # Description: Clean caches
+/// # Description[zh]: 清理缓存
+/// # Author: someone
+/// # Version: 1.0.0
+/// # Param: DAYS=7 - 保留多少天的缓存
+/// # Env: API_TOKEN? - 可选的访问令牌
+/// # Platforms: macos, linux
+/// # Requires-Root: yes
+/// #@import common.sh
+/// #@pip requests rich
+/// ```
+///
+/// 键名不区分大小写，`名称`/`描述`/`参数`/`环境变量`/`平台`/`需要root` 为中文别名
+/// (`描述` 等同于 `Description[zh]`)。`#@import`、`#@pip`、`#@npm` 可出现在脚本任意位置；
+/// `#@rollback` 段见 [`parse_rollback`]。
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScriptMetadata {
+    pub name: Option<String>,
+    /// 语言代码 → 描述，无语言后缀的描述存于空字符串键
+    pub descriptions: BTreeMap<String, String>,
+    pub author: Option<String>,
+    pub version: Option<String>,
+    pub params: Vec<ScriptParam>,
+    pub env: Vec<ScriptEnvVar>,
+    pub imports: Vec<String>,
+    /// 支持的平台 (`std::env::consts::OS` 的取值)，为空表示不限
+    pub platforms: Vec<String>,
+    pub requires_root: bool,
+    pub rollback: Option<String>,
+    /// Python 脚本的依赖 (`#@pip requests`)
+    pub pip: Vec<String>,
+    /// Node 脚本的依赖 (`#@npm lodash`)
+    pub npm: Vec<String>,
+}
+
+impl ScriptMetadata {
+    /// 取指定语言的描述 (`zh-TW` 没有时取 `zh`)，依次回退到无语言描述和任意语言的描述
+    pub fn description(&self, lang: Option<&str>) -> Option<&str> {
+        lang.and_then(|l| self.descriptions.get(l).or_else(|| self.descriptions.get(l.split('-').next()?)))
+            .or_else(|| self.descriptions.get(""))
+            .or_else(|| self.descriptions.values().next())
+            .map(String::as_str)
+    }
+
+    /// 当前平台是否在声明的平台列表中
+    pub fn supports_platform(&self, os: &str) -> bool {
+        self.platforms.is_empty() || self.platforms.iter().any(|p| p.eq_ignore_ascii_case(os))
+    }
+}
+
+/// 解析脚本内容中的元数据
+pub fn parse(content: &str) -> ScriptMetadata {
+    let mut meta = ScriptMetadata {
+        imports: parse_imports(content),
+        rollback: parse_rollback(content),
+        pip: directive_words(content, "#@pip"),
+        npm: directive_words(content, "#@npm"),
+        ..ScriptMetadata::default()
+    };
+
+    for line in header_lines(content) {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let (key, value) = (key.trim().to_lowercase(), value.trim());
+        if value.is_empty() {
+            continue;
+        }
+
+        let (key, lang) = match key.split_once('[') {
+            Some((key, rest)) => (key.trim().to_string(), rest.trim_end_matches(']').trim().to_string()),
+            None => (key, String::new()),
+        };
+        match key.as_str() {
+            "name" | "名称" => meta.name = Some(value.to_string()),
+            "description" => {
+                meta.descriptions.insert(lang, value.to_string());
+            }
+            "描述" => {
+                meta.descriptions.insert("zh".to_string(), value.to_string());
+            }
+            "author" | "作者" => meta.author = Some(value.to_string()),
+            "version" | "版本" => meta.version = Some(value.to_string()),
+            "param" | "参数" => meta.params.push(parse_param(value)),
+            "env" | "环境变量" => meta.env.push(parse_env_var(value)),
+            "platforms" | "platform" | "平台" => meta.platforms.extend(
+                value
+                    .split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|p| !p.is_empty())
+                    .map(|p| p.to_lowercase()),
+            ),
+            "requires-root" | "requires_root" | "需要root" => {
+                meta.requires_root = matches!(value.to_lowercase().as_str(), "yes" | "true" | "1" | "是")
+            }
+            _ => {}
+        }
+    }
+
+    meta
+}
+
+/// 头部注释行 (已去掉 `#` 和首尾空白)，跳过 shebang 和 `#@` 指令
+fn header_lines(content: &str) -> impl Iterator<Item = &str> {
+    content
+        .lines()
+        .map(str::trim)
+        .skip_while(|line| line.starts_with("#!"))
+        .take_while(|line| line.is_empty() || line.starts_with('#'))
+        .filter(|line| !line.starts_with("#@"))
+        .map(|line| line.trim_start_matches('#').trim())
+}
+
+/// `NAME=默认值 - 说明`
+fn parse_param(value: &str) -> ScriptParam {
+    let (spec, description) = split_description(value);
+    let (name, default) = match spec.split_once('=') {
+        Some((name, default)) => (name.trim(), Some(default.trim().to_string())),
+        None => (spec.trim(), None),
+    };
+    ScriptParam {
+        name: name.to_string(),
+        default,
+        description,
+    }
+}
+
+/// `NAME? - 说明`
+fn parse_env_var(value: &str) -> ScriptEnvVar {
+    let (spec, description) = split_description(value);
+    let spec = spec.trim();
+    ScriptEnvVar {
+        name: spec.trim_end_matches('?').to_string(),
+        required: !spec.ends_with('?'),
+        description,
+    }
+}
+
+fn split_description(value: &str) -> (&str, String) {
+    match value.split_once(" - ") {
+        Some((spec, description)) => (spec, description.trim().to_string()),
+        None => (value, String::new()),
+    }
+}
+
+/// 把 `名称=值` 形式的参数按 `# Param:` 的声明顺序转换为位置参数 (`$1`、`$2` ...)，
+/// 未给出的参数使用默认值；后面的参数已给出时，前面没有默认值的参数不能省略
+pub fn positional_args(params: &[ScriptParam], given: &[(String, String)]) -> Result<Vec<String>, String> {
+    if let Some((name, _)) = given.iter().find(|(name, _)| !params.iter().any(|p| &p.name == name)) {
+        return Err(format!("unknown parameter: {}", name));
+    }
+    let value_of = |param: &ScriptParam| {
+        given
+            .iter()
+            .rev()
+            .find(|(name, _)| *name == param.name)
+            .map(|(_, value)| value.clone())
+            .or_else(|| param.default.clone())
+    };
+    let last_given = params.iter().rposition(|p| given.iter().any(|(name, _)| *name == p.name));
+
+    let mut args = Vec::new();
+    for (i, param) in params.iter().enumerate() {
+        match value_of(param) {
+            Some(value) => args.push(value),
+            None if last_given.is_some_and(|last| i < last) => {
+                return Err(format!("missing parameter: {}", param.name));
+            }
+            None => break,
+        }
+    }
+    Ok(args)
+}
+
+/// 解析脚本中的导入声明 (`#@import other.sh`)
+pub fn parse_imports(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("#@import "))
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// 收集 `#@pip a b` 这类指令后的全部单词 (可出现多行)；
+/// JavaScript 中写作 `// #@npm lodash`
+fn directive_words(content: &str, directive: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(|line| line.trim().trim_start_matches("//").trim_start())
+        .filter_map(|line| line.strip_prefix(directive))
+        .filter(|rest| rest.starts_with(char::is_whitespace))
+        .flat_map(str::split_whitespace)
+        .map(str::to_string)
+        .collect()
+}
+
+/// 提取脚本中的回滚段：
+///
+/// ```text
+/// #@rollback
+/// # rm -f /usr/local/bin/tool
+/// #@end
+/// ```
+///
+/// 段内每行去掉开头的 `#` 和一个空格；没有 `#@end` 时到第一个非注释行结束
+pub fn parse_rollback(content: &str) -> Option<String> {
+    let mut lines = content.lines().skip_while(|line| line.trim() != "#@rollback");
+    lines.next()?;
+
+    let mut commands = Vec::new();
+    for line in lines {
+        let trimmed = line.trim_start();
+        if trimmed.trim_end() == "#@end" {
+            break;
+        }
+        let Some(rest) = trimmed.strip_prefix('#') else {
+            break;
+        };
+        commands.push(rest.strip_prefix(' ').unwrap_or(rest));
+    }
+
+    let body = commands.join("\n");
+    if body.trim().is_empty() {
+        None
+    } else {
+        Some(body + "\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCRIPT: &str = "#!/usr/bin/env bash
+# Name: Cache cleaner
+# Description: Clean caches
+# Description[zh]: 清理缓存
+# Version: 1.2.0
+# Param: DAYS=7 - Days of cache to keep
+# Param: TARGET - Directory to clean
+# Env: API_TOKEN? - Optional token
+# Platforms: macos, Linux
+# Requires-Root: yes
+#@import common.sh
+set -e
+# Name: not part of the header
+echo done
+#@import late.sh
+";
+
+    #[test]
+    fn test_positional_args() {
+        let params = parse(SCRIPT).params;
+        let given = |pairs: &[(&str, &str)]| -> Vec<(String, String)> {
+            pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+        };
+        assert_eq!(positional_args(&params, &[]).unwrap(), vec!["7"]);
+        assert_eq!(positional_args(&params, &given(&[("TARGET", "/tmp")])).unwrap(), vec!["7", "/tmp"]);
+        assert_eq!(positional_args(&params, &given(&[("DAYS", "3")])).unwrap(), vec!["3"]);
+        assert!(positional_args(&params, &given(&[("OTHER", "1")])).is_err());
+
+        let required_first = vec![
+            ScriptParam { name: "A".to_string(), default: None, description: String::new() },
+            ScriptParam { name: "B".to_string(), default: None, description: String::new() },
+        ];
+        assert_eq!(
+            positional_args(&required_first, &given(&[("B", "1")])).unwrap_err(),
+            "missing parameter: A"
+        );
+    }
+
+    #[test]
+    fn test_parse_header_block() {
+        let meta = parse(SCRIPT);
+        assert_eq!(meta.name.as_deref(), Some("Cache cleaner"));
+        assert_eq!(meta.version.as_deref(), Some("1.2.0"));
+        assert_eq!(meta.description(Some("zh")), Some("清理缓存"));
+        assert_eq!(meta.description(Some("ja")), Some("Clean caches"));
+        assert_eq!(
+            meta.params,
+            vec![
+                ScriptParam {
+                    name: "DAYS".to_string(),
+                    default: Some("7".to_string()),
+                    description: "Days of cache to keep".to_string(),
+                },
+                ScriptParam {
+                    name: "TARGET".to_string(),
+                    default: None,
+                    description: "Directory to clean".to_string(),
+                },
+            ]
+        );
+        assert_eq!(meta.env.len(), 1);
+        assert!(!meta.env[0].required);
+        assert_eq!(meta.env[0].name, "API_TOKEN");
+        assert_eq!(meta.platforms, vec!["macos", "linux"]);
+        assert!(meta.supports_platform("linux"));
+        assert!(!meta.supports_platform("windows"));
+        assert!(meta.requires_root);
+        assert_eq!(meta.imports, vec!["common.sh", "late.sh"]);
+    }
+
+    #[test]
+    fn test_parse_chinese_aliases() {
+        let meta = parse("#!/bin/sh\n# 名称: 系统信息\n# 描述: 显示系统信息\n# 需要root: 否\necho\n");
+        assert_eq!(meta.name.as_deref(), Some("系统信息"));
+        assert_eq!(meta.description(Some("en")), Some("显示系统信息"));
+        assert!(!meta.requires_root);
+        assert!(meta.supports_platform("linux"));
+    }
+
+    #[test]
+    fn test_parse_rollback_block() {
+        let script = "#!/bin/sh\ncp tool /usr/local/bin/\n#@rollback\n# rm -f /usr/local/bin/tool\n#   echo removed\n#@end\necho done\n";
+        assert_eq!(
+            parse_rollback(script).as_deref(),
+            Some("rm -f /usr/local/bin/tool\n  echo removed\n")
+        );
+
+        let unterminated = "#@rollback\n# apt remove -y foo\necho install\n";
+        assert_eq!(parse_rollback(unterminated).as_deref(), Some("apt remove -y foo\n"));
+        assert_eq!(parse_rollback("#!/bin/sh\necho hi\n"), None);
+    }
+
+    #[test]
+    fn test_parse_dependency_directives() {
+        let script = "#!/usr/bin/env python3\n#@pip requests rich>=13\nimport requests\n#@pip pyyaml\n#@pipx ignored\n";
+        let meta = parse(script);
+        assert_eq!(meta.pip, vec!["requests", "rich>=13", "pyyaml"]);
+        assert!(meta.npm.is_empty());
+        assert_eq!(parse("// x\n// #@npm lodash\n").npm, vec!["lodash"]);
+    }
+}
@@ -1,5 +1,14 @@
+pub mod catalog;
+pub mod helpers;
+pub mod link;
+pub mod lock;
+pub mod metadata;
+pub mod templates;
+
 use crate::fileio;
-use std::{collections::{HashMap, HashSet}, env, io, path::PathBuf, sync::Mutex};
+use crate::errors::GeekToolsError;
+use std::{borrow::Cow, collections::{HashMap, HashSet}, env, io, path::PathBuf, sync::Mutex, thread};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use once_cell::sync::Lazy;
 use rust_embed::RustEmbed;
@@ -13,12 +22,15 @@ pub struct ScriptInfo {
     pub link: Option<String>,
 }
 
-/// 嵌入 scripts 目录下的全部文件
+/// 嵌入 scripts 目录下的全部文件；分语言的描述由 build.rs 合并，不单独嵌入
 #[derive(RustEmbed)]
 #[folder = "src/scripts/"]
-// 若将来想排除临时文件，可加 exclude = ["*.tmp"]
+#[exclude = "i18n/*"]
 struct Assets;
 
+/// build.rs 由 `src/scripts/i18n/<语言>.json` 合并出的内置脚本描述：`{"脚本名": {"en": "...", "zh": "..."}}`
+const EMBEDDED_INFO: &str = include_str!(concat!(env!("OUT_DIR"), "/script_info.json"));
+
 /// 脚本存储目录 ~/.geektools/scripts/
 static SCRIPTS_DIR: Lazy<PathBuf> = Lazy::new(|| {
     let home = env::var("HOME").unwrap_or_else(|_| ".".to_string());
@@ -28,21 +40,33 @@ static SCRIPTS_DIR: Lazy<PathBuf> = Lazy::new(|| {
     dir
 });
 
-/// 依赖解析缓存，避免重复计算
-static DEPENDENCY_CACHE: Lazy<Mutex<HashMap<String, Vec<String>>>> = Lazy::new(|| {
+/// 依赖解析缓存：主脚本 → 解析结果，参与解析的脚本都没变时直接复用
+static DEPENDENCY_CACHE: Lazy<Mutex<HashMap<String, ResolvedGraph>>> = Lazy::new(|| {
+    Mutex::new(HashMap::new())
+});
+
+/// 按内容的 SHA-256 缓存解析出的导入列表，多个主脚本共用的依赖只解析一次
+static IMPORT_CACHE: Lazy<Mutex<HashMap<String, Vec<String>>>> = Lazy::new(|| {
     Mutex::new(HashMap::new())
 });
 
+#[derive(Clone)]
+struct ResolvedGraph {
+    /// 参与解析的每个脚本的版本标识 (见 [`ScriptSource::stamp`])
+    stamps: HashMap<String, String>,
+    order: Vec<String>,
+}
+
 /// 创建脚本信息并保存到 info.json
 fn create_script_info(name: &str) -> io::Result<ScriptInfo> {
-    // 从现有的 info.json 读取描述信息
-    let info_content = get_string("info.json").unwrap_or_default();
+    // 从内置脚本描述读取
+    let info_content = script_info().unwrap_or_default();
     let existing_info: serde_json::Value = serde_json::from_str(&info_content)
         .unwrap_or(serde_json::Value::Object(serde_json::Map::new()));
     
     let script_info = if let Some(info) = existing_info.get(name) {
-        let english_desc = info.get("English").and_then(|v| v.as_str()).unwrap_or(name);
-        let chinese_desc = info.get("Chinese").and_then(|v| v.as_str()).unwrap_or(name);
+        let english_desc = catalog::localized(info, "en").unwrap_or(name);
+        let chinese_desc = catalog::localized(info, "zh").unwrap_or(name);
         let description = format!("{} / {}", english_desc, chinese_desc);
         
         // 如果是 .link 文件，设置链接
@@ -68,13 +92,28 @@ fn create_script_info(name: &str) -> io::Result<ScriptInfo> {
     Ok(script_info)
 }
 
-/// 把指定脚本写到 ~/.geektools/scripts/(脚本名)/ 目录并返回可执行路径
+/// 把指定脚本写到 ~/.geektools/scripts/(脚本名)/ 目录并返回可执行路径；插件和自定义脚本直接返回原文件
 pub fn materialize(name: &str) -> crate::errors::Result<PathBuf> {
-    // 1) 从 embed 中取二进制内容
-    let data = Assets::get(name).ok_or_else(|| crate::errors::GeekToolsError::FileOperationError {
-        path: name.to_string(),
-        source: io::Error::new(io::ErrorKind::NotFound, "Asset not found"),
-    })?;
+    match external_script(name) {
+        Some(path) => path,
+        None => materialize_embedded(name),
+    }
+}
+
+/// 把内置脚本写到 ~/.geektools/scripts/(脚本名)/ 目录
+fn materialize_embedded(name: &str) -> crate::errors::Result<PathBuf> {
+    // 1) 从 embed 中取二进制内容；.link 优先使用脚本目录中更新过的地址
+    let link = name.ends_with(".link").then(|| link_spec(name)).flatten();
+    let data: Cow<'static, [u8]> = match (link, Assets::get(name)) {
+        (Some(spec), _) => Cow::Owned(serde_json::to_vec_pretty(&spec)?),
+        (None, Some(file)) => file.data,
+        (None, None) => {
+            return Err(crate::errors::GeekToolsError::FileOperationError {
+                path: name.to_string(),
+                source: io::Error::new(io::ErrorKind::NotFound, "Asset not found"),
+            });
+        }
+    };
 
     // 2) 创建脚本专用目录 ~/.geektools/scripts/(脚本名)/
     let script_name = name.split('.').next().unwrap_or(name);
@@ -83,8 +122,8 @@ pub fn materialize(name: &str) -> crate::errors::Result<PathBuf> {
     
     // 3) 写入脚本文件
     let dest = script_dir.join(name);
-    if !dest.exists() {
-        fileio::write_bytes(&dest, data.data.as_ref())?;
+    if !dest.exists() || name.ends_with(".link") {
+        fileio::write_bytes(&dest, data.as_ref())?;
         // 4) chmod +x （Unix；Windows 会忽略）
         #[cfg(unix)]
         {
@@ -104,22 +143,33 @@ pub fn materialize(name: &str) -> crate::errors::Result<PathBuf> {
     
     Ok(dest)
 }
+/// 内置脚本描述与已下载脚本目录合并后的脚本信息，描述按语言代码存放，用 [`catalog::localized`] 读取
+pub fn script_info() -> Option<String> {
+    let merged = catalog::merged_info(EMBEDDED_INFO, catalog::load_cached().as_ref());
+    serde_json::to_string(&merged).ok()
+}
+
 pub fn get_string(name: &str) -> Option<String> {
     Assets::get(name).map(|data| String::from_utf8_lossy(data.data.as_ref()).into_owned())
 }
 
-/// 解析脚本中的导入声明
-fn parse_imports(content: &str) -> Vec<String> {
-    content
-        .lines()
-        .filter_map(|line| {
-            let trimmed = line.trim();
-            if trimmed.starts_with("#@import ") {
-                Some(trimmed[9..].trim().to_string())
-            } else {
-                None
-            }
-        })
+/// 内置 `.link` 脚本的内容，合并脚本目录中更新过的地址和校验值
+pub fn link_spec(name: &str) -> Option<link::LinkSpec> {
+    let embedded = get_string(name).and_then(|content| link::LinkSpec::parse(&content).ok());
+    let catalog = catalog::load_cached();
+    match catalog.as_ref().and_then(|c| c.link_for(name)) {
+        Some((url, sha256)) => Some(embedded.unwrap_or_default().with_catalog_link(url, sha256)),
+        None => embedded,
+    }
+}
+
+/// 内置 `.link` 脚本及其目标地址 (含镜像)，每个地址一项
+#[cfg(feature = "network")]
+pub fn links() -> Vec<(String, String)> {
+    Assets::iter()
+        .filter(|name| name.ends_with(".link"))
+        .filter_map(|name| Some((name.to_string(), link_spec(&name)?)))
+        .flat_map(|(name, spec)| spec.urls().map(|url| (name.clone(), url.to_string())).collect::<Vec<_>>())
         .collect()
 }
 
@@ -162,7 +212,7 @@ fn detect_cycles(deps: &HashMap<String, Vec<String>>) -> Result<(), String> {
 }
 
 /// 拓扑排序，返回执行顺序
-fn topological_sort(deps: &HashMap<String, Vec<String>>) -> Result<Vec<String>, String> {
+pub fn topological_sort(deps: &HashMap<String, Vec<String>>) -> Result<Vec<String>, String> {
     detect_cycles(deps)?;
     
     let mut in_degree: HashMap<String, usize> = HashMap::new();
@@ -208,16 +258,41 @@ fn topological_sort(deps: &HashMap<String, Vec<String>>) -> Result<Vec<String>,
     Ok(result)
 }
 
-/// 递归解析脚本及其依赖，带缓存优化
-fn resolve_dependencies(script_name: &str) -> Result<Vec<String>, String> {
-    // 检查缓存
-    if let Ok(cache) = DEPENDENCY_CACHE.lock() {
-        if let Some(cached_result) = cache.get(script_name) {
-            return Ok(cached_result.clone());
-        }
+/// 依赖解析读取脚本的途径
+trait ScriptSource {
+    /// 不读取内容就能得到的版本标识，内容变化时随之变化；找不到脚本时为 None
+    fn stamp(&self, name: &str) -> Option<String>;
+    fn content(&self, name: &str) -> Option<String>;
+}
+
+/// 内容的哈希和其中的导入声明
+fn parsed_imports(content: &str) -> (String, Vec<String>) {
+    let hash = crate::network::sha256_str(content);
+    if let Some(imports) = IMPORT_CACHE.lock().ok().and_then(|cache| cache.get(&hash).cloned()) {
+        return (hash, imports);
+    }
+    let imports = metadata::parse_imports(content);
+    if let Ok(mut cache) = IMPORT_CACHE.lock() {
+        cache.insert(hash.clone(), imports.clone());
+    }
+    (hash, imports)
+}
+
+/// 缓存的执行顺序；任一脚本变了 (如自定义脚本被编辑) 或找不到时失效
+fn cached_order(script_name: &str, source: &dyn ScriptSource) -> Option<Vec<String>> {
+    // 先复制出来再检查脚本，查找插件和自定义脚本时不持有锁
+    let graph = DEPENDENCY_CACHE.lock().ok()?.get(script_name).cloned()?;
+    let unchanged = graph.stamps.iter().all(|(name, stamp)| source.stamp(name).is_some_and(|current| current == *stamp));
+    unchanged.then_some(graph.order)
+}
+
+fn resolve_with(script_name: &str, source: &dyn ScriptSource) -> Result<Vec<String>, String> {
+    if let Some(order) = cached_order(script_name, source) {
+        return Ok(order);
     }
     
     let mut deps = HashMap::new();
+    let mut stamps = HashMap::new();
     let mut to_process = vec![script_name.to_string()];
     let mut processed = HashSet::new();
     
@@ -226,10 +301,15 @@ fn resolve_dependencies(script_name: &str) -> Result<Vec<String>, String> {
             continue;
         }
         
-        let content = get_string(&current)
+        // 先取标识再读内容：两次之间脚本被修改时缓存的是旧标识，下次会重新解析
+        let stamp = source.stamp(&current);
+        let content = source.content(&current)
             .ok_or_else(|| format!("Script not found: {}", current))?;
         
-        let imports = parse_imports(&content);
+        let (_, imports) = parsed_imports(&content);
+        if let Some(stamp) = stamp {
+            stamps.insert(current.clone(), stamp);
+        }
         deps.insert(current.clone(), imports.clone());
         
         for import in imports {
@@ -245,27 +325,315 @@ fn resolve_dependencies(script_name: &str) -> Result<Vec<String>, String> {
     
     // 缓存结果
     if let Ok(mut cache) = DEPENDENCY_CACHE.lock() {
-        cache.insert(script_name.to_string(), result.clone());
+        cache.insert(script_name.to_string(), ResolvedGraph { stamps, order: result.clone() });
     }
     
     Ok(result)
 }
 
-/// 把脚本及其依赖按顺序写到 ~/.geektools/scripts/ 目录并返回执行顺序
+/// 插件脚本的导入：`#@import plugin:<插件 id>/<脚本名或文件名>`
+const PLUGIN_IMPORT: &str = "plugin:";
+/// 自定义脚本的导入：`#@import custom:<名称>`
+const CUSTOM_IMPORT: &str = "custom:";
+
+fn is_namespaced(name: &str) -> bool {
+    name.starts_with(PLUGIN_IMPORT) || name.starts_with(CUSTOM_IMPORT)
+}
+
+/// 带命名空间的导入指向已经在磁盘上的插件脚本或自定义脚本 (只查找已启用的)；内置脚本返回 None
+fn external_script(name: &str) -> Option<crate::errors::Result<PathBuf>> {
+    ScriptLookup::default().external(name)
+}
+
+/// 查找脚本：用户配置和插件注册表在第一次用到时各读取一次，同一个查找器查找多个导入时共用
+#[derive(Default)]
+struct ScriptLookup {
+    config: once_cell::unsync::OnceCell<crate::config::Config>,
+    #[cfg(feature = "plugins")]
+    plugins: once_cell::unsync::OnceCell<crate::plugins::PluginManager>,
+}
+
+impl ScriptLookup {
+    /// 同 [`external_script`]
+    fn external(&self, name: &str) -> Option<crate::errors::Result<PathBuf>> {
+        let not_found = |message: String| GeekToolsError::ValidationError { field: "import".to_string(), message };
+        if let Some(reference) = name.strip_prefix(PLUGIN_IMPORT) {
+            return Some(self.plugin_script(reference).ok_or_else(|| not_found(format!("no enabled plugin script: {}", reference))));
+        }
+        let custom = name.strip_prefix(CUSTOM_IMPORT)?;
+        let config = self.config.get_or_init(crate::config::load_user_config);
+        let path = config
+            .custom_scripts
+            .iter()
+            .find(|script| script.enabled && script.name == custom)
+            .and_then(|script| script.file_path.as_ref().map(PathBuf::from));
+        Some(path.ok_or_else(|| not_found(format!("no enabled custom script: {}", custom))))
+    }
+
+    #[cfg(feature = "plugins")]
+    fn plugin_script(&self, reference: &str) -> Option<PathBuf> {
+        let (plugin_id, script) = reference.split_once('/')?;
+        self.plugins.get_or_init(crate::plugins::PluginManager::new).find_enabled_script(plugin_id, script)
+    }
+
+    // 未编译插件功能时没有插件脚本可以导入
+    #[cfg(not(feature = "plugins"))]
+    fn plugin_script(&self, _reference: &str) -> Option<PathBuf> {
+        None
+    }
+}
+
+/// 内置脚本来自 embed，标识为编译时记录的摘要；插件和自定义脚本从磁盘读取，标识为路径、大小和修改时间
+impl ScriptSource for ScriptLookup {
+    fn stamp(&self, name: &str) -> Option<String> {
+        match self.external(name) {
+            Some(path) => {
+                let path = path.ok()?;
+                let metadata = std::fs::metadata(&path).ok()?;
+                Some(format!("{}:{}:{:?}", path.display(), metadata.len(), metadata.modified().ok()?))
+            }
+            None => Assets::get(name).map(|file| file.metadata.sha256_hash().iter().map(|b| format!("{:02x}", b)).collect()),
+        }
+    }
+
+    fn content(&self, name: &str) -> Option<String> {
+        match self.external(name) {
+            Some(path) => fileio::read(path.ok()?).ok(),
+            None => get_string(name),
+        }
+    }
+}
+
+/// 外部脚本 (如自定义脚本) 中 `#@import` 的依赖：物化后按执行顺序返回，不含脚本本身
+pub fn materialize_imports(path: &std::path::Path) -> crate::errors::Result<Vec<PathBuf>> {
+    let content = fileio::read(path)?;
+    let lookup = ScriptLookup::default();
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for import in metadata::parse_imports(&content) {
+        for dependency in materialize_using(&import, &lookup)? {
+            if !paths.contains(&dependency) {
+                paths.push(dependency);
+            }
+        }
+    }
+    Ok(paths)
+}
+
+/// 并行物化依赖时的最大线程数
+const MAX_MATERIALIZE_WORKERS: usize = 8;
+
+/// 把脚本及其依赖写到 ~/.geektools/scripts/ 目录并返回执行顺序；依赖之间互不影响，并行写入
 pub fn materialize_with_deps(name: &str) -> crate::errors::Result<Vec<PathBuf>> {
-    let execution_order = resolve_dependencies(name)
-        .map_err(|e| crate::errors::GeekToolsError::ConfigError { message: e })?;
+    materialize_using(name, &ScriptLookup::default())
+}
+
+/// 同 [`materialize_with_deps`]，解析和物化共用 `lookup`，用户配置和插件注册表只读取一次
+fn materialize_using(name: &str, lookup: &ScriptLookup) -> crate::errors::Result<Vec<PathBuf>> {
+    let execution_order = resolve_with(name, lookup)
+        .map_err(|e| GeekToolsError::ConfigError { message: e })?;
     
+    // 所有脚本都要物化，包括 .link 文件用于信息存储
+    let results = materialize_all(&execution_order, lookup);
+    let dependencies: Vec<(String, PathBuf)> = execution_order
+        .iter()
+        .zip(&results)
+        .filter(|(script_name, _)| script_name.as_str() != name)
+        .filter_map(|(script_name, result)| Some((script_name.clone(), result.as_ref().ok()?.clone())))
+        .collect();
+    let paths = execution_paths(&execution_order, results)?;
+    verify_lock(name, &dependencies);
+    Ok(paths)
+}
+
+/// 第一次运行带依赖的脚本时写入 deps.lock，之后每次运行与它比较，依赖变化时给出警告 (不阻止运行)
+fn verify_lock(name: &str, dependencies: &[(String, PathBuf)]) {
+    if dependencies.is_empty() {
+        return;
+    }
+    let script_dir = SCRIPTS_DIR.join(name.split('.').next().unwrap_or(name).replace([':', '/'], "_"));
+    let path = script_dir.join(lock::FILE_NAME);
+    let current = match lock::DepsLock::from_files(name, dependencies) {
+        Ok(current) => current,
+        Err(e) => {
+            crate::log_only!("WARN", "DEPS", "无法计算 {} 的依赖哈希: {}", name, e);
+            return;
+        }
+    };
+    let Some(locked) = lock::DepsLock::load(&path) else {
+        if let Err(e) = current.save(&path) {
+            crate::log_only!("WARN", "DEPS", "写入 {} 失败: {}", path.display(), e);
+        }
+        return;
+    };
+    let drift = locked.drift(&current);
+    if drift.is_empty() {
+        return;
+    }
+    let details: Vec<String> = drift.iter().map(|d| format!("  - {}", d)).collect();
+    crate::log_only!("WARN", "DEPS", "{} 的依赖与 {} 不一致: {}", name, path.display(), details.join("; "));
+    crate::terminal::write_str(&format!(
+        "⚠️ {} 的依赖与锁定时不同:\n{}\n   删除 {} 可接受当前版本\n",
+        name,
+        details.join("\n"),
+        path.display()
+    ));
+}
+
+/// 用有界线程池物化多个脚本，结果顺序与输入一致。插件和自定义脚本在当前线程用 `lookup` 查找，
+/// 只有内置脚本需要写入；写入同一目录 (同名不同扩展名，共用 info.json) 的脚本由同一线程按顺序处理
+fn materialize_all(names: &[String], lookup: &ScriptLookup) -> Vec<crate::errors::Result<PathBuf>> {
+    let mut results: Vec<Option<crate::errors::Result<PathBuf>>> = names.iter().map(|name| lookup.external(name)).collect();
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut group_of_dir: HashMap<&str, usize> = HashMap::new();
+    for (i, name) in names.iter().enumerate().filter(|&(i, _)| results[i].is_none()) {
+        let dir = name.split('.').next().unwrap_or(name);
+        let group = *group_of_dir.entry(dir).or_insert_with(|| {
+            groups.push(Vec::new());
+            groups.len() - 1
+        });
+        groups[group].push(i);
+    }
+
+    let workers = thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(MAX_MATERIALIZE_WORKERS)
+        .min(groups.len());
+    if workers < 2 {
+        for &i in groups.iter().flatten() {
+            results[i] = Some(materialize_embedded(&names[i]));
+        }
+    } else {
+        let next = AtomicUsize::new(0);
+        thread::scope(|s| {
+            let handles: Vec<_> = (0..workers)
+                .map(|_| {
+                    s.spawn(|| {
+                        let mut done = Vec::new();
+                        while let Some(group) = groups.get(next.fetch_add(1, Ordering::Relaxed)) {
+                            done.extend(group.iter().map(|&i| (i, materialize_embedded(&names[i]))));
+                        }
+                        done
+                    })
+                })
+                .collect();
+            for handle in handles {
+                // 线程 panic 时它处理的脚本没有结果，下面按失败处理
+                for (i, result) in handle.join().unwrap_or_default() {
+                    results[i] = Some(result);
+                }
+            }
+        });
+    }
+    results
+        .into_iter()
+        .zip(names)
+        .map(|(result, name)| {
+            result.unwrap_or_else(|| {
+                Err(GeekToolsError::ConfigError { message: format!("materializing {} panicked", name) })
+            })
+        })
+        .collect()
+}
+
+/// 汇总物化结果：只有 .sh 脚本和插件、自定义脚本加入执行路径；多个脚本失败时合并成一个错误，逐个列出
+fn execution_paths(names: &[String], results: Vec<crate::errors::Result<PathBuf>>) -> crate::errors::Result<Vec<PathBuf>> {
     let mut paths = Vec::new();
-    
-    for script_name in execution_order {
-        // 所有脚本都要物化，包括 .link 文件用于信息存储
-        let path = materialize(&script_name)?;
-        // 但只有 .sh 脚本才加入执行路径
-        if script_name.ends_with(".sh") {
-            paths.push(path);
+    let mut failures = Vec::new();
+    for (name, result) in names.iter().zip(results) {
+        match result {
+            Ok(path) if name.ends_with(".sh") || is_namespaced(name) => paths.push(path),
+            Ok(_) => {}
+            Err(e) => failures.push((name, e)),
         }
     }
-    
-    Ok(paths)
+    if failures.len() > 1 {
+        let details: Vec<String> = failures.iter().map(|(name, e)| format!("{}: {}", name, e)).collect();
+        return Err(GeekToolsError::ConfigError {
+            message: format!("failed to materialize {} scripts: {}", failures.len(), details.join("; ")),
+        });
+    }
+    match failures.pop() {
+        Some((_, e)) => Err(e),
+        None => Ok(paths),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_materialize_all_keeps_order_and_reports_each_failure() {
+        let names: Vec<String> = ["missing_a.sh", "missing_b.link", "missing_a.link"].map(String::from).to_vec();
+        let results = materialize_all(&names, &ScriptLookup::default());
+        assert_eq!(results.len(), 3);
+        for (name, result) in names.iter().zip(&results) {
+            assert!(matches!(result, Err(GeekToolsError::FileOperationError { path, .. }) if path == name));
+        }
+
+        let message = execution_paths(&names, results).unwrap_err().to_string();
+        assert!(message.contains("failed to materialize 3 scripts"), "{}", message);
+        assert!(message.contains("missing_b.link: "), "{}", message);
+    }
+
+    #[test]
+    fn test_execution_paths_keeps_shell_scripts() {
+        let names: Vec<String> = ["a.link", "a.sh", "b.sh"].map(String::from).to_vec();
+        let results = names.iter().map(|n| Ok(PathBuf::from(n))).collect();
+        assert_eq!(execution_paths(&names, results).unwrap(), [PathBuf::from("a.sh"), PathBuf::from("b.sh")]);
+
+        let names: Vec<String> = ["plugin:net/ping", "custom:backup", "main.sh"].map(String::from).to_vec();
+        let results = ["/p/ping.sh", "/c/custom_1", "/s/main.sh"].map(|p| Ok(PathBuf::from(p))).into_iter().collect();
+        assert_eq!(execution_paths(&names, results).unwrap().len(), 3);
+        assert!(external_script("main.sh").is_none());
+
+        let results = vec![Ok(PathBuf::from("a.link")), Err(GeekToolsError::PermissionError { operation: "write".into() }), Ok(PathBuf::from("b.sh"))];
+        assert!(matches!(execution_paths(&names, results), Err(GeekToolsError::PermissionError { .. })));
+    }
+
+    /// 内存中的脚本，标识为修改次数；记录读取内容的次数
+    struct MemorySource {
+        scripts: Mutex<HashMap<String, (u32, String)>>,
+        reads: AtomicUsize,
+    }
+
+    impl MemorySource {
+        fn edit(&self, name: &str, content: &str) {
+            let mut scripts = self.scripts.lock().unwrap();
+            let version = scripts.get(name).map_or(0, |(version, _)| version + 1);
+            scripts.insert(name.to_string(), (version, content.to_string()));
+        }
+    }
+
+    impl ScriptSource for MemorySource {
+        fn stamp(&self, name: &str) -> Option<String> {
+            self.scripts.lock().unwrap().get(name).map(|(version, _)| version.to_string())
+        }
+
+        fn content(&self, name: &str) -> Option<String> {
+            self.reads.fetch_add(1, Ordering::Relaxed);
+            self.scripts.lock().unwrap().get(name).map(|(_, content)| content.clone())
+        }
+    }
+
+    #[test]
+    fn test_resolution_cache_follows_content() {
+        let source = MemorySource { scripts: Mutex::new(HashMap::new()), reads: AtomicUsize::new(0) };
+        source.edit("cache_root.sh", "#@import cache_a.sh\necho root\n");
+        source.edit("cache_a.sh", "echo a\n");
+        source.edit("cache_b.sh", "echo b\n");
+        assert_eq!(resolve_with("cache_root.sh", &source).unwrap(), ["cache_root.sh", "cache_a.sh"]);
+        // 命中缓存时只比较标识，不读取内容
+        let reads = source.reads.load(Ordering::Relaxed);
+        assert!(cached_order("cache_root.sh", &source).is_some());
+        assert_eq!(source.reads.load(Ordering::Relaxed), reads);
+
+        // 依赖被编辑了：缓存失效，重新解析出新的导入
+        source.edit("cache_a.sh", "#@import cache_b.sh\n");
+        assert!(cached_order("cache_root.sh", &source).is_none());
+        assert_eq!(resolve_with("cache_root.sh", &source).unwrap(), ["cache_root.sh", "cache_a.sh", "cache_b.sh"]);
+
+        let (hash, imports) = parsed_imports("#@import cache_b.sh\n");
+        assert_eq!(IMPORT_CACHE.lock().unwrap().get(&hash), Some(&imports));
+    }
 }
@@ -0,0 +1,120 @@
+/// 新建脚本时可选的模板
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemplateKind {
+    Basic,
+    WithParams,
+    WithRollback,
+    PlatformSwitch,
+}
+
+impl TemplateKind {
+    pub const ALL: [TemplateKind; 4] = [
+        TemplateKind::Basic,
+        TemplateKind::WithParams,
+        TemplateKind::WithRollback,
+        TemplateKind::PlatformSwitch,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TemplateKind::Basic => "基础脚本",
+            TemplateKind::WithParams => "带参数",
+            TemplateKind::WithRollback => "带回滚段",
+            TemplateKind::PlatformSwitch => "按平台分支",
+        }
+    }
+}
+
+/// 生成带 geektools 元数据头部的脚本
+pub fn render(kind: TemplateKind, name: &str, description: &str) -> String {
+    let mut header = format!(
+        "#!/usr/bin/env bash\n# Name: {}\n# Description: {}\n# Version: 0.1.0\n",
+        name, description
+    );
+    let body = match kind {
+        TemplateKind::Basic => "\
+set -euo pipefail
+
+echo \"=== $0 ===\"
+# TODO: 在这里编写脚本逻辑
+echo \"✅ 完成\"
+",
+        TemplateKind::WithParams => {
+            header.push_str("# Param: TARGET=$HOME - 要处理的目录\n# Param: DAYS=7 - 保留天数\n");
+            "\
+set -euo pipefail
+
+TARGET=\"${1:-$HOME}\"
+DAYS=\"${2:-7}\"
+
+if [ ! -d \"$TARGET\" ]; then
+    echo \"❌ 目录不存在: $TARGET\"
+    exit 1
+fi
+
+echo \"处理 $TARGET (保留 $DAYS 天)\"
+# TODO: 在这里编写脚本逻辑
+echo \"✅ 完成\"
+"
+        }
+        TemplateKind::WithRollback => "\
+set -euo pipefail
+
+#@rollback
+# echo \"撤销: 删除安装的文件\"
+# rm -f \"$HOME/.local/bin/example\"
+#@end
+
+# TODO: 在这里编写安装逻辑，并在上面的回滚段中写出对应的撤销命令
+mkdir -p \"$HOME/.local/bin\"
+echo \"✅ 完成\"
+",
+        TemplateKind::PlatformSwitch => {
+            header.push_str("# Platforms: macos, linux\n");
+            "\
+set -euo pipefail
+
+case \"$(uname -s)\" in
+    Darwin)
+        echo \"macOS\"
+        # TODO: macOS 逻辑
+        ;;
+    Linux)
+        echo \"Linux\"
+        # TODO: Linux 逻辑
+        ;;
+    *)
+        echo \"❌ 不支持的系统: $(uname -s)\"
+        exit 1
+        ;;
+esac
+"
+        }
+    };
+    format!("{}\n{}", header, body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scripts::metadata;
+
+    #[test]
+    fn test_templates_have_valid_metadata() {
+        for kind in TemplateKind::ALL {
+            let meta = metadata::parse(&render(kind, "demo", "演示脚本"));
+            assert_eq!(meta.name.as_deref(), Some("demo"), "{:?}", kind);
+            assert_eq!(meta.description(None), Some("演示脚本"), "{:?}", kind);
+        }
+
+        let meta = metadata::parse(&render(TemplateKind::WithParams, "demo", "d"));
+        assert_eq!(meta.params.len(), 2);
+        assert_eq!(meta.params[1].default.as_deref(), Some("7"));
+
+        let meta = metadata::parse(&render(TemplateKind::WithRollback, "demo", "d"));
+        assert!(meta.rollback.unwrap().contains("rm -f"));
+
+        let meta = metadata::parse(&render(TemplateKind::PlatformSwitch, "demo", "d"));
+        assert_eq!(meta.platforms, vec!["macos", "linux"]);
+    }
+}
@@ -0,0 +1,120 @@
+use std::cmp::Reverse;
+
+/// 搜索结果的来源，排序时同分按此顺序
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Source {
+    Builtin,
+    Custom,
+    Plugin,
+    #[cfg(feature = "marketplace")]
+    Marketplace,
+}
+
+impl Source {
+    /// 来源标签的翻译键
+    pub fn label_key(self) -> &'static str {
+        match self {
+            Self::Builtin => "search.source_builtin",
+            Self::Custom => "search.source_custom",
+            Self::Plugin => "search.source_plugin",
+            #[cfg(feature = "marketplace")]
+            Self::Marketplace => "search.source_marketplace",
+        }
+    }
+}
+
+/// 一条搜索结果；`index` 为在对应来源列表中的位置
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hit {
+    pub source: Source,
+    pub index: usize,
+    pub name: String,
+    pub description: String,
+    score: u32,
+}
+
+impl Hit {
+    /// 不匹配 `query` 时返回 None
+    pub fn matching(query: &str, source: Source, index: usize, name: &str, description: &str) -> Option<Self> {
+        let score = score(query, name, description)?;
+        Some(Self { source, index, name: name.to_string(), description: description.to_string(), score })
+    }
+
+    /// 远程已经按关键词过滤过的结果 (可能匹配了标签等本地看不到的字段)，不匹配时计 0 分
+    #[cfg(feature = "marketplace")]
+    pub fn remote(query: &str, source: Source, index: usize, name: &str, description: &str) -> Self {
+        let score = score(query, name, description).unwrap_or(0);
+        Self { source, index, name: name.to_string(), description: description.to_string(), score }
+    }
+}
+
+/// 查询中的每个词 (不区分大小写) 都要出现在名称或描述中；
+/// 名称完全相同、以词开头、包含词分别计 3、2、1 分，只在描述中出现计 0 分
+pub fn score(query: &str, name: &str, description: &str) -> Option<u32> {
+    let name = name.to_lowercase();
+    let description = description.to_lowercase();
+    let terms: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+    if terms.is_empty() {
+        return None;
+    }
+    let mut total = 0;
+    for term in &terms {
+        total += if name == *term {
+            3
+        } else if name.starts_with(term.as_str()) {
+            2
+        } else if name.contains(term.as_str()) {
+            1
+        } else if description.contains(term.as_str()) {
+            0
+        } else {
+            return None;
+        };
+    }
+    Some(total)
+}
+
+/// 合并后的结果：分数高的在前，同分按来源、再按名称
+pub fn rank(mut hits: Vec<Hit>) -> Vec<Hit> {
+    hits.sort_by(|a, b| {
+        (Reverse(a.score), a.source, &a.name).cmp(&(Reverse(b.score), b.source, &b.name))
+    });
+    hits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score_requires_every_term() {
+        assert_eq!(score("docker", "docker", ""), Some(3));
+        assert_eq!(score("Dock", "docker-clean", ""), Some(2));
+        assert_eq!(score("clean", "docker-clean", ""), Some(1));
+        assert_eq!(score("cache", "cleanup.sh", "Clean caches"), Some(0));
+        assert_eq!(score("docker cache", "docker-clean", "remove build cache"), Some(2));
+        assert_eq!(score("docker nginx", "docker-clean", "remove build cache"), None);
+        assert_eq!(score("  ", "docker", ""), None);
+    }
+
+    #[cfg(feature = "marketplace")]
+    #[test]
+    fn test_rank_merges_sources() {
+        let hits = vec![
+            Hit::matching("disk", Source::Marketplace, 0, "disk-kit", "").unwrap(),
+            Hit::matching("disk", Source::Builtin, 4, "cleanup.sh", "free disk space").unwrap(),
+            Hit::matching("disk", Source::Plugin, 0, "disk", "").unwrap(),
+            Hit::matching("disk", Source::Custom, 1, "disk-report", "").unwrap(),
+        ];
+        let ranked: Vec<(Source, String)> = rank(hits).into_iter().map(|h| (h.source, h.name)).collect();
+        assert_eq!(
+            ranked,
+            vec![
+                (Source::Plugin, "disk".to_string()),
+                (Source::Custom, "disk-report".to_string()),
+                (Source::Marketplace, "disk-kit".to_string()),
+                (Source::Builtin, "cleanup.sh".to_string()),
+            ]
+        );
+    }
+}
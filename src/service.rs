@@ -0,0 +1,360 @@
+use crate::log_only;
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
+
+/// systemd 单元名前缀 (`geektools-<id>.service`)
+const UNIT_PREFIX: &str = "geektools-";
+/// launchd 标签前缀 (`com.geektools.<id>`)
+const LABEL_PREFIX: &str = "com.geektools.";
+
+/// 服务管理器
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// Linux 用户级 systemd 单元
+    Systemd,
+    /// macOS LaunchAgent
+    Launchd,
+}
+
+impl Backend {
+    /// 当前平台的服务管理器，不支持时返回 None
+    pub fn current() -> Option<Self> {
+        match env::consts::OS {
+            "linux" => Some(Backend::Systemd),
+            "macos" => Some(Backend::Launchd),
+            _ => None,
+        }
+    }
+
+    fn unit_dir(&self) -> PathBuf {
+        let home = PathBuf::from(env::var("HOME").unwrap_or_else(|_| ".".into()));
+        match self {
+            Backend::Systemd => home.join(".config").join("systemd").join("user"),
+            Backend::Launchd => home.join("Library").join("LaunchAgents"),
+        }
+    }
+
+    fn unit_name(&self, id: &str) -> String {
+        match self {
+            Backend::Systemd => format!("{}{}.service", UNIT_PREFIX, id),
+            Backend::Launchd => format!("{}{}.plist", LABEL_PREFIX, id),
+        }
+    }
+
+    pub fn unit_file(&self, id: &str) -> PathBuf {
+        self.unit_dir().join(self.unit_name(id))
+    }
+}
+
+/// 由脚本生成的常驻服务
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServiceSpec {
+    /// 服务标识，由脚本名生成 (见 [`service_id`])
+    pub id: String,
+    pub description: String,
+    /// 解释器及其参数，最后一项是脚本路径
+    pub command: Vec<String>,
+    pub working_dir: PathBuf,
+    /// 异常退出后自动重启
+    pub restart_on_failure: bool,
+}
+
+impl ServiceSpec {
+    /// 服务输出的日志文件：~/.geektools/services/<id>.log
+    pub fn log_file(&self) -> PathBuf {
+        let home = env::var("HOME").unwrap_or_else(|_| ".".into());
+        PathBuf::from(home).join(".geektools").join("services").join(format!("{}.log", self.id))
+    }
+}
+
+/// 把脚本名转换为可用作单元名的标识 (小写字母、数字和 `-`)；
+/// 含非 ASCII 字符的名称在转换时会丢失这些字符，加上名称摘要的前 8 位以免互相覆盖
+pub fn service_id(name: &str) -> String {
+    let mut id = String::new();
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            id.push(c.to_ascii_lowercase());
+        } else if !id.ends_with('-') {
+            id.push('-');
+        }
+    }
+    let id = id.trim_matches('-');
+    let id = if id.is_empty() { "script" } else { id };
+    if name.is_ascii() {
+        return id.to_string();
+    }
+    let digest = Sha256::digest(name.as_bytes());
+    let suffix: String = digest[..4].iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}-{}", id, suffix)
+}
+
+/// systemd 的 ExecStart 参数转义
+fn systemd_quote(arg: &str) -> String {
+    let escaped = arg.replace('\\', "\\\\").replace('"', "\\\"").replace('%', "%%");
+    if escaped.chars().any(|c| c.is_whitespace()) || escaped.is_empty() {
+        format!("\"{}\"", escaped)
+    } else {
+        escaped
+    }
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// 生成 systemd 用户单元
+pub fn render_systemd_unit(spec: &ServiceSpec) -> String {
+    let exec: Vec<String> = spec.command.iter().map(|a| systemd_quote(a)).collect();
+    let log = spec.log_file();
+    format!(
+        "[Unit]\n\
+         Description=GeekTools: {}\n\
+         After=network-online.target\n\
+         \n\
+         [Service]\n\
+         Type=simple\n\
+         ExecStart={}\n\
+         WorkingDirectory={}\n\
+         Restart={}\n\
+         StandardOutput=append:{}\n\
+         StandardError=append:{}\n\
+         \n\
+         [Install]\n\
+         WantedBy=default.target\n",
+        spec.description,
+        exec.join(" "),
+        systemd_quote(&spec.working_dir.to_string_lossy()),
+        if spec.restart_on_failure { "on-failure" } else { "no" },
+        log.display(),
+        log.display()
+    )
+}
+
+/// 生成 launchd 的 plist
+pub fn render_launchd_plist(spec: &ServiceSpec) -> String {
+    let args: String = spec
+        .command
+        .iter()
+        .map(|a| format!("        <string>{}</string>\n", xml_escape(a)))
+        .collect();
+    let keep_alive = if spec.restart_on_failure {
+        "    <key>KeepAlive</key>\n    <dict>\n        <key>SuccessfulExit</key>\n        <false/>\n    </dict>\n"
+    } else {
+        ""
+    };
+    let log = xml_escape(&spec.log_file().to_string_lossy());
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+         <plist version=\"1.0\">\n\
+         <dict>\n\
+         \x20   <key>Label</key>\n\
+         \x20   <string>{}{}</string>\n\
+         \x20   <key>ProgramArguments</key>\n\
+         \x20   <array>\n\
+         {}\
+         \x20   </array>\n\
+         \x20   <key>WorkingDirectory</key>\n\
+         \x20   <string>{}</string>\n\
+         \x20   <key>RunAtLoad</key>\n\
+         \x20   <true/>\n\
+         {}\
+         \x20   <key>StandardOutPath</key>\n\
+         \x20   <string>{}</string>\n\
+         \x20   <key>StandardErrorPath</key>\n\
+         \x20   <string>{}</string>\n\
+         </dict>\n\
+         </plist>\n",
+        LABEL_PREFIX,
+        spec.id,
+        args,
+        xml_escape(&spec.working_dir.to_string_lossy()),
+        keep_alive,
+        log,
+        log
+    )
+}
+
+fn run(program: &str, args: &[&str]) -> io::Result<ExitStatus> {
+    log_only!("INFO", "SERVICE", "{} {}", program, args.join(" "));
+    Command::new(program).args(args).status()
+}
+
+fn run_checked(program: &str, args: &[&str]) -> io::Result<()> {
+    let status = run(program, args)?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!("{} {} failed: {}", program, args.join(" "), status)))
+    }
+}
+
+/// 写入单元文件并设置为开机 (登录) 自启，立即启动
+pub fn install(backend: Backend, spec: &ServiceSpec) -> io::Result<PathBuf> {
+    let path = backend.unit_file(&spec.id);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if let Some(parent) = spec.log_file().parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let content = match backend {
+        Backend::Systemd => render_systemd_unit(spec),
+        Backend::Launchd => render_launchd_plist(spec),
+    };
+    fs::write(&path, content)?;
+
+    let unit = backend.unit_name(&spec.id);
+    match backend {
+        Backend::Systemd => {
+            run_checked("systemctl", &["--user", "daemon-reload"])?;
+            run_checked("systemctl", &["--user", "enable", "--now", &unit])?;
+        }
+        Backend::Launchd => run_checked("launchctl", &["load", "-w", &path.to_string_lossy()])?,
+    }
+    Ok(path)
+}
+
+pub fn start(backend: Backend, id: &str) -> io::Result<()> {
+    match backend {
+        Backend::Systemd => run_checked("systemctl", &["--user", "start", &backend.unit_name(id)]),
+        Backend::Launchd => run_checked("launchctl", &["start", &format!("{}{}", LABEL_PREFIX, id)]),
+    }
+}
+
+pub fn stop(backend: Backend, id: &str) -> io::Result<()> {
+    match backend {
+        Backend::Systemd => run_checked("systemctl", &["--user", "stop", &backend.unit_name(id)]),
+        Backend::Launchd => run_checked("launchctl", &["stop", &format!("{}{}", LABEL_PREFIX, id)]),
+    }
+}
+
+/// 把服务状态直接输出到终端
+pub fn status(backend: Backend, id: &str) -> io::Result<ExitStatus> {
+    match backend {
+        Backend::Systemd => run("systemctl", &["--user", "status", "--no-pager", &backend.unit_name(id)]),
+        Backend::Launchd => run("launchctl", &["list", &format!("{}{}", LABEL_PREFIX, id)]),
+    }
+}
+
+/// systemd 的用户单元在用户最后一个会话退出时停止；开启 linger 后开机即启动、注销后继续运行。
+/// 已开启或成功开启时返回 true，launchd 不需要
+pub fn enable_linger(backend: Backend) -> bool {
+    if backend != Backend::Systemd {
+        return true;
+    }
+    let user = env::var("USER").unwrap_or_default();
+    let lingering = Command::new("loginctl")
+        .args(["show-user", &user, "--property=Linger", "--value"])
+        .output()
+        .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).trim() == "yes");
+    // 没有 polkit 授权时会失败，由调用方提示手动执行
+    lingering || run("loginctl", &["enable-linger"]).is_ok_and(|status| status.success())
+}
+
+/// 停止并取消自启，删除单元文件
+pub fn uninstall(backend: Backend, id: &str) -> io::Result<()> {
+    let path = backend.unit_file(id);
+    match backend {
+        Backend::Systemd => {
+            let _ = run("systemctl", &["--user", "disable", "--now", &backend.unit_name(id)]);
+        }
+        Backend::Launchd => {
+            let _ = run("launchctl", &["unload", "-w", &path.to_string_lossy()]);
+        }
+    }
+    if path.exists() {
+        fs::remove_file(&path)?;
+    }
+    if backend == Backend::Systemd {
+        run_checked("systemctl", &["--user", "daemon-reload"])?;
+    }
+    Ok(())
+}
+
+/// 已安装的 geektools 服务标识
+pub fn list_installed(backend: Backend) -> Vec<String> {
+    list_in(backend, &backend.unit_dir())
+}
+
+fn list_in(backend: Backend, dir: &Path) -> Vec<String> {
+    let (prefix, suffix) = match backend {
+        Backend::Systemd => (UNIT_PREFIX, ".service"),
+        Backend::Launchd => (LABEL_PREFIX, ".plist"),
+    };
+    let mut ids: Vec<String> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter_map(|e| {
+                    let name = e.file_name().to_string_lossy().into_owned();
+                    name.strip_prefix(prefix)?.strip_suffix(suffix).map(str::to_string)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    ids.sort();
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn spec() -> ServiceSpec {
+        ServiceSpec {
+            id: service_id("Disk Watcher!"),
+            description: "disk watcher".to_string(),
+            command: vec!["/bin/bash".to_string(), "/home/pi/my scripts/watch.sh".to_string()],
+            working_dir: PathBuf::from("/home/pi"),
+            restart_on_failure: true,
+        }
+    }
+
+    #[test]
+    fn test_service_id() {
+        assert_eq!(service_id("Disk Watcher!"), "disk-watcher");
+        assert_eq!(service_id("a__b"), "a-b");
+        // 非 ASCII 名称带摘要后缀，互不相同
+        assert!(service_id("磁盘").starts_with("script-"));
+        assert_eq!(service_id("磁盘").len(), "script-".len() + 8);
+        assert_ne!(service_id("磁盘"), service_id("网络"));
+        assert_ne!(service_id("备份 db"), service_id("db"));
+    }
+
+    #[test]
+    fn test_render_systemd_unit() {
+        let unit = render_systemd_unit(&spec());
+        assert!(unit.contains("ExecStart=/bin/bash \"/home/pi/my scripts/watch.sh\"\n"));
+        assert!(unit.contains("Restart=on-failure\n"));
+        assert!(unit.contains("WantedBy=default.target\n"));
+        assert_eq!(systemd_quote("100%"), "100%%");
+    }
+
+    #[test]
+    fn test_render_launchd_plist() {
+        let plist = render_launchd_plist(&spec());
+        assert!(plist.contains("<string>com.geektools.disk-watcher</string>"));
+        assert!(plist.contains("        <string>/home/pi/my scripts/watch.sh</string>\n"));
+        assert!(plist.contains("<key>KeepAlive</key>"));
+        assert_eq!(xml_escape("a<b&c"), "a&lt;b&amp;c");
+    }
+
+    #[test]
+    fn test_list_installed_units() {
+        let dir = TempDir::new().unwrap();
+        for name in ["geektools-b.service", "geektools-a.service", "other.service"] {
+            fs::write(dir.path().join(name), "").unwrap();
+        }
+        assert_eq!(list_in(Backend::Systemd, dir.path()), vec!["a", "b"]);
+        assert!(list_in(Backend::Launchd, dir.path()).is_empty());
+    }
+}
@@ -0,0 +1,257 @@
+use base64::Engine;
+use blake2::{Blake2b512, Digest};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use ring::signature::{ED25519, UnparsedPublicKey};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// 远程脚本 (URL 和目录中的 `.link` 脚本) 的签名校验设置
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct SignatureConfig {
+    /// 没有签名的远程脚本也拒绝运行
+    #[serde(default)]
+    pub require: bool,
+    #[serde(default)]
+    pub trusted_keys: Vec<TrustedKey>,
+}
+
+impl SignatureConfig {
+    /// 没有信任的公钥且不要求签名时不去获取签名
+    pub fn enabled(&self) -> bool {
+        self.require || !self.trusted_keys.is_empty()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyKind {
+    Minisign,
+    Gpg,
+}
+
+/// 信任的签名公钥
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TrustedKey {
+    /// 确认运行时显示的签名者名称
+    pub name: String,
+    pub kind: KeyKind,
+    /// minisign 公钥 (`.pub` 文件内容或其中的 base64 行)，或 ASCII armor 格式的 GPG 公钥
+    pub key: String,
+}
+
+/// 签名校验的结果
+#[derive(Debug, PartialEq)]
+pub enum Verification {
+    Unsigned,
+    /// 签名有效，附带签名者
+    Verified(String),
+    /// 签名无效或签名者不受信任
+    Invalid(String),
+}
+
+/// 用信任的公钥校验分离签名；minisign 签名以 `untrusted comment:` 开头，其余按 GPG 签名处理
+pub fn verify(content: &[u8], signature: &[u8], keys: &[TrustedKey]) -> Verification {
+    match std::str::from_utf8(signature) {
+        Ok(text) if text.trim_start().starts_with("untrusted comment:") => verify_minisign(content, text, keys),
+        _ => verify_gpg(content, signature, keys),
+    }
+}
+
+fn verify_minisign(content: &[u8], signature: &str, keys: &[TrustedKey]) -> Verification {
+    let Some(sig) = MinisignSignature::parse(signature) else {
+        return Verification::Invalid("malformed minisign signature".to_string());
+    };
+    let key = keys
+        .iter()
+        .filter(|k| k.kind == KeyKind::Minisign)
+        .find_map(|k| MinisignKey::parse(&k.key).filter(|pk| pk.id == sig.key_id).map(|pk| (k, pk)));
+    let Some((trusted, public_key)) = key else {
+        return Verification::Invalid(format!("signed by an untrusted minisign key {}", hex_id(&sig.key_id)));
+    };
+    let key = UnparsedPublicKey::new(&ED25519, public_key.key);
+    let message = match &sig.algorithm {
+        b"ED" => Blake2b512::digest(content).to_vec(),
+        _ => content.to_vec(),
+    };
+    if key.verify(&message, &sig.signature).is_err() {
+        return Verification::Invalid("minisign signature does not match the content".to_string());
+    }
+    // 全局签名覆盖签名本身和可信注释，防止注释被篡改
+    let mut global = sig.signature.to_vec();
+    global.extend_from_slice(sig.trusted_comment.as_bytes());
+    if key.verify(&global, &sig.global_signature).is_err() {
+        return Verification::Invalid("minisign trusted comment was tampered with".to_string());
+    }
+    if sig.trusted_comment.is_empty() {
+        Verification::Verified(trusted.name.clone())
+    } else {
+        Verification::Verified(format!("{} ({})", trusted.name, sig.trusted_comment))
+    }
+}
+
+struct MinisignKey {
+    id: [u8; 8],
+    key: [u8; 32],
+}
+
+impl MinisignKey {
+    /// `Ed` + 8 字节 key id + 32 字节公钥，base64 编码；忽略注释行
+    fn parse(text: &str) -> Option<Self> {
+        let line = text.lines().map(str::trim).rfind(|l| !l.is_empty() && !l.starts_with("untrusted comment:"))?;
+        let bytes = BASE64.decode(line).ok()?;
+        if bytes.len() != 42 || &bytes[..2] != b"Ed" {
+            return None;
+        }
+        Some(Self { id: bytes[2..10].try_into().ok()?, key: bytes[10..].try_into().ok()? })
+    }
+}
+
+struct MinisignSignature {
+    /// `Ed` 直接签名内容，`ED` 签名内容的 BLAKE2b-512 (minisign 默认)
+    algorithm: [u8; 2],
+    key_id: [u8; 8],
+    signature: [u8; 64],
+    trusted_comment: String,
+    global_signature: [u8; 64],
+}
+
+impl MinisignSignature {
+    fn parse(text: &str) -> Option<Self> {
+        let mut lines = text.lines().map(str::trim_end);
+        lines.next()?.strip_prefix("untrusted comment:")?;
+        let sig = BASE64.decode(lines.next()?.trim()).ok()?;
+        let trusted_comment = lines.next()?.strip_prefix("trusted comment: ")?.to_string();
+        let global = BASE64.decode(lines.next()?.trim()).ok()?;
+        if sig.len() != 74 || !matches!(&sig[..2], b"Ed" | b"ED") {
+            return None;
+        }
+        Some(Self {
+            algorithm: sig[..2].try_into().ok()?,
+            key_id: sig[2..10].try_into().ok()?,
+            signature: sig[10..].try_into().ok()?,
+            trusted_comment,
+            global_signature: global.as_slice().try_into().ok()?,
+        })
+    }
+}
+
+/// minisign 按小端序显示 key id
+fn hex_id(id: &[u8; 8]) -> String {
+    id.iter().rev().map(|b| format!("{:02X}", b)).collect()
+}
+
+/// 在只导入了信任公钥的临时 keyring 中调用 `gpg --verify`
+fn verify_gpg(content: &[u8], signature: &[u8], keys: &[TrustedKey]) -> Verification {
+    let armored: Vec<&str> = keys.iter().filter(|k| k.kind == KeyKind::Gpg).map(|k| k.key.as_str()).collect();
+    if armored.is_empty() {
+        return Verification::Invalid("no trusted GPG keys configured".to_string());
+    }
+    // 临时 homedir 由 tempfile 以随机名称和 0700 权限创建，离开作用域时删除
+    let result = tempfile::Builder::new()
+        .prefix("geektools-gpg-")
+        .tempdir()
+        .and_then(|home| run_gpg(home.path(), content, signature, &armored.join("\n")));
+    match result {
+        Ok(status) => parse_gpg_status(&status),
+        Err(e) => Verification::Invalid(format!("gpg failed: {}", e)),
+    }
+}
+
+fn run_gpg(home: &Path, content: &[u8], signature: &[u8], keys: &str) -> std::io::Result<String> {
+    let (data, sig) = (home.join("script"), home.join("script.sig"));
+    std::fs::write(&data, content)?;
+    std::fs::write(&sig, signature)?;
+
+    let gpg = |args: &[&str]| {
+        let mut cmd = Command::new("gpg");
+        cmd.arg("--homedir").arg(home).args(["--batch", "--no-tty"]).args(args);
+        cmd
+    };
+    let mut import = gpg(&["--quiet", "--import"]).stdin(Stdio::piped()).stderr(Stdio::null()).spawn()?;
+    if let Some(mut stdin) = import.stdin.take() {
+        stdin.write_all(keys.as_bytes())?;
+    }
+    import.wait()?;
+    let output = gpg(&["--status-fd", "1", "--verify"]).arg(&sig).arg(&data).stderr(Stdio::null()).output()?;
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// 解析 `gpg --status-fd` 输出；keyring 中只有信任的公钥，所以 GOODSIG 即可信
+fn parse_gpg_status(status: &str) -> Verification {
+    let mut signer = None;
+    for line in status.lines() {
+        let Some(line) = line.strip_prefix("[GNUPG:] ") else {
+            continue;
+        };
+        let (keyword, rest) = line.split_once(' ').unwrap_or((line, ""));
+        match keyword {
+            "GOODSIG" => signer = rest.split_once(' ').map(|(_, user)| user.to_string()),
+            "BADSIG" => return Verification::Invalid("GPG signature does not match the content".to_string()),
+            "EXPKEYSIG" | "REVKEYSIG" => return Verification::Invalid("GPG key is expired or revoked".to_string()),
+            "ERRSIG" | "NO_PUBKEY" => return Verification::Invalid("signed by an untrusted GPG key".to_string()),
+            _ => {}
+        }
+    }
+    match signer {
+        Some(signer) => Verification::Verified(signer),
+        None => Verification::Invalid("not a valid GPG signature".to_string()),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+
+    /// 按 minisign 的格式生成公钥和签名
+    pub(crate) fn minisign(content: &[u8], algorithm: &[u8; 2], comment: &str) -> (TrustedKey, String) {
+        let pair = Ed25519KeyPair::from_seed_unchecked(&[7u8; 32]).unwrap();
+        let id = [1, 2, 3, 4, 5, 6, 7, 8];
+        let public = [b"Ed".as_slice(), &id, pair.public_key().as_ref()].concat();
+        let key = TrustedKey {
+            name: "Example Maintainers".to_string(),
+            kind: KeyKind::Minisign,
+            key: format!("untrusted comment: minisign public key\n{}\n", BASE64.encode(public)),
+        };
+        let message = match algorithm {
+            b"ED" => Blake2b512::digest(content).to_vec(),
+            _ => content.to_vec(),
+        };
+        let signature = pair.sign(&message);
+        let global = pair.sign(&[signature.as_ref(), comment.as_bytes()].concat());
+        let sig = format!(
+            "untrusted comment: signature from minisign secret key\n{}\ntrusted comment: {}\n{}\n",
+            BASE64.encode([algorithm.as_slice(), &id, signature.as_ref()].concat()),
+            comment,
+            BASE64.encode(global.as_ref())
+        );
+        (key, sig)
+    }
+
+    #[test]
+    fn test_verify_minisign() {
+        let content = b"#!/bin/sh\necho hi\n";
+        for algorithm in [b"ED", b"Ed"] {
+            let (key, sig) = minisign(content, algorithm, "file:install.sh");
+            assert_eq!(
+                verify(content, sig.as_bytes(), std::slice::from_ref(&key)),
+                Verification::Verified("Example Maintainers (file:install.sh)".to_string())
+            );
+            assert!(matches!(verify(b"echo evil\n", sig.as_bytes(), std::slice::from_ref(&key)), Verification::Invalid(_)));
+            let tampered = sig.replace("file:install.sh", "file:other.sh");
+            assert!(matches!(verify(content, tampered.as_bytes(), &[key]), Verification::Invalid(_)));
+            assert!(matches!(verify(content, sig.as_bytes(), &[]), Verification::Invalid(_)));
+        }
+    }
+
+    #[test]
+    fn test_parse_gpg_status() {
+        let good = "[GNUPG:] NEWSIG\n[GNUPG:] GOODSIG 0123456789ABCDEF Alice <alice@example.com>\n[GNUPG:] VALIDSIG ...\n";
+        assert_eq!(parse_gpg_status(good), Verification::Verified("Alice <alice@example.com>".to_string()));
+        assert!(matches!(parse_gpg_status("[GNUPG:] BADSIG 0123 Alice\n"), Verification::Invalid(_)));
+        assert!(matches!(parse_gpg_status("[GNUPG:] ERRSIG 0123 1 8 00 0 9\n[GNUPG:] NO_PUBKEY 0123\n"), Verification::Invalid(_)));
+        assert!(matches!(parse_gpg_status(""), Verification::Invalid(_)));
+    }
+}
@@ -0,0 +1,334 @@
+use crate::fileio;
+use crate::log_only;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// ssh 作为 askpass 程序再次启动 geektools 时，通过此环境变量找到取密码的套接字；
+/// 密码本身不进入环境变量
+const ASKPASS_ENV: &str = "GEEKTOOLS_SSH_ASKPASS";
+
+/// SSH 目标的设置
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct SshConfig {
+    /// 按主机 (与 `host_groups` 中的写法相同) 的认证方式，未列出的主机使用默认值
+    #[serde(default)]
+    pub hosts: BTreeMap<String, HostAuth>,
+}
+
+impl SshConfig {
+    pub fn auth_for(&self, host: &str) -> HostAuth {
+        self.hosts.get(host).cloned().unwrap_or_default()
+    }
+}
+
+/// 单台主机的认证方式
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct HostAuth {
+    /// 私钥路径 (`ssh -i`)，支持 `~/`；设置后只使用此密钥
+    #[serde(default)]
+    pub identity_file: Option<String>,
+    /// 使用 ssh-agent 中的密钥
+    #[serde(default = "default_agent")]
+    pub agent: bool,
+    /// 密码登录：运行前提示输入一次
+    #[serde(default)]
+    pub password: bool,
+}
+
+fn default_agent() -> bool {
+    true
+}
+
+impl Default for HostAuth {
+    fn default() -> Self {
+        Self { identity_file: None, agent: true, password: false }
+    }
+}
+
+/// 托管的 known_hosts：~/.geektools/ssh/known_hosts
+pub fn known_hosts_path() -> PathBuf {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".into());
+    PathBuf::from(home).join(".geektools").join("ssh").join("known_hosts")
+}
+
+/// 用户自己的 known_hosts：~/.ssh/known_hosts
+fn user_known_hosts_path() -> PathBuf {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".into());
+    PathBuf::from(home).join(".ssh").join("known_hosts")
+}
+
+/// 目标主机前的 ssh 参数：只信任托管 known_hosts 和 ~/.ssh/known_hosts 中的主机密钥，再加上主机的认证方式
+pub fn ssh_args(auth: &HostAuth) -> Vec<String> {
+    let mut args = vec![
+        "-o".to_string(),
+        format!("UserKnownHostsFile={} {}", known_hosts_path().display(), user_known_hosts_path().display()),
+        "-o".to_string(),
+        "StrictHostKeyChecking=yes".to_string(),
+    ];
+    // 密码登录需要 ssh 调用 askpass，BatchMode 会禁止一切提示
+    let auth_options = if auth.password { "NumberOfPasswordPrompts=1" } else { "BatchMode=yes" };
+    args.extend(["-o".to_string(), auth_options.to_string()]);
+    if let Some(identity) = &auth.identity_file {
+        args.extend(["-i".to_string(), expand_home(identity), "-o".to_string(), "IdentitiesOnly=yes".to_string()]);
+    }
+    if !auth.agent {
+        args.extend(["-o".to_string(), "IdentityAgent=none".to_string()]);
+    }
+    args
+}
+
+/// 为一次 ssh 调用提供密码的 askpass 服务；在 ssh 退出前保持存活，drop 时删除套接字
+#[cfg(unix)]
+pub struct AskPass {
+    dir: tempfile::TempDir,
+    server: Option<std::thread::JoinHandle<()>>,
+}
+
+#[cfg(unix)]
+impl Drop for AskPass {
+    fn drop(&mut self) {
+        // ssh 没有询问密码 (例如密钥登录成功) 时服务线程还在等待连接，连一次让它退出
+        let _ = std::os::unix::net::UnixStream::connect(askpass_socket(self.dir.path()));
+        if let Some(server) = self.server.take() {
+            let _ = server.join();
+        }
+    }
+}
+
+#[cfg(unix)]
+fn askpass_socket(dir: &Path) -> PathBuf {
+    dir.join("askpass.sock")
+}
+
+/// 让 ssh 通过 askpass 向本程序取密码：密码经仅本用户可访问的 Unix 套接字传递，
+/// 不出现在命令行和环境变量中，并且只回答一次
+#[cfg(unix)]
+pub fn set_password(cmd: &mut Command, password: &str) -> io::Result<AskPass> {
+    let exe = std::env::current_exe()?;
+    // tempdir 的权限为 0700，其他用户无法连接其中的套接字
+    let dir = tempfile::Builder::new().prefix("geektools-askpass-").tempdir()?;
+    let socket = askpass_socket(dir.path());
+    let listener = std::os::unix::net::UnixListener::bind(&socket)?;
+    let password = password.to_string();
+    let server = std::thread::spawn(move || {
+        if let Ok((mut stream, _)) = listener.accept() {
+            let _ = stream.write_all(password.as_bytes());
+        }
+    });
+    cmd.env("SSH_ASKPASS", exe).env("SSH_ASKPASS_REQUIRE", "force").env(ASKPASS_ENV, &socket);
+    Ok(AskPass { dir, server: Some(server) })
+}
+
+/// 作为 askpass 被调用时 (`geektools <提示>`) 返回退出码：只在提示是密码提示时输出密码，
+/// 主机密钥确认、私钥口令等其他提示一律拒绝。不是 askpass 调用时返回 None
+pub fn run_askpass() -> Option<i32> {
+    let socket = std::env::var_os(ASKPASS_ENV)?;
+    let prompt = std::env::args().nth(1).unwrap_or_default();
+    if !is_password_prompt(&prompt) {
+        log_only!("WARN", "SSH", "拒绝 askpass 提示: {}", prompt);
+        return Some(1);
+    }
+    match read_password(Path::new(&socket)) {
+        Ok(password) if !password.is_empty() => {
+            println!("{}", password);
+            Some(0)
+        }
+        _ => Some(1),
+    }
+}
+
+#[cfg(unix)]
+fn read_password(socket: &Path) -> io::Result<String> {
+    use std::io::Read;
+    let mut password = String::new();
+    std::os::unix::net::UnixStream::connect(socket)?.read_to_string(&mut password)?;
+    Ok(password)
+}
+
+#[cfg(not(unix))]
+fn read_password(_socket: &Path) -> io::Result<String> {
+    Err(io::Error::from(io::ErrorKind::Unsupported))
+}
+
+/// ssh 的密码提示：`user@host's password: ` 或键盘交互的 `Password: `
+fn is_password_prompt(prompt: &str) -> bool {
+    prompt.trim_end().to_ascii_lowercase().ends_with("password:")
+}
+
+fn expand_home(path: &str) -> String {
+    match path.strip_prefix("~/") {
+        Some(rest) => format!("{}/{}", std::env::var("HOME").unwrap_or_else(|_| ".".into()), rest),
+        None => path.to_string(),
+    }
+}
+
+/// 首次连接的主机：扫描到的公钥和指纹，确认后写入托管 known_hosts
+#[derive(Debug)]
+pub struct UnknownHost {
+    pub host: String,
+    /// `ssh-keyscan` 输出的 known_hosts 行
+    pub keys: String,
+    /// `SHA256:...` 形式的指纹，附带密钥类型
+    pub fingerprints: Vec<String>,
+}
+
+/// 找出托管 known_hosts 和 ~/.ssh/known_hosts 中都还没有的主机，并扫描它们的公钥
+pub fn unknown_hosts(hosts: &[String]) -> Vec<io::Result<UnknownHost>> {
+    let known = [known_hosts_path(), user_known_hosts_path()]
+        .iter()
+        .map(|path| fileio::read(path).unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join("\n");
+    hosts
+        .iter()
+        .filter_map(|host| {
+            let (hostname, port) = resolve_target(host);
+            (!is_known(&known, &known_hosts_name(&hostname, port))).then(|| {
+                scan(host, &hostname, port).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", host, e)))
+            })
+        })
+        .collect()
+}
+
+fn scan(host: &str, hostname: &str, port: u16) -> io::Result<UnknownHost> {
+    let output = Command::new("ssh-keyscan")
+        .args(["-T", "5", "-p", &port.to_string(), "--", hostname])
+        .stderr(Stdio::null())
+        .output()?;
+    let keys: String = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(|l| format!("{}\n", l))
+        .collect();
+    if keys.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "no host keys returned"));
+    }
+    let fingerprints = fingerprints(&keys)?;
+    Ok(UnknownHost { host: host.to_string(), keys, fingerprints })
+}
+
+fn fingerprints(keys: &str) -> io::Result<Vec<String>> {
+    let mut child = Command::new("ssh-keygen")
+        .args(["-l", "-f", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(keys.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    // `256 SHA256:abc... web1 (ED25519)` → `SHA256:abc... (ED25519)`
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            Some(format!("{} {}", fields.get(1)?, fields.last()?))
+        })
+        .collect())
+}
+
+/// 把确认过的主机公钥追加到托管 known_hosts
+pub fn trust(host: &UnknownHost) -> io::Result<()> {
+    let path = known_hosts_path();
+    if let Some(dir) = path.parent() {
+        fileio::create_dir(dir).map_err(|e| io::Error::other(e.to_string()))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))?;
+        }
+    }
+    let mut file = fileio::open_append(&path).map_err(|e| io::Error::other(e.to_string()))?;
+    file.write_all(host.keys.as_bytes())?;
+    log_only!("INFO", "SSH", "信任主机 {}: {}", host.host, host.fingerprints.join(", "));
+    Ok(())
+}
+
+/// 用 `ssh -G` 解析别名、用户名和端口后的实际主机名和端口
+fn resolve_target(host: &str) -> (String, u16) {
+    Command::new("ssh")
+        .args(["-G", "--", host])
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .and_then(|output| parse_ssh_g(&String::from_utf8_lossy(&output.stdout)))
+        .unwrap_or_else(|| (host.rsplit('@').next().unwrap_or(host).to_string(), 22))
+}
+
+fn parse_ssh_g(output: &str) -> Option<(String, u16)> {
+    let value = |key: &str| output.lines().find_map(|l| l.strip_prefix(key)?.strip_prefix(' ').map(str::trim));
+    Some((value("hostname")?.to_string(), value("port")?.parse().ok()?))
+}
+
+/// known_hosts 中的主机写法：非 22 端口为 `[host]:port`
+fn known_hosts_name(hostname: &str, port: u16) -> String {
+    if port == 22 { hostname.to_string() } else { format!("[{}]:{}", hostname, port) }
+}
+
+fn is_known(known_hosts: &str, name: &str) -> bool {
+    known_hosts
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .any(|names| names.split(',').any(|n| n == name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ssh_args_follow_host_auth() {
+        let known_hosts =
+            format!("UserKnownHostsFile={} {}", known_hosts_path().display(), user_known_hosts_path().display());
+        assert_eq!(
+            ssh_args(&HostAuth::default()),
+            ["-o", known_hosts.as_str(), "-o", "StrictHostKeyChecking=yes", "-o", "BatchMode=yes"]
+        );
+        let auth = HostAuth { identity_file: Some("/keys/deploy".to_string()), agent: false, password: true };
+        assert_eq!(
+            ssh_args(&auth)[4..],
+            ["-o", "NumberOfPasswordPrompts=1", "-i", "/keys/deploy", "-o", "IdentitiesOnly=yes", "-o", "IdentityAgent=none"]
+        );
+        let config: SshConfig = serde_json::from_str(r#"{"hosts": {"db": {"password": true}}}"#).unwrap();
+        assert!(config.auth_for("db").agent);
+        assert_eq!(config.auth_for("web"), HostAuth::default());
+    }
+
+    #[test]
+    fn test_known_hosts_lookup() {
+        let output = "user deploy\nhostname web1.example.com\nport 2222\nhostkeyalias none\n";
+        assert_eq!(parse_ssh_g(output), Some(("web1.example.com".to_string(), 2222)));
+        assert_eq!(known_hosts_name("web1.example.com", 2222), "[web1.example.com]:2222");
+        assert_eq!(known_hosts_name("db", 22), "db");
+
+        let known = "db,10.0.0.5 ssh-ed25519 AAAA\n[web1.example.com]:2222 ssh-rsa AAAA\n";
+        assert!(is_known(known, "10.0.0.5"));
+        assert!(is_known(known, "[web1.example.com]:2222"));
+        assert!(!is_known(known, "web1.example.com"));
+    }
+
+    #[test]
+    fn test_askpass_only_answers_password_prompts() {
+        assert!(is_password_prompt("deploy@web1's password: "));
+        assert!(is_password_prompt("(deploy@web1) Password:"));
+        assert!(!is_password_prompt("Are you sure you want to continue connecting (yes/no/[fingerprint])? "));
+        assert!(!is_password_prompt("Enter passphrase for key '/home/deploy/.ssh/id_ed25519': "));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_askpass_socket_answers_once() {
+        let mut cmd = Command::new("ssh");
+        let askpass = set_password(&mut cmd, "s3cret").unwrap();
+        let socket = cmd.get_envs().find(|(k, _)| *k == ASKPASS_ENV).and_then(|(_, v)| v).unwrap().to_owned();
+        assert!(cmd.get_envs().all(|(_, v)| v.is_none_or(|v| v != "s3cret")));
+        assert_eq!(read_password(Path::new(&socket)).unwrap(), "s3cret");
+        assert_ne!(read_password(Path::new(&socket)).unwrap_or_default(), "s3cret");
+        drop(askpass);
+        assert!(!Path::new(&socket).exists());
+    }
+}
@@ -0,0 +1,57 @@
+use std::fmt::Write as _;
+use std::time::{Duration, Instant};
+
+/// 启动各阶段的耗时，`--profile-startup` 时在菜单出现前输出
+pub struct StartupProfile {
+    start: Instant,
+    last: Instant,
+    phases: Vec<(&'static str, Duration)>,
+}
+
+impl StartupProfile {
+    pub fn start() -> Self {
+        let now = Instant::now();
+        Self { start: now, last: now, phases: Vec::new() }
+    }
+
+    /// 记录从上一个阶段结束到现在的耗时
+    pub fn mark(&mut self, phase: &'static str) {
+        let now = Instant::now();
+        self.phases.push((phase, now - self.last));
+        self.last = now;
+    }
+
+    pub fn total(&self) -> Duration {
+        self.last - self.start
+    }
+
+    /// 每个阶段一行，最后一行为总耗时
+    pub fn render(&self) -> String {
+        let width = self.phases.iter().map(|(name, _)| name.len()).max().unwrap_or(0).max("total".len());
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        let mut out = String::new();
+        for (name, elapsed) in &self.phases {
+            let _ = writeln!(out, "  {:<width$}  {:>8.2} ms", name, ms(*elapsed));
+        }
+        let _ = writeln!(out, "  {:<width$}  {:>8.2} ms", "total", ms(self.total()));
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_lists_phases_and_total() {
+        let mut profile = StartupProfile::start();
+        profile.mark("args");
+        profile.mark("config");
+        let out = profile.render();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].trim_start().starts_with("args "));
+        assert!(lines[2].trim_start().starts_with("total "));
+        assert!(out.ends_with(" ms\n"));
+    }
+}
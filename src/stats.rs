@@ -0,0 +1,140 @@
+use crate::history::ScriptStats;
+use crate::ui::layout::{self, Align};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+/// 统计视图的设置
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StatsConfig {
+    /// 每次成功运行估计节省的手工操作时间 (分钟)，用于计算“节省时间”
+    pub minutes_saved_per_run: f64,
+    /// 表格中显示的脚本数
+    pub top: usize,
+}
+
+impl Default for StatsConfig {
+    fn default() -> Self {
+        Self { minutes_saved_per_run: 5.0, top: 10 }
+    }
+}
+
+/// 统计视图：总览、运行最多的脚本和失败率最高的脚本，均为纯 ASCII 表格
+pub fn render(stats: &BTreeMap<String, ScriptStats>, config: &StatsConfig) -> String {
+    let mut out = String::new();
+    let runs: u64 = stats.values().map(|s| s.runs).sum();
+    let failures: u64 = stats.values().map(|s| s.failures).sum();
+    let seconds: f64 = stats.values().map(|s| s.total_seconds).sum();
+    let succeeded = runs - failures;
+    let saved = succeeded as f64 * config.minutes_saved_per_run * 60.0;
+    let _ = writeln!(out, "Runs: {}  Failures: {} ({})  Time in scripts: {}", runs, failures, percent(failures, runs), duration(seconds));
+    let _ = writeln!(
+        out,
+        "Time saved: ~{} ({} successful runs x {} min)",
+        duration(saved),
+        succeeded,
+        config.minutes_saved_per_run
+    );
+
+    let mut by_runs: Vec<(&String, &ScriptStats)> = stats.iter().collect();
+    by_runs.sort_by(|a, b| b.1.runs.cmp(&a.1.runs).then_with(|| a.0.cmp(b.0)));
+    by_runs.truncate(config.top);
+    let _ = writeln!(out, "\nTop scripts");
+    out.push_str(&table(&by_runs));
+
+    let mut by_failures: Vec<(&String, &ScriptStats)> = stats.iter().filter(|(_, s)| s.failures > 0).collect();
+    by_failures.sort_by(|a, b| {
+        let rate = |s: &ScriptStats| s.failures as f64 / s.runs as f64;
+        rate(b.1).total_cmp(&rate(a.1)).then_with(|| b.1.failures.cmp(&a.1.failures)).then_with(|| a.0.cmp(b.0))
+    });
+    by_failures.truncate(config.top);
+    if !by_failures.is_empty() {
+        let _ = writeln!(out, "\nHighest failure rates");
+        out.push_str(&table(&by_failures));
+    }
+    out
+}
+
+fn table(rows: &[(&String, &ScriptStats)]) -> String {
+    let headers = ["Script", "Runs", "Failed", "Fail %", "Avg", "Total"];
+    let mut cells: Vec<Vec<String>> = vec![headers.map(str::to_string).to_vec()];
+    cells.extend(rows.iter().map(|(name, s)| {
+        vec![
+            name.to_string(),
+            s.runs.to_string(),
+            s.failures.to_string(),
+            percent(s.failures, s.runs),
+            duration(s.total_seconds / s.runs.max(1) as f64),
+            duration(s.total_seconds),
+        ]
+    }));
+    let widths = layout::column_widths(&cells);
+    // 表头下面是分隔线
+    cells.insert(1, widths.iter().map(|w| "-".repeat(*w)).collect());
+
+    // 第一列 (脚本名) 左对齐，数字右对齐
+    let align = [Align::Left, Align::Right, Align::Right, Align::Right, Align::Right, Align::Right];
+    let mut out = String::new();
+    for row in &cells {
+        let _ = writeln!(out, "{}", layout::row(row, &widths, &align));
+    }
+    out
+}
+
+fn percent(part: u64, total: u64) -> String {
+    if total == 0 {
+        return "-".to_string();
+    }
+    format!("{:.1}%", part as f64 * 100.0 / total as f64)
+}
+
+/// 1.5s、2m 05s、3h 20m
+fn duration(seconds: f64) -> String {
+    let whole = seconds.round() as u64;
+    if seconds < 60.0 {
+        format!("{:.1}s", seconds)
+    } else if whole < 3600 {
+        format!("{}m {:02}s", whole / 60, whole % 60)
+    } else {
+        format!("{}h {:02}m", whole / 3600, whole % 3600 / 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(runs: u64, failures: u64, total_seconds: f64) -> ScriptStats {
+        ScriptStats { runs, failures, total_seconds, last_run: String::new() }
+    }
+
+    #[test]
+    fn test_render_tables() {
+        let mut stats = BTreeMap::new();
+        stats.insert("backup.sh".to_string(), entry(10, 1, 125.0));
+        stats.insert("deploy.sh".to_string(), entry(2, 1, 30.0));
+        stats.insert("info.sh".to_string(), entry(30, 0, 15.0));
+        let out = render(&stats, &StatsConfig::default());
+
+        assert!(out.starts_with("Runs: 42  Failures: 2 (4.8%)  Time in scripts: 2m 50s\n"));
+        assert!(out.contains("Time saved: ~3h 20m (40 successful runs x 5 min)\n"));
+        assert!(out.contains(
+            "\nTop scripts\n\
+             Script     Runs  Failed  Fail %    Avg   Total\n\
+             ---------  ----  ------  ------  -----  ------\n\
+             info.sh      30       0    0.0%   0.5s   15.0s\n\
+             backup.sh    10       1   10.0%  12.5s  2m 05s\n\
+             deploy.sh     2       1   50.0%  15.0s   30.0s\n"
+        ));
+        let failures = out.split("Highest failure rates\n").nth(1).unwrap();
+        let names: Vec<&str> = failures.lines().skip(2).map(|l| l.split(' ').next().unwrap()).collect();
+        assert_eq!(names, vec!["deploy.sh", "backup.sh"]);
+    }
+
+    #[test]
+    fn test_render_empty() {
+        let out = render(&BTreeMap::new(), &StatsConfig::default());
+        assert!(out.starts_with("Runs: 0  Failures: 0 (-)"));
+        assert!(!out.contains("Highest failure rates"));
+    }
+}
@@ -0,0 +1,190 @@
+use std::cell::RefCell;
+use std::io::{self, BufRead, Write};
+
+/// 菜单的输入输出，测试中可替换为预设按键并捕获输出
+pub trait Terminal {
+    /// 读取一行 (含换行符)，返回读取的字节数，0 表示输入结束
+    fn read_line(&mut self, buf: &mut String) -> io::Result<usize>;
+    fn write_str(&mut self, text: &str);
+}
+
+/// 标准输入输出
+pub struct StdTerminal;
+
+impl Terminal for StdTerminal {
+    fn read_line(&mut self, buf: &mut String) -> io::Result<usize> {
+        io::stdin().read_line(buf)
+    }
+
+    fn write_str(&mut self, text: &str) {
+        let mut stdout = io::stdout();
+        let _ = stdout.write_all(text.as_bytes());
+        let _ = stdout.flush();
+    }
+}
+
+/// 从控制终端读取输入，标准输入被管道占用时 (`curl … | geektools run -`) 仍能确认
+pub struct TtyTerminal(io::BufReader<std::fs::File>);
+
+impl Terminal for TtyTerminal {
+    fn read_line(&mut self, buf: &mut String) -> io::Result<usize> {
+        self.0.read_line(buf)
+    }
+
+    fn write_str(&mut self, text: &str) {
+        StdTerminal.write_str(text);
+    }
+}
+
+thread_local! {
+    /// 当前线程使用的终端，默认是标准输入输出
+    static CURRENT: RefCell<Box<dyn Terminal>> = RefCell::new(Box::new(StdTerminal));
+}
+
+/// 从当前终端读取一行
+pub fn read_line(buf: &mut String) -> io::Result<usize> {
+    CURRENT.with(|t| t.borrow_mut().read_line(buf))
+}
+
+/// 读取一行而不回显 (用于密码)
+pub fn read_hidden_line(buf: &mut String) -> io::Result<usize> {
+    set_echo(false);
+    let result = read_line(buf);
+    set_echo(true);
+    write_str("\n");
+    result
+}
+
+fn set_echo(on: bool) {
+    #[cfg(unix)]
+    let _ = std::process::Command::new("stty")
+        .arg(if on { "echo" } else { "-echo" })
+        .stderr(std::process::Stdio::null())
+        .status();
+    #[cfg(not(unix))]
+    let _ = on;
+}
+
+/// 当前线程之后的输入改从控制终端读取；没有控制终端时返回错误
+pub fn use_tty() -> io::Result<()> {
+    let path = if cfg!(windows) { "CONIN$" } else { "/dev/tty" };
+    let tty = std::fs::File::open(path)?;
+    CURRENT.with(|t| *t.borrow_mut() = Box::new(TtyTerminal(io::BufReader::new(tty))));
+    Ok(())
+}
+
+/// 输出到当前终端，经过 [`theme::render`](crate::ui::theme::render) 处理
+pub fn write_str(text: &str) {
+    let text = crate::ui::theme::render(text);
+    CURRENT.with(|t| t.borrow_mut().write_str(&text));
+}
+
+/// 清屏并把光标移到左上角；纯文本模式下终端可能不支持控制序列，只输出一个空行分隔
+pub fn clear_screen() {
+    if crate::ui::theme::is_plain() {
+        write_str("\n");
+    } else {
+        write_str("\x1B[2J\x1B[H");
+    }
+}
+
+/// 复制文本到系统剪贴板，依次尝试 pbcopy、wl-copy、xclip、xsel 和 clip，全部不可用时返回 false
+#[cfg(feature = "network")]
+pub fn copy_to_clipboard(text: &str) -> bool {
+    const COMMANDS: &[&[&str]] = &[
+        &["pbcopy"],
+        &["wl-copy"],
+        &["xclip", "-selection", "clipboard"],
+        &["xsel", "--clipboard", "--input"],
+        &["clip"],
+    ];
+    COMMANDS.iter().any(|command| {
+        let child = std::process::Command::new(command[0])
+            .args(&command[1..])
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .spawn();
+        let Ok(mut child) = child else {
+            return false;
+        };
+        let written = child.stdin.take().is_some_and(|mut stdin| stdin.write_all(text.as_bytes()).is_ok());
+        child.wait().is_ok_and(|status| status.success()) && written
+    })
+}
+
+/// 预设输入的终端：输入用完时 panic，避免菜单循环在测试中挂起
+#[cfg(test)]
+pub struct ScriptedTerminal {
+    input: std::collections::VecDeque<String>,
+    output: std::rc::Rc<RefCell<String>>,
+}
+
+#[cfg(test)]
+impl Terminal for ScriptedTerminal {
+    fn read_line(&mut self, buf: &mut String) -> io::Result<usize> {
+        let output = self.output.borrow();
+        let line = self
+            .input
+            .pop_front()
+            .unwrap_or_else(|| panic!("scripted input exhausted, output so far:\n{}", output));
+        buf.push_str(&line);
+        buf.push('\n');
+        Ok(line.len() + 1)
+    }
+
+    fn write_str(&mut self, text: &str) {
+        self.output.borrow_mut().push_str(text);
+    }
+}
+
+/// 在当前线程上用预设输入运行 `f`，返回期间的全部输出
+#[cfg(test)]
+pub fn run_scripted(input: &[&str], f: impl FnOnce()) -> String {
+    let output = std::rc::Rc::new(RefCell::new(String::new()));
+    let scripted = ScriptedTerminal {
+        input: input.iter().map(|s| s.to_string()).collect(),
+        output: output.clone(),
+    };
+    let previous = CURRENT.with(|t| t.replace(Box::new(scripted)));
+    // f panic 时也恢复原来的终端
+    struct Restore(Option<Box<dyn Terminal>>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            if let Some(previous) = self.0.take() {
+                CURRENT.with(|t| *t.borrow_mut() = previous);
+            }
+        }
+    }
+    let restore = Restore(Some(previous));
+    f();
+    drop(restore);
+    output.take()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_scripted_feeds_input_and_captures_output() {
+        let output = run_scripted(&["2", "yes"], || {
+            write_str("choice: ");
+            let mut line = String::new();
+            read_line(&mut line).unwrap();
+            assert_eq!(line, "2\n");
+            line.clear();
+            read_line(&mut line).unwrap();
+            write_str(&format!("got {}", line.trim()));
+        });
+        assert_eq!(output, "choice: got yes");
+    }
+
+    #[test]
+    #[should_panic(expected = "scripted input exhausted")]
+    fn test_exhausted_input_panics() {
+        run_scripted(&[], || {
+            let _ = read_line(&mut String::new());
+        });
+    }
+}
@@ -0,0 +1,197 @@
+use std::borrow::Cow;
+use unicode_width::UnicodeWidthChar;
+
+/// 第一强方向隔离符 (FSI) 和隔离结束符 (PDI)：从右到左的文字包在里面，不会打乱相邻的列
+const FSI: char = '\u{2068}';
+const PDI: char = '\u{2069}';
+
+/// 列的对齐方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+/// 字符在终端中占的列数 (按 Unicode 东亚宽度)：组合符号、零宽字符和控制字符为 0，中日韩文字、全角符号和 emoji 为 2
+pub fn char_width(c: char) -> usize {
+    UnicodeWidthChar::width(c).unwrap_or(0)
+}
+
+/// 文本在终端中占的列数
+pub fn width(text: &str) -> usize {
+    text.chars().map(char_width).sum()
+}
+
+/// 是否为从右到左书写的字符 (希伯来文、阿拉伯文等)
+fn is_rtl(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x0590..=0x08FF | 0xFB1D..=0xFDFF | 0xFE70..=0xFEFE | 0x10800..=0x10FFF | 0x1E800..=0x1EFFF
+    )
+}
+
+/// 含有从右到左文字时用方向隔离符包起来，终端重新排列时不会越过列的边界
+pub fn isolate(text: &str) -> Cow<'_, str> {
+    if text.chars().any(is_rtl) {
+        Cow::Owned(format!("{}{}{}", FSI, text, PDI))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+/// 按显示宽度补齐到 `width` 列，已经超过时原样返回
+pub fn pad(text: &str, width: usize, align: Align) -> String {
+    let fill = " ".repeat(width.saturating_sub(self::width(text)));
+    match align {
+        Align::Left => format!("{}{}", text, fill),
+        Align::Right => format!("{}{}", fill, text),
+    }
+}
+
+/// 截断到最多 `max` 列，被截掉时以 `…` 结尾；不会把宽字符切成两半，未闭合的方向隔离会补上结束符
+pub fn truncate(text: &str, max: usize) -> Cow<'_, str> {
+    if width(text) <= max {
+        return Cow::Borrowed(text);
+    }
+    let mut out = String::new();
+    let mut used = 0;
+    let mut open = 0usize;
+    for c in text.chars() {
+        let w = char_width(c);
+        if used + w + 1 > max {
+            break;
+        }
+        match c {
+            FSI => open += 1,
+            PDI => open = open.saturating_sub(1),
+            _ => {}
+        }
+        used += w;
+        out.push(c);
+    }
+    if max > 0 {
+        out.push('…');
+    }
+    out.extend(std::iter::repeat_n(PDI, open));
+    Cow::Owned(out)
+}
+
+/// 终端宽度：标准输出是终端时查询实际窗口大小，否则取 `COLUMNS` 环境变量；
+/// 都没有 (如输出被重定向) 时为 None，调用方不截断
+pub fn terminal_width() -> Option<usize> {
+    terminal_size::terminal_size()
+        .map(|(terminal_size::Width(columns), _)| columns as usize)
+        .or_else(|| std::env::var("COLUMNS").ok()?.trim().parse().ok())
+        .filter(|&columns| columns > 0)
+}
+
+/// 每一列的宽度 (该列中最宽单元格的显示宽度)
+pub fn column_widths<S: AsRef<str>>(rows: &[Vec<S>]) -> Vec<usize> {
+    let mut widths = Vec::new();
+    for row in rows {
+        if widths.len() < row.len() {
+            widths.resize(row.len(), 0);
+        }
+        for (column, cell) in widths.iter_mut().zip(row) {
+            *column = (*column).max(width(cell.as_ref()));
+        }
+    }
+    widths
+}
+
+/// 按列宽排一行：列之间两个空格，`align` 中缺少的列左对齐，行尾不留空白
+pub fn row<S: AsRef<str>>(cells: &[S], widths: &[usize], align: &[Align]) -> String {
+    let mut line = String::new();
+    for (i, cell) in cells.iter().enumerate() {
+        if i > 0 {
+            line.push_str("  ");
+        }
+        let cell = isolate(cell.as_ref());
+        let align = align.get(i).copied().unwrap_or(Align::Left);
+        line.push_str(&pad(&cell, widths.get(i).copied().unwrap_or(0), align));
+    }
+    line.trim_end().to_string()
+}
+
+/// 把若干行排成对齐的列，所有列表显示都经过这里，中日韩文字和从右到左的文字也能对齐
+pub fn table<S: AsRef<str>>(rows: &[Vec<S>], align: &[Align]) -> Vec<String> {
+    let widths = column_widths(rows);
+    rows.iter().map(|cells| row(cells, &widths, align)).collect()
+}
+
+/// 编号列表：`1. ` 开头，编号右对齐，其余列按 [`table`] 左对齐，终端宽度已知时每行截断到该宽度
+pub fn numbered<S: AsRef<str>>(rows: &[Vec<S>], first: usize) -> Vec<String> {
+    numbered_within(rows, first, terminal_width())
+}
+
+fn numbered_within<S: AsRef<str>>(rows: &[Vec<S>], first: usize, max: Option<usize>) -> Vec<String> {
+    let number_width = width(&format!("{}.", first + rows.len().saturating_sub(1)));
+    table(rows, &[])
+        .into_iter()
+        .enumerate()
+        .map(|(i, line)| {
+            let line = format!("{} {}", pad(&format!("{}.", first + i), number_width, Align::Right), line);
+            match max {
+                Some(max) => truncate(&line, max).into_owned(),
+                None => line,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_width_counts_wide_and_zero_width_characters() {
+        assert_eq!(width("abc"), 3);
+        assert_eq!(width("插件管理"), 8);
+        assert_eq!(width("プラグイン"), 10);
+        assert_eq!(width("플러그인"), 8);
+        assert_eq!(width("ｆｕｌｌ"), 8);
+        assert_eq!(width("✅ ok"), 5);
+        assert_eq!(width("e\u{301}"), 1);
+        assert_eq!(width("שלום"), 4);
+    }
+
+    #[test]
+    fn test_pad_and_truncate_use_display_width() {
+        assert_eq!(pad("中文", 6, Align::Left), "中文  ");
+        assert_eq!(pad("7", 3, Align::Right), "  7");
+        assert_eq!(pad("too long", 3, Align::Left), "too long");
+        assert_eq!(truncate("short", 10), "short");
+        assert_eq!(truncate("插件管理器", 7), "插件管…");
+        assert_eq!(truncate("插件管理器", 8), "插件管…");
+        assert_eq!(truncate("abcdef", 4), "abc…");
+        assert_eq!(truncate("\u{2068}שלום עולם\u{2069}", 5), "\u{2068}שלום…\u{2069}");
+    }
+
+    #[test]
+    fn test_table_aligns_mixed_scripts() {
+        let rows = vec![
+            vec!["1.", "hello.sh", "Say hello"],
+            vec!["10.", "系统信息.sh", "显示系统信息"],
+            vec!["11.", "שלום.sh", "RTL"],
+        ];
+        let lines = table(&rows, &[Align::Right]);
+        assert_eq!(lines[0], " 1.  hello.sh     Say hello");
+        assert_eq!(lines[1], "10.  系统信息.sh  显示系统信息");
+        assert_eq!(lines[2], "11.  \u{2068}שלום.sh\u{2069}      RTL");
+        assert!(lines.iter().all(|line| width(line) <= width(&lines[1])));
+    }
+
+    #[test]
+    fn test_numbered_right_aligns_numbers() {
+        let rows: Vec<Vec<String>> = (0..10).map(|i| vec![format!("item{}", i), "x".to_string()]).collect();
+        let lines = numbered(&rows, 1);
+        assert_eq!(lines[0], " 1. item0  x");
+        assert_eq!(lines[9], "10. item9  x");
+        assert_eq!(numbered(&[vec!["one"]], 1), vec!["1. one"]);
+
+        // 宽度未知 (输出不是终端) 时不截断
+        let long = vec![vec!["x".repeat(120)]];
+        assert_eq!(width(&numbered_within(&long, 1, None)[0]), 123);
+        assert_eq!(numbered_within(&long, 1, Some(10))[0], "1. xxxxxx…");
+    }
+}
@@ -0,0 +1,297 @@
+use super::layout;
+use crate::i18n::{t, Language};
+use crate::logging;
+use crate::terminal;
+use crate::log_only;
+use std::cell::{Cell, RefCell};
+
+/// 离开当前菜单的方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Nav {
+    /// 返回上一级菜单
+    Back,
+    /// 一路返回主菜单
+    Home,
+    /// 退出程序
+    Quit,
+}
+
+impl Nav {
+    /// 所有菜单和提示统一的导航键，`exit` 为旧版本的返回写法
+    pub fn from_key(input: &str) -> Option<Self> {
+        match input.trim().to_ascii_lowercase().as_str() {
+            "b" | "back" | "exit" => Some(Self::Back),
+            "h" | "home" => Some(Self::Home),
+            "q" | "quit" => Some(Self::Quit),
+            _ => None,
+        }
+    }
+}
+
+thread_local! {
+    /// 面包屑：从主菜单到当前菜单的名称
+    static TRAIL: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    /// 尚未处理完的 Home / Quit：外层菜单依次返回，直到主菜单 (Home) 或程序退出 (Quit)
+    static PENDING: Cell<Option<Nav>> = const { Cell::new(None) };
+}
+
+/// 进入一级菜单的面包屑，离开作用域时自动移除
+pub struct Crumb(());
+
+impl Drop for Crumb {
+    fn drop(&mut self) {
+        TRAIL.with(|trail| trail.borrow_mut().pop());
+    }
+}
+
+/// 进入名为 `name` 的菜单，返回的守卫需要在菜单函数结束前一直持有
+pub fn enter(name: &str) -> Crumb {
+    TRAIL.with(|trail| trail.borrow_mut().push(name.to_string()));
+    Crumb(())
+}
+
+fn depth() -> usize {
+    TRAIL.with(|trail| trail.borrow().len())
+}
+
+/// 当前位置，如 `主菜单 › 插件管理 › 插件市场`
+pub fn breadcrumbs() -> String {
+    TRAIL.with(|trail| trail.borrow().join(" › "))
+}
+
+/// 记录导航请求；Back 只影响当前循环，不需要记录
+fn request(nav: Nav) {
+    if nav != Nav::Back {
+        PENDING.with(|pending| pending.set(Some(nav)));
+    }
+}
+
+/// 是否有待处理的 Home / Quit，菜单以外的循环据此提前结束
+pub fn leaving() -> bool {
+    PENDING.with(|pending| pending.get().is_some())
+}
+
+/// 读取一行并去掉首尾空白；输入结束 (EOF) 视为退出
+fn read_input() -> Option<String> {
+    let mut input = String::new();
+    match terminal::read_line(&mut input) {
+        Ok(0) | Err(_) => {
+            request(Nav::Quit);
+            None
+        }
+        Ok(_) => Some(input.trim().to_string()),
+    }
+}
+
+/// 显示提示并读取一行输入；输入导航键或输入结束时返回 None 并记录导航请求
+pub fn prompt(text: &str) -> Option<String> {
+    if leaving() {
+        return None;
+    }
+    terminal::write_str(text);
+    let input = read_input()?;
+    match Nav::from_key(&input) {
+        Some(nav) => {
+            request(nav);
+            None
+        }
+        None => Some(input),
+    }
+}
+
+/// 读取自由文本 (名称、路径、说明等)：`b`/`h`/`q` 是合法的输入，不作为导航键；
+/// 直接回车得到空字符串，由调用方决定取消还是使用默认值。输入结束时返回 None 并记录退出请求
+pub fn prompt_text(text: &str) -> Option<String> {
+    if leaving() {
+        return None;
+    }
+    terminal::write_str(text);
+    read_input()
+}
+
+/// 显示编号菜单并读取选择，返回选中项的下标 (从 0 开始)；
+/// 无效输入提示后重新读取，返回 None 表示应离开当前菜单。
+/// 主菜单中 Home 只是重新显示，Back 被忽略，Quit 返回 None。
+pub fn choose(title: &str, items: &[String], lang: Language) -> Option<usize> {
+    choose_with_commands(title, items, &[], lang)
+}
+
+/// 同 [`choose`]，另外可以输入命令名 (不区分大小写，唯一前缀即可)，
+/// 菜单调整顺序后老的输入习惯依然有效。`commands` 为 (命令名, 返回值)，
+/// 返回值可以不小于 `items.len()`，表示菜单中没有列出的动作。
+pub fn choose_with_commands(title: &str, items: &[String], commands: &[(&str, usize)], lang: Language) -> Option<usize> {
+    let root = depth() <= 1;
+    if let Some(nav) = PENDING.with(|pending| pending.get()) {
+        if !root || nav == Nav::Quit {
+            return None;
+        }
+        PENDING.with(|pending| pending.set(None));
+    }
+
+    terminal::write_str(&render(title, items, commands, lang, root));
+    let prompt = t("menu_nav.prompt", &[("0", &items.len().to_string())], lang);
+    loop {
+        terminal::write_str(&prompt);
+        let input = read_input()?;
+        match parse_choice(&input, items.len(), commands) {
+            Some(Ok(index)) => {
+                let item = items.get(index).map_or(input.as_str(), String::as_str);
+                logging::begin_action();
+                log_only!("INFO", "MENU", "{}: {}", breadcrumbs(), item);
+                return Some(index);
+            }
+            Some(Err(Nav::Back)) if root => {}
+            Some(Err(Nav::Home)) if root => {
+                terminal::write_str(&render(title, items, commands, lang, root));
+            }
+            Some(Err(nav)) => {
+                request(nav);
+                return None;
+            }
+            None if input.is_empty() => {}
+            None => {
+                let matches = match_commands(&input, commands);
+                let message = if matches.len() > 1 {
+                    let names: Vec<&str> = matches.iter().map(|(name, _)| *name).collect();
+                    t("menu_nav.ambiguous", &[("0", &input), ("1", &names.join(", "))], lang)
+                } else {
+                    t("main.invalid_choice", &[], lang)
+                };
+                terminal::write_str(&format!("{}\n", message));
+            }
+        }
+    }
+}
+
+/// 编号 (1..=count) 或唯一匹配的命令名转为下标，导航键转为 Nav；其他输入返回 None
+fn parse_choice(input: &str, count: usize, commands: &[(&str, usize)]) -> Option<Result<usize, Nav>> {
+    if let Some(nav) = Nav::from_key(input) {
+        return Some(Err(nav));
+    }
+    match input.parse::<usize>() {
+        Ok(n) if (1..=count).contains(&n) => Some(Ok(n - 1)),
+        Ok(_) => None,
+        Err(_) => match match_commands(input, commands).as_slice() {
+            [(_, index)] => Some(Ok(*index)),
+            _ => None,
+        },
+    }
+}
+
+/// 与输入匹配的命令：完全相同的优先，否则为所有以输入开头的命令
+fn match_commands<'a>(input: &str, commands: &[(&'a str, usize)]) -> Vec<(&'a str, usize)> {
+    let input = input.to_lowercase();
+    if input.is_empty() {
+        return Vec::new();
+    }
+    if let Some(exact) = commands.iter().find(|(name, _)| *name == input) {
+        return vec![*exact];
+    }
+    commands.iter().filter(|(name, _)| name.starts_with(&input)).copied().collect()
+}
+
+fn render(title: &str, items: &[String], commands: &[(&str, usize)], lang: Language, root: bool) -> String {
+    let mut out = format!("\n{}\n", title);
+    if !root {
+        out.push_str(&format!("{}\n", breadcrumbs()));
+    }
+    let rows: Vec<Vec<&str>> = items.iter().map(|item| vec![item.as_str()]).collect();
+    for line in layout::numbered(&rows, 1) {
+        out.push_str(&format!("{}\n", line));
+    }
+    if !commands.is_empty() {
+        let names: Vec<&str> = commands.iter().map(|(name, _)| *name).collect();
+        out.push_str(&format!("{}\n", t("menu_nav.commands", &[("0", &names.join(", "))], lang)));
+    }
+    let hint = if root { "menu_nav.hint_root" } else { "menu_nav.hint" };
+    out.push_str(&format!("{}\n", t(hint, &[], lang)));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terminal::run_scripted;
+
+    fn items() -> Vec<String> {
+        vec!["one".to_string(), "two".to_string()]
+    }
+
+    #[test]
+    fn test_choose_validates_input() {
+        let _home = enter("Home");
+        let _sub = enter("Sub");
+        let mut choice = None;
+        let output = run_scripted(&["", "9", "x", "2"], || {
+            choice = choose("=== Sub ===", &items(), Language::English);
+        });
+        assert_eq!(choice, Some(1));
+        assert!(output.contains("Home › Sub\n1. one\n2. two\n"));
+        assert_eq!(output.matches(&t("main.invalid_choice", &[], Language::English)).count(), 2);
+    }
+
+    #[test]
+    fn test_home_unwinds_to_root_menu() {
+        let _home = enter("Home");
+        let mut results = Vec::new();
+        run_scripted(&["h", "1"], || {
+            let sub = enter("Sub");
+            results.push(choose("sub", &items(), Language::English));
+            let nested = enter("Nested");
+            // 外层菜单在 Home 之后不再读取输入
+            results.push(choose("nested", &items(), Language::English));
+            drop(nested);
+            drop(sub);
+            results.push(choose("root", &items(), Language::English));
+        });
+        assert_eq!(results, vec![None, None, Some(0)]);
+        assert!(!leaving());
+    }
+
+    #[test]
+    fn test_quit_and_eof_leave_every_menu() {
+        let _home = enter("Home");
+        run_scripted(&["back", "quit"], || {
+            assert_eq!(prompt("name: "), None);
+            assert!(!leaving());
+            assert_eq!(prompt("name: "), None);
+            assert!(leaving());
+            assert_eq!(choose("root", &items(), Language::English), None);
+        });
+        PENDING.with(|pending| pending.set(None));
+
+        assert_eq!(parse_choice("EXIT", 2, &[]), Some(Err(Nav::Back)));
+        assert_eq!(parse_choice("3", 2, &[]), None);
+    }
+
+    #[test]
+    fn test_prompt_text_keeps_navigation_keys() {
+        run_scripted(&["q", "b", ""], || {
+            assert_eq!(prompt_text("reason: ").as_deref(), Some("q"));
+            assert_eq!(prompt_text("name: ").as_deref(), Some("b"));
+            assert_eq!(prompt_text("details: ").as_deref(), Some(""));
+        });
+        assert!(!leaving());
+    }
+
+    #[test]
+    fn test_named_commands_match_prefixes() {
+        let commands = [("run", 0), ("plugins", 3), ("settings", 4), ("setup", 5), ("update", 7)];
+        assert_eq!(parse_choice("run", 7, &commands), Some(Ok(0)));
+        assert_eq!(parse_choice("Plug", 7, &commands), Some(Ok(3)));
+        assert_eq!(parse_choice("u", 7, &commands), Some(Ok(7)));
+        assert_eq!(parse_choice("set", 7, &commands), None);
+        assert_eq!(parse_choice("sett", 7, &commands), Some(Ok(4)));
+        assert_eq!(parse_choice("runs", 7, &commands), None);
+        assert_eq!(parse_choice("q", 7, &commands), Some(Err(Nav::Quit)));
+
+        let _home = enter("Home");
+        let mut choice = None;
+        let output = run_scripted(&["se", "up"], || {
+            choice = choose_with_commands("root", &items(), &commands, Language::English);
+        });
+        assert_eq!(choice, Some(7));
+        assert!(output.contains("Commands: run, plugins, settings, setup, update\n"));
+        assert!(output.contains("Ambiguous command 'se': settings, setup"));
+    }
+}
@@ -0,0 +1,5 @@
+pub mod layout;
+pub mod menu;
+#[cfg(feature = "network")]
+pub mod qr;
+pub mod theme;
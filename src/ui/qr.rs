@@ -0,0 +1,127 @@
+use qrcode::{Color, EcLevel};
+
+/// 四周的空白 (模块数)，规范要求至少 4 个模块，扫码软件靠它找到码的边界
+const QUIET_ZONE: usize = 4;
+
+/// 编码好的 QR 码 (纠错等级 M)
+pub struct QrCode {
+    size: usize,
+    modules: Vec<bool>,
+}
+
+impl QrCode {
+    /// 选能容纳数据的最小版本并编码；超过版本 40 的容量时返回 None
+    pub fn encode(data: &[u8]) -> Option<QrCode> {
+        let code = qrcode::QrCode::with_error_correction_level(data, EcLevel::M).ok()?;
+        Some(QrCode {
+            size: code.width(),
+            modules: code.to_colors().into_iter().map(|color| color == Color::Dark).collect(),
+        })
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// 模块是否为深色；码外 (空白区) 为浅色
+    pub fn get(&self, x: isize, y: isize) -> bool {
+        let size = self.size as isize;
+        (0..size).contains(&x) && (0..size).contains(&y) && self.modules[(y * size + x) as usize]
+    }
+
+    /// 每个字符画上下两个模块，固定黑字白底，深色和浅色终端主题下都能扫
+    pub fn render(&self) -> String {
+        let edge = (self.size + 2 * QUIET_ZONE) as isize;
+        let offset = QUIET_ZONE as isize;
+        let mut out = String::new();
+        for row in (0..edge).step_by(2) {
+            out.push_str("\x1b[30;107m");
+            for x in 0..edge {
+                let top = self.get(x - offset, row - offset);
+                let bottom = row + 1 < edge && self.get(x - offset, row + 1 - offset);
+                out.push(match (top, bottom) {
+                    (true, true) => '█',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (false, false) => ' ',
+                });
+            }
+            out.push_str("\x1b[0m\n");
+        }
+        out
+    }
+}
+
+/// 终端中显示的二维码，在无图形界面的机器上 (如 SSH 登录) 用手机扫码拿到链接；
+/// 纯文本模式、内容过长或终端 (宽度已知时) 太窄时返回 None
+pub fn render(text: &str) -> Option<String> {
+    if super::theme::is_plain() {
+        return None;
+    }
+    let qr = QrCode::encode(text.as_bytes())?;
+    let fits = super::layout::terminal_width().is_none_or(|columns| qr.size() + 2 * QUIET_ZONE <= columns);
+    fits.then(|| qr.render())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_chosen_by_length() {
+        assert_eq!(QrCode::encode(&[b'a'; 14]).unwrap().size(), 21);
+        assert_eq!(QrCode::encode(&[b'a'; 15]).unwrap().size(), 25);
+        assert!(QrCode::encode(&[b'a'; 2331]).is_some());
+        assert!(QrCode::encode(&[b'a'; 2332]).is_none());
+    }
+
+    #[test]
+    fn test_matches_iso_annex_i() {
+        // ISO/IEC 18004 附录 I 的示例：数字 "01234567"，版本 1，纠错等级 M
+        let expected = [
+            "#######..#.##.#######",
+            "#.....#..####.#.....#",
+            "#.###.#.#.....#.###.#",
+            "#.###.#.##....#.###.#",
+            "#.###.#.#.###.#.###.#",
+            "#.....#.#...#.#.....#",
+            "#######.#.#.#.#######",
+            "........#..##........",
+            "#.#####..#..#.#####..",
+            "...#.#.##.#.#..#.##..",
+            "..#...##.#.#.#..#####",
+            "....#....#.....####..",
+            "...######..#.#..#....",
+            "........#.#####..##..",
+            "#######..##.#.##.....",
+            "#.....#.#.#####...#.#",
+            "#.###.#.#...#..#.##..",
+            "#.###.#.##..#..#.....",
+            "#.###.#.#.##.#..#.#..",
+            "#.....#........##.##.",
+            "#######.####.#..#.#..",
+        ];
+        let qr = QrCode::encode(b"01234567").unwrap();
+        let rows: Vec<String> = (0..qr.size() as isize)
+            .map(|y| (0..qr.size() as isize).map(|x| if qr.get(x, y) { '#' } else { '.' }).collect())
+            .collect();
+        assert_eq!(rows, expected);
+    }
+
+    #[test]
+    fn test_render_has_quiet_zone() {
+        let qr = QrCode::encode(b"01234567").unwrap();
+        let rendered = qr.render();
+        let lines: Vec<&str> = rendered
+            .lines()
+            .map(|line| line.trim_start_matches("\x1b[30;107m").trim_end_matches("\x1b[0m"))
+            .collect();
+        let edge = 21 + 2 * QUIET_ZONE;
+        assert_eq!(lines.len(), edge.div_ceil(2));
+        assert_eq!(lines[0], " ".repeat(edge));
+        assert_eq!(lines[1], " ".repeat(edge));
+        // 前 4 行空白之后是定位图案的上边两行
+        assert!(lines[2].starts_with("    █▀▀▀▀▀█ "));
+        assert_eq!(lines.last().unwrap().trim(), "");
+    }
+}
@@ -0,0 +1,108 @@
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// 纯文本模式 (`ui.plain_output`)：去掉 emoji 和制表符，适合串口终端和读屏软件
+static PLAIN: AtomicBool = AtomicBool::new(false);
+
+pub fn set_plain(plain: bool) {
+    PLAIN.store(plain, Ordering::Relaxed);
+}
+
+pub fn is_plain() -> bool {
+    PLAIN.load(Ordering::Relaxed)
+}
+
+/// 所有终端输出经过这里；纯文本模式下转换为 [`plain`] 的结果
+pub fn render(text: &str) -> Cow<'_, str> {
+    if is_plain() {
+        Cow::Owned(plain(text))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+/// 去掉 emoji，制表符和装饰符号换成 ASCII；emoji 后面用来对齐的空格一并去掉
+pub fn plain(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut dropped = false;
+    for c in text.chars() {
+        if is_emoji(c) {
+            dropped = true;
+            continue;
+        }
+        if dropped && c == ' ' && (out.is_empty() || out.ends_with(['\n', '\r', ' '])) {
+            continue;
+        }
+        dropped = false;
+        match ascii_for(c) {
+            Some(replacement) => out.push_str(replacement),
+            None => out.push(c),
+        }
+    }
+    out
+}
+
+/// 把一组非负数画成一行迷你折线图，每个值一个字符，按最大值缩放；纯文本模式下用 ASCII 字符
+#[cfg(feature = "marketplace")]
+pub fn sparkline(values: &[i64]) -> String {
+    const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    const ASCII: [char; 8] = ['_', '.', '-', ':', '=', '+', '*', '#'];
+    let levels = if is_plain() { &ASCII } else { &BLOCKS };
+    let max = values.iter().copied().max().unwrap_or(0).max(1);
+    values
+        .iter()
+        .map(|&v| levels[(v.max(0) * 7 / max) as usize])
+        .collect()
+}
+
+fn is_emoji(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1F000..=0x1FAFF   // emoji、国旗、扑克等
+            | 0x2600..=0x27BF // 杂项符号和 Dingbats (✅ ❌ ⚠)
+            | 0x2300..=0x23FF // 杂项技术符号 (⏱ ⏳)
+            | 0x2B00..=0x2BFF // 箭头和星号 (⬇ ⭐)
+            | 0xFE00..=0xFE0F // 变体选择符
+            | 0x200D           // 零宽连接符
+            | 0x20E3           // 键帽
+    )
+}
+
+fn ascii_for(c: char) -> Option<&'static str> {
+    Some(match c {
+        '═' | '━' => "=",
+        '─' | '┄' | '┈' => "-",
+        '│' | '║' | '┃' => "|",
+        '┌' | '┐' | '└' | '┘' | '├' | '┤' | '┬' | '┴' | '┼' | '╔' | '╗' | '╚' | '╝' | '╠' | '╣' | '╦' | '╩' | '╬' => "+",
+        '\u{2500}'..='\u{257F}' | '\u{2580}'..='\u{259F}' => "#",
+        '•' => "-",
+        '›' => ">",
+        '·' => "|",
+        '→' => "->",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_strips_emoji_and_box_drawing() {
+        assert_eq!(plain("✅ 插件安装成功"), "插件安装成功");
+        assert_eq!(plain("\n⚠️  Warning\n🔍 done"), "\nWarning\ndone");
+        assert_eq!(plain("═══ Menu ═══\n│ a │"), "=== Menu ===\n| a |");
+        assert_eq!(plain("Home › Settings\nb = back · q = quit"), "Home > Settings\nb = back | q = quit");
+        assert_eq!(plain("status: ✅ ok"), "status: ok");
+        assert_eq!(plain("\r⬇️  42%"), "\r42%");
+        assert_eq!(plain("plain ascii, 中文（全角）"), "plain ascii, 中文（全角）");
+    }
+
+    #[cfg(feature = "marketplace")]
+    #[test]
+    fn test_sparkline_scales_to_max() {
+        assert_eq!(sparkline(&[0, 1, 2, 4, 8, 8]), "▁▁▂▄██");
+        assert_eq!(sparkline(&[0, 0]), "▁▁");
+        assert_eq!(sparkline(&[]), "");
+    }
+}
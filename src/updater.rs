@@ -0,0 +1,104 @@
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "self-update")]
+use std::env;
+
+/// 自更新的设置
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct UpdateConfig {
+    /// 指定要下载的发行版文件名 (如 `geektools-linux-armv7`)，不设置时按平台自动选择
+    #[serde(default)]
+    pub asset_name: Option<String>,
+    /// 夜间构建的发布清单 URL (JSON，见 [`NightlyFeed`])，不设置时没有夜间构建通道
+    #[serde(default)]
+    pub nightly_feed: Option<String>,
+}
+
+/// 夜间构建的发布清单：
+///
+/// ```json
+/// {"build_tag": "3f9c0e1a2b4d5c6e", "assets": [
+///   {"name": "geektools-linux-x64", "url": "https://.../geektools-linux-x64", "sha256": "<摘要>"}
+/// ]}
+/// ```
+#[cfg(feature = "self-update")]
+#[derive(Deserialize, Debug)]
+pub struct NightlyFeed {
+    /// 构建标签，与程序启动时显示的 build tag 相同
+    pub build_tag: String,
+    pub assets: Vec<NightlyAsset>,
+}
+
+/// 夜间构建的一个文件，下载后必须与 `sha256` 一致才会替换程序
+#[cfg(feature = "self-update")]
+#[derive(Deserialize, Debug)]
+pub struct NightlyAsset {
+    pub name: String,
+    pub url: String,
+    pub sha256: String,
+}
+
+#[cfg(feature = "self-update")]
+impl NightlyFeed {
+    pub fn asset(&self, name: &str) -> Option<&NightlyAsset> {
+        self.assets.iter().find(|a| a.name == name)
+    }
+}
+
+/// 平台对应的发行版文件名；Linux 版本都是 musl 静态链接，glibc 和 musl 系统通用
+#[cfg(feature = "self-update")]
+pub fn asset_name(os: &str, arch: &str) -> Option<&'static str> {
+    match (os, arch) {
+        ("macos", _) => Some("geektools-macos-universal"),
+        ("linux", "x86_64") => Some("geektools-linux-x64"),
+        ("linux", "aarch64") => Some("geektools-linux-arm64"),
+        // 32 位系统的树莓派 2/3/4
+        ("linux", "armv7") => Some("geektools-linux-armv7"),
+        _ => None,
+    }
+}
+
+/// 当前程序的架构；32 位 ARM 区分出 armv7，armv6 没有对应的发行版
+#[cfg(feature = "self-update")]
+fn current_arch() -> &'static str {
+    if env::consts::ARCH == "arm" && cfg!(target_feature = "v7") { "armv7" } else { env::consts::ARCH }
+}
+
+/// 本程序要下载的发行版文件名：配置了 `update.asset_name` 时使用它
+#[cfg(feature = "self-update")]
+pub fn wanted_asset(config: &UpdateConfig) -> Option<String> {
+    config
+        .asset_name
+        .clone()
+        .or_else(|| asset_name(env::consts::OS, current_arch()).map(str::to_string))
+}
+
+#[cfg(all(test, feature = "self-update"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_asset_name() {
+        assert_eq!(asset_name("macos", "aarch64"), Some("geektools-macos-universal"));
+        assert_eq!(asset_name("linux", "armv7"), Some("geektools-linux-armv7"));
+        assert_eq!(asset_name("linux", "arm"), None);
+        assert_eq!(asset_name("windows", "x86_64"), None);
+
+        let config = UpdateConfig { asset_name: Some("geektools-linux-arm64".to_string()), ..Default::default() };
+        assert_eq!(wanted_asset(&config).as_deref(), Some("geektools-linux-arm64"));
+        assert_eq!(wanted_asset(&UpdateConfig::default()).as_deref(), asset_name(env::consts::OS, current_arch()));
+    }
+
+    #[test]
+    fn test_nightly_feed() {
+        let feed: NightlyFeed = serde_json::from_str(
+            r#"{"build_tag": "3f9c0e1a2b4d5c6e", "assets": [
+                {"name": "geektools-linux-x64", "url": "https://example.com/x64", "sha256": "ab12"}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(feed.asset("geektools-linux-x64").map(|a| a.sha256.as_str()), Some("ab12"));
+        // 当前平台没有夜间构建时由调用方退回正式版
+        assert!(feed.asset("geektools-linux-armv7").is_none());
+        assert!(serde_json::from_str::<NightlyFeed>(r#"{"build_tag": "x", "assets": [{"name": "a", "url": "u"}]}"#).is_err());
+    }
+}
@@ -0,0 +1,185 @@
+use crate::{log_only, terminal};
+use notify::{Config, Event, PollWatcher, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::BTreeSet;
+use std::path::{Component, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+/// 等待事件时多久检查一次停止标志
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 监视模式的参数
+#[derive(Clone, Debug)]
+pub struct WatchOptions {
+    /// 监视的文件或目录 (目录递归)
+    pub paths: Vec<PathBuf>,
+    /// 最后一次变化后等待多久才触发，合并连续的保存
+    pub debounce: Duration,
+    /// 系统的文件通知不可用时 (如 inotify 监视数达到上限) 退回轮询的间隔
+    pub poll_interval: Duration,
+    /// 每次运行前清屏
+    pub clear_screen: bool,
+}
+
+impl WatchOptions {
+    pub fn new(paths: Vec<PathBuf>) -> Self {
+        Self {
+            paths,
+            debounce: Duration::from_millis(500),
+            poll_interval: Duration::from_millis(250),
+            clear_screen: false,
+        }
+    }
+}
+
+type EventReceiver = Receiver<notify::Result<Event>>;
+
+/// 创建监视器并加入所有路径；不跟随符号链接，`dotfiles/x -> ..` 这样的循环不会被反复遍历。
+/// 系统通知 (inotify/FSEvents/kqueue) 不可用时退回 notify 的轮询监视器
+fn start(options: &WatchOptions) -> notify::Result<(Box<dyn Watcher + Send>, EventReceiver)> {
+    let config = Config::default().with_follow_symlinks(false);
+    let (tx, rx) = mpsc::channel();
+    let native = RecommendedWatcher::new(tx, config).and_then(|mut watcher| {
+        for path in &options.paths {
+            watcher.watch(path, RecursiveMode::Recursive)?;
+        }
+        Ok(watcher)
+    });
+    match native {
+        Ok(watcher) => Ok((Box::new(watcher), rx)),
+        Err(e) => {
+            log_only!("WARN", "WATCH", "系统文件通知不可用 ({})，改为每 {:?} 轮询", e, options.poll_interval);
+            let (tx, rx) = mpsc::channel();
+            let mut watcher = PollWatcher::new(tx, config.with_poll_interval(options.poll_interval))?;
+            for path in &options.paths {
+                watcher.watch(path, RecursiveMode::Recursive)?;
+            }
+            Ok((Box::new(watcher), rx))
+        }
+    }
+}
+
+/// 事件涉及的需要处理的路径：忽略只读访问和 `.git` 目录中的变化
+fn relevant_paths(event: &Event) -> impl Iterator<Item = &PathBuf> {
+    let is_access = event.kind.is_access();
+    event
+        .paths
+        .iter()
+        .filter(move |path| !is_access && !path.components().any(|c| c == Component::Normal(".git".as_ref())))
+}
+
+/// 已经建立好的监视；先建立再开始等待，建立失败时调用方可以直接报错而不必先启动其他线程
+pub struct Watch {
+    _watcher: Box<dyn Watcher + Send>,
+    events: EventReceiver,
+}
+
+impl Watch {
+    pub fn start(options: &WatchOptions) -> notify::Result<Self> {
+        let (watcher, events) = start(options)?;
+        Ok(Self { _watcher: watcher, events })
+    }
+
+    /// 变化稳定 `debounce` 之后调用 `on_change`，直到 `stop` 被置位。
+    /// 回调结束后丢弃期间积压的事件，脚本自身写入监视目录不会再次触发。
+    pub fn run<F>(self, options: &WatchOptions, stop: &AtomicBool, on_change: F)
+    where
+        F: FnMut(&BTreeSet<PathBuf>),
+    {
+        run(&self.events, options, stop, on_change);
+    }
+}
+
+fn run<F>(events: &EventReceiver, options: &WatchOptions, stop: &AtomicBool, mut on_change: F)
+where
+    F: FnMut(&BTreeSet<PathBuf>),
+{
+    let mut pending = BTreeSet::new();
+    let mut last_change = Instant::now();
+
+    while !stop.load(Ordering::Relaxed) {
+        match events.recv_timeout(STOP_CHECK_INTERVAL) {
+            Ok(Ok(event)) => {
+                let changed: Vec<PathBuf> = relevant_paths(&event).cloned().collect();
+                if !changed.is_empty() {
+                    pending.extend(changed);
+                    last_change = Instant::now();
+                }
+            }
+            Ok(Err(e)) => log_only!("WARN", "WATCH", "监视出错: {}", e),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        if !pending.is_empty() && last_change.elapsed() >= options.debounce {
+            if options.clear_screen {
+                terminal::clear_screen();
+            }
+            on_change(&pending);
+            pending.clear();
+            while events.try_recv().is_ok() {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{AccessKind, CreateKind, EventKind};
+    use std::fs;
+    use std::sync::Arc;
+    use std::thread;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_relevant_paths_skip_git_and_access() {
+        let event = Event::new(EventKind::Create(CreateKind::File))
+            .add_path(PathBuf::from("/home/me/dotfiles/.zshrc"))
+            .add_path(PathBuf::from("/home/me/dotfiles/.git/index"));
+        let paths: Vec<&PathBuf> = relevant_paths(&event).collect();
+        assert_eq!(paths, vec![&PathBuf::from("/home/me/dotfiles/.zshrc")]);
+
+        let access = Event::new(EventKind::Access(AccessKind::Any)).add_path(PathBuf::from("/home/me/dotfiles/.zshrc"));
+        assert_eq!(relevant_paths(&access).count(), 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_watch_reports_change_despite_symlink_cycle() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("dotfiles")).unwrap();
+        std::os::unix::fs::symlink("..", dir.path().join("dotfiles").join("x")).unwrap();
+
+        let mut options = WatchOptions::new(vec![dir.path().to_path_buf()]);
+        options.debounce = Duration::from_millis(50);
+        let stop = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = mpsc::channel();
+        let watcher = {
+            let stop = stop.clone();
+            let watch = Watch::start(&options).unwrap();
+            thread::spawn(move || {
+                watch.run(&options, &stop, |changed| {
+                    let _ = tx.send(changed.clone());
+                })
+            })
+        };
+
+        // 给监视器建立监视的时间
+        let target = dir.path().join("dotfiles").join(".zshrc");
+        let mut changed = None;
+        for _ in 0..50 {
+            fs::write(&target, "export EDITOR=vim\n").unwrap();
+            if let Ok(paths) = rx.recv_timeout(Duration::from_millis(200)) {
+                changed = Some(paths);
+                break;
+            }
+        }
+        stop.store(true, Ordering::Relaxed);
+        watcher.join().unwrap();
+
+        let changed = changed.expect("no change reported");
+        assert!(changed.iter().any(|p| p.ends_with("dotfiles/.zshrc")), "{:?}", changed);
+        assert!(changed.iter().all(|p| !p.components().any(|c| c == Component::Normal("x".as_ref()))), "{:?}", changed);
+    }
+}
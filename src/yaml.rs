@@ -0,0 +1,266 @@
+use serde_json::{Map, Number, Value};
+
+/// 解析 YAML 的常用子集为 JSON 值：块映射、块序列 (`- `)、注释、
+/// 单/双引号字符串、纯量 (null/布尔/数字/字符串) 以及 JSON 风格的流式写法 (`[a, b]` 用 `["a", "b"]`)。
+/// 不支持锚点、多文档和 `|` / `>` 块标量。错误信息带行号。
+pub fn parse(content: &str) -> Result<Value, String> {
+    // JSON 文档整体按 JSON 解析
+    if content.trim_start().starts_with(['{', '[']) {
+        return serde_json::from_str(content).map_err(|e| format!("line {}: {}", e.line(), e));
+    }
+    let mut lines = Vec::new();
+    for (index, raw) in content.lines().enumerate() {
+        let number = index + 1;
+        let text = strip_comment(raw);
+        if text.trim().is_empty() || text.trim() == "---" {
+            continue;
+        }
+        let indent = text.len() - text.trim_start_matches(' ').len();
+        if text[indent..].starts_with('\t') {
+            return Err(format!("line {}: tabs are not allowed for indentation", number));
+        }
+        lines.push(Line { number, indent, text: text.trim().to_string() });
+    }
+    if lines.is_empty() {
+        return Ok(Value::Null);
+    }
+
+    let mut parser = Parser { lines, pos: 0 };
+    let indent = parser.lines[0].indent;
+    let value = parser.block(indent)?;
+    match parser.lines.get(parser.pos) {
+        Some(line) => Err(format!("line {}: unexpected indentation", line.number)),
+        None => Ok(value),
+    }
+}
+
+struct Line {
+    number: usize,
+    indent: usize,
+    text: String,
+}
+
+struct Parser {
+    lines: Vec<Line>,
+    pos: usize,
+}
+
+impl Parser {
+    fn block(&mut self, indent: usize) -> Result<Value, String> {
+        if is_sequence_item(&self.lines[self.pos].text) {
+            self.sequence(indent)
+        } else {
+            self.mapping(indent)
+        }
+    }
+
+    fn sequence(&mut self, indent: usize) -> Result<Value, String> {
+        let mut items = Vec::new();
+        while let Some(line) = self.lines.get(self.pos) {
+            if line.indent != indent || !is_sequence_item(&line.text) {
+                break;
+            }
+            let rest = line.text[1..].trim_start();
+            if rest.is_empty() {
+                self.pos += 1;
+                items.push(self.nested(indent)?);
+            } else if split_key(rest).is_some() {
+                // `- key: value`：把本行剩余部分当作缩进更深的映射的第一行
+                let offset = line.text.len() - rest.len();
+                let (number, rest) = (line.number, rest.to_string());
+                self.lines[self.pos] = Line { number, indent: indent + offset, text: rest };
+                items.push(self.mapping(indent + offset)?);
+            } else {
+                let value = scalar(rest).map_err(|e| format!("line {}: {}", line.number, e))?;
+                self.pos += 1;
+                items.push(value);
+            }
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn mapping(&mut self, indent: usize) -> Result<Value, String> {
+        let mut map = Map::new();
+        while let Some(line) = self.lines.get(self.pos) {
+            if line.indent < indent {
+                break;
+            }
+            let number = line.number;
+            if line.indent > indent {
+                return Err(format!("line {}: unexpected indentation", number));
+            }
+            if is_sequence_item(&line.text) {
+                break;
+            }
+            let (key, rest) = split_key(&line.text).ok_or_else(|| format!("line {}: expected `key: value`", number))?;
+            let key = match scalar(key).map_err(|e| format!("line {}: {}", number, e))? {
+                Value::String(key) => key,
+                other => other.to_string(),
+            };
+            let rest = rest.to_string();
+            self.pos += 1;
+            let value = if rest.is_empty() {
+                // 值在下一行：缩进更深的块，或与键同级的序列
+                match self.lines.get(self.pos) {
+                    Some(next) if next.indent == indent && is_sequence_item(&next.text) => self.sequence(indent)?,
+                    _ => self.nested(indent)?,
+                }
+            } else {
+                scalar(&rest).map_err(|e| format!("line {}: {}", number, e))?
+            };
+            if map.insert(key.clone(), value).is_some() {
+                return Err(format!("line {}: duplicate key `{}`", number, key));
+            }
+        }
+        Ok(Value::Object(map))
+    }
+
+    /// 缩进比 `indent` 更深的块，没有时为 null
+    fn nested(&mut self, indent: usize) -> Result<Value, String> {
+        match self.lines.get(self.pos) {
+            Some(next) if next.indent > indent => {
+                let indent = next.indent;
+                self.block(indent)
+            }
+            _ => Ok(Value::Null),
+        }
+    }
+}
+
+fn is_sequence_item(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+/// 在引号之外找到第一个 `: ` (或行尾的 `:`)，返回键和值
+fn split_key(text: &str) -> Option<(&str, &str)> {
+    let mut quote = None;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') if i == 0 => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, ':') => {
+                let rest = &text[i + 1..];
+                if rest.is_empty() || rest.starts_with(' ') {
+                    return Some((text[..i].trim(), rest.trim()));
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// 去掉引号之外以 ` #` 开始的注释
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut previous = ' ';
+    let mut chars = line.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            // 单引号内的 '' 表示一个单引号，相当于先关闭再立即打开
+            (None, '"' | '\'') if matches!(previous, ' ' | ':' | '[' | ',') || previous == c => quote = Some(c),
+            (Some('"'), '\\') => {
+                chars.next();
+            }
+            (Some(q), c) if c == q => quote = None,
+            (None, '#') if previous == ' ' || previous == '\t' => return &line[..i],
+            _ => {}
+        }
+        previous = c;
+    }
+    line
+}
+
+fn scalar(text: &str) -> Result<Value, String> {
+    let text = text.trim();
+    if text.starts_with('|') || text.starts_with('>') {
+        return Err("block scalars (| and >) are not supported".to_string());
+    }
+    if text.starts_with('[') || text.starts_with('{') {
+        return serde_json::from_str(text).map_err(|e| format!("invalid flow value: {}", e));
+    }
+    if let Some(inner) = text.strip_prefix('"') {
+        let inner = inner.strip_suffix('"').ok_or("unterminated string")?;
+        return serde_json::from_str(&format!("\"{}\"", inner)).map_err(|e| format!("invalid string: {}", e));
+    }
+    if let Some(inner) = text.strip_prefix('\'') {
+        let inner = inner.strip_suffix('\'').ok_or("unterminated string")?;
+        return Ok(Value::String(inner.replace("''", "'")));
+    }
+    Ok(match text {
+        "" | "~" | "null" | "Null" | "NULL" => Value::Null,
+        "true" | "True" | "TRUE" => Value::Bool(true),
+        "false" | "False" | "FALSE" => Value::Bool(false),
+        _ => {
+            if let Ok(n) = text.parse::<i64>() {
+                Value::Number(n.into())
+            } else if let Some(n) = text.parse::<f64>().ok().filter(|_| text.contains('.')).and_then(Number::from_f64) {
+                Value::Number(n)
+            } else {
+                Value::String(text.to_string())
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_nested_document() {
+        let content = "\
+# machine state
+plugins:
+  - id: hello-tools
+    version: \"1.0\"   # quoted to stay a string
+    source: https://example.com/hello.tar.gz
+  - id: disk-kit
+    version: 2.1.0
+config:
+  ui.show_timestamps: true
+  security.max_script_execution_time_seconds: 600
+  host_groups.web: [\"a@web1\", \"a@web2\"]
+  github_token: 'it''s # not a comment'
+tags:
+- one
+- ~
+";
+        assert_eq!(
+            parse(content).unwrap(),
+            json!({
+                "plugins": [
+                    {"id": "hello-tools", "version": "1.0", "source": "https://example.com/hello.tar.gz"},
+                    {"id": "disk-kit", "version": "2.1.0"}
+                ],
+                "config": {
+                    "ui.show_timestamps": true,
+                    "security.max_script_execution_time_seconds": 600,
+                    "host_groups.web": ["a@web1", "a@web2"],
+                    "github_token": "it's # not a comment"
+                },
+                "tags": ["one", null]
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_scalars() {
+        assert_eq!(scalar("1.5").unwrap(), json!(1.5));
+        assert_eq!(scalar("1.2.3").unwrap(), json!("1.2.3"));
+        assert_eq!(scalar("-7").unwrap(), json!(-7));
+        assert_eq!(scalar("\"a\\\"b\"").unwrap(), json!("a\"b"));
+        assert_eq!(scalar("False").unwrap(), json!(false));
+        assert_eq!(parse("").unwrap(), Value::Null);
+    }
+
+    #[test]
+    fn test_parse_errors_report_line() {
+        assert_eq!(parse("a: 1\n   b: 2\n").unwrap_err(), "line 2: unexpected indentation");
+        assert_eq!(parse("a: 1\na: 2\n").unwrap_err(), "line 2: duplicate key `a`");
+        assert!(parse("a: |\n  text\n").unwrap_err().starts_with("line 1: block scalars"));
+        assert!(parse("a: \"open\n").unwrap_err().starts_with("line 1:"));
+        assert!(parse("just text\n").unwrap_err().starts_with("line 1: expected"));
+    }
+}